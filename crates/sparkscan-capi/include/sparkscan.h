@@ -0,0 +1,116 @@
+#ifndef SPARKSCAN_H
+#define SPARKSCAN_H
+
+/* Generated by cbindgen from crates/sparkscan-capi. Do not edit manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of the functions that do not return a handle.
+typedef enum SparkScanStatus {
+  // The call succeeded
+  SPARK_SCAN_STATUS_OK = 0,
+  // A pointer argument was `NULL` or a string was not valid UTF-8
+  SPARK_SCAN_STATUS_INVALID_ARGUMENT = 1,
+  // The WebSocket connection could not be opened
+  SPARK_SCAN_STATUS_CONNECTION_FAILED = 2,
+} SparkScanStatus;
+
+// Connection to the SparkScan WebSocket feed with its own async runtime.
+typedef struct SparkScanClient SparkScanClient;
+
+// Subscription buffering its messages until they are polled.
+typedef struct SparkScanSubscription SparkScanSubscription;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Get the description of the last error on the calling thread, or `NULL` if none occurred.
+//
+// The string is owned by the library and valid until the next call on the same thread.
+const char *sparkscan_last_error_message(void);
+
+// Create a client for the feed at `url`, or the mainnet feed if `url` is `NULL`.
+//
+// Returns `NULL` on failure. The connection is opened by [`sparkscan_client_connect`].
+//
+// # Safety
+//
+// `url` must be `NULL` or point to a NUL-terminated string.
+struct SparkScanClient *sparkscan_client_new(const char *url);
+
+// Open the connection of `client`, which then reconnects automatically.
+//
+// # Safety
+//
+// `client` must be `NULL` or a handle returned by [`sparkscan_client_new`] and not yet freed.
+enum SparkScanStatus sparkscan_client_connect(const struct SparkScanClient *client);
+
+// Free `client` and close its connection.
+//
+// Subscriptions created from the client must be freed first.
+//
+// # Safety
+//
+// `client` must be `NULL` or a handle returned by [`sparkscan_client_new`] and not yet freed.
+void sparkscan_client_free(struct SparkScanClient *client);
+
+// Subscribe to `topic` (e.g. `balances` or `/balance/address/sp1...`), buffering up to
+// `capacity` messages.
+//
+// When the buffer is full, new messages are dropped and counted by
+// [`sparkscan_subscription_dropped`]. Returns `NULL` on failure.
+//
+// # Safety
+//
+// `client` must be `NULL` or a live handle returned by [`sparkscan_client_new`], and `topic`
+// `NULL` or a NUL-terminated string.
+struct SparkScanSubscription *sparkscan_subscribe(const struct SparkScanClient *client,
+                                                  const char *topic,
+                                                  uintptr_t capacity);
+
+// Wait up to `timeout_ms` milliseconds for the next message of `subscription`.
+//
+// Returns the message as a JSON object of the form `{"type": ..., "data": ...}`, to be freed
+// with [`sparkscan_string_free`], or `NULL` if none arrived in time. A timeout of `0` returns
+// immediately.
+//
+// # Safety
+//
+// `subscription` must be `NULL` or a handle returned by [`sparkscan_subscribe`] and not yet
+// freed.
+char *sparkscan_subscription_poll(const struct SparkScanSubscription *subscription,
+                                  uint32_t timeout_ms);
+
+// Get the number of messages of `subscription` dropped because its buffer was full.
+//
+// # Safety
+//
+// `subscription` must be `NULL` or a handle returned by [`sparkscan_subscribe`] and not yet
+// freed.
+uint64_t sparkscan_subscription_dropped(const struct SparkScanSubscription *subscription);
+
+// Unsubscribe and free `subscription`, discarding buffered messages.
+//
+// # Safety
+//
+// `subscription` must be `NULL` or a handle returned by [`sparkscan_subscribe`] and not yet
+// freed.
+void sparkscan_subscription_free(struct SparkScanSubscription *subscription);
+
+// Free a string returned by the library.
+//
+// # Safety
+//
+// `value` must be `NULL` or a string returned by [`sparkscan_subscription_poll`] and not yet
+// freed.
+void sparkscan_string_free(char *value);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SPARKSCAN_H */
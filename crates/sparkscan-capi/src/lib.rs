@@ -0,0 +1,354 @@
+//! C API for the SparkScan WebSocket client.
+//!
+//! The library exposes opaque handles and plain C functions so that C, C++ and Go (through
+//! cgo) services can embed the client; the declarations are in `include/sparkscan.h`, which
+//! `make capi-header` regenerates with cbindgen. Messages are handed out as JSON strings pulled
+//! with [`sparkscan_subscription_poll`], so the caller decides on which thread and at which pace
+//! they are processed:
+//!
+//! ```c
+//! SparkScanClient *client = sparkscan_client_new(NULL);
+//! if (client == NULL || sparkscan_client_connect(client) != SPARK_SCAN_STATUS_OK) {
+//!     fprintf(stderr, "%s\n", sparkscan_last_error_message());
+//!     return 1;
+//! }
+//!
+//! SparkScanSubscription *subscription = sparkscan_subscribe(client, "balances", 1024);
+//! for (;;) {
+//!     char *message = sparkscan_subscription_poll(subscription, 1000);
+//!     if (message != NULL) {
+//!         puts(message);
+//!         sparkscan_string_free(message);
+//!     }
+//! }
+//!
+//! sparkscan_subscription_free(subscription);
+//! sparkscan_client_free(client);
+//! ```
+//!
+//! Every handle owns its resources until passed to the matching `_free` function. Functions
+//! returning `NULL` or a status other than `SPARK_SCAN_STATUS_OK` record a description retrieved
+//! with [`sparkscan_last_error_message`].
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sparkscan_ws::{SparkScanSubscription as WsSubscription, SparkScanWsClient, Topic};
+
+/// Result of the functions that do not return a handle.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparkScanStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A pointer argument was `NULL` or a string was not valid UTF-8
+    InvalidArgument = 1,
+    /// The WebSocket connection could not be opened
+    ConnectionFailed = 2,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Into<String>) {
+    let message = CString::new(message.into().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// Read a C string argument, recording an error for `NULL` or invalid UTF-8.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point to a NUL-terminated string.
+unsafe fn read_str<'a>(value: *const c_char, name: &str) -> Option<&'a str> {
+    if value.is_null() {
+        set_last_error(format!("{} is NULL", name));
+        return None;
+    }
+    match unsafe { CStr::from_ptr(value) }.to_str() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            set_last_error(format!("{} is not valid UTF-8", name));
+            None
+        }
+    }
+}
+
+/// Connection to the SparkScan WebSocket feed with its own async runtime.
+pub struct SparkScanClient {
+    // Dropped before the runtime its tasks run on
+    client: SparkScanWsClient,
+    runtime: tokio::runtime::Runtime,
+}
+
+/// Subscription buffering its messages until they are polled.
+pub struct SparkScanSubscription {
+    subscription: WsSubscription,
+    messages: Mutex<Receiver<String>>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// Get the description of the last error on the calling thread, or `NULL` if none occurred.
+///
+/// The string is owned by the library and valid until the next call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn sparkscan_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Create a client for the feed at `url`, or the mainnet feed if `url` is `NULL`.
+///
+/// Returns `NULL` on failure. The connection is opened by [`sparkscan_client_connect`].
+///
+/// # Safety
+///
+/// `url` must be `NULL` or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sparkscan_client_new(url: *const c_char) -> *mut SparkScanClient {
+    let url = if url.is_null() {
+        sparkscan_ws::DEFAULT_MAINNET_URL
+    } else {
+        match unsafe { read_str(url, "url") } {
+            Some(url) => url,
+            None => return std::ptr::null_mut(),
+        }
+    };
+
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            set_last_error(format!("failed to start the async runtime: {}", e));
+            return std::ptr::null_mut();
+        }
+    };
+    let client = {
+        let _guard = runtime.enter();
+        SparkScanWsClient::new(url)
+    };
+
+    Box::into_raw(Box::new(SparkScanClient { client, runtime }))
+}
+
+/// Open the connection of `client`, which then reconnects automatically.
+///
+/// # Safety
+///
+/// `client` must be `NULL` or a handle returned by [`sparkscan_client_new`] and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sparkscan_client_connect(
+    client: *const SparkScanClient,
+) -> SparkScanStatus {
+    let Some(client) = (unsafe { client.as_ref() }) else {
+        set_last_error("client is NULL");
+        return SparkScanStatus::InvalidArgument;
+    };
+    match client.runtime.block_on(client.client.connect()) {
+        Ok(()) => SparkScanStatus::Ok,
+        Err(e) => {
+            set_last_error(e.to_string());
+            SparkScanStatus::ConnectionFailed
+        }
+    }
+}
+
+/// Free `client` and close its connection.
+///
+/// Subscriptions created from the client must be freed first.
+///
+/// # Safety
+///
+/// `client` must be `NULL` or a handle returned by [`sparkscan_client_new`] and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sparkscan_client_free(client: *mut SparkScanClient) {
+    if !client.is_null() {
+        drop(unsafe { Box::from_raw(client) });
+    }
+}
+
+/// Subscribe to `topic` (e.g. `balances` or `/balance/address/sp1...`), buffering up to
+/// `capacity` messages.
+///
+/// When the buffer is full, new messages are dropped and counted by
+/// [`sparkscan_subscription_dropped`]. Returns `NULL` on failure.
+///
+/// # Safety
+///
+/// `client` must be `NULL` or a live handle returned by [`sparkscan_client_new`], and `topic`
+/// `NULL` or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sparkscan_subscribe(
+    client: *const SparkScanClient,
+    topic: *const c_char,
+    capacity: usize,
+) -> *mut SparkScanSubscription {
+    let Some(client) = (unsafe { client.as_ref() }) else {
+        set_last_error("client is NULL");
+        return std::ptr::null_mut();
+    };
+    let Some(topic) = (unsafe { read_str(topic, "topic") }) else {
+        return std::ptr::null_mut();
+    };
+
+    let topic = match Topic::try_from(topic) {
+        Ok(topic) => topic,
+        Err(e) => {
+            set_last_error(e.to_string());
+            return std::ptr::null_mut();
+        }
+    };
+    let subscription = match client.runtime.block_on(client.client.subscribe(topic)) {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            set_last_error(e.to_string());
+            return std::ptr::null_mut();
+        }
+    };
+
+    let (sender, receiver) = std::sync::mpsc::sync_channel::<String>(capacity.max(1));
+    let dropped = Arc::new(AtomicU64::new(0));
+    let dropped_messages = Arc::clone(&dropped);
+    subscription.on_message(move |message| {
+        let Ok(message) = serde_json::to_string(&message) else {
+            return;
+        };
+        if let Err(TrySendError::Full(_)) = sender.try_send(message) {
+            dropped_messages.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    subscription.subscribe();
+
+    Box::into_raw(Box::new(SparkScanSubscription {
+        subscription,
+        messages: Mutex::new(receiver),
+        dropped,
+    }))
+}
+
+/// Wait up to `timeout_ms` milliseconds for the next message of `subscription`.
+///
+/// Returns the message as a JSON object of the form `{"type": ..., "data": ...}`, to be freed
+/// with [`sparkscan_string_free`], or `NULL` if none arrived in time. A timeout of `0` returns
+/// immediately.
+///
+/// # Safety
+///
+/// `subscription` must be `NULL` or a handle returned by [`sparkscan_subscribe`] and not yet
+/// freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sparkscan_subscription_poll(
+    subscription: *const SparkScanSubscription,
+    timeout_ms: u32,
+) -> *mut c_char {
+    let Some(subscription) = (unsafe { subscription.as_ref() }) else {
+        set_last_error("subscription is NULL");
+        return std::ptr::null_mut();
+    };
+    let messages = subscription
+        .messages
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let message = if timeout_ms == 0 {
+        messages.try_recv().ok()
+    } else {
+        messages
+            .recv_timeout(Duration::from_millis(u64::from(timeout_ms)))
+            .ok()
+    };
+    message
+        .and_then(|message| CString::new(message).ok())
+        .map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Get the number of messages of `subscription` dropped because its buffer was full.
+///
+/// # Safety
+///
+/// `subscription` must be `NULL` or a handle returned by [`sparkscan_subscribe`] and not yet
+/// freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sparkscan_subscription_dropped(
+    subscription: *const SparkScanSubscription,
+) -> u64 {
+    unsafe { subscription.as_ref() }.map_or(0, |subscription| {
+        subscription.dropped.load(Ordering::Relaxed)
+    })
+}
+
+/// Unsubscribe and free `subscription`, discarding buffered messages.
+///
+/// # Safety
+///
+/// `subscription` must be `NULL` or a handle returned by [`sparkscan_subscribe`] and not yet
+/// freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sparkscan_subscription_free(subscription: *mut SparkScanSubscription) {
+    if !subscription.is_null() {
+        let subscription = unsafe { Box::from_raw(subscription) };
+        subscription.subscription.unsubscribe();
+    }
+}
+
+/// Free a string returned by the library.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or a string returned by [`sparkscan_subscription_poll`] and not yet
+/// freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sparkscan_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(unsafe { CString::from_raw(value) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_null_arguments() {
+        unsafe {
+            assert_eq!(
+                sparkscan_client_connect(std::ptr::null()),
+                SparkScanStatus::InvalidArgument
+            );
+            let message = CStr::from_ptr(sparkscan_last_error_message());
+            assert_eq!(message.to_str().unwrap(), "client is NULL");
+
+            assert!(sparkscan_subscription_poll(std::ptr::null(), 0).is_null());
+            assert_eq!(sparkscan_subscription_dropped(std::ptr::null()), 0);
+            sparkscan_subscription_free(std::ptr::null_mut());
+            sparkscan_client_free(std::ptr::null_mut());
+            sparkscan_string_free(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_subscribe_invalid_topic() {
+        unsafe {
+            let client = sparkscan_client_new(std::ptr::null());
+            assert!(!client.is_null());
+
+            let topic = CString::new("not-a-topic").unwrap();
+            assert!(sparkscan_subscribe(client, topic.as_ptr(), 16).is_null());
+            let message = CStr::from_ptr(sparkscan_last_error_message());
+            assert!(message.to_str().unwrap().contains("not-a-topic"));
+
+            sparkscan_client_free(client);
+        }
+    }
+}
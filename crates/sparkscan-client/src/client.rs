@@ -102,6 +102,9 @@ where
     }
 }
 
+/// Header carrying the identifier used to correlate a request with server-side logs.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
 /// Information about an operation, consumed by hook implementations.
 pub struct OperationInfo {
     /// The corresponding operationId from the source OpenAPI document.
@@ -142,24 +145,38 @@ where
         request: reqwest::Request,
         info: &OperationInfo,
     ) -> reqwest::Result<reqwest::Response> {
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "middleware")] {
-                match self.client().execute(request).await {
-                    Ok(response) => Ok(response),
-                    Err(reqwest_middleware::Error::Reqwest(req_err)) => Err(req_err),
-                    // TODO: Simplifiy
-                    Err(reqwest_middleware::Error::Middleware(_err)) => {
-                        // Convert middleware error to a reqwest error by attempting a request that will fail
-                        let client = reqwest::Client::new();
-                        match client.get("http://127.0.0.1:0").send().await {
-                            Err(e) => Err(e),
-                            Ok(_) => unreachable!(),
-                        }
+        execute_request::<Self, Inner>(self, request).await
+    }
+}
+
+/// Execute a request with the underlying client of `client`.
+///
+/// This is the default [`ClientHooks::exec`] implementation, exposed so that
+/// overrides can wrap it.
+pub async fn execute_request<C, Inner>(
+    client: &C,
+    request: reqwest::Request,
+) -> reqwest::Result<reqwest::Response>
+where
+    C: ClientInfo<Inner> + ?Sized,
+{
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "middleware")] {
+            match client.client().execute(request).await {
+                Ok(response) => Ok(response),
+                Err(reqwest_middleware::Error::Reqwest(req_err)) => Err(req_err),
+                // TODO: Simplifiy
+                Err(reqwest_middleware::Error::Middleware(_err)) => {
+                    // Convert middleware error to a reqwest error by attempting a request that will fail
+                    let client = reqwest::Client::new();
+                    match client.get("http://127.0.0.1:0").send().await {
+                        Err(e) => Err(e),
+                        Ok(_) => unreachable!(),
                     }
                 }
-            } else {
-                self.client().execute(request).await
             }
+        } else {
+            client.client().execute(request).await
         }
     }
 }
@@ -181,8 +198,10 @@ impl<T: DeserializeOwned> ResponseValue<T> {
         let status = response.status();
         let headers = response.headers().clone();
         let full = response.bytes().await.map_err(Error::ResponseBodyError)?;
-        let inner =
-            serde_json::from_slice(&full).map_err(|e| Error::InvalidResponsePayload(full, e))?;
+        let inner = match decode_body(&full) {
+            Ok(inner) => inner,
+            Err(e) => return Err(Error::InvalidResponsePayload(full, e)),
+        };
 
         Ok(Self {
             inner,
@@ -269,6 +288,13 @@ impl<T> ResponseValue<T> {
         &self.headers
     }
 
+    /// Gets the value of the `x-request-id` header, if present.
+    ///
+    /// Quote this identifier when reporting a problem with a specific call.
+    pub fn request_id(&self) -> Option<&str> {
+        self.headers.get(REQUEST_ID_HEADER)?.to_str().ok()
+    }
+
     /// Gets the parsed value of the Content-Length header, if present and
     /// valid.
     pub fn content_length(&self) -> Option<u64> {
@@ -332,6 +358,91 @@ impl<T: std::fmt::Debug> std::fmt::Debug for ResponseValue<T> {
     }
 }
 
+/// Maximum number of body bytes included when displaying a decode error.
+pub const BODY_SNIPPET_LEN: usize = 512;
+
+/// Failure to deserialize a response body into the generated type.
+///
+/// Records the path of the offending field (e.g. `data[3].balance`) next to
+/// the serde error, which is usually enough to pinpoint schema drift.
+#[derive(Debug)]
+pub struct DecodeError {
+    path: String,
+    inner: serde_json::Error,
+}
+
+impl DecodeError {
+    /// Path of the field that failed to deserialize (`.` for the root).
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The underlying serde error.
+    pub fn inner(&self) -> &serde_json::Error {
+        &self.inner
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for DecodeError {
+    fn from(e: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        Self {
+            path: e.path().to_string(),
+            inner: e.into_inner(),
+        }
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at `{}`: {}", self.path, self.inner)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+fn decode_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeError> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let value = serde_path_to_error::deserialize(&mut deserializer)?;
+    // Reject trailing characters like `serde_json::from_slice` does
+    deserializer.end().map_err(|inner| DecodeError {
+        path: ".".to_string(),
+        inner,
+    })?;
+    Ok(value)
+}
+
+/// Read a response as untyped JSON, whatever its status code.
+///
+/// Backs the `send_raw` builder methods; an empty body is returned as
+/// `Value::Null`.
+#[doc(hidden)]
+pub async fn raw_json_response<E>(
+    response: reqwest::Response,
+) -> Result<(reqwest::StatusCode, serde_json::Value), Error<E>> {
+    let status = response.status();
+    let full = response.bytes().await.map_err(Error::ResponseBodyError)?;
+    if full.is_empty() {
+        return Ok((status, serde_json::Value::Null));
+    }
+    match decode_body(&full) {
+        Ok(value) => Ok((status, value)),
+        Err(e) => Err(Error::InvalidResponsePayload(full, e)),
+    }
+}
+
+/// Get the start of a response body for error messages, lossily decoded as
+/// UTF-8 and cut after [`BODY_SNIPPET_LEN`] bytes.
+pub fn body_snippet(body: &[u8]) -> String {
+    if body.len() <= BODY_SNIPPET_LEN {
+        return String::from_utf8_lossy(body).into_owned();
+    }
+    format!(
+        "{}... ({} bytes total)",
+        String::from_utf8_lossy(&body[..BODY_SNIPPET_LEN]),
+        body.len()
+    )
+}
+
 /// Error produced by generated client methods.
 ///
 /// The type parameter may be a struct if there's a single expected error type
@@ -354,7 +465,9 @@ pub enum Error<E = ()> {
     ResponseBodyError(reqwest::Error),
 
     /// An expected response code whose deserialization failed.
-    InvalidResponsePayload(Bytes, serde_json::Error),
+    ///
+    /// Holds the full response body and the path of the offending field.
+    InvalidResponsePayload(Bytes, DecodeError),
 
     /// A response not listed in the API description. This may represent a
     /// success or failure response; check `status().is_success()`.
@@ -379,6 +492,17 @@ impl<E> Error<E> {
         }
     }
 
+    /// Returns the `x-request-id` header, if the error was generated from a
+    /// response carrying one.
+    pub fn request_id(&self) -> Option<&str> {
+        let headers = match self {
+            Error::ErrorResponse(rv) => rv.headers(),
+            Error::UnexpectedResponse(r) => r.headers(),
+            _ => return None,
+        };
+        headers.get(REQUEST_ID_HEADER)?.to_str().ok()
+    }
+
     /// Converts this error into one without a typed body.
     ///
     /// This is useful for unified error handling with APIs that distinguish
@@ -446,7 +570,12 @@ where
                 write!(f, "Invalid Response Body Bytes: {}", e)?;
             }
             Error::InvalidResponsePayload(b, e) => {
-                write!(f, "Invalid Response Payload ({:?}): {}", b, e)?;
+                write!(
+                    f,
+                    "Invalid Response Payload {}; body: {}",
+                    e,
+                    body_snippet(b)
+                )?;
             }
             Error::UnexpectedResponse(r) => {
                 write!(f, "Unexpected Response: {:?}", r)?;
@@ -541,6 +670,7 @@ pub fn encode_path(pc: &str) -> String {
 
 #[doc(hidden)]
 pub trait RequestBuilderExt<E> {
+    #[allow(clippy::result_large_err)]
     fn form_urlencoded<T: Serialize + ?Sized>(self, body: &T) -> Result<RequestBuilder, Error<E>>;
 }
 
@@ -0,0 +1,79 @@
+/// Error raised to the foreign language.
+#[derive(Debug, uniffi::Error)]
+pub enum SparkScanError {
+    /// An argument could not be parsed, e.g. a malformed Spark address
+    InvalidInput {
+        /// Description of the rejected input
+        message: String,
+    },
+    /// A REST request failed or returned an error status
+    Api {
+        /// HTTP status code, when a response was received
+        status: Option<u16>,
+        /// Description of the failure
+        message: String,
+    },
+    /// The WebSocket connection or subscription failed
+    WebSocket {
+        /// Description of the failure
+        message: String,
+    },
+}
+
+impl std::fmt::Display for SparkScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SparkScanError::InvalidInput { message } => write!(f, "Invalid input: {}", message),
+            SparkScanError::Api {
+                status: Some(status),
+                message,
+            } => write!(f, "API error ({}): {}", status, message),
+            SparkScanError::Api {
+                status: None,
+                message,
+            } => write!(f, "API error: {}", message),
+            SparkScanError::WebSocket { message } => write!(f, "WebSocket error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SparkScanError {}
+
+impl From<sparkscan::ApiError> for SparkScanError {
+    fn from(e: sparkscan::ApiError) -> Self {
+        SparkScanError::Api {
+            status: e.status().map(|status| status.as_u16()),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<sparkscan_types::ParseIdError> for SparkScanError {
+    fn from(e: sparkscan_types::ParseIdError) -> Self {
+        SparkScanError::InvalidInput {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<sparkscan_ws::SparkScanWsError> for SparkScanError {
+    fn from(e: sparkscan_ws::SparkScanWsError) -> Self {
+        SparkScanError::WebSocket {
+            message: e.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        let err = SparkScanError::from("sp1bad".parse::<sparkscan::SparkAddress>().unwrap_err());
+        assert!(matches!(err, SparkScanError::InvalidInput { .. }));
+
+        let err = SparkScanError::from(sparkscan::ApiError::InvalidRequest("bad limit".into()));
+        assert_eq!(err.to_string(), "API error: Invalid Request: bad limit");
+    }
+}
@@ -0,0 +1,273 @@
+use sparkscan::{Client, SparkAddress, SparkScanApi, TokenIdentifier, presets, types};
+
+use crate::{Network, SparkScanError};
+
+/// Clamp an API integer to the 64 bits supported by every binding language.
+fn saturating_i64(value: i128) -> i64 {
+    i64::try_from(value).unwrap_or(if value < 0 { i64::MIN } else { i64::MAX })
+}
+
+/// Summary of a Spark address.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct AddressSummary {
+    /// Spark address
+    pub spark_address: String,
+    /// Identity public key of the address
+    pub public_key: String,
+    /// Soft (spendable) bitcoin balance in satoshis
+    pub soft_balance_sats: i64,
+    /// Hard (settled) bitcoin balance in satoshis
+    pub hard_balance_sats: i64,
+    /// Value of bitcoin and token holdings in USD
+    pub total_value_usd: f64,
+    /// Number of transactions involving the address
+    pub transaction_count: i64,
+    /// Number of tokens held
+    pub token_count: i64,
+}
+
+impl From<types::AddressSummaryResponse> for AddressSummary {
+    fn from(summary: types::AddressSummaryResponse) -> Self {
+        Self {
+            spark_address: summary.spark_address,
+            public_key: summary.public_key,
+            soft_balance_sats: saturating_i64(summary.balance.btc_soft_balance_sats),
+            hard_balance_sats: saturating_i64(summary.balance.btc_hard_balance_sats),
+            total_value_usd: summary.total_value_usd,
+            transaction_count: saturating_i64(summary.transaction_count),
+            token_count: saturating_i64(summary.token_count),
+        }
+    }
+}
+
+/// Metadata and market data of a token.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct TokenDetails {
+    /// Token identifier in hex form
+    pub token_identifier: String,
+    /// Token identifier in bech32 form
+    pub token_address: String,
+    /// Token name
+    pub name: String,
+    /// Token ticker
+    pub ticker: String,
+    /// Number of decimals of the token amounts
+    pub decimals: i64,
+    /// Public key of the issuer
+    pub issuer_public_key: String,
+    /// Number of holders
+    pub holder_count: i64,
+    /// Price of one token in USD
+    pub price_usd: f64,
+    /// Total supply in the smallest unit, as a decimal string
+    pub total_supply: String,
+    /// Market capitalization in USD
+    pub market_cap_usd: f64,
+    /// Trading volume over the last 24 hours in USD
+    pub volume_24h_usd: f64,
+}
+
+impl From<types::TokenDetailsResponse> for TokenDetails {
+    fn from(details: types::TokenDetailsResponse) -> Self {
+        Self {
+            token_identifier: details.metadata.token_identifier,
+            token_address: details.metadata.token_address,
+            name: details.metadata.name,
+            ticker: details.metadata.ticker,
+            decimals: saturating_i64(details.metadata.decimals),
+            issuer_public_key: details.metadata.issuer_public_key,
+            holder_count: saturating_i64(details.metadata.holder_count),
+            price_usd: details.metadata.price_usd,
+            total_supply: details.total_supply.to_string(),
+            market_cap_usd: details.market_cap_usd,
+            volume_24h_usd: details.volume24h_usd,
+        }
+    }
+}
+
+/// Holder of a token.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct TokenHolder {
+    /// Spark address of the holder
+    pub address: String,
+    /// Identity public key of the holder
+    pub pubkey: String,
+    /// Balance in the smallest unit, as a decimal string
+    pub balance: String,
+    /// Value of the balance in USD
+    pub value_usd: f64,
+    /// Share of the supply held, in percent
+    pub percentage: f64,
+}
+
+impl From<types::TokenHolder> for TokenHolder {
+    fn from(holder: types::TokenHolder) -> Self {
+        Self {
+            address: holder.address,
+            pubkey: holder.pubkey,
+            balance: holder.balance.to_string(),
+            value_usd: holder.value_usd,
+            percentage: holder.percentage,
+        }
+    }
+}
+
+/// Network-wide statistics.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct NetworkStats {
+    /// Total value locked in satoshis
+    pub total_value_locked_sats: i64,
+    /// Total value locked in USD
+    pub total_value_locked_usd: f64,
+    /// Number of active accounts
+    pub active_accounts: i64,
+    /// Number of transactions over the last 24 hours
+    pub transactions_24h: i64,
+    /// Current bitcoin price in USD
+    pub current_btc_price_usd: f64,
+}
+
+impl From<types::NetworkStats> for NetworkStats {
+    fn from(stats: types::NetworkStats) -> Self {
+        Self {
+            total_value_locked_sats: saturating_i64(stats.total_value_locked_sats),
+            total_value_locked_usd: stats.total_value_locked_usd,
+            active_accounts: saturating_i64(stats.active_accounts),
+            transactions_24h: saturating_i64(stats.transactions24h),
+            current_btc_price_usd: stats.current_btc_price_usd,
+        }
+    }
+}
+
+/// Transaction from the latest network activity.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Transaction {
+    /// Transaction id
+    pub id: String,
+    /// Transaction type, e.g. `spark_transfer`
+    pub kind: String,
+    /// Transaction status, e.g. `confirmed`
+    pub status: String,
+    /// Creation time in RFC 3339 format
+    pub created_at: Option<String>,
+    /// Bitcoin amount in satoshis
+    pub amount_sats: Option<i64>,
+    /// Token amount in the smallest unit, as a decimal string
+    pub token_amount: Option<String>,
+    /// Bitcoin transaction id, for deposits and withdrawals
+    pub bitcoin_txid: Option<String>,
+    /// Value in USD
+    pub value_usd: f64,
+}
+
+impl From<types::LatestNetworkTransactionItem> for Transaction {
+    fn from(tx: types::LatestNetworkTransactionItem) -> Self {
+        Self {
+            id: tx.id,
+            kind: tx.type_.to_string(),
+            status: tx.status.to_string(),
+            created_at: tx.created_at.map(|created_at| created_at.to_rfc3339()),
+            amount_sats: tx.amount_sats.map(saturating_i64),
+            token_amount: tx.token_amount.map(|amount| amount.to_string()),
+            bitcoin_txid: tx.bitcoin_txid,
+            value_usd: tx.value_usd,
+        }
+    }
+}
+
+/// SparkScan REST API bound to a network.
+#[derive(uniffi::Object)]
+pub struct SparkScanClient {
+    api: SparkScanApi,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl SparkScanClient {
+    /// Create a client for `network`, authenticated with `api_key` when given.
+    ///
+    /// Fails for [`Network::Unknown`], which only describes networks reported by the server.
+    #[uniffi::constructor]
+    pub fn new(api_key: Option<String>, network: Network) -> Result<Self, SparkScanError> {
+        let baseurl = match network {
+            Network::Regtest => presets::REGTEST_BASE_URL,
+            _ => presets::MAINNET_BASE_URL,
+        };
+        let client = match api_key {
+            Some(api_key) => Client::new_with_api_key(baseurl, &api_key),
+            None => Client::new(baseurl),
+        };
+        Ok(Self {
+            api: SparkScanApi::new(client, network.try_into()?),
+        })
+    }
+
+    /// Get the network requests are issued against.
+    pub fn network(&self) -> Network {
+        self.api.network().into()
+    }
+
+    /// Get the summary of a Spark address.
+    pub async fn address_summary(&self, address: String) -> Result<AddressSummary, SparkScanError> {
+        let address: SparkAddress = address.parse()?;
+        Ok(self.api.address(&address).summary().await?.into())
+    }
+
+    /// Get the metadata and market data of a token.
+    pub async fn token_details(&self, identifier: String) -> Result<TokenDetails, SparkScanError> {
+        let identifier: TokenIdentifier = identifier.parse()?;
+        Ok(self.api.token(&identifier).details().await?.into())
+    }
+
+    /// Get a page of the holders of a token.
+    pub async fn token_holders(
+        &self,
+        identifier: String,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<TokenHolder>, SparkScanError> {
+        let identifier: TokenIdentifier = identifier.parse()?;
+        let page = self
+            .api
+            .token(&identifier)
+            .holders_page(offset, limit)
+            .await?;
+        Ok(page.items.into_iter().map(TokenHolder::from).collect())
+    }
+
+    /// Get network-wide statistics.
+    pub async fn network_stats(&self) -> Result<NetworkStats, SparkScanError> {
+        Ok(self.api.stats().summary().await?.into())
+    }
+
+    /// Get a page of the most recent transactions on the network.
+    pub async fn latest_transactions(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Transaction>, SparkScanError> {
+        let page = self.api.latest_transactions_page(offset, limit).await?;
+        Ok(page.items.into_iter().map(Transaction::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saturating_i64() {
+        assert_eq!(saturating_i64(42), 42);
+        assert_eq!(saturating_i64(i128::MAX), i64::MAX);
+        assert_eq!(saturating_i64(i128::MIN), i64::MIN);
+    }
+
+    #[test]
+    fn test_client_network() {
+        let client = SparkScanClient::new(None, Network::Regtest).unwrap();
+        assert_eq!(client.network(), Network::Regtest);
+        assert!(matches!(
+            SparkScanClient::new(None, Network::Unknown),
+            Err(SparkScanError::InvalidInput { .. })
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use sparkscan_ws::{SparkScanMessage, SparkScanSubscription, SparkScanWsClient, Topic};
+
+use crate::{Network, SparkScanError};
+
+/// Message received on a subscription.
+///
+/// Amounts and prices are decimal strings, as sent by the feed; times are in RFC 3339 format.
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum WsMessage {
+    /// Bitcoin balance update of an address
+    Balance {
+        network: Network,
+        address: String,
+        soft_balance: String,
+        hard_balance: String,
+        processed_at: String,
+    },
+    /// Token balance update of an address
+    TokenBalance {
+        network: Network,
+        address: String,
+        token_address: String,
+        balance: String,
+        processed_at: String,
+    },
+    /// Token price update
+    TokenPrice {
+        network: Network,
+        address: String,
+        price_sats: String,
+        processed_at: String,
+    },
+    /// Token metadata update
+    Token {
+        network: Network,
+        address: String,
+        name: String,
+        ticker: String,
+        decimals: i64,
+        issuer: String,
+        holders: i64,
+    },
+    /// Transaction update
+    Transaction {
+        network: Network,
+        id: String,
+        kind: String,
+        status: String,
+        amount_sats: Option<String>,
+        token_amount: Option<String>,
+        token_address: Option<String>,
+        from_identifier: Option<String>,
+        to_identifier: Option<String>,
+        bitcoin_txid: Option<String>,
+        processed_at: String,
+    },
+}
+
+impl From<SparkScanMessage> for WsMessage {
+    fn from(message: SparkScanMessage) -> Self {
+        let network = message.spark_network().into();
+        match message {
+            SparkScanMessage::Balance(balance) => WsMessage::Balance {
+                network,
+                address: balance.address.to_string(),
+                soft_balance: balance.soft_balance,
+                hard_balance: balance.hard_balance,
+                processed_at: balance.processed_at.to_rfc3339(),
+            },
+            SparkScanMessage::TokenBalance(balance) => WsMessage::TokenBalance {
+                network,
+                address: balance.address.to_string(),
+                token_address: balance.token_address.to_string(),
+                balance: balance.balance,
+                processed_at: balance.processed_at.to_rfc3339(),
+            },
+            SparkScanMessage::TokenPrice(price) => WsMessage::TokenPrice {
+                network,
+                address: price.address.to_string(),
+                price_sats: price.price_sats.to_string(),
+                processed_at: price.processed_at.to_rfc3339(),
+            },
+            SparkScanMessage::Token(token) => WsMessage::Token {
+                network,
+                address: token.address.to_string(),
+                name: token.name,
+                ticker: token.ticker,
+                decimals: token.decimals,
+                issuer: token.issuer.to_string(),
+                holders: token.holders,
+            },
+            SparkScanMessage::Transaction(tx) => WsMessage::Transaction {
+                network,
+                id: tx.id,
+                kind: tx.type_.to_string(),
+                status: tx.status.to_string(),
+                amount_sats: tx.amount_sats,
+                token_amount: tx.token_amount,
+                token_address: tx.token_address,
+                from_identifier: tx.from_identifier,
+                to_identifier: tx.to_identifier,
+                bitcoin_txid: tx.bitcoin_txid,
+                processed_at: tx.processed_at.to_rfc3339(),
+            },
+        }
+    }
+}
+
+/// Receiver of subscription events, implemented in the foreign language.
+///
+/// Callbacks run on the client's background threads and must not block.
+#[uniffi::export(callback_interface)]
+pub trait SubscriptionListener: Send + Sync {
+    /// Called for every message received on the subscription.
+    fn on_message(&self, message: WsMessage);
+
+    /// Called when the subscription reports an error.
+    fn on_error(&self, error: String);
+}
+
+/// Connection to the SparkScan WebSocket feed, reconnecting automatically.
+#[derive(uniffi::Object)]
+pub struct SparkScanStream {
+    client: SparkScanWsClient,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl SparkScanStream {
+    /// Create a client for `url`, or the mainnet feed when omitted.
+    #[uniffi::constructor]
+    pub fn new(url: Option<String>) -> Self {
+        let url = url.unwrap_or_else(|| sparkscan_ws::DEFAULT_MAINNET_URL.to_string());
+        Self {
+            client: SparkScanWsClient::new(url),
+        }
+    }
+
+    /// Open the connection.
+    pub async fn connect(&self) -> Result<(), SparkScanError> {
+        Ok(self.client.connect().await?)
+    }
+
+    /// Subscribe to `topic` (e.g. `balances` or `/balance/address/sp1...`) and deliver its
+    /// messages to `listener`.
+    pub async fn subscribe(
+        &self,
+        topic: String,
+        listener: Box<dyn SubscriptionListener>,
+    ) -> Result<Arc<Subscription>, SparkScanError> {
+        let topic = Topic::try_from(topic.as_str())?;
+        let subscription = self.client.subscribe(topic).await?;
+
+        let listener: Arc<dyn SubscriptionListener> = Arc::from(listener);
+        let on_message = Arc::clone(&listener);
+        subscription.on_message(move |message| on_message.on_message(message.into()));
+        subscription.on_error(move |error| listener.on_error(error));
+        subscription.subscribe();
+
+        Ok(Arc::new(Subscription { subscription }))
+    }
+}
+
+/// Active subscription, returned by [`SparkScanStream::subscribe`].
+#[derive(uniffi::Object)]
+pub struct Subscription {
+    subscription: SparkScanSubscription,
+}
+
+#[uniffi::export]
+impl Subscription {
+    /// Stop delivering messages to the listener.
+    pub fn unsubscribe(&self) {
+        self.subscription.unsubscribe();
+    }
+}
@@ -0,0 +1,203 @@
+//! Node.js bindings for the SparkScan WebSocket client, built with [napi-rs](https://napi.rs).
+//!
+//! Messages are parsed and reconnections handled by `sparkscan-ws`; JavaScript only receives
+//! the parsed messages as plain objects:
+//!
+//! ```js
+//! const { SparkScanWs } = require('@flashnet/sparkscan')
+//!
+//! const ws = new SparkScanWs()
+//! ws.on('error', (error) => console.error(error))
+//! await ws.connect()
+//!
+//! const subscription = await ws.subscribe('/balance/address/sp1pgss...')
+//! subscription.on('message', (message) => console.log(message.type, message.data))
+//! ```
+//!
+//! Each listener has a bounded queue (`maxQueueSize`, 1024 by default). When the event loop falls
+//! behind, new messages are dropped instead of growing memory without bound, and counted in
+//! `subscription.droppedMessages`.
+//!
+//! Build the addon with `npm run build` in this directory.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use napi::bindgen_prelude::ToNapiValue;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Error, JsFunction, Result, Status};
+use napi_derive::napi;
+use sparkscan_ws::{SparkScanSubscription, SparkScanWsClient, Topic};
+
+/// Default number of queued calls per listener before messages are dropped.
+const DEFAULT_MAX_QUEUE_SIZE: u32 = 1024;
+
+type Listener<T> = ThreadsafeFunction<T, ErrorStrategy::Fatal>;
+
+fn threadsafe_listener<T>(callback: &JsFunction, max_queue_size: u32) -> Result<Listener<T>>
+where
+    T: ToNapiValue + 'static,
+{
+    callback.create_threadsafe_function(max_queue_size as usize, |ctx: ThreadSafeCallContext<T>| {
+        Ok(vec![ctx.value])
+    })
+}
+
+fn unknown_event(event: &str) -> Error {
+    Error::new(Status::InvalidArg, format!("unknown event {:?}", event))
+}
+
+/// Options of [`SparkScanWs`].
+#[napi(object)]
+pub struct SparkScanWsOptions {
+    /// WebSocket URL, the mainnet feed by default
+    pub url: Option<String>,
+    /// Queued messages per listener before new ones are dropped
+    pub max_queue_size: Option<u32>,
+}
+
+/// Connection to the SparkScan WebSocket feed, reconnecting automatically.
+#[napi]
+pub struct SparkScanWs {
+    client: SparkScanWsClient,
+    max_queue_size: u32,
+}
+
+#[napi]
+impl SparkScanWs {
+    /// Create a client; call `connect` to open the connection.
+    #[napi(constructor)]
+    pub fn new(options: Option<SparkScanWsOptions>) -> Self {
+        let options = options.unwrap_or(SparkScanWsOptions {
+            url: None,
+            max_queue_size: None,
+        });
+        let url = options
+            .url
+            .unwrap_or_else(|| sparkscan_ws::DEFAULT_MAINNET_URL.to_string());
+        Self {
+            client: SparkScanWsClient::new(url),
+            max_queue_size: options.max_queue_size.unwrap_or(DEFAULT_MAX_QUEUE_SIZE),
+        }
+    }
+
+    /// Register a listener for `connected`, `disconnected` or `error` events.
+    #[napi(
+        ts_args_type = "event: 'connected' | 'disconnected' | 'error', callback: (error?: string) => void"
+    )]
+    pub fn on(&self, event: String, callback: JsFunction) -> Result<()> {
+        match event.as_str() {
+            "connected" => {
+                let listener: Listener<()> = threadsafe_listener(&callback, self.max_queue_size)?;
+                self.client.on_connected(move || {
+                    listener.call((), ThreadsafeFunctionCallMode::NonBlocking);
+                });
+            }
+            "disconnected" => {
+                let listener: Listener<()> = threadsafe_listener(&callback, self.max_queue_size)?;
+                self.client.on_disconnected(move || {
+                    listener.call((), ThreadsafeFunctionCallMode::NonBlocking);
+                });
+            }
+            "error" => {
+                let listener: Listener<String> =
+                    threadsafe_listener(&callback, self.max_queue_size)?;
+                self.client.on_error(move |error| {
+                    listener.call(error, ThreadsafeFunctionCallMode::NonBlocking);
+                });
+            }
+            _ => return Err(unknown_event(&event)),
+        }
+        Ok(())
+    }
+
+    /// Open the connection.
+    #[napi]
+    pub async fn connect(&self) -> Result<()> {
+        self.client
+            .connect()
+            .await
+            .map_err(|e| Error::from_reason(e.to_string()))
+    }
+
+    /// Subscribe to `topic`, e.g. `balances` or `/balance/address/sp1...`.
+    ///
+    /// Messages are delivered once a `message` listener is registered.
+    #[napi]
+    pub async fn subscribe(&self, topic: String) -> Result<Subscription> {
+        let topic = Topic::try_from(topic.as_str())
+            .map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?;
+        let subscription = self
+            .client
+            .subscribe(topic)
+            .await
+            .map_err(|e| Error::from_reason(e.to_string()))?;
+        Ok(Subscription {
+            subscription,
+            max_queue_size: self.max_queue_size,
+            dropped_messages: Arc::new(AtomicU32::new(0)),
+        })
+    }
+}
+
+/// Subscription to a topic of the feed.
+#[napi]
+pub struct Subscription {
+    subscription: SparkScanSubscription,
+    max_queue_size: u32,
+    dropped_messages: Arc<AtomicU32>,
+}
+
+#[napi]
+impl Subscription {
+    /// Register a listener for `message` or `error` events and start the subscription.
+    ///
+    /// Messages are objects of the form `{ type, data }`, e.g.
+    /// `{ type: 'balance', data: { address, network, soft_balance, ... } }`.
+    #[napi(
+        ts_args_type = "event: 'message' | 'error', callback: (value: { type: string, data: any } | string) => void"
+    )]
+    pub fn on(&self, event: String, callback: JsFunction) -> Result<()> {
+        match event.as_str() {
+            "message" => {
+                let listener: Listener<serde_json::Value> =
+                    threadsafe_listener(&callback, self.max_queue_size)?;
+                let dropped_messages = Arc::clone(&self.dropped_messages);
+                self.subscription.on_message(move |message| {
+                    let Ok(message) = serde_json::to_value(&message) else {
+                        return;
+                    };
+                    if listener.call(message, ThreadsafeFunctionCallMode::NonBlocking)
+                        == Status::QueueFull
+                    {
+                        dropped_messages.fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+                self.subscription.subscribe();
+            }
+            "error" => {
+                let listener: Listener<String> =
+                    threadsafe_listener(&callback, self.max_queue_size)?;
+                self.subscription.on_error(move |error| {
+                    listener.call(error, ThreadsafeFunctionCallMode::NonBlocking);
+                });
+            }
+            _ => return Err(unknown_event(&event)),
+        }
+        Ok(())
+    }
+
+    /// Number of messages dropped because a listener's queue was full.
+    #[napi(getter)]
+    pub fn dropped_messages(&self) -> u32 {
+        self.dropped_messages.load(Ordering::Relaxed)
+    }
+
+    /// Stop receiving messages.
+    #[napi]
+    pub fn unsubscribe(&self) {
+        self.subscription.unsubscribe();
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use sparkscan_sdk::Network;
+use sparkscan_sdk::ws::redaction::{self, Redaction};
+
+use crate::config::AlertConfig;
+use crate::events::{Event, Log};
+use crate::outbox::Outbox;
+use crate::rules::Level;
+
+/// Alert fields masked with `redact`, on top of the defaults of [`Redaction`].
+const REDACTED_FIELDS: [&str; 4] = ["subject", "value", "minimum", "maximum"];
+
+/// Get the redaction enabled by `redact`, covering alerts as well as messages.
+pub fn redaction() -> Redaction {
+    REDACTED_FIELDS
+        .iter()
+        .fold(Redaction::new(), |redaction, field| {
+            redaction.with_field(*field)
+        })
+}
+
+/// Threshold transition of a watched value.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    /// `balance` or `token_price`
+    pub rule: &'static str,
+    /// Address or token identifier
+    pub subject: String,
+    pub label: Option<String>,
+    pub network: Network,
+    pub value: f64,
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+    pub previous: Level,
+    pub level: Level,
+}
+
+/// Delivers alerts to the configured sinks.
+pub struct Alerter {
+    stdout: bool,
+    webhooks: Vec<String>,
+    http: reqwest::Client,
+    outbox: Option<Arc<Outbox>>,
+    log: Log,
+}
+
+impl Alerter {
+    /// Create the alerter, starting the delivery of the outbox if one is configured.
+    pub fn new(config: &AlertConfig, log: Log) -> Result<Self, String> {
+        let http = reqwest::Client::new();
+        let outbox = match &config.outbox {
+            Some(dir) => {
+                let outbox = Outbox::open(dir)
+                    .map_err(|e| format!("failed to open the outbox {}: {}", dir.display(), e))?;
+                let outbox = Arc::new(outbox);
+                tokio::spawn(Arc::clone(&outbox).flush(http.clone(), log));
+                Some(outbox)
+            }
+            None => None,
+        };
+        Ok(Self {
+            stdout: config.stdout,
+            webhooks: config.webhooks.clone(),
+            http,
+            outbox,
+            log,
+        })
+    }
+
+    /// Deliver `alert` to every sink, reporting delivery failures on stderr.
+    ///
+    /// With an outbox, webhook deliveries are persisted and left to the outbox, which retries
+    /// them until they succeed.
+    pub async fn send(&self, alert: &Alert) {
+        metrics::counter!(
+            "sparkscan_monitor_alerts_total",
+            "rule" => alert.rule,
+            "level" => level_label(alert.level),
+        )
+        .increment(1);
+
+        let payload = payload(alert, redaction::enabled().as_ref());
+        if self.stdout {
+            println!("{}", payload);
+        }
+
+        for webhook in &self.webhooks {
+            if let Some(outbox) = &self.outbox {
+                match outbox.push(webhook, &payload) {
+                    Ok(()) => continue,
+                    // Delivered right away instead, without retries
+                    Err(e) => self.log.emit(&Event::AlertFailed {
+                        sink: "outbox",
+                        error: e.to_string(),
+                    }),
+                }
+            }
+            let result = self
+                .http
+                .post(webhook)
+                .json(&payload)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                self.log.emit(&Event::AlertFailed {
+                    sink: webhook,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Get `alert` as JSON, masked with `redaction` if any.
+fn payload(alert: &Alert, redaction: Option<&Redaction>) -> serde_json::Value {
+    let mut payload = serde_json::to_value(alert).expect("alerts serialize to JSON");
+    if let Some(redaction) = redaction {
+        redaction.redact_json(&mut payload);
+    }
+    payload
+}
+
+fn level_label(level: Level) -> &'static str {
+    match level {
+        Level::Normal => "normal",
+        Level::Below => "below",
+        Level::Above => "above",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacted_payload() {
+        let alert = Alert {
+            rule: "balance",
+            subject: "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k"
+                .to_string(),
+            label: Some("treasury".to_string()),
+            network: Network::Mainnet,
+            value: 5.0,
+            minimum: Some(10.0),
+            maximum: None,
+            previous: Level::Normal,
+            level: Level::Below,
+        };
+
+        let plain = payload(&alert, None);
+        assert_eq!(plain["subject"], alert.subject.as_str());
+        assert_eq!(plain["value"], 5.0);
+
+        let redacted = payload(&alert, Some(&redaction()));
+        assert_eq!(redacted["subject"], redaction::MASK);
+        assert_eq!(redacted["value"], redaction::MASK);
+        assert_eq!(redacted["minimum"], redaction::MASK);
+        assert!(redacted["maximum"].is_null());
+        assert_eq!(redacted["label"], "treasury");
+        assert_eq!(redacted["level"], "below");
+    }
+}
@@ -0,0 +1,187 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use sparkscan_sdk::{Network, Sats, SparkAddress, TokenIdentifier};
+
+/// Daemon configuration, read from YAML.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Environment variable holding the API key
+    #[serde(default = "default_api_key_env")]
+    pub api_key_env: String,
+    /// Network to monitor
+    #[serde(default)]
+    pub network: Network,
+    /// Addresses whose bitcoin balance is watched
+    #[serde(default)]
+    pub addresses: Vec<AddressRule>,
+    /// Tokens whose price is watched
+    #[serde(default)]
+    pub tokens: Vec<TokenRule>,
+    /// Where alerts are delivered
+    #[serde(default)]
+    pub alerts: AlertConfig,
+    /// Evaluate at most one update per address or token every this many seconds, using the
+    /// latest one, to avoid alert storms on busy addresses
+    #[serde(default)]
+    pub debounce_secs: u64,
+    /// Mask addresses and amounts in alerts and diagnostics, leaving the rule labels to tell
+    /// alerts apart
+    #[serde(default)]
+    pub redact: bool,
+}
+
+/// Balance thresholds of an address.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddressRule {
+    pub address: SparkAddress,
+    pub label: Option<String>,
+    /// Alert when the soft balance falls below this amount
+    pub min_balance_sats: Option<Sats>,
+    /// Alert when the soft balance rises above this amount
+    pub max_balance_sats: Option<Sats>,
+}
+
+/// Price thresholds of a token.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenRule {
+    /// Bech32 token identifier, as used by the price feed
+    pub identifier: TokenIdentifier,
+    pub label: Option<String>,
+    /// Alert when the price falls below this many satoshis per token
+    pub min_price_sats: Option<f64>,
+    /// Alert when the price rises above this many satoshis per token
+    pub max_price_sats: Option<f64>,
+}
+
+/// Alert sinks.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertConfig {
+    /// Print alerts to stdout as JSON lines
+    #[serde(default = "default_true")]
+    pub stdout: bool,
+    /// URLs receiving each alert as a JSON `POST`
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+    /// Directory persisting alerts until the webhooks accept them, so that none is lost while
+    /// a webhook is down or the daemon restarts
+    pub outbox: Option<PathBuf>,
+    /// Address of the Prometheus scrape endpoint
+    pub prometheus: Option<SocketAddr>,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            stdout: true,
+            webhooks: Vec::new(),
+            outbox: None,
+            prometheus: None,
+        }
+    }
+}
+
+fn default_api_key_env() -> String {
+    "SPARKSCAN_API_KEY".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Config {
+    /// Read and validate the configuration at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let config: Config = serde_yaml::from_str(&contents)
+            .map_err(|e| format!("invalid configuration {}: {}", path.display(), e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.addresses.is_empty() && self.tokens.is_empty() {
+            return Err("nothing to monitor: configure addresses or tokens".to_string());
+        }
+        for rule in &self.addresses {
+            let (min, max) = (rule.min_balance_sats, rule.max_balance_sats);
+            if min.zip(max).is_some_and(|(min, max)| min > max) {
+                return Err(format!(
+                    "{}: min_balance_sats is above max_balance_sats",
+                    rule.address
+                ));
+            }
+        }
+        for rule in &self.tokens {
+            if !rule.identifier.is_bech32() {
+                return Err(format!(
+                    "{}: token prices are published under the bech32 identifier",
+                    rule.identifier
+                ));
+            }
+            let (min, max) = (rule.min_price_sats, rule.max_price_sats);
+            if min.zip(max).is_some_and(|(min, max)| min > max) {
+                return Err(format!(
+                    "{}: min_price_sats is above max_price_sats",
+                    rule.identifier
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+network: REGTEST
+addresses:
+  - address: sprt1pgssyv42njtx
+    label: treasury
+    min_balance_sats: 100000
+tokens:
+  - identifier: btknrt1qpzry9x8gf2
+    max_price_sats: 120.5
+alerts:
+  webhooks: ["https://hooks.example.com/sparkscan"]
+  outbox: /var/lib/sparkscan-monitord/outbox
+  prometheus: 127.0.0.1:9100
+"#;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.network, Network::Regtest);
+        assert_eq!(config.api_key_env, "SPARKSCAN_API_KEY");
+        assert_eq!(config.addresses[0].min_balance_sats, Some(Sats(100_000)));
+        assert_eq!(config.tokens[0].max_price_sats, Some(120.5));
+        assert!(config.alerts.stdout);
+        assert_eq!(
+            config.alerts.outbox.as_deref(),
+            Some(Path::new("/var/lib/sparkscan-monitord/outbox"))
+        );
+        assert_eq!(config.debounce_secs, 0);
+    }
+
+    #[test]
+    fn test_reject_invalid_config() {
+        let inverted = CONFIG.replace(
+            "min_balance_sats: 100000",
+            "min_balance_sats: 100000\n    max_balance_sats: 10",
+        );
+        let config: Config = serde_yaml::from_str(&inverted).unwrap();
+        assert!(config.validate().is_err());
+
+        assert!(serde_yaml::from_str::<Config>("addresses: [{address: sp1bad}]").is_err());
+        assert!(serde_yaml::from_str::<Config>("unknown: 1").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+
+use serde::Serialize;
+use sparkscan_sdk::ws::redaction::{self, Redaction};
+
+/// Diagnostic event of the daemon, printed to stderr.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Connected,
+    Disconnected,
+    FeedError { error: &'a str },
+    SubscriptionError { topic: String, error: &'a str },
+    ReconnectFailed { error: String },
+    Watching { token: &'a str, ticker: &'a str },
+    InvalidValue { subject: &'a str, value: &'a str },
+    AlertFailed { sink: &'a str, error: String },
+    Fatal { error: &'a str },
+}
+
+impl fmt::Display for Event<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Connected => write!(f, "connected to the update feed"),
+            Event::Disconnected => write!(f, "disconnected from the update feed"),
+            Event::FeedError { error } => write!(f, "update feed error: {}", error),
+            Event::SubscriptionError { topic, error } => write!(f, "{}: {}", topic, error),
+            Event::ReconnectFailed { error } => {
+                write!(f, "failed to reconnect to the update feed: {}", error)
+            }
+            Event::Watching { ticker, .. } => write!(f, "watching the price of {}", ticker),
+            Event::InvalidValue { subject, value } => {
+                write!(f, "{}: invalid value {}", subject, value)
+            }
+            Event::AlertFailed { sink, error } => {
+                write!(f, "failed to deliver alert to {}: {}", sink, error)
+            }
+            Event::Fatal { error } => write!(f, "sparkscan-monitord: {}", error),
+        }
+    }
+}
+
+/// Prints events as text, or as JSON lines with `--json`, masked once redaction is enabled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Log {
+    pub json: bool,
+}
+
+impl Log {
+    pub fn emit(&self, event: &Event<'_>) {
+        eprintln!("{}", self.line(event, redaction::enabled().as_ref()));
+    }
+
+    fn line(&self, event: &Event<'_>, redaction: Option<&Redaction>) -> String {
+        let line = if self.json {
+            serde_json::to_string(event).unwrap_or_else(|_| event.to_string())
+        } else {
+            event.to_string()
+        };
+        match redaction {
+            Some(redaction) => redaction.redact_text(&line),
+            None => line,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_json() {
+        let event = Event::InvalidValue {
+            subject: "btkn1token",
+            value: "NaN",
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"invalid_value","subject":"btkn1token","value":"NaN"}"#
+        );
+        assert_eq!(event.to_string(), "btkn1token: invalid value NaN");
+        assert_eq!(
+            serde_json::to_string(&Event::Connected).unwrap(),
+            r#"{"event":"connected"}"#
+        );
+    }
+
+    #[test]
+    fn test_redacted_lines() {
+        let event = Event::InvalidValue {
+            subject: "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k",
+            value: "-5",
+        };
+        let redaction = crate::alerts::redaction();
+
+        let text = Log { json: false }.line(&event, Some(&redaction));
+        assert_eq!(text, "[redacted]: invalid value -5");
+        let json = Log { json: true }.line(&event, Some(&redaction));
+        assert_eq!(
+            json,
+            r#"{"event":"invalid_value","subject":"[redacted]","value":"[redacted]"}"#
+        );
+    }
+}
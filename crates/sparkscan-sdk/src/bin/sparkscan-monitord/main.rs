@@ -0,0 +1,321 @@
+//! Monitoring daemon watching address balances and token prices on SparkScan.
+//!
+//! The daemon reads a YAML configuration (see `monitord.example.yaml`), takes an initial snapshot
+//! through the REST API, then follows the WebSocket feed and alerts when a watched value crosses
+//! one of its thresholds, and again when it recovers:
+//!
+//! ```text
+//! SPARKSCAN_API_KEY=... sparkscan-monitord [--json] monitord.yaml
+//! ```
+//!
+//! Alerts are printed to stdout as JSON lines, posted to the configured webhooks and counted in
+//! the Prometheus endpoint, next to the balance and price gauges and the REST client metrics.
+//! With an `outbox` directory, alerts are persisted before they are posted and retried until
+//! the webhooks accept them, across webhook outages and restarts.
+//! Diagnostics go to stderr, as JSON lines as well with `--json`, for log shippers and `jq`.
+//! With `debounce_secs` set, bursts of updates of the same address or token are coalesced and
+//! only the latest one is evaluated.
+//! With `redact` set, addresses and amounts are masked in the alerts, wherever they are
+//! delivered, and in the diagnostics.
+
+mod alerts;
+mod config;
+mod events;
+mod outbox;
+mod rules;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use sparkscan_sdk::prelude::*;
+use sparkscan_sdk::ws::{Debouncer, redaction};
+use tokio::sync::mpsc;
+
+use crate::alerts::{Alert, Alerter};
+use crate::config::{AddressRule, Config, TokenRule};
+use crate::events::{Event, Log};
+use crate::rules::{Level, Levels};
+
+/// Configuration file read when no path is given.
+const DEFAULT_CONFIG_PATH: &str = "sparkscan-monitord.yaml";
+
+/// Interval between checks that the WebSocket connection is up.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut log = Log::default();
+    let mut path = None;
+    for arg in std::env::args_os().skip(1) {
+        if arg == "--json" {
+            log.json = true;
+        } else {
+            path.get_or_insert_with(|| PathBuf::from(arg));
+        }
+    }
+    let path = path.unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+    match run(&path, log).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            log.emit(&Event::Fatal { error: &e });
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(path: &Path, log: Log) -> Result<(), String> {
+    let config = Config::load(path)?;
+    if config.redact {
+        redaction::enable(alerts::redaction());
+    }
+    let api_key = std::env::var(&config.api_key_env)
+        .map_err(|_| format!("{} is not set", config.api_key_env))?;
+
+    if let Some(address) = config.alerts.prometheus {
+        metrics_exporter_prometheus::PrometheusBuilder::new()
+            .with_http_listener(address)
+            .install()
+            .map_err(|e| format!("failed to start the Prometheus endpoint: {}", e))?;
+        sparkscan_sdk::rest::metrics::describe_metrics();
+        describe_metrics();
+    }
+
+    let sparkscan = SparkScan::connect(&api_key)
+        .await
+        .map_err(|e| e.to_string())?
+        .with_network(config.network);
+    // The client does not expose its connection state, so track it from the events
+    let connected = Arc::new(AtomicBool::new(true));
+    let on_connected = Arc::clone(&connected);
+    sparkscan.ws().on_connected(move || {
+        on_connected.store(true, Ordering::Relaxed);
+        log.emit(&Event::Connected);
+    });
+    let on_disconnected = Arc::clone(&connected);
+    sparkscan.ws().on_disconnected(move || {
+        on_disconnected.store(false, Ordering::Relaxed);
+        log.emit(&Event::Disconnected);
+    });
+    sparkscan
+        .ws()
+        .on_error(move |error| log.emit(&Event::FeedError { error: &error }));
+
+    let alerter = Alerter::new(&config.alerts, log)?;
+    let mut monitor = Monitor {
+        network: config.network,
+        addresses: config
+            .addresses
+            .iter()
+            .map(|rule| (rule.address.to_string(), rule))
+            .collect(),
+        tokens: config
+            .tokens
+            .iter()
+            .map(|rule| (rule.identifier.to_string(), rule))
+            .collect(),
+        levels: Levels::default(),
+        log,
+    };
+
+    for rule in &config.addresses {
+        let summary = sparkscan
+            .api()
+            .address(&rule.address)
+            .summary()
+            .await
+            .map_err(|e| format!("{}: {}", rule.address, e))?;
+        let alert = Sats::try_from(summary.balance.btc_soft_balance_sats)
+            .ok()
+            .and_then(|balance| monitor.balance(rule, balance));
+        if let Some(alert) = alert {
+            alerter.send(&alert).await;
+        }
+    }
+    for rule in &config.tokens {
+        let details = sparkscan
+            .api()
+            .token(&rule.identifier)
+            .details()
+            .await
+            .map_err(|e| format!("{}: {}", rule.identifier, e))?;
+        log.emit(&Event::Watching {
+            token: rule.identifier.as_str(),
+            ticker: &details.metadata.ticker,
+        });
+    }
+
+    let (sender, mut messages) = mpsc::unbounded_channel();
+    let mut subscriptions = Vec::new();
+    let topics = config
+        .addresses
+        .iter()
+        .map(|rule| Topic::BalanceAddress(rule.address.to_string()))
+        .chain(
+            config
+                .tokens
+                .iter()
+                .map(|rule| Topic::TokenPriceIdentifier(rule.identifier.to_string())),
+        );
+    for topic in topics {
+        let subscription = sparkscan
+            .ws()
+            .subscribe(topic.clone())
+            .await
+            .map_err(|e| format!("failed to subscribe to {:?}: {}", topic, e))?;
+        let sender = sender.clone();
+        subscription.on_message(move |message| {
+            let _ = sender.send(message);
+        });
+        subscription.on_error(move |error| {
+            log.emit(&Event::SubscriptionError {
+                topic: topic.as_str(),
+                error: &error,
+            })
+        });
+        subscription.subscribe();
+        subscriptions.push(subscription);
+    }
+
+    let mut debouncer = Debouncer::new(Duration::from_secs(config.debounce_secs));
+    let mut reconnect = tokio::time::interval(RECONNECT_INTERVAL);
+    loop {
+        let next_due = debouncer.next_due();
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            Some(message) = messages.recv() => {
+                let key = message.ordering_key().to_string();
+                let message = debouncer.offer(key, message);
+                if let Some(alert) = message.and_then(|message| monitor.handle(message)) {
+                    alerter.send(&alert).await;
+                }
+            }
+            _ = sleep_until(next_due), if next_due.is_some() => {
+                for (_, message) in debouncer.take_due() {
+                    if let Some(alert) = monitor.handle(message) {
+                        alerter.send(&alert).await;
+                    }
+                }
+            }
+            // The client stops retrying after its configured number of attempts
+            _ = reconnect.tick(), if !connected.load(Ordering::Relaxed) => {
+                if let Err(e) = sparkscan.ws().connect().await {
+                    log.emit(&Event::ReconnectFailed { error: e.to_string() });
+                }
+            }
+        }
+    }
+
+    for subscription in &subscriptions {
+        subscription.unsubscribe();
+    }
+    Ok(())
+}
+
+/// Sleep until `deadline`, or forever without one.
+async fn sleep_until(deadline: Option<std::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+fn describe_metrics() {
+    metrics::describe_gauge!(
+        "sparkscan_monitor_balance_sats",
+        "Soft balance of the watched addresses, in satoshis"
+    );
+    metrics::describe_gauge!(
+        "sparkscan_monitor_token_price_sats",
+        "Price of the watched tokens, in satoshis per token"
+    );
+    metrics::describe_counter!(
+        "sparkscan_monitor_alerts_total",
+        "Threshold transitions of the watched values"
+    );
+}
+
+/// Watched values and their last known levels.
+struct Monitor<'a> {
+    network: Network,
+    addresses: HashMap<String, &'a AddressRule>,
+    tokens: HashMap<String, &'a TokenRule>,
+    levels: Levels,
+    log: Log,
+}
+
+impl Monitor<'_> {
+    fn handle(&mut self, message: SparkScanMessage) -> Option<Alert> {
+        if message.spark_network() != self.network {
+            return None;
+        }
+        match message {
+            SparkScanMessage::Balance(balance) => {
+                let rule = *self.addresses.get(balance.address.as_str())?;
+                let Ok(soft_balance) = balance.soft_balance.parse::<Sats>() else {
+                    self.log.emit(&Event::InvalidValue {
+                        subject: rule.address.as_str(),
+                        value: &balance.soft_balance,
+                    });
+                    return None;
+                };
+                self.balance(rule, soft_balance)
+            }
+            SparkScanMessage::TokenPrice(price) => {
+                let rule = *self.tokens.get(price.address.as_str())?;
+                let Ok(price_sats) = price.price_sats.to_string().parse::<f64>() else {
+                    self.log.emit(&Event::InvalidValue {
+                        subject: rule.identifier.as_str(),
+                        value: price.price_sats.as_str(),
+                    });
+                    return None;
+                };
+                self.token_price(rule, price_sats)
+            }
+            _ => None,
+        }
+    }
+
+    fn balance(&mut self, rule: &AddressRule, balance: Sats) -> Option<Alert> {
+        let subject = rule.address.to_string();
+        metrics::gauge!("sparkscan_monitor_balance_sats", "address" => subject.clone())
+            .set(balance.value() as f64);
+
+        let level = Level::of(balance, rule.min_balance_sats, rule.max_balance_sats);
+        let previous = self.levels.update(&subject, level)?;
+        Some(Alert {
+            rule: "balance",
+            subject,
+            label: rule.label.clone(),
+            network: self.network,
+            value: balance.value() as f64,
+            minimum: rule.min_balance_sats.map(|sats| sats.value() as f64),
+            maximum: rule.max_balance_sats.map(|sats| sats.value() as f64),
+            previous,
+            level,
+        })
+    }
+
+    fn token_price(&mut self, rule: &TokenRule, price_sats: f64) -> Option<Alert> {
+        let subject = rule.identifier.to_string();
+        metrics::gauge!("sparkscan_monitor_token_price_sats", "token" => subject.clone())
+            .set(price_sats);
+
+        let level = Level::of(price_sats, rule.min_price_sats, rule.max_price_sats);
+        let previous = self.levels.update(&subject, level)?;
+        Some(Alert {
+            rule: "token_price",
+            subject,
+            label: rule.label.clone(),
+            network: self.network,
+            value: price_sats,
+            minimum: rule.min_price_sats,
+            maximum: rule.max_price_sats,
+            previous,
+            level,
+        })
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::events::{Event, Log};
+
+/// Delay before retrying a failed delivery, doubled on every further failure.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between delivery attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Number of unreadable entries kept for inspection; older ones are removed.
+const MAX_INVALID: usize = 100;
+
+/// Alert waiting for delivery to a webhook.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub webhook: String,
+    pub alert: serde_json::Value,
+}
+
+/// Alerts persisted to a directory before delivery, one file each, removed once delivered.
+///
+/// Entries are written to a temporary file then renamed, so that a crash never leaves a
+/// partial entry behind, and are delivered in the order they were added. Entries left by a
+/// previous run are delivered on startup, and the temporary files of writes it did not finish
+/// are removed. Unreadable entries are renamed to `.invalid` rather than retried, keeping the
+/// latest [`MAX_INVALID`] of them.
+pub struct Outbox {
+    dir: PathBuf,
+    next: AtomicU64,
+    added: Notify,
+}
+
+impl Outbox {
+    /// Open the outbox in `dir`, creating the directory if needed.
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut outbox = Self {
+            dir: dir.to_path_buf(),
+            next: AtomicU64::new(0),
+            added: Notify::new(),
+        };
+        // Writes interrupted by a crash, never renamed into entries
+        for path in outbox.files("tmp")? {
+            fs::remove_file(path)?;
+        }
+        let last = outbox
+            .pending()?
+            .iter()
+            .filter_map(|path| sequence(path))
+            .max();
+        *outbox.next.get_mut() = last.map_or(0, |last| last + 1);
+        Ok(outbox)
+    }
+
+    /// Persist the JSON `alert` for delivery to `webhook`.
+    pub fn push(&self, webhook: &str, alert: &serde_json::Value) -> io::Result<()> {
+        let entry = Entry {
+            webhook: webhook.to_string(),
+            alert: alert.clone(),
+        };
+        let name = format!("{:020}", self.next.fetch_add(1, Ordering::Relaxed));
+        let temporary = self.dir.join(format!("{}.tmp", name));
+        let mut file = File::create(&temporary)?;
+        file.write_all(&serde_json::to_vec(&entry)?)?;
+        file.sync_all()?;
+        fs::rename(&temporary, self.dir.join(format!("{}.json", name)))?;
+        self.added.notify_one();
+        Ok(())
+    }
+
+    /// Get the paths of the entries waiting for delivery, oldest first.
+    pub fn pending(&self) -> io::Result<Vec<PathBuf>> {
+        self.files("json")
+    }
+
+    /// Get the paths of the files with `extension`, oldest first.
+    fn files(&self, extension: &str) -> io::Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|found| found == extension) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Entry> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Set the entry at `path` aside, removing the oldest entries set aside beyond
+    /// [`MAX_INVALID`].
+    fn set_aside(&self, path: &Path) -> io::Result<()> {
+        fs::rename(path, path.with_extension("invalid"))?;
+        let invalid = self.files("invalid")?;
+        for path in &invalid[..invalid.len().saturating_sub(MAX_INVALID)] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Deliver the entries until the task is dropped, retrying failed deliveries with a
+    /// growing delay.
+    pub async fn flush(self: Arc<Self>, http: reqwest::Client, log: Log) {
+        let mut delay = RETRY_DELAY;
+        loop {
+            if self.deliver(&http, log).await {
+                delay = RETRY_DELAY;
+                self.added.notified().await;
+            } else {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+
+    /// Deliver the pending entries, returning whether all of them were delivered.
+    ///
+    /// Once a delivery to a webhook fails, its later entries wait for the next attempt, so
+    /// that each webhook receives the alerts in order.
+    async fn deliver(&self, http: &reqwest::Client, log: Log) -> bool {
+        let paths = match self.pending() {
+            Ok(paths) => paths,
+            Err(e) => {
+                log.emit(&Event::AlertFailed {
+                    sink: "outbox",
+                    error: e.to_string(),
+                });
+                return false;
+            }
+        };
+        let mut failed = HashSet::new();
+        for path in paths {
+            let entry = match self.read(&path) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    // Kept aside for inspection rather than retried forever
+                    log.emit(&Event::AlertFailed {
+                        sink: "outbox",
+                        error: format!("invalid entry {}: {}", path.display(), e),
+                    });
+                    let _ = self.set_aside(&path);
+                    continue;
+                }
+            };
+            if failed.contains(&entry.webhook) {
+                continue;
+            }
+            let result = http
+                .post(&entry.webhook)
+                .json(&entry.alert)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match result {
+                Ok(_) => {
+                    if let Err(e) = fs::remove_file(&path) {
+                        log.emit(&Event::AlertFailed {
+                            sink: "outbox",
+                            error: e.to_string(),
+                        });
+                    }
+                }
+                Err(e) => {
+                    log.emit(&Event::AlertFailed {
+                        sink: &entry.webhook,
+                        error: e.to_string(),
+                    });
+                    failed.insert(entry.webhook);
+                }
+            }
+        }
+        failed.is_empty()
+    }
+}
+
+/// Get the sequence number of the entry at `path`.
+fn sequence(path: &Path) -> Option<u64> {
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::Alert;
+    use crate::rules::Level;
+    use sparkscan_sdk::Network;
+
+    #[test]
+    fn test_entries_survive_reopening() {
+        let dir = std::env::temp_dir().join(format!("sparkscan-outbox-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let alert = Alert {
+            rule: "balance",
+            subject: "sp1a".to_string(),
+            label: None,
+            network: Network::Mainnet,
+            value: 5.0,
+            minimum: Some(10.0),
+            maximum: None,
+            previous: Level::Normal,
+            level: Level::Below,
+        };
+        let alert = serde_json::to_value(&alert).unwrap();
+
+        let outbox = Outbox::open(&dir).unwrap();
+        outbox.push("https://a.example.com", &alert).unwrap();
+        outbox.push("https://b.example.com", &alert).unwrap();
+        fs::remove_file(&outbox.pending().unwrap()[0]).unwrap();
+
+        // Sequence numbers continue after the entries left by a previous run
+        let outbox = Outbox::open(&dir).unwrap();
+        outbox.push("https://c.example.com", &alert).unwrap();
+        let pending = outbox.pending().unwrap();
+        let webhooks: Vec<String> = pending
+            .iter()
+            .map(|path| outbox.read(path).unwrap().webhook)
+            .collect();
+        assert_eq!(webhooks, ["https://b.example.com", "https://c.example.com"]);
+        assert_eq!(sequence(&pending[1]), Some(2));
+        assert_eq!(outbox.read(&pending[0]).unwrap().alert["level"], "below");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_removes_unfinished_writes() {
+        let dir = std::env::temp_dir().join(format!("sparkscan-outbox-tmp-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("{:020}.tmp", 0)), b"{\"webhook\"").unwrap();
+
+        let outbox = Outbox::open(&dir).unwrap();
+        assert!(outbox.files("tmp").unwrap().is_empty());
+        assert!(outbox.pending().unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_entries_are_bounded() {
+        let dir =
+            std::env::temp_dir().join(format!("sparkscan-outbox-invalid-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let outbox = Outbox::open(&dir).unwrap();
+        for sequence in 0..MAX_INVALID + 5 {
+            let path = dir.join(format!("{:020}.json", sequence));
+            fs::write(&path, b"not json").unwrap();
+            outbox.set_aside(&path).unwrap();
+        }
+
+        let invalid = outbox.files("invalid").unwrap();
+        assert_eq!(invalid.len(), MAX_INVALID);
+        assert_eq!(sequence(&invalid[0]), Some(5));
+        assert!(outbox.pending().unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// Position of a value relative to its thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    /// Within the thresholds
+    Normal,
+    /// Below the minimum
+    Below,
+    /// Above the maximum
+    Above,
+}
+
+impl Level {
+    /// Classify `value` against the optional `minimum` and `maximum`.
+    pub fn of<T: PartialOrd>(value: T, minimum: Option<T>, maximum: Option<T>) -> Self {
+        if minimum.is_some_and(|minimum| value < minimum) {
+            Level::Below
+        } else if maximum.is_some_and(|maximum| value > maximum) {
+            Level::Above
+        } else {
+            Level::Normal
+        }
+    }
+}
+
+/// Last known level of every watched value, so that alerts fire on transitions only.
+#[derive(Debug, Default)]
+pub struct Levels {
+    levels: HashMap<String, Level>,
+}
+
+impl Levels {
+    /// Record the level of `key` and return the previous one if it changed.
+    ///
+    /// A value first observed outside its thresholds counts as a change from
+    /// [`Level::Normal`], so breaches present at startup are reported.
+    pub fn update(&mut self, key: &str, level: Level) -> Option<Level> {
+        let previous = self
+            .levels
+            .insert(key.to_string(), level)
+            .unwrap_or(Level::Normal);
+        (previous != level).then_some(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level() {
+        assert_eq!(Level::of(5, Some(10), None), Level::Below);
+        assert_eq!(Level::of(15, Some(10), Some(20)), Level::Normal);
+        assert_eq!(Level::of(25.0, None, Some(20.0)), Level::Above);
+        assert_eq!(Level::of(10, Some(10), Some(10)), Level::Normal);
+    }
+
+    #[test]
+    fn test_alerts_fire_on_transitions() {
+        let mut levels = Levels::default();
+        assert_eq!(levels.update("sp1a", Level::Normal), None);
+        assert_eq!(levels.update("sp1a", Level::Below), Some(Level::Normal));
+        assert_eq!(levels.update("sp1a", Level::Below), None);
+        assert_eq!(levels.update("sp1a", Level::Normal), Some(Level::Below));
+
+        assert_eq!(levels.update("sp1b", Level::Above), Some(Level::Normal));
+    }
+}
@@ -0,0 +1,334 @@
+//! Canonical form of the transactions delivered by the REST API and the WebSocket feed.
+//!
+//! The two clients are generated from separate schemas, which encode the same transaction
+//! differently: amounts are numbers over REST and decimal strings over WebSocket, the kinds use
+//! two vocabularies, and parties and token metadata are nested objects over REST.
+//! [`CanonicalTransaction`] converts both into one shape, and [`compare_transactions`] checks that
+//! a transaction fetched over REST and the same transaction delivered over WebSocket agree, which
+//! guards against the two schemas drifting apart:
+//!
+//! ```rust,no_run
+//! use sparkscan_sdk::contract::assert_same_transaction;
+//! use sparkscan_sdk::prelude::*;
+//! use sparkscan_sdk::ws::TransactionPayload;
+//!
+//! # fn delivered() -> TransactionPayload { unimplemented!() }
+//! tokio_test::block_on(async {
+//!     let sparkscan = SparkScan::connect("api-key").await.unwrap();
+//!     let ws = delivered();
+//!
+//!     let latest = sparkscan.api().latest_transactions_page(0, 100).await.unwrap();
+//!     if let Some(rest) = latest.items.iter().find(|tx| tx.id == ws.id) {
+//!         // Panics with every field that differs
+//!         assert_same_transaction(rest, &ws);
+//!     }
+//! });
+//! ```
+//!
+//! Fields only one side carries, such as the USD value of the REST API or the processing time of
+//! the feed, are left out of the canonical form.
+//!
+//! Requires the `rest` and `ws` features.
+
+use std::fmt;
+
+use sparkscan::types::LatestNetworkTransactionItem;
+use sparkscan_ws::TransactionPayload;
+
+use crate::{Sats, TokenAmount, TransactionStatus, TransactionType};
+
+/// A transaction in the representation shared by the REST API and the WebSocket feed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalTransaction {
+    /// Transaction id
+    pub id: String,
+    /// Kind, in the REST vocabulary (see [`TransactionType::canonical`])
+    pub kind: TransactionType,
+    /// Status
+    pub status: TransactionStatus,
+    /// Bitcoin amount
+    pub amount_sats: Option<Sats>,
+    /// Token amount, in the smallest unit
+    pub token_amount: Option<TokenAmount>,
+    /// Address of the transferred token
+    pub token_address: Option<String>,
+    /// Identifier of the sender
+    pub from: Option<String>,
+    /// Identifier of the receiver
+    pub to: Option<String>,
+    /// Bitcoin transaction id, for deposits and withdrawals
+    pub bitcoin_txid: Option<String>,
+}
+
+impl CanonicalTransaction {
+    /// Get the fields that differ between `self`, from REST, and `ws`.
+    fn diff(&self, ws: &Self) -> Vec<FieldMismatch> {
+        let mut mismatches = Vec::new();
+        macro_rules! compare {
+            ($($field:ident),+) => {
+                $(
+                    if self.$field != ws.$field {
+                        mismatches.push(FieldMismatch {
+                            field: stringify!($field),
+                            rest: format!("{:?}", self.$field),
+                            ws: format!("{:?}", ws.$field),
+                        });
+                    }
+                )+
+            };
+        }
+        compare!(
+            id,
+            kind,
+            status,
+            amount_sats,
+            token_amount,
+            token_address,
+            from,
+            to,
+            bitcoin_txid
+        );
+        mismatches
+    }
+}
+
+impl TryFrom<&LatestNetworkTransactionItem> for CanonicalTransaction {
+    type Error = ContractError;
+
+    fn try_from(tx: &LatestNetworkTransactionItem) -> Result<Self, Self::Error> {
+        let invalid = |field, value: String| ContractError::Invalid {
+            source: "REST",
+            field,
+            value,
+        };
+        let kind: TransactionType = tx
+            .type_
+            .to_string()
+            .parse()
+            .map_err(|_| invalid("kind", tx.type_.to_string()))?;
+        Ok(Self {
+            id: tx.id.clone(),
+            kind: kind.canonical(),
+            status: tx
+                .status
+                .to_string()
+                .parse()
+                .map_err(|_| invalid("status", tx.status.to_string()))?,
+            amount_sats: tx
+                .amount_sats
+                .map(|amount| {
+                    Sats::try_from(amount).map_err(|_| invalid("amount_sats", amount.to_string()))
+                })
+                .transpose()?,
+            token_amount: tx
+                .token_amount
+                .map(|amount| {
+                    TokenAmount::try_from(amount)
+                        .map_err(|_| invalid("token_amount", amount.to_string()))
+                })
+                .transpose()?,
+            token_address: tx
+                .token_metadata
+                .as_ref()
+                .map(|metadata| metadata.token_address.clone()),
+            from: tx.from.as_ref().map(|party| party.identifier.clone()),
+            to: tx.to.as_ref().map(|party| party.identifier.clone()),
+            bitcoin_txid: tx.bitcoin_txid.clone(),
+        })
+    }
+}
+
+impl TryFrom<&TransactionPayload> for CanonicalTransaction {
+    type Error = ContractError;
+
+    fn try_from(tx: &TransactionPayload) -> Result<Self, Self::Error> {
+        let invalid = |field, value: &str| ContractError::Invalid {
+            source: "WebSocket",
+            field,
+            value: value.to_string(),
+        };
+        Ok(Self {
+            id: tx.id.clone(),
+            kind: TransactionType::from(tx.type_).canonical(),
+            status: tx.status.into(),
+            amount_sats: tx
+                .amount_sats
+                .as_deref()
+                .map(|amount| amount.parse().map_err(|_| invalid("amount_sats", amount)))
+                .transpose()?,
+            token_amount: tx
+                .token_amount
+                .as_deref()
+                .map(|amount| amount.parse().map_err(|_| invalid("token_amount", amount)))
+                .transpose()?,
+            token_address: tx.token_address.clone(),
+            from: tx.from_identifier.clone(),
+            to: tx.to_identifier.clone(),
+            bitcoin_txid: tx.bitcoin_txid.clone(),
+        })
+    }
+}
+
+/// A field on which the REST and WebSocket representations of a transaction disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMismatch {
+    /// Name of the field in [`CanonicalTransaction`]
+    pub field: &'static str,
+    /// Value over REST
+    pub rest: String,
+    /// Value over WebSocket
+    pub ws: String,
+}
+
+impl fmt::Display for FieldMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} over REST, {} over WebSocket",
+            self.field, self.rest, self.ws
+        )
+    }
+}
+
+/// Error returned by [`compare_transactions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractError {
+    /// A field could not be converted into its canonical type
+    Invalid {
+        /// Representation the field comes from, `REST` or `WebSocket`
+        source: &'static str,
+        /// Name of the field in [`CanonicalTransaction`]
+        field: &'static str,
+        /// Value as received
+        value: String,
+    },
+    /// Both representations converted, but disagree
+    Mismatch(Vec<FieldMismatch>),
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ContractError::Invalid {
+                source,
+                field,
+                ref value,
+            } => write!(f, "invalid {} over {}: {}", field, source, value),
+            ContractError::Mismatch(ref mismatches) => {
+                write!(f, "REST and WebSocket transactions differ:")?;
+                for mismatch in mismatches {
+                    write!(f, "\n  {}", mismatch)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContractError {}
+
+/// Convert both representations of a transaction and check that they agree.
+///
+/// Returns the canonical transaction they agree on.
+pub fn compare_transactions(
+    rest: &LatestNetworkTransactionItem,
+    ws: &TransactionPayload,
+) -> Result<CanonicalTransaction, ContractError> {
+    let canonical = CanonicalTransaction::try_from(rest)?;
+    let mismatches = canonical.diff(&CanonicalTransaction::try_from(ws)?);
+    if mismatches.is_empty() {
+        Ok(canonical)
+    } else {
+        Err(ContractError::Mismatch(mismatches))
+    }
+}
+
+/// Assert that both representations of a transaction agree, for use in tests.
+///
+/// # Panics
+///
+/// Panics with every field that differs, or that does not convert.
+#[track_caller]
+pub fn assert_same_transaction(rest: &LatestNetworkTransactionItem, ws: &TransactionPayload) {
+    if let Err(e) = compare_transactions(rest, ws) {
+        panic!("{}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rest_withdrawal() -> serde_json::Value {
+        json!({
+            "id": "0196bb7e-1c4a-7b0e-9b1f-3c3a3e3d6f10",
+            "type": "bitcoin_withdrawal",
+            "status": "confirmed",
+            "createdAt": "2025-06-01T12:00:00Z",
+            "amountSats": 250000,
+            "from": {
+                "type": "spark",
+                "identifier": "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k",
+            },
+            "to": {
+                "type": "bitcoin",
+                "identifier": "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            },
+            "bitcoinTxid": "f".repeat(64),
+            "valueUsd": 262.5,
+        })
+    }
+
+    fn ws_withdrawal() -> serde_json::Value {
+        json!({
+            "id": "0196bb7e-1c4a-7b0e-9b1f-3c3a3e3d6f10",
+            "network": "MAINNET",
+            "type": "spark_to_bitcoin",
+            "status": "confirmed",
+            "amount_sats": "250000",
+            "from_identifier": "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k",
+            "to_identifier": "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            "bitcoin_txid": "f".repeat(64),
+            "processed_at": "2025-06-01T12:00:05Z",
+        })
+    }
+
+    #[test]
+    fn test_representations_agree() {
+        let rest = serde_json::from_value(rest_withdrawal()).unwrap();
+        let ws = serde_json::from_value(ws_withdrawal()).unwrap();
+        let canonical = compare_transactions(&rest, &ws).unwrap();
+        assert_eq!(canonical.kind, TransactionType::BitcoinWithdrawal);
+        assert_eq!(canonical.amount_sats, Some(Sats(250000)));
+        assert_same_transaction(&rest, &ws);
+    }
+
+    #[test]
+    fn test_drift_is_reported() {
+        let rest = serde_json::from_value(rest_withdrawal()).unwrap();
+        let mut ws = ws_withdrawal();
+        ws["status"] = json!("pending");
+        ws["amount_sats"] = json!("25000");
+        let ws = serde_json::from_value(ws).unwrap();
+        let err = compare_transactions(&rest, &ws).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "REST and WebSocket transactions differ:\n  \
+             status: Confirmed over REST, Pending over WebSocket\n  \
+             amount_sats: Some(Sats(250000)) over REST, Some(Sats(25000)) over WebSocket"
+        );
+
+        let mut ws = ws_withdrawal();
+        ws["amount_sats"] = json!("a lot");
+        let ws = serde_json::from_value(ws).unwrap();
+        assert!(matches!(
+            compare_transactions(&rest, &ws),
+            Err(ContractError::Invalid {
+                source: "WebSocket",
+                field: "amount_sats",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+//! Health of the WebSocket feed, and the REST fallback of the watchers while it is down.
+//!
+//! [`SparkScan`](crate::SparkScan) records when its WebSocket connection drops and recovers in
+//! a [`FeedHealth`]. The watchers built on the feed read it to switch to REST polling once the
+//! connection has been down longer than the [`RestFallback`] threshold, and look everything up
+//! again as soon as it recovers, so that the updates missed in the meantime are reconciled.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Time the feed has to be down before the watchers poll the REST API instead, by default.
+const DEFAULT_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Interval between REST lookups while the feed is down, by default.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Connection state of the WebSocket feed of a [`SparkScan`](crate::SparkScan).
+///
+/// Shared by the clones of the entry point.
+#[derive(Debug, Default)]
+pub struct FeedHealth {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    down_since: Option<Instant>,
+    recoveries: u64,
+}
+
+impl FeedHealth {
+    /// Track the connection of `ws`.
+    pub(crate) fn watch(ws: &sparkscan_ws::SparkScanWsClient) -> Arc<Self> {
+        let health = Arc::new(Self::default());
+        let down = Arc::clone(&health);
+        ws.on_disconnected(move || down.mark_down(Instant::now()));
+        let up = Arc::clone(&health);
+        ws.on_connected(move || up.mark_up());
+        health
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record a disconnection, keeping the time of the first one while reconnecting.
+    fn mark_down(&self, now: Instant) {
+        self.state().down_since.get_or_insert(now);
+    }
+
+    fn mark_up(&self) {
+        let mut state = self.state();
+        if state.down_since.take().is_some() {
+            state.recoveries += 1;
+        }
+    }
+
+    /// Check whether the feed is currently disconnected.
+    pub fn is_down(&self) -> bool {
+        self.state().down_since.is_some()
+    }
+
+    /// Get the time the feed has been disconnected for, `None` while it is connected.
+    pub fn down_for(&self) -> Option<Duration> {
+        self.state().down_since.map(|since| since.elapsed())
+    }
+
+    /// Get the number of times the feed reconnected after a disconnection.
+    pub fn recoveries(&self) -> u64 {
+        self.state().recoveries
+    }
+}
+
+/// When and how often the watchers poll the REST API while the feed is down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestFallback {
+    /// Time the feed has to be down before switching to REST polling (default: 30s)
+    pub threshold: Duration,
+    /// Interval between REST lookups while switched (default: 10s)
+    pub poll_interval: Duration,
+}
+
+impl Default for RestFallback {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+}
+
+impl RestFallback {
+    /// Set the time the feed has to be down before switching to REST polling.
+    pub fn with_threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the interval between REST lookups while switched.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+}
+
+/// Fallback state of one watcher, checked every fallback poll interval.
+#[cfg(any(feature = "withdrawals", feature = "token-watch"))]
+pub(crate) struct Degraded {
+    policy: RestFallback,
+    recoveries: u64,
+}
+
+#[cfg(any(feature = "withdrawals", feature = "token-watch"))]
+impl Degraded {
+    pub(crate) fn new(policy: RestFallback, health: &FeedHealth) -> Self {
+        Self {
+            policy,
+            recoveries: health.recoveries(),
+        }
+    }
+
+    pub(crate) fn poll_interval(&self) -> Duration {
+        self.policy.poll_interval
+    }
+
+    /// Check whether the watcher should poll the REST API: while the feed has been down longer
+    /// than the threshold, and once after every recovery to reconcile the missed updates.
+    pub(crate) fn should_poll(&mut self, health: &FeedHealth, now: Instant) -> bool {
+        let state = health.state();
+        let recovered = state.recoveries != self.recoveries;
+        self.recoveries = state.recoveries;
+        let degraded = state
+            .down_since
+            .is_some_and(|since| now.saturating_duration_since(since) >= self.policy.threshold);
+        degraded || recovered
+    }
+}
+
+#[cfg(all(test, any(feature = "withdrawals", feature = "token-watch")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degraded_after_threshold_and_reconciled_on_recovery() {
+        let start = Instant::now();
+        let health = FeedHealth::default();
+        let policy = RestFallback::default().with_threshold(Duration::from_secs(30));
+        let mut degraded = Degraded::new(policy, &health);
+        assert!(!degraded.should_poll(&health, start));
+
+        health.mark_down(start);
+        // Reconnection attempts failing keep the time of the first disconnection
+        health.mark_down(start + Duration::from_secs(20));
+        assert!(health.is_down());
+        assert!(!degraded.should_poll(&health, start + Duration::from_secs(29)));
+        assert!(degraded.should_poll(&health, start + Duration::from_secs(30)));
+
+        health.mark_up();
+        assert_eq!(health.recoveries(), 1);
+        assert!(degraded.should_poll(&health, start + Duration::from_secs(40)));
+        assert!(!degraded.should_poll(&health, start + Duration::from_secs(50)));
+
+        // Short outages are reconciled without switching
+        health.mark_down(start + Duration::from_secs(60));
+        assert!(!degraded.should_poll(&health, start + Duration::from_secs(61)));
+        health.mark_up();
+        assert!(degraded.should_poll(&health, start + Duration::from_secs(62)));
+    }
+}
@@ -0,0 +1,205 @@
+//! SparkScan REST and WebSocket clients behind a single entry point.
+//!
+//! [`SparkScan::connect`] creates the REST facade and opens the WebSocket connection with one
+//! API key, and [`prelude`] brings the types of both clients into scope:
+//!
+//! ```rust,no_run
+//! use sparkscan_sdk::prelude::*;
+//!
+//! tokio_test::block_on(async {
+//!     let sparkscan = SparkScan::connect("api-key").await.unwrap();
+//!
+//!     let address: SparkAddress = "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k"
+//!         .parse()
+//!         .unwrap();
+//!     let summary = sparkscan.api().address(&address).summary().await.unwrap();
+//!     println!("Token count: {}", summary.token_count);
+//!
+//!     let subscription = sparkscan
+//!         .ws()
+//!         .subscribe(Topic::BalanceAddress(address.to_string()))
+//!         .await
+//!         .unwrap();
+//!     subscription.on_message(|message| println!("{:?} update", message.spark_network()));
+//!     subscription.subscribe();
+//! });
+//! ```
+//!
+//! # Features
+//!
+//! - `rest` (default): the REST client, re-exported as [`rest`]
+//! - `ws` (default): the WebSocket client, re-exported as [`ws`]. With `rest`, [`contract`]
+//!   checks that both clients represent a transaction the same way
+//! - `native-tls` (default), `rustls-tls`: TLS backend of the REST client
+//! - `serde`: (de)serialization of the shared domain types
+//! - `tracing`: spans for both clients
+//! - `high-throughput`: reused parsing buffers in the WebSocket client
+//! - `metrics`, `http-cache`, `request-id`, `hedging`, `poll-watcher`, `export`, `csv`,
+//!   `response-validation`: forwarded to the REST client
+//! - `withdrawals`: [`withdrawals::WithdrawalTracker`], following outgoing withdrawals through
+//!   the transaction feed and the REST API
+//! - `token-watch`: [`token_watch::TokenWatcher`], emitting supply and holder count changes of
+//!   tokens from the token feed and the REST API
+//! - `monitord`: the `sparkscan-monitord` binary, a daemon alerting on address balance and token
+//!   price thresholds (see `monitord.example.yaml`)
+
+#[cfg(feature = "rest")]
+pub use sparkscan as rest;
+#[cfg(feature = "ws")]
+pub use sparkscan_ws as ws;
+
+#[cfg(all(feature = "rest", feature = "ws"))]
+pub mod contract;
+#[cfg(feature = "ws")]
+pub mod feed;
+#[cfg(feature = "token-watch")]
+pub mod token_watch;
+#[cfg(feature = "withdrawals")]
+pub mod withdrawals;
+
+pub use sparkscan_types::{
+    BitcoinTxid, Network, ParseAmountError, ParseEnumError, ParseIdError, Sats, SparkAddress,
+    TokenAmount, TokenIdentifier, TransactionStatus, TransactionType,
+};
+
+/// Glob import of the entry point, the shared domain types and the most used client types.
+pub mod prelude {
+    pub use crate::{
+        BitcoinTxid, Error, Network, Sats, SparkAddress, SparkScan, TokenAmount, TokenIdentifier,
+        TransactionStatus, TransactionType,
+    };
+
+    #[cfg(feature = "rest")]
+    pub use sparkscan::{ApiError, Client, SparkScanApi, numeric::I128Ext};
+
+    #[cfg(feature = "ws")]
+    pub use sparkscan_ws::{
+        SparkScanMessage, SparkScanSubscription, SparkScanWsClient, SparkScanWsConfig, Topic,
+    };
+}
+
+/// Error returned by [`SparkScan::connect`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The WebSocket connection could not be opened
+    #[cfg(feature = "ws")]
+    Ws(sparkscan_ws::SparkScanWsError),
+}
+
+impl std::fmt::Display for Error {
+    #[cfg_attr(not(feature = "ws"), allow(unused_variables))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            #[cfg(feature = "ws")]
+            Error::Ws(ref e) => write!(f, "WebSocket connection failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            #[cfg(feature = "ws")]
+            Error::Ws(ref e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "ws")]
+impl From<sparkscan_ws::SparkScanWsError> for Error {
+    fn from(e: sparkscan_ws::SparkScanWsError) -> Self {
+        Error::Ws(e)
+    }
+}
+
+/// REST facade and WebSocket client sharing one API key and network.
+///
+/// Cloning is cheap: clones share the connection pools and the WebSocket connection.
+#[derive(Clone)]
+pub struct SparkScan {
+    #[cfg(feature = "rest")]
+    api: sparkscan::SparkScanApi,
+    #[cfg(feature = "ws")]
+    ws: sparkscan_ws::SparkScanWsClient,
+    #[cfg(feature = "ws")]
+    feed: std::sync::Arc<feed::FeedHealth>,
+    network: Network,
+}
+
+impl SparkScan {
+    /// Create the mainnet REST facade authenticated with `api_key` and open the WebSocket
+    /// connection to the mainnet update feed.
+    #[cfg_attr(not(feature = "rest"), allow(unused_variables))]
+    pub async fn connect(api_key: &str) -> Result<Self, Error> {
+        #[cfg(feature = "ws")]
+        let ws = sparkscan_ws::SparkScanWsClient::new(sparkscan_ws::DEFAULT_MAINNET_URL);
+        let sparkscan = Self {
+            #[cfg(feature = "rest")]
+            api: sparkscan::SparkScanApi::new(
+                sparkscan::Client::mainnet(api_key),
+                Network::Mainnet,
+            ),
+            #[cfg(feature = "ws")]
+            feed: feed::FeedHealth::watch(&ws),
+            #[cfg(feature = "ws")]
+            ws,
+            network: Network::Mainnet,
+        };
+        #[cfg(feature = "ws")]
+        sparkscan.ws.connect().await?;
+        Ok(sparkscan)
+    }
+
+    /// Get a copy issuing REST requests against `network`, sharing the same connections.
+    ///
+    /// The WebSocket feed carries every network; filter messages with
+    /// [`SparkScanMessage::spark_network`](sparkscan_ws::SparkScanMessage::spark_network) or
+    /// subscribe to a network topic.
+    pub fn with_network(&self, network: Network) -> Self {
+        let mut sparkscan = self.clone();
+        #[cfg(feature = "rest")]
+        {
+            sparkscan.api = self.api.with_network(network);
+        }
+        sparkscan.network = network;
+        sparkscan
+    }
+
+    /// Get the network REST requests are issued against.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Get the REST facade.
+    #[cfg(feature = "rest")]
+    pub fn api(&self) -> &sparkscan::SparkScanApi {
+        &self.api
+    }
+
+    /// Get the generated REST client, for parameters the facade does not expose.
+    #[cfg(feature = "rest")]
+    pub fn client(&self) -> &sparkscan::Client {
+        self.api.client()
+    }
+
+    /// Get the WebSocket client.
+    #[cfg(feature = "ws")]
+    pub fn ws(&self) -> &sparkscan_ws::SparkScanWsClient {
+        &self.ws
+    }
+
+    /// Get the connection state of the WebSocket feed.
+    #[cfg(feature = "ws")]
+    pub fn feed(&self) -> &feed::FeedHealth {
+        &self.feed
+    }
+}
+
+impl std::fmt::Debug for SparkScan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SparkScan")
+            .field("network", &self.network)
+            .finish_non_exhaustive()
+    }
+}
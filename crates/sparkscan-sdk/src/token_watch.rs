@@ -0,0 +1,397 @@
+//! Supply and holder count changes of tokens, enabled by the `token-watch` feature.
+//!
+//! A [`TokenWatcher`] follows a set of tokens through the token feed of the network, and polls
+//! the REST token details for the holder counts and supplies the feed has not reported, so
+//! that token issuers can monitor their asset without polling themselves. While the feed is
+//! down for longer than the [`RestFallback`] threshold, the tokens are polled more often, and
+//! looked up again as soon as the feed recovers:
+//!
+//! ```rust,no_run
+//! use sparkscan_sdk::prelude::*;
+//! use sparkscan_sdk::token_watch::{TokenEvent, TokenWatcher};
+//!
+//! tokio_test::block_on(async {
+//!     let sparkscan = SparkScan::connect("api-key").await.unwrap();
+//!     let watcher = TokenWatcher::new(sparkscan);
+//!     watcher.track(
+//!         "btkn1daywtenlww42njymqzyegvcwuy3p9f26zknme0srxa7tagewvuys86h553"
+//!             .parse()
+//!             .unwrap(),
+//!     );
+//!
+//!     watcher.on_change(|event| match event {
+//!         TokenEvent::SupplyChanged { token, delta, .. } => {
+//!             println!("{}: supply {:+}", token, delta)
+//!         }
+//!         TokenEvent::HolderCountChanged { token, delta, .. } => {
+//!             println!("{}: holders {:+}", token, delta)
+//!         }
+//!     });
+//!
+//!     // Runs until an error occurs or the future is dropped
+//!     watcher.run().await.unwrap();
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use sparkscan::numeric::I128Ext;
+use sparkscan_ws::{SparkScanMessage, Topic};
+use tokio::sync::mpsc;
+
+use crate::feed::{Degraded, RestFallback};
+use crate::{SparkScan, TokenAmount, TokenIdentifier};
+
+/// Interval between REST lookups of the watched tokens, by default.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+type ChangeCallback = Box<dyn Fn(&TokenEvent) + Send + Sync>;
+
+/// Where a change was observed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeSource {
+    /// The WebSocket token feed
+    Stream,
+    /// A REST lookup of the token details
+    Rest,
+}
+
+/// Change of a watched token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenEvent {
+    /// The circulating supply changed, by minting or burning
+    SupplyChanged {
+        /// Token identifier
+        token: String,
+        /// Previous supply, in the smallest unit of the token
+        previous: TokenAmount,
+        /// Current supply, in the smallest unit of the token
+        current: TokenAmount,
+        /// Change of the supply
+        delta: i128,
+        /// Where the change was observed
+        source: ChangeSource,
+    },
+    /// The number of holders changed
+    HolderCountChanged {
+        /// Token identifier
+        token: String,
+        /// Previous number of holders
+        previous: u64,
+        /// Current number of holders
+        current: u64,
+        /// Change of the number of holders
+        delta: i64,
+        /// Where the change was observed
+        source: ChangeSource,
+    },
+}
+
+impl TokenEvent {
+    /// Get the identifier of the token that changed.
+    pub fn token(&self) -> &str {
+        match self {
+            TokenEvent::SupplyChanged { token, .. } => token,
+            TokenEvent::HolderCountChanged { token, .. } => token,
+        }
+    }
+}
+
+/// Follows the supply and holder count of a set of tokens.
+///
+/// The first values seen for a token are its baseline and emit no event. Updates of the feed
+/// are matched by bech32 token identifier, so tokens tracked by another identifier are only
+/// followed through the REST API.
+pub struct TokenWatcher {
+    sparkscan: SparkScan,
+    poll_interval: Duration,
+    fallback: RestFallback,
+    tokens: Mutex<Tokens>,
+    callbacks: Mutex<Vec<ChangeCallback>>,
+}
+
+impl TokenWatcher {
+    /// Create a watcher of tokens on the network of `sparkscan`.
+    pub fn new(sparkscan: SparkScan) -> Self {
+        Self {
+            sparkscan,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            fallback: RestFallback::default(),
+            tokens: Mutex::new(Tokens::default()),
+            callbacks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Set the interval between REST lookups of the watched tokens.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Set when and how often tokens are looked up through the REST API while the feed is
+    /// down.
+    pub fn with_rest_fallback(mut self, fallback: RestFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    fn tokens(&self) -> MutexGuard<'_, Tokens> {
+        self.tokens.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Start watching `token`.
+    ///
+    /// Watching a token again has no effect.
+    pub fn track(&self, token: TokenIdentifier) {
+        self.tokens().track(token);
+    }
+
+    /// Stop watching `token`.
+    pub fn untrack(&self, token: &TokenIdentifier) {
+        self.tokens().known.remove(token);
+    }
+
+    /// Register a callback for the changes of the watched tokens.
+    pub fn on_change<F>(&self, callback: F)
+    where
+        F: Fn(&TokenEvent) + Send + Sync + 'static,
+    {
+        self.callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Follow the watched tokens until the future is dropped.
+    ///
+    /// Subscribes to the tokens of the network for the duration of the call, and looks up
+    /// every watched token through the REST API right away, then every poll interval.
+    pub async fn run(&self) -> Result<(), crate::Error> {
+        let network = self.sparkscan.network();
+        let subscription = self
+            .sparkscan
+            .ws()
+            .subscribe(Topic::TokenNetwork(network.as_str().to_lowercase()))
+            .await?;
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        subscription.on_message(move |message| {
+            if let SparkScanMessage::Token(token) = message {
+                let _ = sender.send(token);
+            }
+        });
+        subscription.subscribe();
+
+        let mut poll = tokio::time::interval(self.poll_interval);
+        let mut degraded = Degraded::new(self.fallback, self.sparkscan.feed());
+        let mut fallback = tokio::time::interval(degraded.poll_interval());
+        loop {
+            tokio::select! {
+                message = messages.recv() => {
+                    // The sender lives as long as the subscription callbacks
+                    let Some(token) = message else { break };
+                    let supply = token
+                        .circulating_supply
+                        .as_deref()
+                        .and_then(|supply| supply.parse().ok());
+                    let holders = u64::try_from(token.holders).ok();
+                    self.observe(token.address.as_str(), supply, holders, ChangeSource::Stream);
+                }
+                _ = poll.tick() => self.poll().await,
+                _ = fallback.tick() => {
+                    if degraded.should_poll(self.sparkscan.feed(), Instant::now()) {
+                        self.poll().await;
+                    }
+                }
+            }
+        }
+
+        subscription.unsubscribe();
+        Ok(())
+    }
+
+    /// Look up the watched tokens through the REST API.
+    async fn poll(&self) {
+        let tokens: Vec<TokenIdentifier> = self.tokens().known.keys().cloned().collect();
+        for token in tokens {
+            // Failed lookups are retried on the next poll
+            let Ok(details) = self
+                .sparkscan
+                .api()
+                .token(&token)
+                .force_refresh()
+                .details()
+                .await
+            else {
+                continue;
+            };
+            let supply = TokenAmount::try_from(details.total_supply).ok();
+            let holders = details.metadata.holder_count.as_u64_checked();
+            self.observe(token.as_str(), supply, holders, ChangeSource::Rest);
+        }
+    }
+
+    fn observe(
+        &self,
+        token: &str,
+        supply: Option<TokenAmount>,
+        holders: Option<u64>,
+        source: ChangeSource,
+    ) {
+        let events = self.tokens().observe(token, supply, holders, source);
+        let callbacks = self
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for event in &events {
+            for callback in callbacks.iter() {
+                callback(event);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for TokenWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenWatcher")
+            .field("poll_interval", &self.poll_interval)
+            .field("fallback", &self.fallback)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Last known values of the watched tokens.
+#[derive(Default)]
+struct Tokens {
+    known: HashMap<TokenIdentifier, Known>,
+}
+
+#[derive(Default)]
+struct Known {
+    supply: Option<TokenAmount>,
+    holders: Option<u64>,
+}
+
+impl Tokens {
+    fn track(&mut self, token: TokenIdentifier) {
+        self.known.entry(token).or_default();
+    }
+
+    /// Record the values of `token`, returning the changes if it is watched.
+    fn observe(
+        &mut self,
+        token: &str,
+        supply: Option<TokenAmount>,
+        holders: Option<u64>,
+        source: ChangeSource,
+    ) -> Vec<TokenEvent> {
+        let mut events = Vec::new();
+        let Some(known) = token
+            .parse::<TokenIdentifier>()
+            .ok()
+            .and_then(|token| self.known.get_mut(&token))
+        else {
+            return events;
+        };
+        if let Some(current) = supply
+            && let Some(previous) = known.supply.replace(current).filter(|p| *p != current)
+        {
+            events.push(TokenEvent::SupplyChanged {
+                token: token.to_string(),
+                previous,
+                current,
+                delta: signed_delta(previous.value(), current.value()),
+                source,
+            });
+        }
+        if let Some(current) = holders
+            && let Some(previous) = known.holders.replace(current).filter(|p| *p != current)
+        {
+            events.push(TokenEvent::HolderCountChanged {
+                token: token.to_string(),
+                previous,
+                current,
+                delta: current as i64 - previous as i64,
+                source,
+            });
+        }
+        events
+    }
+}
+
+/// Get `current - previous`, saturating at the bounds of `i128`.
+fn signed_delta(previous: u128, current: u128) -> i128 {
+    if current >= previous {
+        i128::try_from(current - previous).unwrap_or(i128::MAX)
+    } else {
+        i128::try_from(previous - current).map_or(i128::MIN, |delta| -delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "btkn1daywtenlww42njymqzyegvcwuy3p9f26zknme0srxa7tagewvuys86h553";
+
+    #[test]
+    fn test_changes_are_emitted_after_the_baseline() {
+        let mut tokens = Tokens::default();
+        tokens.track(TOKEN.parse().unwrap());
+
+        let baseline = tokens.observe(
+            TOKEN,
+            Some(TokenAmount(1_000)),
+            Some(10),
+            ChangeSource::Rest,
+        );
+        assert!(baseline.is_empty());
+
+        let events = tokens.observe(
+            TOKEN,
+            Some(TokenAmount(400)),
+            Some(12),
+            ChangeSource::Stream,
+        );
+        assert_eq!(
+            events,
+            [
+                TokenEvent::SupplyChanged {
+                    token: TOKEN.to_string(),
+                    previous: TokenAmount(1_000),
+                    current: TokenAmount(400),
+                    delta: -600,
+                    source: ChangeSource::Stream,
+                },
+                TokenEvent::HolderCountChanged {
+                    token: TOKEN.to_string(),
+                    previous: 10,
+                    current: 12,
+                    delta: 2,
+                    source: ChangeSource::Stream,
+                },
+            ]
+        );
+
+        // Unchanged, missing and untracked values emit nothing
+        assert!(
+            tokens
+                .observe(TOKEN, None, Some(12), ChangeSource::Rest)
+                .is_empty()
+        );
+        assert!(
+            tokens
+                .observe("btkn1other", Some(TokenAmount(1)), None, ChangeSource::Rest)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_signed_delta() {
+        assert_eq!(signed_delta(5, 8), 3);
+        assert_eq!(signed_delta(8, 5), -3);
+        assert_eq!(signed_delta(0, u128::MAX), i128::MAX);
+        assert_eq!(signed_delta(u128::MAX, 0), i128::MIN);
+    }
+}
@@ -0,0 +1,427 @@
+//! Tracking of outgoing withdrawals by Bitcoin transaction id, enabled by the `withdrawals`
+//! feature.
+//!
+//! A [`WithdrawalTracker`] follows the transactions of a set of expected Bitcoin txids until
+//! each of them is confirmed, fails or expires. Status changes come from the transaction feed
+//! of the network, and the REST API is polled for the withdrawals the feed has not reported
+//! on, so that a missed message or a dropped connection does not leave a withdrawal unknown.
+//! While the feed is down for longer than the [`RestFallback`] threshold, the withdrawals are
+//! polled more often, and looked up again as soon as the feed recovers:
+//!
+//! ```rust,no_run
+//! use sparkscan_sdk::prelude::*;
+//! use sparkscan_sdk::withdrawals::WithdrawalTracker;
+//! use std::time::Duration;
+//!
+//! tokio_test::block_on(async {
+//!     let sparkscan = SparkScan::connect("api-key").await.unwrap();
+//!     let tracker = WithdrawalTracker::new(sparkscan).with_timeout(Duration::from_secs(3600));
+//!     tracker.track("f".repeat(64).parse().unwrap());
+//!
+//!     tracker.on_transition(|update| {
+//!         println!("{}: {:?} -> {}", update.txid, update.previous, update.status);
+//!     });
+//!     tracker.on_timeout(|txid, elapsed, escalation| {
+//!         eprintln!("{} still pending after {:?} (escalation {})", txid, elapsed, escalation);
+//!     });
+//!
+//!     // Returns once every tracked withdrawal is confirmed, failed or expired
+//!     tracker.run().await.unwrap();
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use sparkscan_ws::{SparkScanMessage, Topic};
+use tokio::sync::mpsc;
+
+use crate::feed::{Degraded, RestFallback};
+use crate::{BitcoinTxid, SparkScan, TransactionStatus};
+
+/// Interval between REST lookups of the withdrawals without a final status, by default.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time after which a withdrawal without a final status is escalated, by default.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+type TransitionCallback = Box<dyn Fn(&WithdrawalUpdate) + Send + Sync>;
+type TimeoutCallback = Box<dyn Fn(&BitcoinTxid, Duration, u32) + Send + Sync>;
+
+/// Where a status change was observed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusSource {
+    /// The WebSocket transaction feed
+    Stream,
+    /// A REST lookup of the transaction
+    Rest,
+}
+
+/// Status change of a tracked withdrawal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WithdrawalUpdate {
+    /// Bitcoin transaction id of the withdrawal
+    pub txid: BitcoinTxid,
+    /// Status before the change, `None` for the first status seen
+    pub previous: Option<TransactionStatus>,
+    /// New status
+    pub status: TransactionStatus,
+    /// Where the change was observed
+    pub source: StatusSource,
+}
+
+impl WithdrawalUpdate {
+    /// Check whether the withdrawal reached a final status and is no longer tracked.
+    pub fn is_final(&self) -> bool {
+        is_final(self.status)
+    }
+}
+
+fn is_final(status: TransactionStatus) -> bool {
+    matches!(
+        status,
+        TransactionStatus::Confirmed | TransactionStatus::Failed | TransactionStatus::Expired
+    )
+}
+
+/// Follows expected withdrawals until they are confirmed, fail or expire.
+///
+/// Withdrawals can be added with [`track`](Self::track) while [`run`](Self::run) is in
+/// progress, by sharing the tracker behind an `Arc`.
+pub struct WithdrawalTracker {
+    sparkscan: SparkScan,
+    poll_interval: Duration,
+    timeout: Duration,
+    fallback: RestFallback,
+    withdrawals: Mutex<Withdrawals>,
+    transition_callbacks: Mutex<Vec<TransitionCallback>>,
+    timeout_callbacks: Mutex<Vec<TimeoutCallback>>,
+}
+
+impl WithdrawalTracker {
+    /// Create a tracker of the withdrawals on the network of `sparkscan`.
+    pub fn new(sparkscan: SparkScan) -> Self {
+        Self {
+            sparkscan,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            timeout: DEFAULT_TIMEOUT,
+            fallback: RestFallback::default(),
+            withdrawals: Mutex::new(Withdrawals::default()),
+            transition_callbacks: Mutex::new(Vec::new()),
+            timeout_callbacks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Set the interval between REST lookups of withdrawals without a final status.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Set the time after which, and every multiple of which, a withdrawal without a final
+    /// status is escalated to the [timeout callbacks](Self::on_timeout).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set when and how often withdrawals are looked up through the REST API while the feed
+    /// is down.
+    pub fn with_rest_fallback(mut self, fallback: RestFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    fn withdrawals(&self) -> MutexGuard<'_, Withdrawals> {
+        self.withdrawals
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Start tracking the withdrawal with Bitcoin transaction id `txid`.
+    ///
+    /// Tracking a withdrawal again has no effect.
+    pub fn track(&self, txid: BitcoinTxid) {
+        self.withdrawals().track(txid, Instant::now());
+    }
+
+    /// Get the withdrawals without a final status, with their last known status.
+    pub fn pending(&self) -> Vec<(BitcoinTxid, Option<TransactionStatus>)> {
+        self.withdrawals()
+            .pending
+            .iter()
+            .map(|(txid, pending)| (txid.clone(), pending.status))
+            .collect()
+    }
+
+    /// Register a callback for the status changes of the tracked withdrawals.
+    pub fn on_transition<F>(&self, callback: F)
+    where
+        F: Fn(&WithdrawalUpdate) + Send + Sync + 'static,
+    {
+        self.transition_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Register a callback for the withdrawals still without a final status after the timeout.
+    ///
+    /// The callback receives the txid, the time since it was tracked and the escalation level,
+    /// which starts at 1 and grows by one every further timeout.
+    pub fn on_timeout<F>(&self, callback: F)
+    where
+        F: Fn(&BitcoinTxid, Duration, u32) + Send + Sync + 'static,
+    {
+        self.timeout_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Follow the tracked withdrawals until none is left without a final status.
+    ///
+    /// Subscribes to the transactions of the network for the duration of the call.
+    pub async fn run(&self) -> Result<(), crate::Error> {
+        let network = self.sparkscan.network();
+        let subscription = self
+            .sparkscan
+            .ws()
+            .subscribe(Topic::TransactionNetwork(network.as_str().to_lowercase()))
+            .await?;
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        subscription.on_message(move |message| {
+            if let SparkScanMessage::Transaction(transaction) = message {
+                let _ = sender.send(transaction);
+            }
+        });
+        subscription.subscribe();
+
+        let mut poll = tokio::time::interval(self.poll_interval);
+        let mut check = tokio::time::interval(self.timeout.min(self.poll_interval));
+        let mut degraded = Degraded::new(self.fallback, self.sparkscan.feed());
+        let mut fallback = tokio::time::interval(degraded.poll_interval());
+        while !self.withdrawals().pending.is_empty() {
+            tokio::select! {
+                Some(transaction) = messages.recv() => {
+                    let status: TransactionStatus = transaction.status.into();
+                    if let Some(txid) = transaction.bitcoin_txid {
+                        self.observe(&txid, status, StatusSource::Stream);
+                    }
+                }
+                _ = poll.tick() => self.poll().await,
+                _ = check.tick() => self.escalate(),
+                _ = fallback.tick() => {
+                    if degraded.should_poll(self.sparkscan.feed(), Instant::now()) {
+                        self.poll().await;
+                    }
+                }
+            }
+        }
+
+        subscription.unsubscribe();
+        Ok(())
+    }
+
+    /// Look up the withdrawals the feed has not settled through the REST API.
+    async fn poll(&self) {
+        let txids: Vec<BitcoinTxid> = self.withdrawals().pending.keys().cloned().collect();
+        for txid in txids {
+            let response = self
+                .sparkscan
+                .client()
+                .get_transaction_details_by_id_v1_tx_txid_get()
+                .txid(txid.as_str())
+                .network(self.sparkscan.network())
+                .send()
+                .await;
+            // Lookups failing or not finding the transaction yet are retried on the next poll;
+            // both transaction shapes returned by the endpoint carry the status
+            let status = response
+                .ok()
+                .and_then(|response| serde_json::to_value(response.into_inner()).ok())
+                .and_then(|value| value["status"].as_str()?.parse().ok());
+            if let Some(status) = status {
+                self.observe(txid.as_str(), status, StatusSource::Rest);
+            }
+        }
+    }
+
+    fn observe(&self, txid: &str, status: TransactionStatus, source: StatusSource) {
+        let update = self.withdrawals().observe(txid, status, source);
+        if let Some(update) = update {
+            for callback in self
+                .transition_callbacks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+            {
+                callback(&update);
+            }
+        }
+    }
+
+    fn escalate(&self) {
+        let overdue = self.withdrawals().overdue(Instant::now(), self.timeout);
+        let callbacks = self
+            .timeout_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for (txid, elapsed, escalation) in overdue {
+            for callback in callbacks.iter() {
+                callback(&txid, elapsed, escalation);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for WithdrawalTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WithdrawalTracker")
+            .field("poll_interval", &self.poll_interval)
+            .field("timeout", &self.timeout)
+            .field("fallback", &self.fallback)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Withdrawals without a final status.
+#[derive(Default)]
+struct Withdrawals {
+    pending: HashMap<BitcoinTxid, Pending>,
+}
+
+struct Pending {
+    status: Option<TransactionStatus>,
+    tracked_at: Instant,
+    escalations: u32,
+}
+
+impl Withdrawals {
+    fn track(&mut self, txid: BitcoinTxid, now: Instant) {
+        self.pending.entry(txid).or_insert(Pending {
+            status: None,
+            tracked_at: now,
+            escalations: 0,
+        });
+    }
+
+    /// Record the status of `txid`, returning the change if it is tracked and the status is new.
+    fn observe(
+        &mut self,
+        txid: &str,
+        status: TransactionStatus,
+        source: StatusSource,
+    ) -> Option<WithdrawalUpdate> {
+        let txid: BitcoinTxid = txid.parse().ok()?;
+        let pending = self.pending.get_mut(&txid)?;
+        if pending.status == Some(status) {
+            return None;
+        }
+        let previous = pending.status.replace(status);
+        if is_final(status) {
+            self.pending.remove(&txid);
+        }
+        Some(WithdrawalUpdate {
+            txid,
+            previous,
+            status,
+            source,
+        })
+    }
+
+    /// Get the withdrawals due for another escalation, with their age and escalation level.
+    fn overdue(&mut self, now: Instant, timeout: Duration) -> Vec<(BitcoinTxid, Duration, u32)> {
+        let mut overdue = Vec::new();
+        for (txid, pending) in &mut self.pending {
+            let elapsed = now.saturating_duration_since(pending.tracked_at);
+            let due = timeout.saturating_mul(pending.escalations + 1);
+            if elapsed >= due {
+                pending.escalations += 1;
+                overdue.push((txid.clone(), elapsed, pending.escalations));
+            }
+        }
+        overdue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn txid(c: char) -> BitcoinTxid {
+        c.to_string().repeat(64).parse().unwrap()
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        let mut withdrawals = Withdrawals::default();
+        withdrawals.track(txid('a'), Instant::now());
+
+        let update = withdrawals
+            .observe(
+                &"A".repeat(64),
+                TransactionStatus::Pending,
+                StatusSource::Stream,
+            )
+            .unwrap();
+        assert_eq!(update.previous, None);
+        assert!(!update.is_final());
+        assert!(
+            withdrawals
+                .observe(
+                    &"a".repeat(64),
+                    TransactionStatus::Pending,
+                    StatusSource::Rest
+                )
+                .is_none()
+        );
+
+        let update = withdrawals
+            .observe(
+                &"a".repeat(64),
+                TransactionStatus::Confirmed,
+                StatusSource::Rest,
+            )
+            .unwrap();
+        assert_eq!(update.previous, Some(TransactionStatus::Pending));
+        assert_eq!(update.source, StatusSource::Rest);
+        assert!(update.is_final());
+        assert!(withdrawals.pending.is_empty());
+
+        // Untracked and malformed txids are ignored
+        assert!(
+            withdrawals
+                .observe(
+                    &"b".repeat(64),
+                    TransactionStatus::Pending,
+                    StatusSource::Stream
+                )
+                .is_none()
+        );
+        assert!(
+            withdrawals
+                .observe(
+                    "not a txid",
+                    TransactionStatus::Pending,
+                    StatusSource::Stream
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_timeout_escalation() {
+        let start = Instant::now();
+        let timeout = Duration::from_secs(60);
+        let mut withdrawals = Withdrawals::default();
+        withdrawals.track(txid('a'), start);
+
+        assert!(withdrawals.overdue(start + timeout / 2, timeout).is_empty());
+        let overdue = withdrawals.overdue(start + timeout, timeout);
+        assert_eq!(overdue, [(txid('a'), timeout, 1)]);
+        assert!(withdrawals.overdue(start + timeout, timeout).is_empty());
+        assert_eq!(withdrawals.overdue(start + timeout * 2, timeout)[0].2, 2);
+    }
+}
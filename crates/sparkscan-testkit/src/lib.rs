@@ -0,0 +1,344 @@
+//! Recorded SparkScan REST responses served by a mock server, for offline tests.
+//!
+//! [`SparkScanMock`] starts a [`wiremock`] server answering every endpoint of the API with a
+//! recorded response, and hands out clients pointed at it:
+//!
+//! ```rust,no_run
+//! use sparkscan::Network;
+//! use sparkscan_testkit::SparkScanMock;
+//!
+//! # tokio_test::block_on(async {
+//! let mock = SparkScanMock::start().await;
+//! let stats = mock.api(Network::Mainnet).stats().summary().await.unwrap();
+//! assert_eq!(stats.active_accounts, 42);
+//!
+//! // Override an endpoint, e.g. to test error handling
+//! mock.respond_with(
+//!     "get_network_stats_v1_stats_summary_get",
+//!     503,
+//!     serde_json::json!({ "detail": "maintenance" }),
+//! )
+//! .await;
+//! assert!(mock.api(Network::Mainnet).stats().summary().await.is_err());
+//! # });
+//! ```
+//!
+//! The fixtures match the schemas of the bundled OpenAPI document, and are available on their
+//! own through [`FIXTURES`] and [`fixture`]. Every endpoint is served regardless of the endpoint
+//! features of `sparkscan`, which only decide what the clients can call.
+
+use sparkscan::{Client, Network, SparkScanApi};
+use wiremock::matchers::{method, path, path_regex};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+/// A recorded response of an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixture {
+    /// Operation id of the endpoint, as in the OpenAPI document
+    pub operation_id: &'static str,
+    /// HTTP method, in upper case
+    pub method: &'static str,
+    /// Path template, e.g. `/v1/address/{address}`
+    pub path: &'static str,
+    /// JSON body of the `200 OK` response
+    pub body: &'static str,
+}
+
+impl Fixture {
+    /// Get the body as JSON.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(self.body).expect("fixtures are valid JSON")
+    }
+
+    /// Build a mock of the endpoint answering with `status` and `body`.
+    fn mock(&self, status: u16, body: impl Into<Vec<u8>>) -> Mock {
+        let builder = Mock::given(method(self.method));
+        let builder = if self.path.contains('{') {
+            builder.and(path_regex(path_pattern(self.path)))
+        } else {
+            builder.and(path(self.path))
+        };
+        builder
+            .respond_with(ResponseTemplate::new(status).set_body_raw(body, "application/json"))
+            .named(self.operation_id)
+    }
+}
+
+macro_rules! fixture {
+    ($method:literal, $path:literal, $operation_id:literal) => {
+        Fixture {
+            operation_id: $operation_id,
+            method: $method,
+            path: $path,
+            body: include_str!(concat!("../fixtures/", $operation_id, ".json")),
+        }
+    };
+}
+
+/// Recorded responses of every endpoint of the API.
+pub const FIXTURES: &[Fixture] = &[
+    fixture!("GET", "/", "root__get"),
+    fixture!(
+        "GET",
+        "/v1/address/{address}",
+        "address_summary_v1_address__address__get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/address/{address}/transactions",
+        "get_address_transactions_v1_address__address__transactions_get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/address/{address}/tokens",
+        "get_address_tokens_v1_address__address__tokens_get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/tx/latest",
+        "get_latest_transactions_v1_tx_latest_get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/tx/{txid}",
+        "get_transaction_details_by_id_v1_tx__txid__get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/stats/summary",
+        "get_network_stats_v1_stats_summary_get"
+    ),
+    fixture!("GET", "/v1/stats/tpv", "get_tpv_stats_v1_stats_tpv_get"),
+    fixture!(
+        "GET",
+        "/v1/stats/leaderboard/wallets",
+        "get_wallet_leaderboard_v1_stats_leaderboard_wallets_get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/stats/leaderboard/tokens",
+        "get_token_leaderboard_v1_stats_leaderboard_tokens_get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/stats/historical/tvl",
+        "get_historical_tvl_v1_stats_historical_tvl_get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/stats/historical/active-wallets",
+        "get_historical_active_wallets_v1_stats_historical_active_wallets_get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/stats/historical/tpv",
+        "get_historical_tpv_v1_stats_historical_tpv_get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/tokens/{identifier}",
+        "get_token_info_by_identifier_v1_tokens__identifier__get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/tokens/{identifier}/transactions",
+        "get_token_transactions_v1_tokens__identifier__transactions_get"
+    ),
+    fixture!(
+        "GET",
+        "/v1/tokens/{identifier}/holders",
+        "get_token_holders_v1_tokens__identifier__holders_get"
+    ),
+    fixture!(
+        "POST",
+        "/v1/tokens/metadata/batch",
+        "get_batch_token_metadata_v1_tokens_metadata_batch_post"
+    ),
+    fixture!(
+        "POST",
+        "/v1/tokens/issuer-lookup",
+        "token_issuer_lookup_v1_tokens_issuer_lookup_post"
+    ),
+    fixture!(
+        "POST",
+        "/v1/bitcoin/addresses/latest-txid",
+        "get_addresses_latest_txid_v1_bitcoin_addresses_latest_txid_post"
+    ),
+];
+
+/// Get the recorded response of the endpoint `operation_id`.
+pub fn fixture(operation_id: &str) -> Option<&'static Fixture> {
+    FIXTURES
+        .iter()
+        .find(|fixture| fixture.operation_id == operation_id)
+}
+
+/// Priority of the fixtures with a templated path, below the literal ones so that e.g.
+/// `/v1/tx/latest` is not answered as `/v1/tx/{txid}`.
+const TEMPLATED_PRIORITY: u8 = 10;
+
+/// Priority of the responses set with [`SparkScanMock::respond_with`], above every fixture.
+const OVERRIDE_PRIORITY: u8 = 1;
+
+/// A mock SparkScan API, serving recorded responses.
+///
+/// The server shuts down when the mock is dropped.
+pub struct SparkScanMock {
+    server: MockServer,
+}
+
+impl SparkScanMock {
+    /// Start a mock answering every endpoint with its fixture.
+    pub async fn start() -> Self {
+        let mock = Self::start_empty().await;
+        for fixture in FIXTURES {
+            mock.mount_fixture(fixture).await;
+        }
+        mock
+    }
+
+    /// Start a mock without any endpoint, answering every request with `404 Not Found`.
+    pub async fn start_empty() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// Get the base URL of the mock.
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Get the underlying server, to mount custom [`Mock`]s.
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// Create a client issuing requests to the mock.
+    pub fn client(&self) -> Client {
+        Client::new(&self.server.uri())
+    }
+
+    /// Create a facade issuing requests for `network` to the mock.
+    pub fn api(&self, network: Network) -> SparkScanApi {
+        SparkScanApi::new(self.client(), network)
+    }
+
+    /// Answer the endpoint of `fixture` with its recorded response.
+    pub async fn mount_fixture(&self, fixture: &Fixture) {
+        let mut mock = fixture.mock(200, fixture.body);
+        if fixture.path.contains('{') {
+            mock = mock.with_priority(TEMPLATED_PRIORITY);
+        }
+        self.server.register(mock).await;
+    }
+
+    /// Answer the endpoint `operation_id` with `status` and `body`, instead of its fixture.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `operation_id` is not an operation of the API.
+    pub async fn respond_with(&self, operation_id: &str, status: u16, body: serde_json::Value) {
+        let fixture = fixture(operation_id)
+            .unwrap_or_else(|| panic!("unknown SparkScan operation: {}", operation_id));
+        let body = serde_json::to_vec(&body).expect("JSON values always serialize");
+        let mock = fixture.mock(status, body).with_priority(OVERRIDE_PRIORITY);
+        self.server.register(mock).await;
+    }
+
+    /// Remove every endpoint, including the fixtures, and forget the received requests.
+    pub async fn reset(&self) {
+        self.server.reset().await;
+    }
+
+    /// Get the requests received so far.
+    pub async fn received_requests(&self) -> Vec<Request> {
+        self.server.received_requests().await.unwrap_or_default()
+    }
+}
+
+impl std::fmt::Debug for SparkScanMock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SparkScanMock")
+            .field("uri", &self.server.uri())
+            .finish()
+    }
+}
+
+/// Turn a path template into an anchored regular expression, matching one segment per parameter.
+fn path_pattern(template: &str) -> String {
+    let mut pattern = String::from("^");
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        escape_into(&mut pattern, &rest[..start]);
+        pattern.push_str("[^/]+");
+        rest = rest[start..].split_once('}').map_or("", |(_, after)| after);
+    }
+    escape_into(&mut pattern, rest);
+    pattern.push('$');
+    pattern
+}
+
+fn escape_into(pattern: &mut String, literal: &str) {
+    for c in literal.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sparkscan::{SparkAddress, openapi, schema};
+
+    #[test]
+    fn test_fixtures_cover_operations() {
+        assert_eq!(FIXTURES.len(), openapi::operations().len());
+        for operation in openapi::operations() {
+            let fixture = fixture(operation.operation_id).unwrap();
+            assert_eq!(
+                (fixture.method, fixture.path),
+                (operation.method, operation.path)
+            );
+            let mismatches =
+                schema::validate_body(fixture.operation_id, 200, &fixture.json()).unwrap();
+            assert!(
+                mismatches.is_empty(),
+                "{}: {:?}",
+                fixture.operation_id,
+                mismatches
+            );
+        }
+        assert_eq!(
+            path_pattern("/v1/address/{address}/tokens"),
+            r"^/v1/address/[^/]+/tokens$"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_serves_fixtures() {
+        let mock = SparkScanMock::start().await;
+        let api = mock.api(Network::Mainnet);
+
+        let stats = api.stats().summary().await.unwrap();
+        assert_eq!(stats.active_accounts, 42);
+        let latest = api.latest_transactions_page(0, 10).await.unwrap();
+        assert!(!latest.items.is_empty());
+        let address: SparkAddress =
+            "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k"
+                .parse()
+                .unwrap();
+        api.address(&address).summary().await.unwrap();
+
+        mock.respond_with(
+            "get_network_stats_v1_stats_summary_get",
+            500,
+            serde_json::json!({ "detail": "internal error" }),
+        )
+        .await;
+        assert!(api.stats().summary().await.is_err());
+        assert_eq!(mock.received_requests().await.len(), 4);
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Error returned when a string is not a valid amount.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAmountError {
+    kind: &'static str,
+    value: String,
+}
+
+impl ParseAmountError {
+    /// Name of the amount type that failed to parse, e.g. `"sats amount"`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// The rejected input.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid {} {:?}: expected a non-negative integer",
+            self.kind, self.value
+        )
+    }
+}
+
+impl std::error::Error for ParseAmountError {}
+
+macro_rules! amount_newtype {
+    ($name:ident, $inner:ty, $kind:literal) => {
+        impl $name {
+            /// Zero.
+            pub const ZERO: Self = Self(0);
+
+            /// Get the raw value.
+            pub fn value(self) -> $inner {
+                self.0
+            }
+
+            /// Add `other`, returning `None` on overflow.
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map(Self)
+            }
+
+            /// Subtract `other`, returning `None` if the result would be negative.
+            pub fn checked_sub(self, other: Self) -> Option<Self> {
+                self.0.checked_sub(other.0).map(Self)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl From<$inner> for $name {
+            fn from(value: $inner) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        /// Converts the integers of the REST API, which are decoded as `i128`.
+        impl TryFrom<i128> for $name {
+            type Error = std::num::TryFromIntError;
+
+            fn try_from(value: i128) -> Result<Self, Self::Error> {
+                <$inner>::try_from(value).map(Self)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseAmountError;
+
+            /// Parse a decimal integer, as sent by the WebSocket feed (e.g. `"2229"`).
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                // `<$inner>::from_str` accepts a leading `+`, which the API never sends
+                if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+                    if let Ok(amount) = value.parse() {
+                        return Ok(Self(amount));
+                    }
+                }
+                Err(ParseAmountError {
+                    kind: $kind,
+                    value: value.to_string(),
+                })
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serde::Serialize::serialize(&self.0, serializer)
+            }
+        }
+
+        /// Accepts both JSON numbers (REST API) and decimal strings (WebSocket feed).
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct Visitor;
+
+                impl serde::de::Visitor<'_> for Visitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        write!(f, "a non-negative integer or a decimal string")
+                    }
+
+                    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<$name, E> {
+                        <$inner>::try_from(value)
+                            .map($name)
+                            .map_err(|_| E::custom(concat!($kind, " out of range")))
+                    }
+
+                    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<$name, E> {
+                        <$inner>::try_from(value)
+                            .map($name)
+                            .map_err(|_| E::custom(concat!($kind, " out of range")))
+                    }
+
+                    fn visit_u128<E: serde::de::Error>(self, value: u128) -> Result<$name, E> {
+                        <$inner>::try_from(value)
+                            .map($name)
+                            .map_err(|_| E::custom(concat!($kind, " out of range")))
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<$name, E> {
+                        value.parse().map_err(E::custom)
+                    }
+                }
+
+                deserializer.deserialize_any(Visitor)
+            }
+        }
+    };
+}
+
+/// Amount of bitcoin in satoshis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Sats(pub u64);
+
+impl Sats {
+    /// Number of satoshis in one bitcoin.
+    pub const PER_BTC: u64 = 100_000_000;
+
+    /// Get the amount in bitcoin, for display purposes.
+    pub fn to_btc(self) -> f64 {
+        self.0 as f64 / Self::PER_BTC as f64
+    }
+}
+
+amount_newtype!(Sats, u64, "sats amount");
+
+/// Amount of a token in its smallest unit, before applying the token's decimals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TokenAmount(pub u128);
+
+amount_newtype!(TokenAmount, u128, "token amount");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_amounts() {
+        assert_eq!("2229".parse::<Sats>(), Ok(Sats(2229)));
+        assert_eq!(
+            "2100000000000000".parse::<TokenAmount>(),
+            Ok(TokenAmount(2_100_000_000_000_000))
+        );
+
+        for value in ["", "-1", "+1", "1.5", "18446744073709551616"] {
+            let err = value.parse::<Sats>().unwrap_err();
+            assert_eq!(err.value(), value);
+        }
+    }
+
+    #[test]
+    fn test_sats_arithmetic() {
+        assert_eq!(Sats(150_000_000).to_btc(), 1.5);
+        assert_eq!(Sats(1).checked_sub(Sats(2)), None);
+        assert_eq!(Sats(1).checked_add(Sats(2)), Some(Sats(3)));
+        assert_eq!(Sats::ZERO.to_string(), "0");
+    }
+
+    #[test]
+    fn test_try_from_i128() {
+        assert_eq!(Sats::try_from(379i128), Ok(Sats(379)));
+        assert!(Sats::try_from(-1i128).is_err());
+        assert!(Sats::try_from(i128::from(u64::MAX) + 1).is_err());
+        assert_eq!(
+            TokenAmount::try_from(i128::MAX),
+            Ok(TokenAmount(i128::MAX as u128))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_numbers_and_strings() {
+        assert_eq!(serde_json::from_str::<Sats>("379").unwrap(), Sats(379));
+        assert_eq!(serde_json::from_str::<Sats>("\"379\"").unwrap(), Sats(379));
+        assert!(serde_json::from_str::<Sats>("-1").is_err());
+        assert_eq!(serde_json::to_string(&Sats(379)).unwrap(), "379");
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Error returned when a string does not name a known variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseEnumError {
+    kind: &'static str,
+    value: String,
+}
+
+impl ParseEnumError {
+    /// Name of the enum that failed to parse, e.g. `"network"`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// The rejected input.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for ParseEnumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown {}: {}", self.kind, self.value)
+    }
+}
+
+impl std::error::Error for ParseEnumError {}
+
+/// Define a fieldless enum whose variants map one-to-one onto wire strings.
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident, $kind:literal, case_insensitive = $case_insensitive:literal {
+            $($(#[$variant_meta:meta])* $variant:ident => $wire:literal,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)+
+        }
+
+        impl $name {
+            /// Every variant, in declaration order.
+            pub const ALL: &'static [Self] = &[$(Self::$variant,)+];
+
+            /// Get the name as sent by the API.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $wire,)+
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseEnumError;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                Self::ALL
+                    .iter()
+                    .copied()
+                    .find(|variant| {
+                        if $case_insensitive {
+                            variant.as_str().eq_ignore_ascii_case(value)
+                        } else {
+                            variant.as_str() == value
+                        }
+                    })
+                    .ok_or_else(|| ParseEnumError {
+                        kind: $kind,
+                        value: value.to_string(),
+                    })
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                value.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+string_enum! {
+    /// Spark network.
+    ///
+    /// The REST API serves [`Network::Mainnet`] and [`Network::Regtest`]; the WebSocket feed also
+    /// reports the other networks. Parsing ignores case.
+    #[derive(Default)]
+    Network, "network", case_insensitive = true {
+        /// Spark mainnet
+        #[default]
+        Mainnet => "MAINNET",
+        /// Spark regtest
+        Regtest => "REGTEST",
+        /// Spark testnet
+        Testnet => "TESTNET",
+        /// Spark signet
+        Signet => "SIGNET",
+        /// Spark load-testing network
+        Loadtest => "LOADTEST",
+    }
+}
+
+string_enum! {
+    /// Status of a Spark transaction.
+    TransactionStatus, "transaction status", case_insensitive = false {
+        /// Settled
+        Confirmed => "confirmed",
+        /// Not settled yet
+        Pending => "pending",
+        /// Sent but not yet claimed by the receiver
+        Sent => "sent",
+        /// Failed
+        Failed => "failed",
+        /// Expired before settling
+        Expired => "expired",
+    }
+}
+
+string_enum! {
+    /// Kind of a Spark transaction.
+    ///
+    /// The REST API and the WebSocket feed name transfers differently (`spark_transfer` versus
+    /// `spark_to_spark`); both vocabularies are represented so that values round-trip unchanged.
+    TransactionType, "transaction type", case_insensitive = false {
+        /// Transfer between Spark addresses
+        SparkTransfer => "spark_transfer",
+        /// Lightning payment into or out of Spark
+        LightningPayment => "lightning_payment",
+        /// Deposit from Bitcoin L1
+        BitcoinDeposit => "bitcoin_deposit",
+        /// Withdrawal to Bitcoin L1
+        BitcoinWithdrawal => "bitcoin_withdrawal",
+        /// Token transfer
+        TokenTransfer => "token_transfer",
+        /// Token mint
+        TokenMint => "token_mint",
+        /// Token burn
+        TokenBurn => "token_burn",
+        /// Token transfer with several outputs
+        TokenMultiTransfer => "token_multi_transfer",
+        /// Unrecognized token operation
+        UnknownTokenOp => "unknown_token_op",
+        /// Unrecognized transfer
+        UnknownTransfer => "unknown_transfer",
+        /// Deposit from Bitcoin L1, as named by the WebSocket feed
+        BitcoinToSpark => "bitcoin_to_spark",
+        /// Incoming Lightning payment, as named by the WebSocket feed
+        LightningToSpark => "lightning_to_spark",
+        /// Withdrawal to Bitcoin L1, as named by the WebSocket feed
+        SparkToBitcoin => "spark_to_bitcoin",
+        /// Outgoing Lightning payment, as named by the WebSocket feed
+        SparkToLightning => "spark_to_lightning",
+        /// Transfer between Spark addresses, as named by the WebSocket feed
+        SparkToSpark => "spark_to_spark",
+        /// Unrecognized transaction, as named by the WebSocket feed
+        Unknown => "unknown",
+    }
+}
+
+impl TransactionType {
+    /// Get the REST name of a kind named by the WebSocket feed, e.g. [`Self::SparkTransfer`] for
+    /// [`Self::SparkToSpark`], so that both sources compare equal.
+    ///
+    /// The REST vocabulary does not tell incoming and outgoing Lightning payments apart, so both
+    /// map onto [`Self::LightningPayment`]. Other kinds are returned unchanged.
+    pub fn canonical(&self) -> Self {
+        match self {
+            Self::BitcoinToSpark => Self::BitcoinDeposit,
+            Self::SparkToBitcoin => Self::BitcoinWithdrawal,
+            Self::LightningToSpark | Self::SparkToLightning => Self::LightningPayment,
+            Self::SparkToSpark => Self::SparkTransfer,
+            Self::Unknown => Self::UnknownTransfer,
+            other => *other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network() {
+        assert_eq!(Network::default(), Network::Mainnet);
+        assert_eq!(Network::Regtest.to_string(), "REGTEST");
+        assert_eq!("regtest".parse::<Network>(), Ok(Network::Regtest));
+        assert_eq!("LOADTEST".parse::<Network>(), Ok(Network::Loadtest));
+
+        let err = "moonnet".parse::<Network>().unwrap_err();
+        assert_eq!(err.kind(), "network");
+        assert_eq!(err.to_string(), "unknown network: moonnet");
+    }
+
+    #[test]
+    fn test_transaction_enums_round_trip() {
+        for status in TransactionStatus::ALL {
+            assert_eq!(status.as_str().parse::<TransactionStatus>(), Ok(*status));
+        }
+        for kind in TransactionType::ALL {
+            assert_eq!(kind.as_str().parse::<TransactionType>(), Ok(*kind));
+        }
+        assert!("Confirmed".parse::<TransactionStatus>().is_err());
+    }
+
+    #[test]
+    fn test_canonical_transaction_type() {
+        assert_eq!(
+            TransactionType::SparkToSpark.canonical(),
+            TransactionType::SparkTransfer
+        );
+        assert_eq!(
+            TransactionType::SparkToLightning.canonical(),
+            TransactionType::LightningPayment
+        );
+        for kind in TransactionType::ALL {
+            assert_eq!(kind.canonical().canonical(), kind.canonical());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_as_string() {
+        assert_eq!(
+            serde_json::to_string(&TransactionType::SparkToSpark).unwrap(),
+            "\"spark_to_spark\""
+        );
+        assert_eq!(
+            serde_json::from_str::<Network>("\"MAINNET\"").unwrap(),
+            Network::Mainnet
+        );
+        assert!(serde_json::from_str::<TransactionStatus>("\"lost\"").is_err());
+    }
+}
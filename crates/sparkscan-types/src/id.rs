@@ -0,0 +1,313 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Characters allowed in the data part of a bech32 string.
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Length of the bech32 checksum, the minimum length of a data part.
+const BECH32_CHECKSUM_LEN: usize = 6;
+
+/// Human-readable parts of Spark addresses (mainnet, regtest, testnet, signet, local).
+const SPARK_ADDRESS_HRPS: &[&str] = &["sp", "sprt", "spt", "sps", "spl"];
+
+/// Human-readable parts of token identifiers (mainnet, regtest, testnet, signet, local).
+const TOKEN_IDENTIFIER_HRPS: &[&str] = &["btkn", "btknrt", "btknt", "btkns", "btknl"];
+
+/// Error returned when a string is not a valid identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIdError {
+    kind: &'static str,
+    value: String,
+    reason: &'static str,
+}
+
+impl ParseIdError {
+    fn new(kind: &'static str, value: &str, reason: &'static str) -> Self {
+        Self {
+            kind,
+            value: value.to_string(),
+            reason,
+        }
+    }
+
+    /// Name of the identifier type that failed to parse, e.g. `"Spark address"`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// The rejected input.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for ParseIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {} {:?}: {}", self.kind, self.value, self.reason)
+    }
+}
+
+impl std::error::Error for ParseIdError {}
+
+/// Check the shape of a bech32 string with one of `hrps` and return it in lowercase.
+///
+/// The checksum is not verified; the API remains the authority on whether the identifier exists.
+fn parse_bech32(
+    kind: &'static str,
+    value: &str,
+    hrps: &[&str],
+) -> Result<(String, usize), ParseIdError> {
+    let has_lower = value.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = value.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return Err(ParseIdError::new(kind, value, "mixed case"));
+    }
+    let normalized = value.to_ascii_lowercase();
+
+    let Some(separator) = normalized.rfind('1') else {
+        return Err(ParseIdError::new(kind, value, "missing separator"));
+    };
+    let (hrp, data) = (&normalized[..separator], &normalized[separator + 1..]);
+    if !hrps.contains(&hrp) {
+        return Err(ParseIdError::new(kind, value, "unknown prefix"));
+    }
+    if data.len() < BECH32_CHECKSUM_LEN {
+        return Err(ParseIdError::new(kind, value, "too short"));
+    }
+    if !data.chars().all(|c| BECH32_CHARSET.contains(c)) {
+        return Err(ParseIdError::new(kind, value, "invalid character"));
+    }
+
+    Ok((normalized, separator))
+}
+
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+macro_rules! string_newtype {
+    ($name:ident) => {
+        impl $name {
+            /// Get the identifier as a string slice.
+            pub fn as_str(&self) -> &str {
+                &self.value
+            }
+
+            /// Convert into the underlying string.
+            pub fn into_string(self) -> String {
+                self.value
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.value)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.value
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = ParseIdError;
+
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = ParseIdError;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.value
+            }
+        }
+
+        impl From<&$name> for String {
+            fn from(value: &$name) -> Self {
+                value.value.clone()
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.value)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                value.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+/// Spark address, e.g. `sp1pgss...` on mainnet or `sprt1pgss...` on regtest.
+///
+/// Parsing checks the network prefix and the bech32 character set and normalizes to lowercase.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SparkAddress {
+    value: String,
+    separator: usize,
+}
+
+impl SparkAddress {
+    /// Get the human-readable prefix, e.g. `"sp"` or `"sprt"`.
+    pub fn hrp(&self) -> &str {
+        &self.value[..self.separator]
+    }
+}
+
+impl FromStr for SparkAddress {
+    type Err = ParseIdError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (value, separator) = parse_bech32("Spark address", value, SPARK_ADDRESS_HRPS)?;
+        Ok(Self { value, separator })
+    }
+}
+
+string_newtype!(SparkAddress);
+
+/// Token identifier, either bech32 (`btkn1...` on mainnet, `btknrt1...` on regtest) or the
+/// 64-character hex form.
+///
+/// Parsing normalizes to lowercase.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TokenIdentifier {
+    value: String,
+}
+
+impl TokenIdentifier {
+    /// Check if the identifier is in its bech32 form.
+    pub fn is_bech32(&self) -> bool {
+        !is_hex(&self.value, 64)
+    }
+}
+
+impl FromStr for TokenIdentifier {
+    type Err = ParseIdError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if is_hex(value, 64) {
+            return Ok(Self {
+                value: value.to_ascii_lowercase(),
+            });
+        }
+        let (value, _) = parse_bech32("token identifier", value, TOKEN_IDENTIFIER_HRPS)?;
+        Ok(Self { value })
+    }
+}
+
+string_newtype!(TokenIdentifier);
+
+/// Bitcoin transaction id: 64 hexadecimal characters, normalized to lowercase.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BitcoinTxid {
+    value: String,
+}
+
+impl FromStr for BitcoinTxid {
+    type Err = ParseIdError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if !is_hex(value, 64) {
+            return Err(ParseIdError::new(
+                "Bitcoin txid",
+                value,
+                "expected 64 hexadecimal characters",
+            ));
+        }
+        Ok(Self {
+            value: value.to_ascii_lowercase(),
+        })
+    }
+}
+
+string_newtype!(BitcoinTxid);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k";
+
+    #[test]
+    fn test_spark_address() {
+        let address: SparkAddress = ADDRESS.parse().unwrap();
+        assert_eq!(address.hrp(), "sp");
+        assert_eq!(address.as_str(), ADDRESS);
+
+        let upper: SparkAddress = ADDRESS.to_uppercase().parse().unwrap();
+        assert_eq!(upper, address);
+
+        let regtest: SparkAddress = "sprt1pgssyv42njtx".parse().unwrap();
+        assert_eq!(regtest.hrp(), "sprt");
+    }
+
+    #[test]
+    fn test_spark_address_rejections() {
+        let reason = |value: &str| value.parse::<SparkAddress>().unwrap_err().reason;
+        assert_eq!(
+            reason("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"),
+            "unknown prefix"
+        );
+        assert_eq!(reason("sp1pgssyv42njtXa7k"), "mixed case");
+        assert_eq!(reason("sp1pgsb42njtx"), "invalid character");
+        assert_eq!(reason("sp1pgs"), "too short");
+        assert_eq!(reason("btkn"), "missing separator");
+    }
+
+    #[test]
+    fn test_token_identifier() {
+        let bech32: TokenIdentifier = "btkn1qpzry9x8gf2tvdw0".parse().unwrap();
+        assert!(bech32.is_bech32());
+
+        let hex = "AB".repeat(32);
+        let hex: TokenIdentifier = hex.parse().unwrap();
+        assert!(!hex.is_bech32());
+        assert_eq!(hex.as_str(), "ab".repeat(32));
+
+        assert!(ADDRESS.parse::<TokenIdentifier>().is_err());
+    }
+
+    #[test]
+    fn test_bitcoin_txid() {
+        let txid: BitcoinTxid = "F".repeat(64).parse().unwrap();
+        assert_eq!(txid.to_string(), "f".repeat(64));
+        assert!("f".repeat(63).parse::<BitcoinTxid>().is_err());
+        assert!("g".repeat(64).parse::<BitcoinTxid>().is_err());
+    }
+
+    #[test]
+    fn test_string_conversions() {
+        let address = SparkAddress::try_from(ADDRESS).unwrap();
+        assert_eq!(String::from(&address), ADDRESS);
+        assert_eq!(address.into_string(), ADDRESS);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_as_string() {
+        let address: SparkAddress = serde_json::from_str(&format!("\"{}\"", ADDRESS)).unwrap();
+        assert_eq!(
+            serde_json::to_string(&address).unwrap(),
+            format!("\"{}\"", ADDRESS)
+        );
+        assert!(serde_json::from_str::<SparkAddress>("\"sp1bad\"").is_err());
+    }
+}
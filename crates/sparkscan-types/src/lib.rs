@@ -0,0 +1,30 @@
+//! Domain types shared by the SparkScan API clients.
+//!
+//! Identifiers are validated newtypes, so that passing a token identifier where a Spark address
+//! is expected fails to compile instead of returning an empty result:
+//!
+//! ```rust
+//! use sparkscan_types::{BitcoinTxid, SparkAddress};
+//!
+//! let address: SparkAddress = "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k"
+//!     .parse()
+//!     .unwrap();
+//! assert_eq!(address.hrp(), "sp");
+//!
+//! assert!("not-a-txid".parse::<BitcoinTxid>().is_err());
+//! ```
+//!
+//! [`Network`], [`TransactionStatus`] and [`TransactionType`] carry the names used on the wire, and
+//! [`Sats`] and [`TokenAmount`] parse both the numbers of the REST API and the decimal strings of
+//! the WebSocket feed.
+//!
+//! The crate has no required dependencies; enable the `serde` feature to (de)serialize the types
+//! as plain strings, and amounts as numbers.
+
+mod amount;
+mod enums;
+mod id;
+
+pub use amount::{ParseAmountError, Sats, TokenAmount};
+pub use enums::{Network, ParseEnumError, TransactionStatus, TransactionType};
+pub use id::{BitcoinTxid, ParseIdError, SparkAddress, TokenIdentifier};
@@ -0,0 +1,123 @@
+//! Benchmarks of `parse_message_for_topic` for every payload type and envelope.
+//!
+//! Run with `cargo bench -p sparkscan-ws`, and with `--features high-throughput` to measure the
+//! pooled buffers, which apply to the `double_encoded` and `data_string` envelopes.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::json;
+use sparkscan_ws::{types::parse_message_for_topic, Topic};
+use std::hint::black_box;
+
+fn payloads() -> Vec<(&'static str, Topic, serde_json::Value)> {
+    vec![
+        (
+            "balance",
+            Topic::Balances,
+            json!({
+                "address": "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s",
+                "network": "MAINNET",
+                "soft_balance": "379",
+                "hard_balance": "379",
+                "processed_at": "2025-08-03T13:26:31.271938Z"
+            }),
+        ),
+        (
+            "token_balance",
+            Topic::TokenBalances,
+            json!({
+                "address": "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s",
+                "network": "MAINNET",
+                "token_address": "btkn1daywtenlww42njymqzyegvcwuy3p9f26zknme0srxa7tagewvuys86h553",
+                "balance": "2099110000000000",
+                "processed_at": "2025-08-03T13:26:31.271938Z"
+            }),
+        ),
+        (
+            "token_price",
+            Topic::TokenPrices,
+            json!({
+                "address": "btkn1daywtenlww42njymqzyegvcwuy3p9f26zknme0srxa7tagewvuys86h553",
+                "network": "MAINNET",
+                "protocol": "sparksat",
+                "price_sats": "68.8",
+                "processed_at": "2025-08-02T12:00:00Z"
+            }),
+        ),
+        (
+            "token",
+            Topic::Tokens,
+            json!({
+                "address": "btkn1daywtenlww42njymqzyegvcwuy3p9f26zknme0srxa7tagewvuys86h553",
+                "network": "MAINNET",
+                "name": "FlashSparks",
+                "ticker": "FSPKS",
+                "decimals": 8,
+                "issuer": "sp1pgss98jd2runrstsuyqvrdcjnc6nehwknj9w2zljwnn6dzc9z3803d27rdn5nz",
+                "is_freezable": false,
+                "holders": 3507,
+                "max_supply": "2100000000000000",
+                "circulating_supply": "2099110000000000",
+                "price_sats": "68.8",
+                "pricing_source": "sparksat",
+                "calculated_at": "2025-08-02T12:00:00Z"
+            }),
+        ),
+        (
+            "transaction",
+            Topic::Transactions,
+            json!({
+                "id": "0198741d-2d2b-7e4a-9f42-0d5c2a9b8c11",
+                "network": "MAINNET",
+                "type": "spark_to_spark",
+                "status": "confirmed",
+                "amount_sats": "1000",
+                "processed_at": "2025-08-06T16:28:42.955000Z"
+            }),
+        ),
+        (
+            "transaction_fallback",
+            Topic::Transactions,
+            json!({
+                "id": "0198741d-2d2b-7e4a-9f42-0d5c2a9b8c11",
+                "network": "MAINNET",
+                "type": "token_multi_transfer",
+                "status": "confirmed",
+                "processed_at": "2025-08-06T16:28:42.955000Z",
+                "from_identifier": "invalid_address_format_that_breaks_parsing",
+                "token_address": "invalid_token_address"
+            }),
+        ),
+    ]
+}
+
+/// Encodings of `payload` as published, from the plain object to double-encoded envelopes.
+fn envelopes(payload: &serde_json::Value) -> Vec<(&'static str, String)> {
+    let encoded = payload.to_string();
+    vec![
+        ("direct", encoded.clone()),
+        ("data_object", json!({ "data": payload }).to_string()),
+        ("payload_object", json!({ "payload": payload }).to_string()),
+        ("message_object", json!({ "message": payload }).to_string()),
+        ("double_encoded", json!(encoded).to_string()),
+        ("data_string", json!({ "data": encoded }).to_string()),
+    ]
+}
+
+fn bench_parse_message_for_topic(c: &mut Criterion) {
+    for (name, topic, payload) in payloads() {
+        let mut group = c.benchmark_group(format!("parse_message_for_topic/{}", name));
+        for (envelope, data) in envelopes(&payload) {
+            parse_message_for_topic(&topic, data.as_bytes())
+                .unwrap_or_else(|e| panic!("{} in {} envelope: {}", name, envelope, e));
+
+            group.throughput(Throughput::Bytes(data.len() as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(envelope), &data, |b, data| {
+                b.iter(|| parse_message_for_topic(black_box(&topic), black_box(data.as_bytes())))
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parse_message_for_topic);
+criterion_main!(benches);
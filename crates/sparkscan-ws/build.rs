@@ -1,5 +1,6 @@
 use std::fs;
 use std::path::Path;
+use syn::parse::Parser;
 
 fn indent_code(code: &str) -> String {
     code.lines()
@@ -14,6 +15,88 @@ fn indent_code(code: &str) -> String {
         .join("\n")
 }
 
+/// FNV-1a hash of the schemas, from their compact JSON with sorted keys so that reformatting a
+/// schema does not change it.
+fn schema_hash(schemas: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for schema in schemas {
+        let value: serde_json::Value =
+            serde_json::from_str(schema).expect("Failed to parse schema");
+        for byte in value.to_string().bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Deserialize the timestamp fields of the payload structs with the lenient parser of the
+/// crate, which also accepts timestamps without a timezone.
+fn lenient_timestamps(file: &mut syn::File) {
+    for item in &mut file.items {
+        let syn::Item::Struct(item) = item else {
+            continue;
+        };
+        if !attribute_mentions(&item.attrs, "derive", "Deserialize") {
+            continue;
+        }
+        for field in item.fields.iter_mut() {
+            let Some(function) = timestamp_deserializer(&field.ty) else {
+                continue;
+            };
+            // Missing optional fields keep deserializing to `None`
+            let default = function == "deserialize_option"
+                && !attribute_mentions(&field.attrs, "serde", "default");
+            let attribute = format!(
+                r#"#[serde({}deserialize_with = "crate::timestamp::{}")]"#,
+                if default { "default, " } else { "" },
+                function
+            );
+            field.attrs.extend(
+                syn::Attribute::parse_outer
+                    .parse_str(&attribute)
+                    .expect("Failed to parse serde attribute"),
+            );
+        }
+    }
+}
+
+/// Check whether one of the `name` attributes mentions `token`.
+fn attribute_mentions(attrs: &[syn::Attribute], name: &str, token: &str) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident(name)
+            && attr
+                .meta
+                .require_list()
+                .is_ok_and(|list| list.tokens.to_string().contains(token))
+    })
+}
+
+/// Get the deserializer of a `DateTime` or `Option<DateTime>` field.
+fn timestamp_deserializer(ty: &syn::Type) -> Option<&'static str> {
+    fn last_segment(ty: &syn::Type) -> Option<&syn::PathSegment> {
+        match ty {
+            syn::Type::Path(path) => path.path.segments.last(),
+            _ => None,
+        }
+    }
+    let is_datetime = |ty: &syn::Type| last_segment(ty).is_some_and(|s| s.ident == "DateTime");
+
+    if is_datetime(ty) {
+        return Some("deserialize");
+    }
+    let segment = last_segment(ty).filter(|segment| segment.ident == "Option")?;
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+            Some(syn::GenericArgument::Type(inner)) if is_datetime(inner) => {
+                Some("deserialize_option")
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn main() {
     println!("cargo:rerun-if-changed=schemas/");
 
@@ -32,6 +115,16 @@ fn main() {
     let transaction_schema = fs::read_to_string("schemas/transaction_schema.json")
         .expect("Failed to read transaction_schema.json");
 
+    // Version of the schemas, compared with the one advertised by the server
+    let hash = schema_hash(&[
+        balance_schema.as_str(),
+        token_balance_schema.as_str(),
+        token_price_schema.as_str(),
+        token_schema.as_str(),
+        transaction_schema.as_str(),
+    ]);
+    println!("cargo:rustc-env=SPARKSCAN_WS_SCHEMA_HASH={:016x}", hash);
+
     // Parse schemas into schemars::schema::RootSchema
     let balance_schema: schemars::schema::RootSchema =
         serde_json::from_str(&balance_schema).expect("Failed to parse balance_schema.json");
@@ -84,50 +177,48 @@ fn main() {
     let generated_code_token = type_space_token.to_stream();
     let generated_code_transaction = type_space_transaction.to_stream();
 
-    let parsed_code_balance =
+    let mut parsed_code_balance: syn::File =
         syn::parse2(generated_code_balance).expect("Failed to parse generated code");
+    lenient_timestamps(&mut parsed_code_balance);
     let formatted_code_balance = prettyplease::unparse(&parsed_code_balance);
 
-    let parsed_code_token_balance =
+    let mut parsed_code_token_balance: syn::File =
         syn::parse2(generated_code_token_balance).expect("Failed to parse generated code");
+    lenient_timestamps(&mut parsed_code_token_balance);
     let formatted_code_token_balance = prettyplease::unparse(&parsed_code_token_balance);
 
-    let parsed_code_token_price =
+    let mut parsed_code_token_price: syn::File =
         syn::parse2(generated_code_token_price).expect("Failed to parse generated code");
+    lenient_timestamps(&mut parsed_code_token_price);
     let formatted_code_token_price = prettyplease::unparse(&parsed_code_token_price);
 
-    let parsed_code_token =
+    let mut parsed_code_token: syn::File =
         syn::parse2(generated_code_token).expect("Failed to parse generated code");
+    lenient_timestamps(&mut parsed_code_token);
     let formatted_code_token = prettyplease::unparse(&parsed_code_token);
 
-    let parsed_code_transaction =
+    let mut parsed_code_transaction: syn::File =
         syn::parse2(generated_code_transaction).expect("Failed to parse generated code");
+    lenient_timestamps(&mut parsed_code_transaction);
     let formatted_code_transaction = prettyplease::unparse(&parsed_code_transaction);
 
+    // Lints the typify output trips, which is not ours to fix
+    let allow =
+        "#[allow(clippy::clone_on_copy, clippy::explicit_auto_deref, clippy::needless_borrow)]";
     let contents = format!(
-        "{}\n{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}",
+        "{}\n{}\n\n\
+         {allow}\npub mod balance {{\n{}\n}}\n\n\
+         {allow}\npub mod token_balance {{\n{}\n}}\n\n\
+         {allow}\npub mod token_price {{\n{}\n}}\n\n\
+         {allow}\npub mod token {{\n{}\n}}\n\n\
+         {allow}\npub mod transaction {{\n{}\n}}",
         "// This file is generated by build.rs from JSON schemas. Do not edit manually.",
         "// Generated types for SparkScan WebSocket API messages.",
-        format!(
-            "pub mod balance {{\n{}\n}}",
-            indent_code(&formatted_code_balance)
-        ),
-        format!(
-            "pub mod token_balance {{\n{}\n}}",
-            indent_code(&formatted_code_token_balance)
-        ),
-        format!(
-            "pub mod token_price {{\n{}\n}}",
-            indent_code(&formatted_code_token_price)
-        ),
-        format!(
-            "pub mod token {{\n{}\n}}",
-            indent_code(&formatted_code_token)
-        ),
-        format!(
-            "pub mod transaction {{\n{}\n}}",
-            indent_code(&formatted_code_transaction)
-        )
+        indent_code(&formatted_code_balance),
+        indent_code(&formatted_code_token_balance),
+        indent_code(&formatted_code_token_price),
+        indent_code(&formatted_code_token),
+        indent_code(&formatted_code_transaction)
     );
 
     fs::write(&dest_path, contents).expect("Failed to write generated types");
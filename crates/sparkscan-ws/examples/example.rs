@@ -6,19 +6,35 @@
 //!
 //! Run with: cargo run --example example
 //! Run with debug logging: RUST_LOG=debug cargo run --example example
+//! Print messages as JSON lines: cargo run --example example -- --json | jq .
 
 use sparkscan_ws::{SparkScanMessage, SparkScanWsClient, Topic};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
+/// Print progress to stdout, or to stderr with `--json` so that stdout only carries messages.
+macro_rules! progress {
+    ($json:expr, $($arg:tt)*) => {
+        if $json {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
+    let json = std::env::args().skip(1).any(|arg| arg == "--json");
 
-    println!("SparkScan WebSocket API Example");
-    println!("===============================");
-    println!("Connecting to SparkScan API to receive real-time data...\n");
+    progress!(json, "SparkScan WebSocket API Example");
+    progress!(json, "===============================");
+    progress!(
+        json,
+        "Connecting to SparkScan API to receive real-time data...\n"
+    );
 
     // Create WebSocket client
     let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
@@ -31,7 +47,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     {
         let connected = connected.clone();
         client.on_connected(move || {
-            println!("Connected to SparkScan WebSocket API");
+            progress!(json, "Connected to SparkScan WebSocket API");
             connected.store(true, Ordering::Relaxed);
         });
     }
@@ -41,7 +57,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
 
     // Establish connection
-    println!("Connecting...");
+    progress!(json, "Connecting...");
     client.connect().await?;
 
     // Wait for connection establishment
@@ -49,7 +65,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         if connected.load(Ordering::Relaxed) {
             break;
         }
-        println!("Waiting for connection... ({}/10)", i);
+        progress!(json, "Waiting for connection... ({}/10)", i);
         tokio::time::sleep(Duration::from_secs(1)).await;
     }
 
@@ -57,7 +73,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Err("Failed to establish connection".into());
     }
 
-    println!("Setting up subscriptions...\n");
+    progress!(json, "Setting up subscriptions...\n");
 
     // Subscribe to main data topics
     let topics = vec![
@@ -70,12 +86,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     for (topic, description) in topics {
         let topic_name = topic.as_str();
-        println!("Subscribing to: {} ({})", topic_name, description);
+        progress!(json, "Subscribing to: {} ({})", topic_name, description);
 
         let subscription = client.subscribe(topic).await?;
 
-        subscription.on_subscribed(|| {
-            println!("  Subscription active");
+        subscription.on_subscribed(move || {
+            progress!(json, "  Subscription active");
         });
 
         subscription.on_error(|err| {
@@ -86,6 +102,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let message_count = message_count.clone();
         subscription.on_message(move |message| {
             let count = message_count.fetch_add(1, Ordering::Relaxed) + 1;
+            if json {
+                // One `{"type": ..., "data": ...}` object per line
+                match serde_json::to_string(&message) {
+                    Ok(line) => println!("{}", line),
+                    Err(e) => eprintln!("Failed to serialize message #{}: {}", count, e),
+                }
+                return;
+            }
             println!("\nMessage #{} received:", count);
 
             match message {
@@ -176,9 +200,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         subscription.subscribe();
     }
 
-    println!("\nAll subscriptions configured");
-    println!("Waiting for real-time messages...");
-    println!("Press Ctrl+C to exit\n");
+    progress!(json, "\nAll subscriptions configured");
+    progress!(json, "Waiting for real-time messages...");
+    progress!(json, "Press Ctrl+C to exit\n");
 
     // Wait for messages with periodic status updates
     let mut seconds_elapsed = 0;
@@ -188,7 +212,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Check for shutdown signal with timeout
         match tokio::time::timeout(Duration::from_secs(1), tokio::signal::ctrl_c()).await {
             Ok(Ok(())) => {
-                println!("\nShutdown signal received");
+                progress!(json, "\nShutdown signal received");
                 break;
             }
             Ok(Err(_)) => break,
@@ -197,16 +221,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 seconds_elapsed += 1;
 
                 if seconds_elapsed >= max_wait {
-                    println!("\n60 seconds elapsed - ending example");
+                    progress!(json, "\n60 seconds elapsed - ending example");
                     break;
                 }
 
                 // Status update every 15 seconds
                 if seconds_elapsed % 15 == 0 {
                     let msg_count = message_count.load(Ordering::Relaxed);
-                    println!(
+                    progress!(
+                        json,
                         "Status: {}s elapsed, {} messages received",
-                        seconds_elapsed, msg_count
+                        seconds_elapsed,
+                        msg_count
                     );
                 }
             }
@@ -214,18 +240,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let final_count = message_count.load(Ordering::Relaxed);
-    println!("\nExample Summary:");
-    println!("  Total messages received: {}", final_count);
+    progress!(json, "\nExample Summary:");
+    progress!(json, "  Total messages received: {}", final_count);
 
     if final_count == 0 {
-        println!("  No messages received - possible reasons:");
-        println!("    - API may not have active data streams at this time");
-        println!("    - Network connectivity issues");
-        println!("    - API maintenance mode");
+        progress!(json, "  No messages received - possible reasons:");
+        progress!(
+            json,
+            "    - API may not have active data streams at this time"
+        );
+        progress!(json, "    - Network connectivity issues");
+        progress!(json, "    - API maintenance mode");
     } else {
-        println!("  Success: SparkScan API is sending real-time data");
+        progress!(json, "  Success: SparkScan API is sending real-time data");
     }
 
-    println!("\nExample complete");
+    progress!(json, "\nExample complete");
     Ok(())
 }
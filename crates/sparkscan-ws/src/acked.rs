@@ -0,0 +1,499 @@
+//! At-least-once delivery with consumer acknowledgements.
+//!
+//! An [`AckedSubscription`] hands each message to its handler as a [`Delivery`], and keeps the
+//! message until the handler [acknowledges](Delivery::ack) it. Deliveries that are
+//! [rejected](Delivery::nack), dropped without an acknowledgement (including by a panicking
+//! handler) or left unacknowledged past the acknowledgement timeout are delivered again, with
+//! a growing [redelivery count](Delivery::redeliveries). This suits consumers writing to
+//! external systems that may fail transiently:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # use std::time::Duration;
+//! # fn write_to_database(_: &SparkScanMessage) -> std::result::Result<(), ()> { Ok(()) }
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let acked = AckedSubscription::new(|delivery| {
+//!     match write_to_database(delivery.message()) {
+//!         Ok(()) => delivery.ack(),
+//!         Err(_) => delivery.nack(),
+//!     }
+//! })
+//! .with_ack_timeout(Duration::from_secs(30))
+//! .with_max_redeliveries(10);
+//! acked.on_dead_letter(|message, redeliveries| {
+//!     eprintln!("giving up on {} after {} redeliveries", message, redeliveries);
+//! });
+//!
+//! let subscription = client.subscribe(Topic::Transactions).await?;
+//! subscription.on_message_acked(&acked);
+//! subscription.subscribe();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Messages are kept in memory only: the feed has no history to replay them from, so messages
+//! still unacknowledged when the process exits are lost. Redeliveries happen on a background
+//! thread and may overtake newer messages.
+
+use crate::logging::{self, Level, LogCategory};
+use crate::types::SparkScanMessage;
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError, Weak};
+use std::time::{Duration, Instant};
+
+/// Time after which an unacknowledged delivery is redelivered, by default.
+const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Number of unacknowledged messages kept, by default.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Longest wait of the redelivery thread, bounding how long it outlives the subscription.
+const MAX_REDELIVERY_WAIT: Duration = Duration::from_secs(1);
+
+type Handler = Box<dyn Fn(Delivery) + Send + Sync>;
+type DeadLetterCallback = Box<dyn Fn(&SparkScanMessage, u32) + Send + Sync>;
+/// Message id, redelivery count and message.
+type Redelivery = (u64, u32, SparkScanMessage);
+/// Message and redelivery count.
+type DeadLetter = (SparkScanMessage, u32);
+
+/// Message handed to the handler of an [`AckedSubscription`].
+///
+/// Dropping a delivery without [acknowledging](Self::ack) it rejects it, so that a handler
+/// returning early or panicking gets the message again.
+pub struct Delivery {
+    id: u64,
+    redeliveries: u32,
+    message: SparkScanMessage,
+    state: Arc<State>,
+    settled: bool,
+}
+
+impl Delivery {
+    /// Get the message.
+    pub fn message(&self) -> &SparkScanMessage {
+        &self.message
+    }
+
+    /// Get the number of times the message was delivered before, 0 on the first delivery.
+    pub fn redeliveries(&self) -> u32 {
+        self.redeliveries
+    }
+
+    /// Get the id of the message, shared by all of its deliveries.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Acknowledge the message, which is then no longer delivered.
+    pub fn ack(mut self) {
+        self.settled = true;
+        self.state.ack(self.id);
+    }
+
+    /// Reject the message, which is delivered again right away.
+    pub fn nack(mut self) {
+        self.settled = true;
+        self.state.nack(self.id, self.redeliveries);
+    }
+}
+
+impl Drop for Delivery {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.state.nack(self.id, self.redeliveries);
+        }
+    }
+}
+
+impl std::fmt::Debug for Delivery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Delivery")
+            .field("id", &self.id)
+            .field("redeliveries", &self.redeliveries)
+            .field("message", &self.message)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Message handler with acknowledgements and redelivery of unacknowledged messages.
+///
+/// Attach it to a subscription with
+/// [`SparkScanSubscription::on_message_acked`](crate::SparkScanSubscription::on_message_acked),
+/// or feed it with [`deliver`](Self::deliver) from any message callback. Cloning the handle
+/// shares the same buffer.
+#[derive(Clone)]
+pub struct AckedSubscription {
+    state: Arc<State>,
+}
+
+struct State {
+    handler: Handler,
+    ack_timeout: Duration,
+    capacity: usize,
+    max_redeliveries: Option<u32>,
+    unacked: Mutex<Unacked>,
+    /// Signalled when a message is acknowledged or rejected
+    changed: Condvar,
+    dead_letter_callbacks: Mutex<Vec<DeadLetterCallback>>,
+    redelivered: AtomicU64,
+    redelivery_thread: OnceLock<()>,
+}
+
+/// Messages delivered and not acknowledged yet.
+#[derive(Default)]
+struct Unacked {
+    next_id: u64,
+    messages: HashMap<u64, Entry>,
+}
+
+struct Entry {
+    message: SparkScanMessage,
+    redeliveries: u32,
+    /// Time of the next delivery, unless acknowledged by then
+    due: Instant,
+}
+
+impl AckedSubscription {
+    /// Create a handler passing each message to `handler` until it is acknowledged.
+    ///
+    /// Unacknowledged messages are redelivered after 30 seconds, and at most 1,024 of them
+    /// are kept, without limit on the number of redeliveries.
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(Delivery) + Send + Sync + 'static,
+    {
+        Self {
+            state: Arc::new(State {
+                handler: Box::new(handler),
+                ack_timeout: DEFAULT_ACK_TIMEOUT,
+                capacity: DEFAULT_CAPACITY,
+                max_redeliveries: None,
+                unacked: Mutex::new(Unacked::default()),
+                changed: Condvar::new(),
+                dead_letter_callbacks: Mutex::new(Vec::new()),
+                redelivered: AtomicU64::new(0),
+                redelivery_thread: OnceLock::new(),
+            }),
+        }
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        Arc::get_mut(&mut self.state)
+            .expect("AckedSubscription must be configured before it is cloned or used")
+    }
+
+    /// Set the time after which an unacknowledged delivery is redelivered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler was cloned or already received messages.
+    pub fn with_ack_timeout(mut self, timeout: Duration) -> Self {
+        self.state_mut().ack_timeout = timeout;
+        self
+    }
+
+    /// Set the number of unacknowledged messages kept.
+    ///
+    /// Once reached, [`deliver`](Self::deliver) waits for an acknowledgement, so that new
+    /// messages back up into the dispatch queue of the subscription and are dropped there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler was cloned or already received messages.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.state_mut().capacity = capacity.max(1);
+        self
+    }
+
+    /// Give up on messages after `max` redeliveries, passing them to the
+    /// [dead letter callbacks](Self::on_dead_letter).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler was cloned or already received messages.
+    pub fn with_max_redeliveries(mut self, max: u32) -> Self {
+        self.state_mut().max_redeliveries = Some(max);
+        self
+    }
+
+    /// Register a callback for the messages given up on, with their number of redeliveries.
+    pub fn on_dead_letter<F>(&self, callback: F)
+    where
+        F: Fn(&SparkScanMessage, u32) + Send + Sync + 'static,
+    {
+        self.state
+            .dead_letter_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Deliver a new message to the handler.
+    ///
+    /// Waits while the maximum number of unacknowledged messages is reached.
+    pub fn deliver(&self, message: SparkScanMessage) {
+        self.state.start_redelivery();
+        let delivery = {
+            let mut unacked = self.state.lock();
+            while unacked.messages.len() >= self.state.capacity {
+                unacked = self
+                    .state
+                    .changed
+                    .wait(unacked)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            let id = unacked.next_id;
+            unacked.next_id += 1;
+            unacked.messages.insert(
+                id,
+                Entry {
+                    message: message.clone(),
+                    redeliveries: 0,
+                    due: Instant::now() + self.state.ack_timeout,
+                },
+            );
+            self.state.delivery(id, 0, message)
+        };
+        self.state.handle(delivery);
+    }
+
+    /// Get the number of messages delivered and not acknowledged yet.
+    pub fn unacked(&self) -> usize {
+        self.state.lock().messages.len()
+    }
+
+    /// Get the number of redeliveries so far.
+    pub fn redelivered(&self) -> u64 {
+        self.state.redelivered.load(Ordering::Relaxed)
+    }
+}
+
+impl std::fmt::Debug for AckedSubscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AckedSubscription")
+            .field("ack_timeout", &self.state.ack_timeout)
+            .field("capacity", &self.state.capacity)
+            .field("max_redeliveries", &self.state.max_redeliveries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl State {
+    fn lock(&self) -> MutexGuard<'_, Unacked> {
+        // Entries are inserted and removed whole, so a panicking caller leaves them consistent
+        self.unacked.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn delivery(
+        self: &Arc<Self>,
+        id: u64,
+        redeliveries: u32,
+        message: SparkScanMessage,
+    ) -> Delivery {
+        Delivery {
+            id,
+            redeliveries,
+            message,
+            state: Arc::clone(self),
+            settled: false,
+        }
+    }
+
+    fn handle(&self, delivery: Delivery) {
+        // A panicking handler drops the delivery, which is then redelivered
+        if catch_unwind(AssertUnwindSafe(|| (self.handler)(delivery))).is_err() {
+            logging::log(
+                LogCategory::Dispatch,
+                Level::Error,
+                format_args!("Acknowledged message handler panicked"),
+            );
+        }
+    }
+
+    fn ack(&self, id: u64) {
+        if self.lock().messages.remove(&id).is_some() {
+            self.changed.notify_all();
+        }
+    }
+
+    /// Schedule the message for redelivery, unless the delivery was already superseded.
+    fn nack(&self, id: u64, redeliveries: u32) {
+        let mut unacked = self.lock();
+        if let Some(entry) = unacked.messages.get_mut(&id) {
+            if entry.redeliveries == redeliveries {
+                entry.due = Instant::now();
+                drop(unacked);
+                self.changed.notify_all();
+            }
+        }
+    }
+
+    fn start_redelivery(self: &Arc<Self>) {
+        self.redelivery_thread.get_or_init(|| {
+            let state = Arc::downgrade(self);
+            #[cfg(feature = "tracing")]
+            let span = tracing::info_span!("sparkscan_redelivery");
+            let spawned = std::thread::Builder::new()
+                .name("sparkscan-redelivery".to_string())
+                .spawn(move || {
+                    #[cfg(feature = "tracing")]
+                    let _span = span.entered();
+                    redeliver(state)
+                });
+            if let Err(e) = spawned {
+                logging::log(
+                    LogCategory::Dispatch,
+                    Level::Error,
+                    format_args!("Failed to start the redelivery thread: {}", e),
+                );
+            }
+        });
+    }
+
+    /// Take the messages due for redelivery at `now`, and the messages given up on.
+    fn take_due(&self, now: Instant) -> (Vec<Redelivery>, Vec<DeadLetter>) {
+        let mut unacked = self.lock();
+        let mut due = Vec::new();
+        let mut dead = Vec::new();
+        unacked.messages.retain(|id, entry| {
+            if entry.due > now {
+                return true;
+            }
+            if self
+                .max_redeliveries
+                .is_some_and(|max| entry.redeliveries >= max)
+            {
+                dead.push((entry.message.clone(), entry.redeliveries));
+                return false;
+            }
+            entry.redeliveries += 1;
+            entry.due = now + self.ack_timeout;
+            due.push((*id, entry.redeliveries, entry.message.clone()));
+            true
+        });
+        if !dead.is_empty() {
+            self.changed.notify_all();
+        }
+        (due, dead)
+    }
+
+    /// Wait until a message may be due for redelivery, or for `max` at most.
+    fn wait(&self, max: Duration) {
+        let unacked = self.lock();
+        let now = Instant::now();
+        let next = unacked.messages.values().map(|entry| entry.due).min();
+        if let Some(wait) = next.map_or(Some(max), |due| due.checked_duration_since(now)) {
+            if !wait.is_zero() {
+                let _ = self.changed.wait_timeout(unacked, wait.min(max));
+            }
+        }
+    }
+}
+
+/// Redeliver the messages due until the handler is dropped.
+fn redeliver(state: Weak<State>) {
+    while let Some(state) = state.upgrade() {
+        let (due, dead) = state.take_due(Instant::now());
+        if !dead.is_empty() {
+            let callbacks = state
+                .dead_letter_callbacks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            for (message, redeliveries) in &dead {
+                for callback in callbacks.iter() {
+                    callback(message, *redeliveries);
+                }
+            }
+        }
+        for (id, redeliveries, message) in due {
+            state.redelivered.fetch_add(1, Ordering::Relaxed);
+            state.handle(state.delivery(id, redeliveries, message));
+        }
+        state.wait(MAX_REDELIVERY_WAIT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{parse_message_for_topic, Topic};
+    use serde_json::json;
+    use std::sync::mpsc::channel;
+
+    fn transaction(id: &str) -> SparkScanMessage {
+        let transaction_json = json!({
+            "id": id,
+            "network": "MAINNET",
+            "type": "spark_to_spark",
+            "status": "confirmed",
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap()
+    }
+
+    fn id(message: &SparkScanMessage) -> String {
+        match message {
+            SparkScanMessage::Transaction(data) => data.id.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_rejected_messages_are_redelivered() {
+        let (sender, receiver) = channel();
+        let sender = Mutex::new(sender);
+        let acked = AckedSubscription::new(move |delivery| {
+            let seen = (id(delivery.message()), delivery.redeliveries());
+            // Reject the first delivery of every message, acknowledge the second one
+            if delivery.redeliveries() == 0 {
+                delivery.nack();
+            } else {
+                delivery.ack();
+            }
+            sender.lock().unwrap().send(seen).unwrap();
+        });
+
+        acked.deliver(transaction("a"));
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            receiver.recv_timeout(timeout).unwrap(),
+            ("a".to_string(), 0)
+        );
+        assert_eq!(
+            receiver.recv_timeout(timeout).unwrap(),
+            ("a".to_string(), 1)
+        );
+        assert_eq!(acked.unacked(), 0);
+        assert_eq!(acked.redelivered(), 1);
+    }
+
+    #[test]
+    fn test_dead_letters() {
+        let acked = AckedSubscription::new(drop)
+            .with_ack_timeout(Duration::from_secs(60))
+            .with_max_redeliveries(2);
+        let (sender, receiver) = channel();
+        let sender = Mutex::new(sender);
+        acked.on_dead_letter(move |message, redeliveries| {
+            sender
+                .lock()
+                .unwrap()
+                .send((id(message), redeliveries))
+                .unwrap();
+        });
+
+        acked.deliver(transaction("a"));
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            receiver.recv_timeout(timeout).unwrap(),
+            ("a".to_string(), 2)
+        );
+        assert_eq!(acked.unacked(), 0);
+        assert_eq!(acked.redelivered(), 2);
+    }
+}
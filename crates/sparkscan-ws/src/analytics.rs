@@ -0,0 +1,270 @@
+//! Funds-flow graph of transactions, enabled by the `analytics` feature.
+//!
+//! [`FundsFlowGraph`] consumes transaction messages and aggregates them into a directed graph
+//! with one node per identifier (Spark address, Bitcoin address or Lightning invoice) and one
+//! edge per sender and receiver pair. The graph can be inspected through `petgraph`, or
+//! exported to DOT for Graphviz and to JSON for other tooling:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # use sparkscan_ws::analytics::FundsFlowGraph;
+//! # use std::sync::{Arc, Mutex};
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let graph = Arc::new(Mutex::new(FundsFlowGraph::new()));
+//!
+//! let subscription = client.subscribe(Topic::Transactions).await?;
+//! let sink = Arc::clone(&graph);
+//! subscription.on_message(move |message| {
+//!     sink.lock().unwrap().add_message(&message);
+//! });
+//! subscription.subscribe();
+//!
+//! // Later on
+//! std::fs::write("flows.dot", graph.lock().unwrap().to_dot()).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::format::format_sats;
+use crate::types::{transaction, SparkScanMessage};
+use petgraph::dot::Dot;
+use petgraph::graph::{DiGraph, NodeIndex};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// Aggregated transfers from one identifier to another.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Flow {
+    /// Number of transactions
+    pub transactions: u64,
+    /// Total amount in satoshis
+    pub amount_sats: u64,
+    /// Total amount of each token, in its smallest unit, by token identifier
+    #[serde(serialize_with = "serialize_token_amounts")]
+    pub token_amounts: BTreeMap<String, u128>,
+}
+
+/// Serialize token amounts as decimal strings, as they may not fit in a JSON number.
+fn serialize_token_amounts<S: serde::Serializer>(
+    amounts: &BTreeMap<String, u128>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        amounts
+            .iter()
+            .map(|(token, amount)| (token, amount.to_string())),
+    )
+}
+
+/// Label of the edge in DOT exports.
+impl fmt::Display for Flow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} tx, {}",
+            self.transactions,
+            format_sats(self.amount_sats)
+        )?;
+        if !self.token_amounts.is_empty() {
+            write!(f, ", {} tokens", self.token_amounts.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Directed graph of the flows between identifiers, built incrementally from transactions.
+///
+/// Every transaction is counted once, however many updates of it are received; transactions
+/// that failed or expired, and transactions missing their sender or receiver, are skipped.
+/// The ids of counted transactions are kept for the lifetime of the graph.
+#[derive(Debug, Default)]
+pub struct FundsFlowGraph {
+    graph: DiGraph<String, Flow>,
+    nodes: HashMap<String, NodeIndex>,
+    /// Transactions already counted
+    seen: HashSet<String>,
+}
+
+/// Edge of a JSON export.
+#[derive(Serialize)]
+struct JsonEdge<'a> {
+    from: &'a str,
+    to: &'a str,
+    #[serde(flatten)]
+    flow: &'a Flow,
+}
+
+impl FundsFlowGraph {
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a message to the graph, ignoring messages other than transactions.
+    ///
+    /// Returns whether the graph changed.
+    pub fn add_message(&mut self, message: &SparkScanMessage) -> bool {
+        match message {
+            SparkScanMessage::Transaction(transaction) => self.add_transaction(transaction),
+            _ => false,
+        }
+    }
+
+    /// Add a transaction to the graph.
+    ///
+    /// Returns whether the graph changed.
+    pub fn add_transaction(&mut self, transaction: &transaction::TransactionPayload) -> bool {
+        if matches!(
+            transaction.status,
+            transaction::Status::Failed | transaction::Status::Expired
+        ) {
+            return false;
+        }
+        let (Some(from), Some(to)) = (&transaction.from_identifier, &transaction.to_identifier)
+        else {
+            return false;
+        };
+        if !self.seen.insert(transaction.id.clone()) {
+            return false;
+        }
+
+        let from = self.node(from);
+        let to = self.node(to);
+        let edge = match self.graph.find_edge(from, to) {
+            Some(edge) => edge,
+            None => self.graph.add_edge(from, to, Flow::default()),
+        };
+        let flow = &mut self.graph[edge];
+        flow.transactions += 1;
+        if let Some(sats) = transaction
+            .amount_sats
+            .as_deref()
+            .and_then(|a| a.parse().ok())
+        {
+            flow.amount_sats = flow.amount_sats.saturating_add(sats);
+        }
+        if let (Some(token), Some(amount)) = (&transaction.token_address, &transaction.token_amount)
+        {
+            if let Ok(amount) = amount.parse::<u128>() {
+                let total = flow.token_amounts.entry(token.to_string()).or_default();
+                *total = total.saturating_add(amount);
+            }
+        }
+        true
+    }
+
+    fn node(&mut self, identifier: &str) -> NodeIndex {
+        if let Some(&node) = self.nodes.get(identifier) {
+            return node;
+        }
+        let node = self.graph.add_node(identifier.to_string());
+        self.nodes.insert(identifier.to_string(), node);
+        node
+    }
+
+    /// Get the underlying graph, with identifiers as node weights.
+    pub fn graph(&self) -> &DiGraph<String, Flow> {
+        &self.graph
+    }
+
+    /// Get the flow from one identifier to another, if any.
+    pub fn flow(&self, from: &str, to: &str) -> Option<&Flow> {
+        let edge = self
+            .graph
+            .find_edge(*self.nodes.get(from)?, *self.nodes.get(to)?)?;
+        Some(&self.graph[edge])
+    }
+
+    /// Get the number of identifiers in the graph.
+    pub fn identifier_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// Get the number of sender and receiver pairs in the graph.
+    pub fn flow_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Export the graph in the Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        Dot::new(&self.graph).to_string()
+    }
+
+    /// Export the graph as JSON, with the list of identifiers and the list of flows.
+    pub fn to_json(&self) -> serde_json::Value {
+        let edges: Vec<_> = self
+            .graph
+            .edge_indices()
+            .filter_map(|edge| {
+                let (from, to) = self.graph.edge_endpoints(edge)?;
+                Some(JsonEdge {
+                    from: &self.graph[from],
+                    to: &self.graph[to],
+                    flow: &self.graph[edge],
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "nodes": self.graph.node_weights().collect::<Vec<_>>(),
+            "edges": edges,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{parse_message_for_topic, Topic};
+    use serde_json::json;
+
+    fn transaction(id: &str, from: &str, to: &str, status: &str) -> SparkScanMessage {
+        let transaction_json = json!({
+            "id": id,
+            "network": "MAINNET",
+            "type": "spark_to_spark",
+            "status": status,
+            "amount_sats": "1500",
+            "from_identifier": from,
+            "to_identifier": to,
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_flows_are_aggregated() {
+        let mut graph = FundsFlowGraph::new();
+        assert!(graph.add_message(&transaction("a", "alice", "bob", "pending")));
+        // A later update of the same transaction is not counted again
+        assert!(!graph.add_message(&transaction("a", "alice", "bob", "confirmed")));
+        assert!(graph.add_message(&transaction("b", "alice", "bob", "confirmed")));
+        assert!(graph.add_message(&transaction("c", "bob", "carol", "confirmed")));
+        assert!(!graph.add_message(&transaction("d", "carol", "alice", "failed")));
+
+        assert_eq!(graph.identifier_count(), 3);
+        assert_eq!(graph.flow_count(), 2);
+        let flow = graph.flow("alice", "bob").unwrap();
+        assert_eq!(flow.transactions, 2);
+        assert_eq!(flow.amount_sats, 3000);
+        assert!(graph.flow("bob", "alice").is_none());
+        assert!(graph.flow("alice", "dave").is_none());
+    }
+
+    #[test]
+    fn test_exports() {
+        let mut graph = FundsFlowGraph::new();
+        graph.add_message(&transaction("a", "alice", "bob", "confirmed"));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph"));
+        assert!(dot.contains("1 tx, 1,500 sats"));
+
+        let json = graph.to_json();
+        assert_eq!(json["nodes"], json!(["alice", "bob"]));
+        assert_eq!(json["edges"][0]["from"], "alice");
+        assert_eq!(json["edges"][0]["amount_sats"], 1500);
+    }
+}
@@ -0,0 +1,78 @@
+//! Latest message per key, kept for synchronous lookups.
+
+use crate::types::SparkScanMessage;
+use hashlink::LruCache;
+use std::sync::{Mutex, PoisonError};
+
+/// Least recently used cache of the latest message per
+/// [ordering key](SparkScanMessage::ordering_key).
+pub(crate) struct StateCache {
+    messages: Mutex<LruCache<String, SparkScanMessage>>,
+}
+
+impl StateCache {
+    /// Create a cache holding the messages of up to `capacity` keys.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            messages: Mutex::new(LruCache::new(capacity.max(1))),
+        }
+    }
+
+    /// Store `message` as the latest one of its key, evicting the least recently used key if
+    /// the cache is full.
+    pub(crate) fn insert(&self, message: SparkScanMessage) {
+        let key = message.ordering_key().to_string();
+        self.lock().insert(key, message);
+    }
+
+    /// Get the latest message of `key`, marking the key as recently used.
+    pub(crate) fn get(&self, key: &str) -> Option<SparkScanMessage> {
+        self.lock().get(key).cloned()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<String, SparkScanMessage>> {
+        // The cache holds no invariant a panicking insert could break
+        self.messages.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{parse_message_for_topic, Topic};
+
+    fn balance(address: &str, soft_balance: u64) -> SparkScanMessage {
+        let json = serde_json::json!({
+            "address": address,
+            "network": "MAINNET",
+            "soft_balance": soft_balance.to_string(),
+            "hard_balance": soft_balance.to_string(),
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+        parse_message_for_topic(&Topic::Balances, json.to_string().as_bytes()).unwrap()
+    }
+
+    fn soft_balance(message: Option<SparkScanMessage>) -> Option<String> {
+        match message? {
+            SparkScanMessage::Balance(balance) => Some(balance.soft_balance),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_state_cache_keeps_latest_message_per_key() {
+        let [first, second, third] = ["q", "p", "z"].map(|c| format!("sp1{}", c.repeat(60)));
+        let cache = StateCache::new(2);
+
+        cache.insert(balance(&first, 1));
+        cache.insert(balance(&first, 2));
+        cache.insert(balance(&second, 3));
+        assert_eq!(soft_balance(cache.get(&first)).as_deref(), Some("2"));
+
+        // The lookup above made the second address the least recently used
+        cache.insert(balance(&third, 4));
+        assert_eq!(soft_balance(cache.get(&second)), None);
+        assert_eq!(soft_balance(cache.get(&first)).as_deref(), Some("2"));
+        assert_eq!(soft_balance(cache.get(&third)).as_deref(), Some("4"));
+    }
+}
@@ -0,0 +1,57 @@
+//! Decoding of CBOR-encoded publications.
+//!
+//! In its low-bandwidth mode, enabled with
+//! [`SparkScanWsConfig::with_cbor`](crate::SparkScanWsConfig::with_cbor), the server encodes
+//! publications as CBOR instead of JSON. They are transcoded into JSON as they are parsed, so
+//! envelopes, fallbacks and the payload types behave the same for both encodings.
+//!
+//! Requires the `cbor` feature.
+
+use crate::error::{Result, SparkScanWsError};
+use std::borrow::Cow;
+
+/// Tag marking self-described CBOR (RFC 8949, section 3.4.6).
+const SELF_DESCRIBED: [u8; 3] = [0xd9, 0xd9, 0xf7];
+
+/// Check whether `data` is a CBOR publication rather than a JSON one.
+///
+/// Publications are maps, whose CBOR initial byte is outside of the ASCII range JSON text
+/// starts with, so the two encodings cannot be confused.
+pub(crate) fn is_cbor(data: &[u8]) -> bool {
+    data.starts_with(&SELF_DESCRIBED) || matches!(data.first(), Some(0xa0..=0xbf))
+}
+
+/// Get the JSON of a publication, transcoding it if it is CBOR.
+pub(crate) fn to_json(data: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if !is_cbor(data) {
+        return Ok(Cow::Borrowed(data));
+    }
+    let value: serde_json::Value = ciborium::from_reader(data).map_err(|e| {
+        SparkScanWsError::InvalidMessageFormat(format!("Failed to decode CBOR: {}", e))
+    })?;
+    Ok(Cow::Owned(serde_json::to_vec(&value)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_transcode_cbor() {
+        let envelope = json!({"offset": 5, "data": {"id": "test_id", "amount_sats": "1000"}});
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&envelope, &mut cbor).unwrap();
+        assert!(is_cbor(&cbor));
+
+        let json = to_json(&cbor).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&json).unwrap(),
+            envelope
+        );
+
+        let text = br#"{"id": "test_id"}"#;
+        assert!(matches!(to_json(text).unwrap(), Cow::Borrowed(_)));
+        assert!(to_json(&[0xa1, 0x61]).is_err());
+    }
+}
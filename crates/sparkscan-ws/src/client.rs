@@ -1,8 +1,34 @@
 //! SparkScan WebSocket client implementation.
 
-use crate::{error::Result, subscription::SparkScanSubscription, types::Topic};
-use std::sync::Arc;
-use tokio_centrifuge::{client::Client as CentrifugeClient, config::Config};
+use crate::{
+    connections::{Connections, Event},
+    decoder::{decode_payload, DecodedSubscription, DecoderRegistry},
+    dispatch::{self, DEFAULT_DISPATCH_QUEUE_SIZE},
+    error::{Result, SparkScanWsError, VerificationError},
+    limits::PayloadLimits,
+    logging::{self, Level, LogCategory},
+    raw::RawSubscription,
+    schema_check::SchemaCheck,
+    schemas::SchemaMismatch,
+    shutdown::{DispatchStats, Lifecycle, ShutdownReport},
+    skew::ClockSkew,
+    subscription::{
+        AddressSubscription, AddressTopicKind, HandlerOrdering, NetworkTopicKind,
+        SparkScanSubscription,
+    },
+    types::Topic,
+    verify::{UnverifiedPublication, Verification},
+};
+use serde::de::DeserializeOwned;
+use sparkscan_types::{Network, SparkAddress};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Interval between checks of the dispatch queues during a shutdown.
+const DRAIN_POLL: Duration = Duration::from_millis(10);
 
 /// Configuration parameters for the SparkScan WebSocket client.
 ///
@@ -12,8 +38,13 @@ use tokio_centrifuge::{client::Client as CentrifugeClient, config::Config};
 pub struct SparkScanWsConfig {
     /// The WebSocket URL endpoint for the SparkScan API
     pub url: String,
+    /// Token or API key sent in the connect command to authenticate (default: none)
+    pub token: Option<String>,
     /// Message serialization format selection (default: false for JSON, true for protobuf)
     pub use_protobuf: bool,
+    /// Ask the server for CBOR-encoded publications (default: false)
+    #[cfg(feature = "cbor")]
+    pub cbor: bool,
     /// Maximum time to wait for connection establishment in seconds (default: 30)
     pub connection_timeout: u64,
     /// Enable automatic reconnection on connection loss (default: true for production reliability)
@@ -22,17 +53,58 @@ pub struct SparkScanWsConfig {
     pub max_reconnect_attempts: u32,
     /// Delay between reconnection attempts in milliseconds (default: 1000ms)
     pub reconnect_delay: u64,
+    /// Messages queued per message callback before new ones are dropped (default: 1024)
+    pub dispatch_queue_size: usize,
+    /// Messages processed concurrently by each message callback (default: 1)
+    pub handler_concurrency: usize,
+    /// Ordering of messages processed concurrently (default: unordered)
+    pub handler_ordering: HandlerOrdering,
+    /// Clock skew reported to `on_clock_skew` callbacks in milliseconds (default: 1000ms)
+    pub clock_skew_threshold: u64,
+    /// Channels the server allows per connection (default: 128)
+    pub subscription_limit: usize,
+    /// Open additional connections for subscriptions beyond the limit (default: false)
+    pub overflow_connections: bool,
+    /// Token shutting the client down when cancelled (default: none)
+    pub cancellation_token: Option<CancellationToken>,
+    /// Time given to the queued messages when the cancellation token shuts the client down
+    /// (default: 5s)
+    pub shutdown_grace: Duration,
+    /// Namespace prepended to the channel of every topic (default: none)
+    pub channel_prefix: String,
+    /// Limits publications must stay within to be parsed (default: [`PayloadLimits::default`])
+    pub payload_limits: PayloadLimits,
+    /// Time the server has to acknowledge a subscription (default: none, waiting forever)
+    pub subscribe_timeout: Option<Duration>,
+    /// Messages pending over the client from which low-priority messages are shed
+    /// (default: none, shedding nothing)
+    pub load_shedding: Option<usize>,
 }
 
 impl Default for SparkScanWsConfig {
     fn default() -> Self {
         Self {
             url: "ws://updates.sparkscan.io/".to_string(),
+            token: None,
             use_protobuf: false,
+            #[cfg(feature = "cbor")]
+            cbor: false,
             connection_timeout: 30,
             auto_reconnect: true,
             max_reconnect_attempts: 5,
             reconnect_delay: 1000,
+            dispatch_queue_size: DEFAULT_DISPATCH_QUEUE_SIZE,
+            handler_concurrency: 1,
+            handler_ordering: HandlerOrdering::default(),
+            clock_skew_threshold: 1000,
+            subscription_limit: 128,
+            overflow_connections: false,
+            cancellation_token: None,
+            shutdown_grace: Duration::from_secs(5),
+            channel_prefix: String::new(),
+            payload_limits: PayloadLimits::default(),
+            subscribe_timeout: None,
+            load_shedding: None,
         }
     }
 }
@@ -48,6 +120,19 @@ impl SparkScanWsConfig {
         }
     }
 
+    /// Authenticate the connections with a token or API key.
+    ///
+    /// The token is sent in the connect command of the Centrifuge protocol rather than in the
+    /// URL, so that it stays out of proxy and server access logs.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - Connection token or API key
+    pub fn with_token<S: Into<String>>(mut self, token: S) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
     /// Configure message serialization format.
     ///
     /// # Arguments
@@ -58,6 +143,25 @@ impl SparkScanWsConfig {
         self
     }
 
+    /// Configure the encoding of publications.
+    ///
+    /// The server encodes publications as JSON by default. With `cbor`, connections ask for the
+    /// low-bandwidth mode of the server by adding `encoding=cbor` to the query of the URL. Its
+    /// CBOR-encoded publications are decoded transparently, and publications still sent as JSON
+    /// are parsed as usual. Independent of the [message format](Self::with_protobuf) of the
+    /// protocol itself.
+    ///
+    /// Requires the `cbor` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `cbor` - true to ask for CBOR-encoded publications
+    #[cfg(feature = "cbor")]
+    pub fn with_cbor(mut self, cbor: bool) -> Self {
+        self.cbor = cbor;
+        self
+    }
+
     /// Configure connection establishment timeout.
     ///
     /// # Arguments
@@ -97,6 +201,179 @@ impl SparkScanWsConfig {
         self.reconnect_delay = delay_ms;
         self
     }
+
+    /// Configure the queue between the connection and message callbacks.
+    ///
+    /// Message callbacks run on a dedicated thread per subscription, so that a slow callback
+    /// does not stall the connection. Messages arriving while `size` messages are already
+    /// queued are dropped and counted by
+    /// [`SparkScanSubscription::dropped_messages`](crate::SparkScanSubscription::dropped_messages).
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Maximum number of queued messages per message callback
+    pub fn with_dispatch_queue_size(mut self, size: usize) -> Self {
+        self.dispatch_queue_size = size;
+        self
+    }
+
+    /// Configure how many messages each message callback processes concurrently.
+    ///
+    /// With the default of 1, messages of a subscription are processed one at a time and in
+    /// order. Higher values let CPU-heavy callbacks keep up with bursty feeds, at the cost of
+    /// ordering: messages are processed by `concurrency` threads and may complete in any order,
+    /// unless ordered by key with [`with_handler_ordering`](Self::with_handler_ordering).
+    ///
+    /// # Arguments
+    ///
+    /// * `concurrency` - Number of threads running each message callback
+    pub fn with_handler_concurrency(mut self, concurrency: usize) -> Self {
+        self.handler_concurrency = concurrency;
+        self
+    }
+
+    /// Configure the ordering of messages processed concurrently.
+    ///
+    /// With [`HandlerOrdering::KeyedOrdering`], messages sharing an address or token identifier
+    /// are processed one at a time and in order, while messages for different keys still run
+    /// in parallel. Has no effect with a handler concurrency of 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `ordering` - Ordering guarantee of each message callback
+    pub fn with_handler_ordering(mut self, ordering: HandlerOrdering) -> Self {
+        self.handler_ordering = ordering;
+        self
+    }
+
+    /// Set the clock skew above which
+    /// [`SparkScanWsClient::on_clock_skew`] callbacks are notified.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold_ms` - Skew between the server and local clocks in milliseconds
+    pub fn with_clock_skew_threshold(mut self, threshold_ms: u64) -> Self {
+        self.clock_skew_threshold = threshold_ms;
+        self
+    }
+
+    /// Set the number of channels the server allows per connection.
+    ///
+    /// Subscriptions take a channel on their connection until they are dropped.
+    /// [`SparkScanWsClient::on_subscription_limit`] callbacks are warned when a connection
+    /// reaches 90% of the limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of subscriptions per connection
+    pub fn with_subscription_limit(mut self, limit: usize) -> Self {
+        self.subscription_limit = limit;
+        self
+    }
+
+    /// Enable or disable additional connections for subscriptions beyond the limit.
+    ///
+    /// When enabled, a subscription created while every connection is at the
+    /// [subscription limit](Self::with_subscription_limit) opens a new connection to the same
+    /// server, which shares the connection event callbacks of the client. When disabled, the
+    /// server rejects subscriptions beyond the limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to open additional connections
+    pub fn with_overflow_connections(mut self, enabled: bool) -> Self {
+        self.overflow_connections = enabled;
+        self
+    }
+
+    /// Shut the client down when `token` is cancelled.
+    ///
+    /// Ties the client into the shutdown orchestration of the application: once connected, the
+    /// client [shuts down](SparkScanWsClient::shutdown) with the
+    /// [grace period](Self::with_shutdown_grace) as soon as the token is cancelled, instead of
+    /// when it is dropped. Until then, the connection stays open even if every handle to the
+    /// client is dropped, unless it is shut down explicitly.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - Token cancelled by the application on shutdown
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Set the time given to the queued messages when the cancellation token shuts the client
+    /// down.
+    ///
+    /// # Arguments
+    ///
+    /// * `grace` - Grace period passed to [`SparkScanWsClient::shutdown`]
+    pub fn with_shutdown_grace(mut self, grace: Duration) -> Self {
+        self.shutdown_grace = grace;
+        self
+    }
+
+    /// Set the namespace prepended to the channel of every topic.
+    ///
+    /// Self-hosted relays often run Centrifugo with namespaced channels, e.g. `spark:balances`
+    /// rather than `balances`. Topics keep their unprefixed names everywhere else, see
+    /// [`Topic::channel`].
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Prefix of the channels, including its separator, e.g. `spark:`
+    pub fn with_channel_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.channel_prefix = prefix.into();
+        self
+    }
+
+    /// Set the limits publications must stay within to be parsed.
+    ///
+    /// Publications exceeding the size, nesting depth or array length limits are rejected
+    /// before parsing and logged as parse errors, so that a pathological publication cannot
+    /// exhaust memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - Limits checked before parsing each publication
+    pub fn with_payload_limits(mut self, limits: PayloadLimits) -> Self {
+        self.payload_limits = limits;
+        self
+    }
+
+    /// Set the time the server has to acknowledge a subscription.
+    ///
+    /// Without an acknowledgement in time, `subscribe_and_wait()` fails and the error callbacks
+    /// of the subscription are called with [`SubscribeError::Timeout`].
+    ///
+    /// [`SubscribeError::Timeout`]: crate::SubscribeError::Timeout
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - Maximum time between subscribing and the acknowledgement of the server
+    pub fn with_subscribe_timeout(mut self, timeout: Duration) -> Self {
+        self.subscribe_timeout = Some(timeout);
+        self
+    }
+
+    /// Shed messages by subscription priority while the client is overloaded.
+    ///
+    /// Once `threshold` messages are queued or being handled over all subscriptions, new
+    /// messages of [`Priority::Low`] subscriptions are shed, and once twice as many are, those
+    /// of [`Priority::Normal`] ones too. Messages of [`Priority::High`] subscriptions are never
+    /// shed. Shed messages are counted in [`SparkScanWsClient::dispatch_stats`].
+    ///
+    /// [`Priority::Low`]: crate::Priority::Low
+    /// [`Priority::Normal`]: crate::Priority::Normal
+    /// [`Priority::High`]: crate::Priority::High
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - Pending messages from which low-priority messages are shed
+    pub fn with_load_shedding(mut self, threshold: usize) -> Self {
+        self.load_shedding = Some(threshold);
+        self
+    }
 }
 
 /// WebSocket client for SparkScan API connectivity.
@@ -141,10 +418,20 @@ impl SparkScanWsConfig {
 /// }
 /// ```
 pub struct SparkScanWsClient {
-    /// The underlying centrifuge client
-    inner: Arc<CentrifugeClient>,
-    /// Client configuration
-    config: SparkScanWsConfig,
+    /// The underlying centrifuge connections
+    connections: Arc<Connections>,
+    /// Client configuration, shared by clones and updated at runtime
+    config: Arc<RwLock<SparkScanWsConfig>>,
+    /// Clock skew estimated from the messages of all subscriptions
+    clock_skew: Arc<ClockSkew>,
+    /// Schema version advertised in the publications of all subscriptions
+    schema_check: Arc<SchemaCheck>,
+    /// Verifier of the publications of all subscriptions
+    verification: Arc<Verification>,
+    /// Shutdown state shared with the subscriptions
+    lifecycle: Arc<Lifecycle>,
+    /// Decoders registered for custom channels, shared by clones
+    decoders: Arc<DecoderRegistry>,
 }
 
 impl SparkScanWsClient {
@@ -162,25 +449,74 @@ impl SparkScanWsClient {
     /// Provides full control over connection parameters, message format,
     /// and reconnection behavior for production deployments.
     pub fn with_config(config: SparkScanWsConfig) -> Self {
-        let centrifuge_config = if config.use_protobuf {
-            Config::new().use_protobuf()
-        } else {
-            Config::new()
-        };
-
-        let inner = CentrifugeClient::new(&config.url, centrifuge_config);
-
         Self {
-            inner: Arc::new(inner),
-            config,
+            connections: Arc::new(Connections::new(&config)),
+            clock_skew: Arc::new(ClockSkew::new(config.clock_skew_threshold)),
+            schema_check: Arc::new(SchemaCheck::default()),
+            verification: Arc::new(Verification::default()),
+            config: Arc::new(RwLock::new(config)),
+            lifecycle: Arc::new(Lifecycle::default()),
+            decoders: Arc::new(DecoderRegistry::default()),
         }
     }
 
     /// Get the current client configuration.
     ///
-    /// Returns a reference to the configuration used for this client instance.
-    pub fn config(&self) -> &SparkScanWsConfig {
-        &self.config
+    /// Returns a snapshot of the configuration used for this client instance, including
+    /// changes made with [`update_config`](Self::update_config).
+    pub fn config(&self) -> SparkScanWsConfig {
+        self.config
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Change the configuration of a running client.
+    ///
+    /// `f` edits a copy of the current configuration, which replaces it for this client and
+    /// its clones without dropping the connection. The clock skew threshold and the
+    /// subscription limits apply right away; the dispatch and handler settings, the payload
+    /// limits, the subscribe timeout and the channel prefix apply to subscriptions created
+    /// afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns a configuration error, leaving the configuration unchanged, if `f` changes the
+    /// URL, the message format or the encoding of publications, which require a new client.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::SparkScanWsClient;
+    /// # fn example() -> sparkscan_ws::Result<()> {
+    /// let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// client.update_config(|config| {
+    ///     config.handler_concurrency = 4;
+    ///     config.clock_skew_threshold = 5000;
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update_config<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut SparkScanWsConfig),
+    {
+        let mut config = self.config.write().unwrap_or_else(PoisonError::into_inner);
+        let mut updated = config.clone();
+        f(&mut updated);
+        let format_changed = updated.use_protobuf != config.use_protobuf;
+        #[cfg(feature = "cbor")]
+        let format_changed = format_changed || updated.cbor != config.cbor;
+        if updated.url != config.url || format_changed {
+            return Err(SparkScanWsError::config(
+                "the URL and message format cannot change at runtime",
+            ));
+        }
+
+        self.connections
+            .set_limit(updated.subscription_limit, updated.overflow_connections);
+        self.clock_skew.set_threshold(updated.clock_skew_threshold);
+        *config = updated;
+        Ok(())
     }
 
     /// Register callback for connection initiation events.
@@ -200,7 +536,7 @@ impl SparkScanWsClient {
     where
         F: Fn() + Send + Sync + 'static,
     {
-        self.inner.on_connecting(callback);
+        self.connections.on(Event::Connecting(Arc::new(callback)));
     }
 
     /// Register callback for successful connection events.
@@ -211,7 +547,7 @@ impl SparkScanWsClient {
     where
         F: Fn() + Send + Sync + 'static,
     {
-        self.inner.on_connected(callback);
+        self.connections.on(Event::Connected(Arc::new(callback)));
     }
 
     /// Register callback for disconnection events.
@@ -223,7 +559,38 @@ impl SparkScanWsClient {
     where
         F: Fn() + Send + Sync + 'static,
     {
-        self.inner.on_disconnected(callback);
+        self.connections.on(Event::Disconnected(Arc::new(callback)));
+    }
+
+    /// Register callback for disconnection events with the reason of the disconnection.
+    ///
+    /// Like [`on_disconnected`](Self::on_disconnected), with a [`DisconnectReason`] telling
+    /// reconnection policies and alerting whether the server is shutting down, the session
+    /// expired, the client was too slow or rejected, or the network failed.
+    ///
+    /// # Note
+    ///
+    /// The underlying tokio-centrifuge crate does not currently expose the disconnect code and
+    /// reason sent by the server, so the reason is reported as [`DisconnectReason::Unknown`]
+    /// until it does.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::SparkScanWsClient;
+    /// let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// client.on_disconnected_with_reason(|reason| {
+    ///     if !reason.should_reconnect() {
+    ///         eprintln!("Rejected by the server: {:?}", reason);
+    ///     }
+    /// });
+    /// ```
+    pub fn on_disconnected_with_reason<F>(&self, callback: F)
+    where
+        F: Fn(DisconnectReason) + Send + Sync + 'static,
+    {
+        self.connections.on(Event::Disconnected(Arc::new(move || {
+            callback(DisconnectReason::Unknown)
+        })));
     }
 
     /// Register callback for connection error events.
@@ -244,9 +611,147 @@ impl SparkScanWsClient {
     where
         F: Fn(String) + Send + Sync + 'static,
     {
-        self.inner.on_error(move |err| {
-            callback(format!("{:?}", err));
-        });
+        self.connections.on(Event::Error(Arc::new(callback)));
+    }
+
+    /// Register callback for subscription limit warnings.
+    ///
+    /// This callback is invoked with the number of subscriptions on a connection and the
+    /// configured [limit](SparkScanWsConfig::with_subscription_limit) when the connection
+    /// reaches 90% of it, so that topics can be consolidated before the server starts
+    /// rejecting subscriptions.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::SparkScanWsClient;
+    /// let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// client.on_subscription_limit(|subscriptions, limit| {
+    ///     eprintln!("{} of {} subscriptions in use", subscriptions, limit);
+    /// });
+    /// ```
+    pub fn on_subscription_limit<F>(&self, callback: F)
+    where
+        F: Fn(usize, usize) + Send + Sync + 'static,
+    {
+        self.connections.on_limit(callback);
+    }
+
+    /// Get the messages pending, dropped and shed over the subscriptions of the client.
+    ///
+    /// Subscriptions count until they are dropped.
+    pub fn dispatch_stats(&self) -> DispatchStats {
+        self.lifecycle.stats()
+    }
+
+    /// Get the number of subscriptions over all connections of the client.
+    ///
+    /// Subscriptions count from their creation with [`subscribe`](Self::subscribe) until they
+    /// are dropped.
+    pub fn active_subscriptions(&self) -> usize {
+        self.connections.active_subscriptions()
+    }
+
+    /// Get the number of connections of the client, including
+    /// [overflow connections](SparkScanWsConfig::with_overflow_connections).
+    pub fn connection_count(&self) -> usize {
+        self.connections.count()
+    }
+
+    /// Get the estimated offset of the local clock from the SparkScan server clock.
+    ///
+    /// The offset is the smallest difference between the local receive time and the
+    /// `processed_at` timestamp over the latest messages, so it includes the shortest delivery
+    /// delay on top of the clock skew. It is positive when the local clock is ahead, and `None`
+    /// until a message was received.
+    pub fn clock_skew(&self) -> Option<chrono::Duration> {
+        self.clock_skew.offset()
+    }
+
+    /// Register callback for clock skew warnings.
+    ///
+    /// This callback is invoked with the estimated offset when it exceeds the configured
+    /// [threshold](SparkScanWsConfig::with_clock_skew_threshold), and again each time it
+    /// exceeds it after returning below. Latency measured against `processed_at` timestamps is
+    /// off by the skew, so hosts with drifting clocks should correct it or resynchronize.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::SparkScanWsClient;
+    /// let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// client.on_clock_skew(|offset| {
+    ///     eprintln!("Local clock is {} ms off", offset.num_milliseconds());
+    /// });
+    /// ```
+    pub fn on_clock_skew<F>(&self, callback: F)
+    where
+        F: Fn(chrono::Duration) + Send + Sync + 'static,
+    {
+        self.clock_skew.on_exceeded(callback);
+    }
+
+    /// Register callback for schema version mismatches.
+    ///
+    /// This callback is invoked once when the server advertises a version of its schemas other
+    /// than [`schemas::SCHEMA_VERSION`](crate::schemas::SCHEMA_VERSION), in the
+    /// [`SCHEMA_VERSION_TAG`](crate::schemas::SCHEMA_VERSION_TAG) tag of its publications or
+    /// through [`check_schema_version`](Self::check_schema_version), and again if it changes.
+    /// The mismatch is also logged as a warning.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::SparkScanWsClient;
+    /// let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// client.on_schema_mismatch(|mismatch| {
+    ///     eprintln!("Plan an upgrade of sparkscan-ws: {}", mismatch);
+    /// });
+    /// ```
+    pub fn on_schema_mismatch<F>(&self, callback: F)
+    where
+        F: Fn(&SchemaMismatch) + Send + Sync + 'static,
+    {
+        self.schema_check.on_mismatch(callback);
+    }
+
+    /// Compare a schema version advertised by the server with the bundled one.
+    ///
+    /// For versions obtained outside of publications, such as from a response header of the
+    /// REST API. Returns the mismatch, reported like the ones found in publications, the first
+    /// time a differing version is passed.
+    pub fn check_schema_version(&self, advertised: &str) -> Option<SchemaMismatch> {
+        self.schema_check.advertise(advertised)
+    }
+
+    /// Verify every publication of the subscriptions with `verifier` before dispatch.
+    ///
+    /// `verifier` receives the channel, data and tags of each publication, and returns the
+    /// reason it is rejected, if so. Rejected publications are not dispatched, and are reported to
+    /// [`on_verification_failure`](Self::on_verification_failure) callbacks. Replaces the
+    /// previous verifier, for the existing subscriptions as well. See [`crate::verify`] for an
+    /// example.
+    pub fn verify_publications<F>(&self, verifier: F)
+    where
+        F: Fn(&UnverifiedPublication<'_>) -> std::result::Result<(), String>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.verification.set_verifier(verifier);
+    }
+
+    /// Register callback for publications rejected by the
+    /// [verifier](Self::verify_publications).
+    ///
+    /// The rejections are also logged as warnings.
+    pub fn on_verification_failure<F>(&self, callback: F)
+    where
+        F: Fn(&VerificationError) + Send + Sync + 'static,
+    {
+        self.verification.on_failure(callback);
+    }
+
+    /// Get the number of publications rejected by the [verifier](Self::verify_publications).
+    pub fn rejected_publications(&self) -> u64 {
+        self.verification.rejected()
     }
 
     /// Initiate WebSocket connection to the SparkScan API server.
@@ -260,12 +765,43 @@ impl SparkScanWsClient {
     /// Returns error if connection initiation fails due to invalid configuration
     /// or immediate network issues.
     pub async fn connect(&self) -> Result<()> {
-        self.inner.connect();
+        self.connections.connect();
+        self.watch_cancellation();
         // Wait a bit to allow connection to establish
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         Ok(())
     }
 
+    /// Shut the client down once the configured cancellation token is cancelled.
+    ///
+    /// The watch ends early if the client is shut down first.
+    fn watch_cancellation(&self) {
+        let Some(token) = self.config().cancellation_token else {
+            return;
+        };
+        if !self.lifecycle.start_watching() {
+            return;
+        }
+        let client = self.clone();
+        let closed = self.lifecycle.closed();
+        let watch = async move {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    let grace = client.config().shutdown_grace;
+                    client.shutdown(grace).await;
+                }
+                _ = closed.cancelled() => {}
+            }
+        };
+        if let Err(e) = dispatch::spawn_task("shutdown", "client", watch) {
+            logging::log(
+                LogCategory::Connection,
+                Level::Error,
+                format_args!("Failed to start the cancellation watch: {}", e),
+            );
+        }
+    }
+
     /// Terminate WebSocket connection gracefully.
     ///
     /// # Note
@@ -277,6 +813,55 @@ impl SparkScanWsClient {
         todo!("Explicit disconnect not supported by tokio-centrifuge")
     }
 
+    /// Shut the client down, handling the messages already received.
+    ///
+    /// Stops handing new messages to the message callbacks, unsubscribes every subscription,
+    /// and waits up to `grace` for the queued messages to be handled before closing the
+    /// connections. Messages still queued after `grace` are left to the dispatch threads,
+    /// which stop once the subscriptions are dropped. Applies to the clones of the client,
+    /// which cannot create subscriptions afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use sparkscan_ws::{SparkScanWsClient, Topic};
+    /// # use std::time::Duration;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let balances = client.subscribe(Topic::Balances).await?;
+    /// balances.on_message(|message| println!("{:?}", message));
+    /// balances.subscribe();
+    /// client.connect().await?;
+    ///
+    /// tokio::signal::ctrl_c().await?;
+    /// let report = client.shutdown(Duration::from_secs(5)).await;
+    /// if !report.is_drained() {
+    ///     eprintln!("{} messages were not handled", report.abandoned);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn shutdown(&self, grace: Duration) -> ShutdownReport {
+        let unsubscribed = self.lifecycle.close();
+        let deadline = tokio::time::Instant::now() + grace;
+        let mut pending = self.lifecycle.pending();
+        while pending > 0 && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(DRAIN_POLL).await;
+            pending = self.lifecycle.pending();
+        }
+        self.connections.close();
+
+        let report = self.lifecycle.report(unsubscribed, pending);
+
+        logging::log(
+            LogCategory::Connection,
+            Level::Info,
+            format_args!("Client shut down: {:?}", report),
+        );
+
+        report
+    }
+
     /// Create subscription for specified topic.
     ///
     /// Establishes a typed subscription to receive real-time updates for the specified topic.
@@ -311,10 +896,182 @@ impl SparkScanWsClient {
     /// # }
     /// ```
     pub async fn subscribe(&self, topic: Topic) -> Result<SparkScanSubscription> {
-        let topic_str = topic.as_str();
-        let centrifuge_subscription = self.inner.new_subscription(&topic_str);
+        if self.lifecycle.is_closing() {
+            return Err(SparkScanWsError::subscription("the client was shut down"));
+        }
+        let config = self.config();
+        let channel = topic.channel(&config.channel_prefix);
+        let (centrifuge_subscription, slot) = self.connections.new_subscription(&channel);
+
+        let mut subscription = SparkScanSubscription::new(centrifuge_subscription, topic)
+            .with_slot(slot)
+            .with_dispatch_queue_size(config.dispatch_queue_size)
+            .with_handler_concurrency(config.handler_concurrency)
+            .with_handler_ordering(config.handler_ordering)
+            .with_payload_limits(config.payload_limits)
+            .with_clock_skew(Arc::clone(&self.clock_skew))
+            .with_schema_check(Arc::clone(&self.schema_check))
+            .with_verification(Arc::clone(&self.verification))
+            .with_lifecycle(Arc::clone(&self.lifecycle));
+        if let Some(timeout) = config.subscribe_timeout {
+            subscription = subscription.with_subscribe_timeout(timeout);
+        }
+        if let Some(threshold) = config.load_shedding {
+            subscription = subscription.with_load_shedding(threshold);
+        }
+        Ok(subscription)
+    }
+
+    /// Subscribe to a channel by name, receiving its publications without typed parsing.
+    ///
+    /// An escape hatch for channels [`Topic`] does not cover yet, such as experimental ones.
+    /// `channel` is used as given: the [channel prefix](SparkScanWsConfig::with_channel_prefix)
+    /// is not applied. The subscription must be activated using its `subscribe()` method.
+    ///
+    /// See [`RawSubscription`] for an example.
+    pub async fn subscribe_raw(&self, channel: &str) -> Result<RawSubscription> {
+        if self.lifecycle.is_closing() {
+            return Err(SparkScanWsError::subscription("the client was shut down"));
+        }
+        let (centrifuge_subscription, slot) = self.connections.new_subscription(channel);
+
+        Ok(RawSubscription::new(centrifuge_subscription, channel)
+            .with_slot(slot)
+            .with_lifecycle(Arc::clone(&self.lifecycle))
+            .with_verification(Arc::clone(&self.verification)))
+    }
+
+    /// Decode the publications of `channel` into `T`.
+    ///
+    /// Payloads are unwrapped from their envelope like the ones of the built-in topics. Replaces
+    /// any decoder previously registered for `channel`. See the [`decoder`](crate::decoder)
+    /// module for an example.
+    pub fn register_decoder<T>(&self, channel: &str)
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.decoders
+            .register::<T>(channel, Arc::new(decode_payload::<T>));
+    }
+
+    /// Decode the publications of `channel` with a custom function, e.g. for binary payloads.
+    ///
+    /// The function receives the data of each publication as sent by the server. Replaces any
+    /// decoder previously registered for `channel`.
+    pub fn register_decoder_with<T, F>(&self, channel: &str, decode: F)
+    where
+        T: Send + 'static,
+        F: Fn(&[u8]) -> Result<T> + Send + Sync + 'static,
+    {
+        self.decoders.register::<T>(channel, Arc::new(decode));
+    }
+
+    /// Subscribe to a channel with a registered decoder, receiving its publications as `T`.
+    ///
+    /// Like [`subscribe_raw`](Self::subscribe_raw), `channel` is used as given. Fails with
+    /// [`SparkScanWsError::ConfigError`] if no decoder into `T` is registered for `channel`.
+    /// The subscription must be activated using its `subscribe()` method.
+    pub async fn subscribe_decoded<T>(&self, channel: &str) -> Result<DecodedSubscription<T>>
+    where
+        T: Send + 'static,
+    {
+        let decode = self.decoders.get::<T>(channel)?;
+        let raw = self.subscribe_raw(channel).await?;
+        Ok(DecodedSubscription::new(raw, decode))
+    }
+
+    /// Subscribe to the per-address topics of several addresses through one handle.
+    ///
+    /// Creates a subscription for each address and topic kind, and merges their messages into
+    /// the returned [`AddressSubscription`], which can add and remove addresses later on. The
+    /// topics must be activated using its `subscribe()` method.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use sparkscan_ws::{AddressTopicKind, Network, SparkAddress, SparkScanWsClient};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let treasury: SparkAddress =
+    ///     "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s".parse()?;
+    ///
+    /// let wallets = client
+    ///     .subscribe_addresses(
+    ///         Network::Mainnet,
+    ///         [treasury],
+    ///         &[AddressTopicKind::Balance, AddressTopicKind::TransactionsIn],
+    ///     )
+    ///     .await?;
+    /// wallets.on_message(|message| println!("{:?}", message));
+    /// wallets.subscribe();
+    ///
+    /// let hot_wallet = "sp1pgssywn703tnm4elyt5m3wknme0t9nxx7vqu6k7rjjxvzl5fjs6t3sa4zs9gre";
+    /// wallets.add_address(hot_wallet.parse()?).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_addresses(
+        &self,
+        network: Network,
+        addresses: impl IntoIterator<Item = SparkAddress>,
+        kinds: &[AddressTopicKind],
+    ) -> Result<AddressSubscription> {
+        let subscription = AddressSubscription::new(self.clone(), network, kinds);
+        for address in addresses {
+            subscription.add_address(address).await?;
+        }
+        Ok(subscription)
+    }
 
-        Ok(SparkScanSubscription::new(centrifuge_subscription, topic))
+    /// Subscribe to a topic separately for each of `networks`, to process them independently.
+    ///
+    /// Each network gets its own subscription to the network topic of `kind`, and therefore its
+    /// own dispatch queue and threads, so that the processing of one network never waits on
+    /// another. Tune each partition with the dispatch settings of [`SparkScanSubscription`],
+    /// e.g. to give mainnet more handler threads than regtest. The subscriptions must be
+    /// activated using their `subscribe()` method.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use sparkscan_ws::{Network, NetworkTopicKind, SparkScanWsClient};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let mut partitions = client
+    ///     .partitions_by_network(
+    ///         NetworkTopicKind::Transactions,
+    ///         &[Network::Mainnet, Network::Regtest],
+    ///     )
+    ///     .await?;
+    ///
+    /// let mainnet = partitions
+    ///     .remove(&Network::Mainnet)
+    ///     .unwrap()
+    ///     .with_handler_concurrency(8);
+    /// mainnet.on_message(|message| println!("mainnet: {}", message));
+    /// mainnet.subscribe();
+    ///
+    /// let regtest = partitions
+    ///     .remove(&Network::Regtest)
+    ///     .unwrap()
+    ///     .with_dispatch_queue_size(64);
+    /// regtest.on_message(|message| println!("regtest: {}", message));
+    /// regtest.subscribe();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn partitions_by_network(
+        &self,
+        kind: NetworkTopicKind,
+        networks: &[Network],
+    ) -> Result<HashMap<Network, SparkScanSubscription>> {
+        let mut partitions = HashMap::with_capacity(networks.len());
+        for &network in networks {
+            if let Entry::Vacant(entry) = partitions.entry(network) {
+                entry.insert(self.subscribe(kind.topic(network)).await?);
+            }
+        }
+        Ok(partitions)
     }
 
     /// Check current WebSocket connection status.
@@ -352,6 +1109,71 @@ pub struct ConnectionStats {
     pub reconnect_attempts: u32,
     /// Most recent connection error message, if any error has occurred
     pub last_error: Option<String>,
+    /// Number of subscriptions over all connections
+    pub active_subscriptions: usize,
+    /// Number of connections, including overflow connections
+    pub connections: usize,
+}
+
+/// Reason of a WebSocket disconnection.
+///
+/// Server-initiated disconnections carry a Centrifugo disconnect code: codes from 3000 to
+/// 3499 let the client reconnect, while codes from 3500 to 3999 mean that reconnecting will
+/// fail again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// The server is shutting down or restarting (code 3001)
+    ServerShutdown,
+    /// The connection token or session expired (code 3005)
+    TokenExpired,
+    /// The client did not read messages fast enough (code 3008)
+    SlowClient,
+    /// The connection was closed or stopped responding (codes 3000, 3009 and 3012)
+    NetworkError,
+    /// The server asked the client to reconnect (code 3011)
+    ForceReconnect,
+    /// The server rejected the client, and reconnecting will not help (codes 3500 to 3999)
+    Rejected {
+        /// Centrifugo disconnect code
+        code: u32,
+        /// Reason sent by the server
+        reason: String,
+    },
+    /// Any other disconnect code sent by the server
+    Server {
+        /// Centrifugo disconnect code
+        code: u32,
+        /// Reason sent by the server
+        reason: String,
+    },
+    /// The reason of the disconnection is not known
+    Unknown,
+}
+
+impl DisconnectReason {
+    /// Classify a Centrifugo disconnect code and the reason sent with it.
+    pub fn from_code(code: u32, reason: impl Into<String>) -> Self {
+        match code {
+            3000 | 3009 | 3012 => Self::NetworkError,
+            3001 => Self::ServerShutdown,
+            3005 => Self::TokenExpired,
+            3008 => Self::SlowClient,
+            3011 => Self::ForceReconnect,
+            3500..=3999 => Self::Rejected {
+                code,
+                reason: reason.into(),
+            },
+            _ => Self::Server {
+                code,
+                reason: reason.into(),
+            },
+        }
+    }
+
+    /// Check whether reconnecting may succeed after this disconnection.
+    pub fn should_reconnect(&self) -> bool {
+        !matches!(self, Self::Rejected { .. })
+    }
 }
 
 // Implement Clone for SparkScanWsClient to enable sharing client instances
@@ -359,8 +1181,13 @@ pub struct ConnectionStats {
 impl Clone for SparkScanWsClient {
     fn clone(&self) -> Self {
         Self {
-            inner: Arc::clone(&self.inner),
-            config: self.config.clone(),
+            connections: Arc::clone(&self.connections),
+            config: Arc::clone(&self.config),
+            clock_skew: Arc::clone(&self.clock_skew),
+            schema_check: Arc::clone(&self.schema_check),
+            verification: Arc::clone(&self.verification),
+            lifecycle: Arc::clone(&self.lifecycle),
+            decoders: Arc::clone(&self.decoders),
         }
     }
 }
@@ -376,7 +1203,17 @@ mod tests {
             .with_timeout(60)
             .with_auto_reconnect(false)
             .with_max_reconnect_attempts(10)
-            .with_reconnect_delay(2000);
+            .with_reconnect_delay(2000)
+            .with_dispatch_queue_size(64)
+            .with_handler_concurrency(4)
+            .with_handler_ordering(HandlerOrdering::KeyedOrdering)
+            .with_clock_skew_threshold(500)
+            .with_subscription_limit(16)
+            .with_overflow_connections(true)
+            .with_channel_prefix("spark:")
+            .with_payload_limits(PayloadLimits::new().with_max_depth(8))
+            .with_subscribe_timeout(Duration::from_secs(10))
+            .with_load_shedding(4096);
 
         assert_eq!(config.url, "ws://sparkscan.io/");
         assert!(config.use_protobuf);
@@ -384,6 +1221,47 @@ mod tests {
         assert!(!config.auto_reconnect);
         assert_eq!(config.max_reconnect_attempts, 10);
         assert_eq!(config.reconnect_delay, 2000);
+        assert_eq!(config.dispatch_queue_size, 64);
+        assert_eq!(config.handler_concurrency, 4);
+        assert_eq!(config.handler_ordering, HandlerOrdering::KeyedOrdering);
+        assert_eq!(config.clock_skew_threshold, 500);
+        assert_eq!(config.subscription_limit, 16);
+        assert!(config.overflow_connections);
+        assert_eq!(config.channel_prefix, "spark:");
+        assert_eq!(config.payload_limits.max_depth, 8);
+        assert_eq!(config.subscribe_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(config.load_shedding, Some(4096));
+    }
+
+    #[test]
+    fn test_disconnect_reason_from_code() {
+        assert_eq!(
+            DisconnectReason::from_code(3001, "shutdown"),
+            DisconnectReason::ServerShutdown
+        );
+        assert_eq!(
+            DisconnectReason::from_code(3005, "connection expired"),
+            DisconnectReason::TokenExpired
+        );
+        assert_eq!(
+            DisconnectReason::from_code(3008, "slow"),
+            DisconnectReason::SlowClient
+        );
+        assert_eq!(
+            DisconnectReason::from_code(3012, "no pong"),
+            DisconnectReason::NetworkError
+        );
+        assert!(DisconnectReason::from_code(3004, "internal server error").should_reconnect());
+
+        let rejected = DisconnectReason::from_code(3500, "invalid token");
+        assert_eq!(
+            rejected,
+            DisconnectReason::Rejected {
+                code: 3500,
+                reason: "invalid token".to_string()
+            }
+        );
+        assert!(!rejected.should_reconnect());
     }
 
     #[tokio::test]
@@ -398,5 +1276,40 @@ mod tests {
         let client = SparkScanWsClient::new("ws://sparkscan.io/");
         let cloned = client.clone();
         assert_eq!(client.config().url, cloned.config().url);
+
+        client
+            .update_config(|config| config.handler_concurrency = 4)
+            .unwrap();
+        assert_eq!(cloned.config().handler_concurrency, 4);
+    }
+
+    #[tokio::test]
+    async fn test_partitions_by_network() {
+        let client = SparkScanWsClient::new("ws://sparkscan.io/");
+        let partitions = client
+            .partitions_by_network(
+                NetworkTopicKind::TokenPrices,
+                &[Network::Mainnet, Network::Regtest, Network::Mainnet],
+            )
+            .await
+            .unwrap();
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(
+            partitions[&Network::Regtest].topic().as_str(),
+            "/token_price/network/regtest"
+        );
+        assert_eq!(client.active_subscriptions(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_update_config_rejects_new_url() {
+        let client = SparkScanWsClient::new("ws://sparkscan.io/");
+        let result = client.update_config(|config| {
+            config.url = "ws://example.com/".to_string();
+            config.reconnect_delay = 5000;
+        });
+        assert!(matches!(result, Err(SparkScanWsError::ConfigError(_))));
+        assert_eq!(client.config().url, "ws://sparkscan.io/");
+        assert_eq!(client.config().reconnect_delay, 1000);
     }
 }
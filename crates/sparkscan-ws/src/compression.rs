@@ -0,0 +1,101 @@
+//! Decompression of compressed publications.
+//!
+//! Some relays compress large publications, such as transactions with many token outputs.
+//! Compressed publications are recognized by their magic bytes and decompressed before parsing:
+//! gzip with the `gzip` feature, zstd with the `zstd` feature. Other publications are parsed as
+//! they are.
+//!
+//! Decompressed publications are limited to the
+//! [`max_payload_bytes`](crate::PayloadLimits::max_payload_bytes) of the payload limits, so that
+//! a small malicious publication cannot exhaust memory.
+
+use crate::error::{Result, SparkScanWsError};
+use std::borrow::Cow;
+use std::io::Read;
+
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Decompress `data` if it is compressed with a supported format, to at most `max_size` bytes.
+pub(crate) fn decompress(data: &[u8], max_size: usize) -> Result<Cow<'_, [u8]>> {
+    #[cfg(feature = "gzip")]
+    if data.starts_with(&GZIP_MAGIC) {
+        let decoder = flate2::read::MultiGzDecoder::new(data);
+        return read_limited("gzip", decoder, max_size).map(Cow::Owned);
+    }
+    #[cfg(feature = "zstd")]
+    if data.starts_with(&ZSTD_MAGIC) {
+        let decoder = zstd::stream::read::Decoder::new(data).map_err(|e| failed("zstd", e))?;
+        return read_limited("zstd", decoder, max_size).map(Cow::Owned);
+    }
+    Ok(Cow::Borrowed(data))
+}
+
+/// Read a decompressed stream, failing past `max_size` bytes.
+fn read_limited(format: &str, decoder: impl Read, max_size: usize) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    let limit = u64::try_from(max_size).unwrap_or(u64::MAX);
+    decoder
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut decompressed)
+        .map_err(|e| failed(format, e))?;
+    if decompressed.len() > max_size {
+        return Err(SparkScanWsError::InvalidMessageFormat(format!(
+            "{} publication decompresses to more than {} bytes",
+            format, max_size
+        )));
+    }
+    Ok(decompressed)
+}
+
+fn failed(format: &str, e: std::io::Error) -> SparkScanWsError {
+    SparkScanWsError::InvalidMessageFormat(format!(
+        "Failed to decompress {} publication: {}",
+        format, e
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD: &[u8] = br#"{"offset": 5, "data": {"id": "test_id", "status": "pending"}}"#;
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_decompress_gzip() {
+        use std::io::Write;
+
+        let compress = |data: &[u8]| {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+        assert_eq!(&*decompress(&compress(PAYLOAD), 1024).unwrap(), PAYLOAD);
+        assert!(matches!(
+            decompress(PAYLOAD, 1024).unwrap(),
+            Cow::Borrowed(_)
+        ));
+        assert!(decompress(&GZIP_MAGIC, 1024).is_err());
+
+        let bomb = compress(&vec![b' '; 1025]);
+        let err = decompress(&bomb, 1024).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid message format: gzip publication decompresses to more than 1024 bytes"
+        );
+        assert!(decompress(&bomb, usize::MAX).is_ok());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_decompress_zstd() {
+        let compressed = zstd::encode_all(PAYLOAD, 0).unwrap();
+        assert_eq!(&*decompress(&compressed, PAYLOAD.len()).unwrap(), PAYLOAD);
+        assert!(decompress(&compressed, PAYLOAD.len() - 1).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Golden corpus of publications, and a conformance suite to check handling code against it.
+//!
+//! [`FIXTURES`] holds anonymized payloads recorded from the feed for every message type, kept in
+//! the `fixtures/` directory of the crate. Each is published in every [`Envelope`] the server
+//! uses, and [`check`] parses all of them with [`parse_message_for_topic`] before handing the
+//! messages to the code under test, so that downstream crates agree with this one on what the
+//! feed sends:
+//!
+//! ```rust
+//! use sparkscan_ws::conformance;
+//!
+//! let failures = conformance::check(|case, message| {
+//!     // Run the handling code of the application on `message`
+//!     match message.network() {
+//!         Some(_) => Ok(()),
+//!         None => Err(format!("no network in {}", case.fixture.name)),
+//!     }
+//! });
+//! assert!(failures.is_empty(), "{:?}", failures);
+//! ```
+
+use crate::types::parse_message_for_topic;
+use crate::{Result, SparkScanMessage, Topic};
+use std::fmt;
+
+/// A recorded payload of a message type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixture {
+    /// Name of the fixture, as in the `fixtures/` directory
+    pub name: &'static str,
+    /// Channel the payload was published on
+    pub channel: &'static str,
+    /// Type of the parsed message, as returned by [`SparkScanMessage::message_type`]
+    pub message_type: &'static str,
+    /// JSON payload, without envelope
+    pub payload: &'static str,
+}
+
+impl Fixture {
+    /// Get the topic of the channel.
+    pub fn topic(&self) -> Topic {
+        Topic::from_str(self.channel)
+    }
+
+    /// Get the payload as JSON.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(self.payload).expect("fixtures are valid JSON")
+    }
+}
+
+macro_rules! fixture {
+    ($channel:literal, $message_type:literal, $name:literal) => {
+        Fixture {
+            name: $name,
+            channel: $channel,
+            message_type: $message_type,
+            payload: include_str!(concat!("../fixtures/", $name, ".json")),
+        }
+    };
+}
+
+/// Recorded payloads of every message type.
+pub const FIXTURES: &[Fixture] = &[
+    fixture!("balances", "balance", "balance"),
+    fixture!("token_balances", "token_balance", "token_balance"),
+    fixture!("token_prices", "token_price", "token_price"),
+    fixture!("tokens", "token", "token"),
+    fixture!("transactions", "transaction", "transaction_spark_to_spark"),
+    fixture!(
+        "/transaction/network/MAINNET",
+        "transaction",
+        "transaction_bitcoin_to_spark"
+    ),
+    fixture!(
+        "transactions",
+        "transaction",
+        "transaction_spark_to_lightning"
+    ),
+    fixture!("transactions", "transaction", "transaction_token_transfer"),
+    // Parsed through the fallback, with the unknown fields kept in `token_io_details`
+    fixture!("transactions", "transaction", "transaction_unknown_type"),
+];
+
+/// Get the recorded payload named `name`.
+pub fn fixture(name: &str) -> Option<&'static Fixture> {
+    FIXTURES.iter().find(|fixture| fixture.name == name)
+}
+
+/// Encoding of a payload in a publication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Envelope {
+    /// The payload object itself
+    Direct,
+    /// `{"data": payload}`
+    Data,
+    /// `{"payload": payload}`
+    Payload,
+    /// `{"message": payload}`
+    Message,
+    /// The payload encoded as a JSON string
+    DoubleEncoded,
+    /// `{"data": "payload"}`, with the payload encoded as a JSON string
+    DataString,
+    /// `{"offset": 42, "tags": {...}, "data": payload}`, as published with history enabled
+    Published,
+}
+
+impl Envelope {
+    /// Every envelope, starting with [`Envelope::Direct`].
+    pub const ALL: [Envelope; 7] = [
+        Envelope::Direct,
+        Envelope::Data,
+        Envelope::Payload,
+        Envelope::Message,
+        Envelope::DoubleEncoded,
+        Envelope::DataString,
+        Envelope::Published,
+    ];
+
+    /// Get the name of the envelope.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Envelope::Direct => "direct",
+            Envelope::Data => "data",
+            Envelope::Payload => "payload",
+            Envelope::Message => "message",
+            Envelope::DoubleEncoded => "double_encoded",
+            Envelope::DataString => "data_string",
+            Envelope::Published => "published",
+        }
+    }
+
+    /// Wrap the JSON `payload` in the envelope.
+    pub fn wrap(&self, payload: &str) -> String {
+        let encoded = || serde_json::to_string(payload).expect("strings serialize");
+        match self {
+            Envelope::Direct => payload.to_string(),
+            Envelope::Data => format!(r#"{{"data": {}}}"#, payload),
+            Envelope::Payload => format!(r#"{{"payload": {}}}"#, payload),
+            Envelope::Message => format!(r#"{{"message": {}}}"#, payload),
+            Envelope::DoubleEncoded => encoded(),
+            Envelope::DataString => format!(r#"{{"data": {}}}"#, encoded()),
+            Envelope::Published => format!(
+                r#"{{"offset": 42, "tags": {{"source": "conformance"}}, "data": {}}}"#,
+                payload
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Envelope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A fixture published in an envelope.
+#[derive(Debug, Clone)]
+pub struct Case {
+    /// Recorded payload
+    pub fixture: &'static Fixture,
+    /// Encoding of the payload
+    pub envelope: Envelope,
+    /// Publication data, as received from the server
+    pub data: String,
+}
+
+impl Case {
+    /// Parse the publication like the client does.
+    pub fn parse(&self) -> Result<SparkScanMessage> {
+        parse_message_for_topic(&self.fixture.topic(), self.data.as_bytes())
+    }
+}
+
+/// Get every fixture in every envelope.
+pub fn cases() -> impl Iterator<Item = Case> {
+    FIXTURES.iter().flat_map(|fixture| {
+        Envelope::ALL.into_iter().map(move |envelope| Case {
+            fixture,
+            envelope,
+            data: envelope.wrap(fixture.payload),
+        })
+    })
+}
+
+/// A case failing the conformance suite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Name of the fixture
+    pub fixture: &'static str,
+    /// Envelope of the publication
+    pub envelope: Envelope,
+    /// What went wrong
+    pub error: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.fixture, self.envelope, self.error)
+    }
+}
+
+/// Run `handler` on every case of the corpus, returning the failures.
+///
+/// A case fails when it does not parse, parses to another message type or to another message
+/// than its direct envelope, or when `handler` returns an error for it.
+pub fn check<F>(mut handler: F) -> Vec<Failure>
+where
+    F: FnMut(&Case, &SparkScanMessage) -> std::result::Result<(), String>,
+{
+    let mut failures = Vec::new();
+    let mut expected = None;
+    for case in cases() {
+        if case.envelope == Envelope::Direct {
+            expected = None;
+        }
+        let fail = |error: String| Failure {
+            fixture: case.fixture.name,
+            envelope: case.envelope,
+            error,
+        };
+        let message = match case.parse() {
+            Ok(message) => message,
+            Err(e) => {
+                failures.push(fail(format!("failed to parse: {}", e)));
+                continue;
+            }
+        };
+        if message.message_type() != case.fixture.message_type {
+            failures.push(fail(format!(
+                "parsed as {}, expected {}",
+                message.message_type(),
+                case.fixture.message_type
+            )));
+            continue;
+        }
+
+        // Every envelope carries the same message as the direct one
+        let value = serde_json::to_value(&message).map_err(|e| e.to_string());
+        if case.envelope == Envelope::Direct {
+            expected = Some(value);
+        } else if expected.as_ref().is_some_and(|expected| *expected != value) {
+            failures.push(fail(
+                "parsed differently from the direct envelope".to_string(),
+            ));
+            continue;
+        }
+
+        if let Err(error) = handler(&case, &message) {
+            failures.push(fail(error));
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpus_conforms() {
+        let mut message_types = std::collections::BTreeSet::new();
+        let failures = check(|case, message| {
+            message_types.insert(message.message_type());
+            match (case.fixture.name, message) {
+                ("transaction_unknown_type", SparkScanMessage::Transaction(transaction)) => {
+                    let details = transaction.token_io_details.as_ref().ok_or("no details")?;
+                    match details["unmapped_fields"]["swap_id"].as_str() {
+                        Some("0198742b-09f8-7b10-8c4e-6a2d0f1e3b57") => Ok(()),
+                        _ => Err(format!("swap_id not kept: {:?}", details)),
+                    }
+                }
+                _ => Ok(()),
+            }
+        });
+        assert!(failures.is_empty(), "{:?}", failures);
+        assert_eq!(
+            message_types.into_iter().collect::<Vec<_>>(),
+            [
+                "balance",
+                "token",
+                "token_balance",
+                "token_price",
+                "transaction"
+            ]
+        );
+
+        let failures = check(|case, _| match case.envelope {
+            Envelope::Published => Err("rejected".to_string()),
+            _ => Ok(()),
+        });
+        assert_eq!(failures.len(), FIXTURES.len());
+        assert_eq!(failures[0].to_string(), "balance (published): rejected");
+    }
+}
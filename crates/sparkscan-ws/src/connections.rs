@@ -0,0 +1,317 @@
+//! Connections of a client and the subscriptions they carry.
+//!
+//! The server caps the number of channels per connection. Subscriptions take a slot on a
+//! connection until they are dropped; once a connection approaches the cap, the
+//! registered callbacks are warned, and with overflow connections enabled, subscriptions
+//! beyond the cap are placed on additional connections opened on demand. Connection event
+//! callbacks are kept so that they apply to those connections as well.
+
+use crate::client::SparkScanWsConfig;
+use crate::logging::{self, Level, LogCategory};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio_centrifuge::{
+    client::Client as CentrifugeClient, config::Config, subscription::Subscription,
+};
+
+/// Callback of a connection event.
+pub(crate) type Hook = Arc<dyn Fn() + Send + Sync>;
+
+/// Callback of a connection error.
+pub(crate) type ErrorHook = Arc<dyn Fn(String) + Send + Sync>;
+
+type LimitCallback = Box<dyn Fn(usize, usize) + Send + Sync>;
+
+/// Connection event callback registered on every connection.
+pub(crate) enum Event {
+    Connecting(Hook),
+    Connected(Hook),
+    Disconnected(Hook),
+    Error(ErrorHook),
+}
+
+/// Connections of a client, starting with the primary one.
+pub(crate) struct Connections {
+    url: String,
+    use_protobuf: bool,
+    /// Token sent in the connect command of each connection
+    token: Option<String>,
+    /// Channels allowed per connection
+    limit: AtomicUsize,
+    /// Whether to open connections beyond the primary one
+    overflow: AtomicBool,
+    state: Mutex<State>,
+    limit_callbacks: Mutex<Vec<LimitCallback>>,
+}
+
+struct State {
+    connections: Vec<Connection>,
+    events: Vec<Event>,
+    /// Whether the client was asked to connect, so that new connections connect right away
+    connect_requested: bool,
+}
+
+struct Connection {
+    client: CentrifugeClient,
+    subscriptions: Arc<AtomicUsize>,
+}
+
+/// Slot taken by a subscription on its connection, released when dropped.
+pub(crate) struct SubscriptionSlot {
+    subscriptions: Arc<AtomicUsize>,
+}
+
+impl Drop for SubscriptionSlot {
+    fn drop(&mut self) {
+        self.subscriptions.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Connections {
+    pub(crate) fn new(config: &SparkScanWsConfig) -> Self {
+        let connections = Self {
+            url: connection_url(config),
+            use_protobuf: config.use_protobuf,
+            token: config.token.clone(),
+            limit: AtomicUsize::new(config.subscription_limit.max(1)),
+            overflow: AtomicBool::new(config.overflow_connections),
+            state: Mutex::new(State {
+                connections: Vec::new(),
+                events: Vec::new(),
+                connect_requested: false,
+            }),
+            limit_callbacks: Mutex::new(Vec::new()),
+        };
+        let primary = connections.open();
+        connections.lock().connections.push(primary);
+        connections
+    }
+
+    fn open(&self) -> Connection {
+        let mut config = if self.use_protobuf {
+            Config::new().use_protobuf()
+        } else {
+            Config::new()
+        };
+        if let Some(token) = &self.token {
+            config = config.with_token(token.clone());
+        }
+
+        Connection {
+            client: CentrifugeClient::new(&self.url, config),
+            subscriptions: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Register an event callback on every current and future connection.
+    pub(crate) fn on(&self, event: Event) {
+        let mut state = self.lock();
+        for connection in &state.connections {
+            register(&connection.client, &event);
+        }
+        state.events.push(event);
+    }
+
+    /// Change the channel limit and overflow policy applied to new subscriptions.
+    pub(crate) fn set_limit(&self, limit: usize, overflow: bool) {
+        self.limit.store(limit.max(1), Ordering::Relaxed);
+        self.overflow.store(overflow, Ordering::Relaxed);
+    }
+
+    /// Register a callback warned when a connection nears the channel limit.
+    pub(crate) fn on_limit<F>(&self, callback: F)
+    where
+        F: Fn(usize, usize) + Send + Sync + 'static,
+    {
+        self.limit_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Start connecting every connection.
+    pub(crate) fn connect(&self) {
+        let mut state = self.lock();
+        state.connect_requested = true;
+        for connection in &state.connections {
+            connection.client.connect();
+        }
+    }
+
+    /// Drop every connection, closing it once its subscriptions are dropped as well.
+    ///
+    /// A new primary connection takes their place, not connected, with the event callbacks
+    /// registered, so that the connections are never empty.
+    pub(crate) fn close(&self) {
+        let primary = self.open();
+        let closed = {
+            let mut state = self.lock();
+            for event in &state.events {
+                register(&primary.client, event);
+            }
+            state.connect_requested = false;
+            std::mem::replace(&mut state.connections, vec![primary])
+        };
+        // Dropped outside of the lock, as the transport may call back into the callbacks
+        drop(closed);
+    }
+
+    /// Create a subscription to `channel` on a connection with a free slot.
+    pub(crate) fn new_subscription(&self, channel: &str) -> (Subscription, SubscriptionSlot) {
+        let limit = self.limit.load(Ordering::Relaxed);
+        let overflow = self.overflow.load(Ordering::Relaxed);
+        let (subscription, subscriptions) = {
+            let mut state = self.lock();
+            let free = state
+                .connections
+                .iter()
+                .position(|connection| connection.subscriptions.load(Ordering::Relaxed) < limit);
+            let index = match free {
+                Some(index) if overflow => index,
+                None if overflow => {
+                    let connection = self.open();
+                    for event in &state.events {
+                        register(&connection.client, event);
+                    }
+                    if state.connect_requested {
+                        connection.client.connect();
+                    }
+                    state.connections.push(connection);
+
+                    logging::log(
+                        LogCategory::Connection,
+                        Level::Info,
+                        format_args!(
+                            "Opened connection {} after reaching {} subscriptions per connection",
+                            state.connections.len(),
+                            limit
+                        ),
+                    );
+
+                    state.connections.len() - 1
+                }
+                _ => 0,
+            };
+            let connection = &state.connections[index];
+            let subscriptions = connection.subscriptions.fetch_add(1, Ordering::Relaxed) + 1;
+            let subscription = connection.client.new_subscription(channel);
+            let slot = SubscriptionSlot {
+                subscriptions: Arc::clone(&connection.subscriptions),
+            };
+            ((subscription, slot), subscriptions)
+        };
+
+        self.check_limit(subscriptions, limit);
+        subscription
+    }
+
+    /// Warn when a connection reaches 90% of the limit, and when it goes over.
+    fn check_limit(&self, subscriptions: usize, limit: usize) {
+        if subscriptions > limit {
+            logging::log(
+                LogCategory::Connection,
+                Level::Warn,
+                format_args!(
+                    "{} subscriptions exceed the limit of {} per connection",
+                    subscriptions, limit
+                ),
+            );
+        }
+
+        if subscriptions != warning_threshold(limit) {
+            return;
+        }
+        for callback in self
+            .limit_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            callback(subscriptions, limit);
+        }
+    }
+
+    /// Get the number of subscriptions over all connections.
+    pub(crate) fn active_subscriptions(&self) -> usize {
+        self.lock()
+            .connections
+            .iter()
+            .map(|connection| connection.subscriptions.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Get the number of open connections.
+    pub(crate) fn count(&self) -> usize {
+        self.lock().connections.len()
+    }
+}
+
+/// Get the URL connections open, asking for CBOR publications if configured.
+fn connection_url(config: &SparkScanWsConfig) -> String {
+    #[cfg(feature = "cbor")]
+    if config.cbor {
+        let separator = if config.url.contains('?') { '&' } else { '?' };
+        return format!("{}{}encoding=cbor", config.url, separator);
+    }
+    config.url.clone()
+}
+
+/// Number of subscriptions on a connection at which callbacks are warned.
+fn warning_threshold(limit: usize) -> usize {
+    (limit - limit / 10).max(1)
+}
+
+fn register(client: &CentrifugeClient, event: &Event) {
+    match event {
+        Event::Connecting(hook) => {
+            let hook = Arc::clone(hook);
+            client.on_connecting(move || hook());
+        }
+        Event::Connected(hook) => {
+            let hook = Arc::clone(hook);
+            client.on_connected(move || hook());
+        }
+        Event::Disconnected(hook) => {
+            let hook = Arc::clone(hook);
+            client.on_disconnected(move || hook());
+        }
+        Event::Error(hook) => {
+            let hook = Arc::clone(hook);
+            client.on_error(move |err| hook(format!("{:?}", err)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning_threshold() {
+        assert_eq!(warning_threshold(128), 116);
+        assert_eq!(warning_threshold(10), 9);
+        assert_eq!(warning_threshold(5), 5);
+        assert_eq!(warning_threshold(1), 1);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_connection_url() {
+        let config = SparkScanWsConfig::new("ws://updates.sparkscan.io/");
+        assert_eq!(connection_url(&config), "ws://updates.sparkscan.io/");
+        let config = config.with_cbor(true);
+        assert_eq!(
+            connection_url(&config),
+            "ws://updates.sparkscan.io/?encoding=cbor"
+        );
+        let config = SparkScanWsConfig::new("ws://localhost:8000/?token=abc").with_cbor(true);
+        assert_eq!(
+            connection_url(&config),
+            "ws://localhost:8000/?token=abc&encoding=cbor"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Per-key debouncing of notifications.
+//!
+//! Balance channels can publish many updates of the same address in quick succession.
+//! A [`Debouncer`] lets the first update of a key through immediately, then coalesces the
+//! updates received during the following interval and releases only the latest one when the
+//! interval ends, so that each key emits at most once per interval:
+//!
+//! ```rust
+//! use sparkscan_ws::Debouncer;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut debouncer = Debouncer::new(Duration::from_secs(5));
+//! let start = Instant::now();
+//!
+//! assert_eq!(debouncer.offer_at("sp1...", 100, start), Some(100));
+//! assert_eq!(debouncer.offer_at("sp1...", 200, start), None);
+//! assert_eq!(debouncer.offer_at("sp1...", 300, start), None);
+//!
+//! let later = start + Duration::from_secs(5);
+//! assert_eq!(debouncer.take_due_at(later), vec![("sp1...", 300)]);
+//! ```
+//!
+//! The debouncer does not run timers itself: call [`take_due`](Debouncer::take_due) when
+//! [`next_due`](Debouncer::next_due) is reached, for instance from a `tokio::time::sleep_until`
+//! branch of the loop consuming the messages.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// Emission state of a key.
+#[derive(Debug)]
+struct Slot<V> {
+    /// Time of the last emission
+    emitted_at: Instant,
+    /// Latest value received since the last emission
+    pending: Option<V>,
+}
+
+/// Rate limiter emitting the latest value of each key at most once per interval.
+#[derive(Debug)]
+pub struct Debouncer<K, V> {
+    interval: Duration,
+    slots: HashMap<K, Slot<V>>,
+}
+
+impl<K: Eq + Hash + Clone, V> Debouncer<K, V> {
+    /// Create a debouncer emitting each key at most once per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            slots: HashMap::new(),
+        }
+    }
+
+    /// Get the minimum time between two emissions of a key.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Offer a new value of `key`.
+    ///
+    /// Returns the value if it can be emitted now. Otherwise it replaces any value of the key
+    /// already waiting, and is returned by [`take_due`](Self::take_due) once the interval
+    /// since the last emission has elapsed.
+    pub fn offer(&mut self, key: K, value: V) -> Option<V> {
+        self.offer_at(key, value, Instant::now())
+    }
+
+    /// Offer a new value of `key` at time `now`.
+    pub fn offer_at(&mut self, key: K, value: V, now: Instant) -> Option<V> {
+        match self.slots.get_mut(&key) {
+            Some(slot) if slot.pending.is_some() || now < slot.emitted_at + self.interval => {
+                slot.pending = Some(value);
+                None
+            }
+            Some(slot) => {
+                slot.emitted_at = now;
+                Some(value)
+            }
+            None => {
+                self.slots.insert(
+                    key,
+                    Slot {
+                        emitted_at: now,
+                        pending: None,
+                    },
+                );
+                Some(value)
+            }
+        }
+    }
+
+    /// Take the waiting values whose interval has elapsed.
+    pub fn take_due(&mut self) -> Vec<(K, V)> {
+        self.take_due_at(Instant::now())
+    }
+
+    /// Take the waiting values whose interval has elapsed at time `now`.
+    ///
+    /// Keys that are idle for a whole interval are forgotten, so that their next value is
+    /// emitted immediately.
+    pub fn take_due_at(&mut self, now: Instant) -> Vec<(K, V)> {
+        let interval = self.interval;
+        let mut due = Vec::new();
+        self.slots.retain(|key, slot| {
+            if now < slot.emitted_at + interval {
+                return true;
+            }
+            match slot.pending.take() {
+                Some(value) => {
+                    slot.emitted_at = now;
+                    due.push((key.clone(), value));
+                    true
+                }
+                None => false,
+            }
+        });
+        due
+    }
+
+    /// Get the time at which the next waiting value is due, if any.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.slots
+            .values()
+            .filter(|slot| slot.pending.is_some())
+            .map(|slot| slot.emitted_at + self.interval)
+            .min()
+    }
+
+    /// Get the number of values waiting for their interval to elapse.
+    pub fn pending(&self) -> usize {
+        self.slots
+            .values()
+            .filter(|slot| slot.pending.is_some())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latest_value_is_emitted_once_per_interval() {
+        let interval = Duration::from_secs(10);
+        let mut debouncer = Debouncer::new(interval);
+        let start = Instant::now();
+
+        assert_eq!(debouncer.offer_at("a", 1, start), Some(1));
+        assert_eq!(debouncer.offer_at("b", 1, start), Some(1));
+        for value in 2..=5 {
+            assert_eq!(debouncer.offer_at("a", value, start), None);
+        }
+        assert_eq!(debouncer.pending(), 1);
+        assert_eq!(debouncer.next_due(), Some(start + interval));
+
+        assert!(debouncer.take_due_at(start + interval / 2).is_empty());
+        let flushed = start + interval;
+        assert_eq!(debouncer.take_due_at(flushed), vec![("a", 5)]);
+        assert_eq!(debouncer.next_due(), None);
+
+        // The flush starts a new interval
+        assert_eq!(debouncer.offer_at("a", 6, flushed + interval / 2), None);
+        assert_eq!(debouncer.take_due_at(flushed + interval), vec![("a", 6)]);
+    }
+
+    #[test]
+    fn test_idle_keys_are_forgotten() {
+        let interval = Duration::from_secs(10);
+        let mut debouncer = Debouncer::new(interval);
+        let start = Instant::now();
+
+        assert_eq!(debouncer.offer_at("a", 1, start), Some(1));
+        assert!(debouncer.take_due_at(start + interval).is_empty());
+        assert!(debouncer.slots.is_empty());
+        assert_eq!(debouncer.offer_at("a", 2, start + interval), Some(2));
+    }
+}
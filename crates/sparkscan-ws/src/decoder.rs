@@ -0,0 +1,218 @@
+//! Typed subscriptions to channels with user-defined payload types.
+//!
+//! [`Topic`](crate::Topic) covers the channels the SDK has payload types for. Other channels,
+//! such as the ones of a self-hosted relay, can be mapped to any `Deserialize` type with
+//! [`SparkScanWsClient::register_decoder`](crate::SparkScanWsClient::register_decoder), then
+//! subscribed to with
+//! [`SparkScanWsClient::subscribe_decoded`](crate::SparkScanWsClient::subscribe_decoded):
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # async fn example() -> Result<()> {
+//! #[derive(Debug, serde::Deserialize)]
+//! struct MempoolStats {
+//!     pending: u64,
+//! }
+//!
+//! let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! client.register_decoder::<MempoolStats>("/experimental/mempool");
+//!
+//! let subscription = client
+//!     .subscribe_decoded::<MempoolStats>("/experimental/mempool")
+//!     .await?;
+//! subscription.on_message(|stats| println!("{} pending", stats.pending));
+//! subscription.subscribe();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Payloads are unwrapped from their envelope like the ones of the built-in topics.
+//! [`SparkScanWsClient::register_decoder_with`](crate::SparkScanWsClient::register_decoder_with)
+//! registers a decoding function instead, e.g. for binary payloads.
+
+use crate::{
+    error::{Result, SparkScanWsError},
+    logging::{self, Level, LogCategory},
+    raw::{PublicationMeta, RawSubscription},
+    router::TagRouter,
+};
+use serde::de::DeserializeOwned;
+use std::any::{type_name, Any};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// Function decoding the data of a publication.
+pub(crate) type Decode<T> = Arc<dyn Fn(&[u8]) -> Result<T> + Send + Sync>;
+
+/// Decode the payload of a publication into `T`, unwrapping it from its envelope.
+///
+/// This is the decoder registered by
+/// [`SparkScanWsClient::register_decoder`](crate::SparkScanWsClient::register_decoder).
+pub fn decode_payload<T: DeserializeOwned>(data: &[u8]) -> Result<T> {
+    let data = &*crate::types::decode_publication(data, &crate::PayloadLimits::default())?;
+    crate::types::deserialize_payload(data)
+}
+
+struct Registration {
+    type_name: &'static str,
+    /// A `Decode<T>` of the registered type
+    decode: Arc<dyn Any + Send + Sync>,
+}
+
+/// Decoders registered on a client, by channel.
+#[derive(Default)]
+pub(crate) struct DecoderRegistry {
+    decoders: RwLock<HashMap<String, Registration>>,
+}
+
+impl DecoderRegistry {
+    /// Decode the publications of `channel` with `decode`, replacing any previous decoder.
+    pub(crate) fn register<T: 'static>(&self, channel: &str, decode: Decode<T>) {
+        let registration = Registration {
+            type_name: type_name::<T>(),
+            decode: Arc::new(decode),
+        };
+        self.decoders
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(channel.to_string(), registration);
+    }
+
+    /// Get the decoder of `channel`, which must decode into `T`.
+    pub(crate) fn get<T: 'static>(&self, channel: &str) -> Result<Decode<T>> {
+        let decoders = self.decoders.read().unwrap_or_else(PoisonError::into_inner);
+        let registration = decoders.get(channel).ok_or_else(|| {
+            SparkScanWsError::config(format!("no decoder registered for channel {}", channel))
+        })?;
+        registration
+            .decode
+            .downcast_ref::<Decode<T>>()
+            .cloned()
+            .ok_or_else(|| {
+                SparkScanWsError::config(format!(
+                    "channel {} decodes into {}, not {}",
+                    channel,
+                    registration.type_name,
+                    type_name::<T>()
+                ))
+            })
+    }
+}
+
+/// Subscription to a channel, delivering its publications decoded into `T`.
+///
+/// Created by
+/// [`SparkScanWsClient::subscribe_decoded`](crate::SparkScanWsClient::subscribe_decoded). Like
+/// [`RawSubscription`], callbacks run inline on the connection task.
+pub struct DecodedSubscription<T> {
+    raw: RawSubscription,
+    decode: Decode<T>,
+    decode_errors: Arc<AtomicU64>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Send + 'static> DecodedSubscription<T> {
+    pub(crate) fn new(raw: RawSubscription, decode: Decode<T>) -> Self {
+        Self {
+            raw,
+            decode,
+            decode_errors: Arc::new(AtomicU64::new(0)),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the channel of this subscription.
+    pub fn channel(&self) -> &str {
+        self.raw.channel()
+    }
+
+    /// Register callback for the decoded messages of the channel.
+    ///
+    /// Publications that fail to decode are logged under [`LogCategory::Parse`] and counted by
+    /// [`decode_errors`](Self::decode_errors).
+    pub fn on_message<F>(&self, callback: F)
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        self.on_decoded(move |message, _meta| callback(message));
+    }
+
+    /// Hand the decoded messages of the channel to the handlers of `router`, by tag.
+    ///
+    /// Publications that fail to decode are handled as by [`on_message`](Self::on_message).
+    pub fn on_message_routed(&self, router: TagRouter<T>) {
+        self.on_decoded(move |message, meta| {
+            router.dispatch(message, meta);
+        });
+    }
+
+    fn on_decoded<F>(&self, callback: F)
+    where
+        F: Fn(T, PublicationMeta) + Send + Sync + 'static,
+    {
+        let decode = Arc::clone(&self.decode);
+        let decode_errors = Arc::clone(&self.decode_errors);
+        self.raw
+            .on_publication(move |data, meta| match decode(&data) {
+                Ok(message) => callback(message, meta),
+                Err(e) => {
+                    decode_errors.fetch_add(1, Ordering::Relaxed);
+                    logging::log(
+                        LogCategory::Parse,
+                        Level::Error,
+                        format_args!(
+                            "Failed to decode message on channel {}: {}",
+                            meta.channel, e
+                        ),
+                    );
+                }
+            });
+    }
+
+    /// Get the number of publications that failed to decode.
+    pub fn decode_errors(&self) -> u64 {
+        self.decode_errors.load(Ordering::Relaxed)
+    }
+
+    /// Get the underlying raw subscription, e.g. to register subscription callbacks.
+    pub fn raw(&self) -> &RawSubscription {
+        &self.raw
+    }
+
+    /// Activate subscription to begin receiving messages.
+    pub fn subscribe(&self) {
+        self.raw.subscribe();
+    }
+
+    /// Deactivate subscription.
+    pub fn unsubscribe(&self) {
+        self.raw.unsubscribe();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Custom {
+        value: u64,
+    }
+
+    #[test]
+    fn test_registry_checks_types() {
+        let registry = DecoderRegistry::default();
+        registry.register::<Custom>("custom", Arc::new(decode_payload::<Custom>));
+
+        let decode = registry.get::<Custom>("custom").unwrap();
+        let wrapped = br#"{"offset": 3, "data": {"value": 7}}"#;
+        assert_eq!(decode(wrapped).unwrap(), Custom { value: 7 });
+        assert!(decode(br#"{"other": true}"#).is_err());
+
+        let err = registry.get::<String>("custom").err().unwrap();
+        assert!(err.to_string().contains("decodes into"));
+        assert!(registry.get::<Custom>("unknown").is_err());
+    }
+}
@@ -0,0 +1,461 @@
+//! Deposit detection for exchange integrations.
+//!
+//! A [`DepositMonitor`] watches a set of deposit addresses and turns the transactions they
+//! receive into [`DepositEvent`]s: a deposit is detected when its transaction is first seen,
+//! and confirmed once it meets the [`ConfirmationLevel`] of its address. Balance updates are
+//! used to tell when a deposit has settled.
+//!
+//! Every event carries an [id](Deposit::event_id) derived from the address and the transaction.
+//! The monitor only remembers a bounded number of finished deposits, so crediting is made
+//! idempotent by storing the ids of processed events:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # use std::sync::Arc;
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let deposits = Arc::new(DepositMonitor::new());
+//! deposits.register("sp1...", ConfirmationLevel::Settled);
+//!
+//! for topic in deposits.topics(Network::Mainnet) {
+//!     let subscription = client.subscribe(topic).await?;
+//!     let deposits = Arc::clone(&deposits);
+//!     subscription.on_message(move |message| {
+//!         for event in deposits.process(&message) {
+//!             if let DepositEvent::Confirmed(deposit) = event {
+//!                 println!("credit {} ({})", deposit.address, deposit.event_id);
+//!             }
+//!         }
+//!     });
+//!     subscription.subscribe();
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::types::{transaction, SparkScanMessage, Topic};
+use chrono::{DateTime, Utc};
+use sparkscan_types::{Network, Sats, TokenAmount};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Number of finished deposits remembered, by default.
+const DEFAULT_FINISHED_CAPACITY: usize = 100_000;
+
+/// Point at which a deposit is confirmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ConfirmationLevel {
+    /// As soon as the transaction is seen, whatever its status
+    Detected,
+    /// Once the transaction is reported confirmed
+    #[default]
+    Confirmed,
+    /// Once the transaction is confirmed and a later balance update of the address has no
+    /// pending funds, its hard balance covering its soft balance
+    Settled,
+}
+
+/// Deposit to a registered address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deposit {
+    /// Id of the event, identical whenever the same event of the same deposit is emitted
+    pub event_id: String,
+    /// Id of the transaction
+    pub transaction_id: String,
+    /// Deposit address
+    pub address: String,
+    /// Network of the transaction
+    pub network: Network,
+    /// Sender of the transaction, if known
+    pub from: Option<String>,
+    /// Amount in satoshis, if any
+    pub amount_sats: Option<Sats>,
+    /// Token transferred, if any
+    pub token_address: Option<String>,
+    /// Amount of the token in its smallest unit, if any
+    pub token_amount: Option<TokenAmount>,
+    /// Time the server processed the update that led to the event
+    pub processed_at: DateTime<Utc>,
+}
+
+/// Change in the state of a deposit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DepositEvent {
+    /// The transaction of the deposit was seen for the first time
+    Detected(Deposit),
+    /// The deposit met the confirmation level of its address and can be credited
+    Confirmed(Deposit),
+    /// The transaction of a detected deposit failed or expired
+    Failed(Deposit),
+}
+
+impl DepositEvent {
+    /// Get the deposit the event is about.
+    pub fn deposit(&self) -> &Deposit {
+        match self {
+            DepositEvent::Detected(deposit)
+            | DepositEvent::Confirmed(deposit)
+            | DepositEvent::Failed(deposit) => deposit,
+        }
+    }
+}
+
+/// Watches deposit addresses and derives deposit events from transactions and balances.
+///
+/// The ids of the most recently finished deposits are kept to ignore later updates of their
+/// transactions, so events are emitted at most once per deposit and kind until a deposit is
+/// forgotten. A forgotten deposit updated again is detected anew: callers crediting deposits
+/// must deduplicate them by [event id](Deposit::event_id).
+pub struct DepositMonitor {
+    state: Mutex<State>,
+}
+
+struct State {
+    addresses: HashMap<String, ConfirmationLevel>,
+    /// Detected deposits awaiting confirmation, by transaction id
+    pending: HashMap<String, PendingDeposit>,
+    /// Transactions whose deposit was confirmed or failed
+    finished: HashSet<String>,
+    /// Finished transactions, oldest first
+    finished_order: VecDeque<String>,
+    /// Number of finished transactions kept
+    finished_capacity: usize,
+}
+
+struct PendingDeposit {
+    deposit: Deposit,
+    /// Time the transaction was reported confirmed
+    confirmed_at: Option<DateTime<Utc>>,
+}
+
+impl Default for DepositMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DepositMonitor {
+    /// Create a monitor without addresses.
+    ///
+    /// The last 100,000 finished deposits are remembered.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                addresses: HashMap::new(),
+                pending: HashMap::new(),
+                finished: HashSet::new(),
+                finished_order: VecDeque::new(),
+                finished_capacity: DEFAULT_FINISHED_CAPACITY,
+            }),
+        }
+    }
+
+    /// Set the number of finished deposits remembered, at least one.
+    pub fn with_finished_capacity(self, capacity: usize) -> Self {
+        self.lock().finished_capacity = capacity.max(1);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Watch `address`, confirming its deposits at `level`.
+    ///
+    /// Registering an address again changes its level for deposits not yet confirmed.
+    pub fn register(&self, address: impl ToString, level: ConfirmationLevel) {
+        self.lock().addresses.insert(address.to_string(), level);
+    }
+
+    /// Stop watching `address`, forgetting its pending deposits.
+    ///
+    /// Returns whether the address was registered.
+    pub fn unregister(&self, address: &str) -> bool {
+        let mut state = self.lock();
+        state
+            .pending
+            .retain(|_, pending| pending.deposit.address != address);
+        state.addresses.remove(address).is_some()
+    }
+
+    /// Get the registered addresses.
+    pub fn addresses(&self) -> Vec<String> {
+        self.lock().addresses.keys().cloned().collect()
+    }
+
+    /// Get the topics to subscribe to for the registered addresses on `network`.
+    pub fn topics(&self, network: Network) -> Vec<Topic> {
+        let network_name = network.as_str().to_lowercase();
+        self.lock()
+            .addresses
+            .keys()
+            .flat_map(|address| {
+                [
+                    Topic::TransactionIn(network_name.clone(), address.clone()),
+                    Topic::BalanceAddress(address.clone()),
+                ]
+            })
+            .collect()
+    }
+
+    /// Update the deposits with `message` and get the resulting events.
+    pub fn process(&self, message: &SparkScanMessage) -> Vec<DepositEvent> {
+        let mut state = self.lock();
+        match message {
+            SparkScanMessage::Transaction(data) => state.transaction(data, message.spark_network()),
+            SparkScanMessage::Balance(data) => {
+                let settled = match (
+                    data.soft_balance.parse::<Sats>(),
+                    data.hard_balance.parse::<Sats>(),
+                ) {
+                    (Ok(soft), Ok(hard)) => hard >= soft,
+                    _ => false,
+                };
+                if settled {
+                    state.settle(data.address.as_str(), data.processed_at)
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl State {
+    fn transaction(
+        &mut self,
+        data: &transaction::TransactionPayload,
+        network: Network,
+    ) -> Vec<DepositEvent> {
+        let Some(address) = data.to_identifier.as_deref() else {
+            return Vec::new();
+        };
+        let Some(&level) = self.addresses.get(address) else {
+            return Vec::new();
+        };
+        if self.finished.contains(&data.id) {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        let pending = self.pending.entry(data.id.clone()).or_insert_with(|| {
+            let deposit = Deposit {
+                event_id: String::new(),
+                transaction_id: data.id.clone(),
+                address: address.to_string(),
+                network,
+                from: data.from_identifier.clone(),
+                amount_sats: data.amount_sats.as_deref().and_then(|a| a.parse().ok()),
+                token_address: data.token_address.clone(),
+                token_amount: data.token_amount.as_deref().and_then(|a| a.parse().ok()),
+                processed_at: data.processed_at,
+            };
+            events.push(DepositEvent::Detected(deposit.with_event("detected")));
+            PendingDeposit {
+                deposit,
+                confirmed_at: None,
+            }
+        });
+        pending.deposit.processed_at = data.processed_at;
+
+        match data.status {
+            transaction::Status::Failed | transaction::Status::Expired => {
+                events.push(DepositEvent::Failed(pending.deposit.with_event("failed")));
+                self.finish(&data.id);
+                return events;
+            }
+            transaction::Status::Confirmed => {
+                pending.confirmed_at.get_or_insert(data.processed_at);
+            }
+            transaction::Status::Pending | transaction::Status::Sent => {}
+        }
+
+        let confirmed = match level {
+            ConfirmationLevel::Detected => true,
+            ConfirmationLevel::Confirmed => pending.confirmed_at.is_some(),
+            ConfirmationLevel::Settled => false,
+        };
+        if confirmed {
+            events.push(DepositEvent::Confirmed(
+                pending.deposit.with_event("confirmed"),
+            ));
+            self.finish(&data.id);
+        }
+        events
+    }
+
+    /// Confirm the deposits of `address` confirmed no later than a balance update without
+    /// pending funds processed at `at`.
+    fn settle(&mut self, address: &str, at: DateTime<Utc>) -> Vec<DepositEvent> {
+        if self.addresses.get(address) != Some(&ConfirmationLevel::Settled) {
+            return Vec::new();
+        }
+        let settled: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| {
+                pending.deposit.address == address
+                    && pending
+                        .confirmed_at
+                        .is_some_and(|confirmed| confirmed <= at)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut events = Vec::new();
+        for id in settled {
+            if let Some(mut pending) = self.pending.remove(&id) {
+                pending.deposit.processed_at = at;
+                events.push(DepositEvent::Confirmed(
+                    pending.deposit.with_event("confirmed"),
+                ));
+                self.finish(&id);
+            }
+        }
+        events
+    }
+
+    fn finish(&mut self, id: &str) {
+        self.pending.remove(id);
+        if self.finished.insert(id.to_string()) {
+            self.finished_order.push_back(id.to_string());
+        }
+        while self.finished_order.len() > self.finished_capacity {
+            if let Some(oldest) = self.finished_order.pop_front() {
+                self.finished.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl Deposit {
+    fn with_event(&self, kind: &str) -> Deposit {
+        Deposit {
+            event_id: format!("{}:{}:{}", kind, self.address, self.transaction_id),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::parse_message_for_topic;
+    use serde_json::json;
+
+    const ADDRESS: &str = "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s";
+
+    fn transaction(id: &str, status: &str, seconds: u32) -> SparkScanMessage {
+        let transaction_json = json!({
+            "id": id,
+            "network": "MAINNET",
+            "type": "bitcoin_to_spark",
+            "status": status,
+            "amount_sats": "50000",
+            "to_identifier": ADDRESS,
+            "processed_at": format!("2025-08-06T16:28:{:02}Z", seconds)
+        });
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap()
+    }
+
+    fn balance(soft: &str, hard: &str, seconds: u32) -> SparkScanMessage {
+        let balance_json = json!({
+            "address": ADDRESS,
+            "network": "MAINNET",
+            "soft_balance": soft,
+            "hard_balance": hard,
+            "processed_at": format!("2025-08-06T16:28:{:02}Z", seconds)
+        });
+        let json_str = serde_json::to_string(&balance_json).unwrap();
+        parse_message_for_topic(&Topic::Balances, json_str.as_bytes()).unwrap()
+    }
+
+    fn event_ids(events: &[DepositEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .map(|event| event.deposit().event_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_confirmed_deposits() {
+        let monitor = DepositMonitor::new();
+        monitor.register(ADDRESS, ConfirmationLevel::Confirmed);
+
+        let events = monitor.process(&transaction("tx", "pending", 0));
+        assert_eq!(event_ids(&events), [format!("detected:{}:tx", ADDRESS)]);
+        assert!(monitor.process(&transaction("tx", "pending", 1)).is_empty());
+
+        let events = monitor.process(&transaction("tx", "confirmed", 2));
+        assert_eq!(event_ids(&events), [format!("confirmed:{}:tx", ADDRESS)]);
+        let DepositEvent::Confirmed(deposit) = &events[0] else {
+            panic!("expected a confirmed deposit");
+        };
+        assert_eq!(deposit.amount_sats, Some(Sats(50_000)));
+
+        // Replays of a finished deposit are ignored
+        assert!(monitor
+            .process(&transaction("tx", "confirmed", 3))
+            .is_empty());
+
+        monitor.process(&transaction("failed", "pending", 4));
+        let events = monitor.process(&transaction("failed", "failed", 5));
+        assert!(matches!(events[..], [DepositEvent::Failed(_)]));
+    }
+
+    #[test]
+    fn test_settled_deposits() {
+        let monitor = DepositMonitor::new();
+        monitor.register(ADDRESS, ConfirmationLevel::Settled);
+
+        let events = monitor.process(&transaction("tx", "confirmed", 0));
+        assert!(matches!(events[..], [DepositEvent::Detected(_)]));
+        assert!(monitor.process(&balance("50000", "0", 1)).is_empty());
+
+        let events = monitor.process(&balance("50000", "50000", 2));
+        assert_eq!(event_ids(&events), [format!("confirmed:{}:tx", ADDRESS)]);
+        assert!(monitor.process(&balance("50000", "50000", 3)).is_empty());
+    }
+
+    #[test]
+    fn test_finished_capacity() {
+        let monitor = DepositMonitor::new().with_finished_capacity(1);
+        monitor.register(ADDRESS, ConfirmationLevel::Confirmed);
+
+        monitor.process(&transaction("first", "confirmed", 0));
+        monitor.process(&transaction("second", "confirmed", 1));
+        assert!(monitor
+            .process(&transaction("second", "confirmed", 2))
+            .is_empty());
+
+        // The oldest finished deposit was forgotten, and is detected again
+        let events = monitor.process(&transaction("first", "confirmed", 3));
+        assert_eq!(
+            event_ids(&events),
+            [
+                format!("detected:{}:first", ADDRESS),
+                format!("confirmed:{}:first", ADDRESS)
+            ]
+        );
+        assert_eq!(monitor.lock().finished.len(), 1);
+    }
+
+    #[test]
+    fn test_unregistered_addresses_are_ignored() {
+        let monitor = DepositMonitor::new();
+        assert!(monitor
+            .process(&transaction("tx", "confirmed", 0))
+            .is_empty());
+
+        monitor.register(ADDRESS, ConfirmationLevel::Detected);
+        let events = monitor.process(&transaction("tx", "pending", 1));
+        assert!(matches!(
+            events[..],
+            [DepositEvent::Detected(_), DepositEvent::Confirmed(_)]
+        ));
+        assert_eq!(monitor.topics(Network::Mainnet).len(), 2);
+        assert!(monitor.unregister(ADDRESS));
+    }
+}
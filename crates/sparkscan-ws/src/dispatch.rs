@@ -0,0 +1,580 @@
+//! Message dispatch off the transport task.
+//!
+//! Publications are handed from the centrifuge connection task to dedicated dispatch threads
+//! through a bounded queue; parsing and user callbacks run on those threads, so a slow callback
+//! delays its own subscription only. When the queue is full, new publications are dropped and
+//! counted instead of stalling the connection.
+//!
+//! A single dispatch thread preserves the order of publications. With a handler concurrency
+//! above one, that many threads take publications from the shared queue and may complete them
+//! out of order. With [`HandlerOrdering::KeyedOrdering`], one thread parses the publications
+//! instead and routes each message to a handler thread chosen by its
+//! [ordering key](SparkScanMessage::ordering_key), so messages sharing a key are handled in
+//! order, one at a time.
+//!
+//! Dispatch threads are named after their role and topic, such as
+//! `sparkscan-dispatch:balances`, and run in a `sparkscan_dispatch` tracing span with the same
+//! fields, so that thread dumps and traces tell the subscriptions apart. Each publication is
+//! parsed and handled in a `sparkscan_message` span, recording its topic, offset, message type
+//! and parse duration, which is current while the callback runs. The Tokio tasks of the client
+//! run in a `sparkscan_task` span, and are named the same way for tokio-console when built with
+//! `--cfg tokio_unstable` and the `tracing` feature.
+
+use crate::limits::PayloadLimits;
+use crate::logging::{self, Level, LogCategory};
+use crate::subscription::HandlerOrdering;
+use crate::types::{parse_message_for_topic_with_limits, SparkScanMessage, Topic};
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio_centrifuge::protocol::Publication;
+
+/// Default number of publications queued per message callback.
+pub(crate) const DEFAULT_DISPATCH_QUEUE_SIZE: usize = 1024;
+
+/// Dispatch settings of a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DispatchOptions {
+    /// Publications queued per message callback
+    pub(crate) queue_size: usize,
+    /// Dispatch threads per message callback
+    pub(crate) concurrency: usize,
+    /// Ordering of messages across dispatch threads
+    pub(crate) ordering: HandlerOrdering,
+    /// Limits publications must stay within to be parsed
+    pub(crate) limits: PayloadLimits,
+}
+
+impl Default for DispatchOptions {
+    fn default() -> Self {
+        Self {
+            queue_size: DEFAULT_DISPATCH_QUEUE_SIZE,
+            concurrency: 1,
+            ordering: HandlerOrdering::default(),
+            limits: PayloadLimits::default(),
+        }
+    }
+}
+
+/// Sending half of the queue between the transport and the dispatch threads.
+pub(crate) struct Dispatcher {
+    sender: SyncSender<Publication>,
+    topic: Topic,
+    dropped: Arc<AtomicU64>,
+    /// Publications queued or being handled
+    pending: Arc<AtomicUsize>,
+}
+
+impl Dispatcher {
+    /// Start the dispatch threads parsing publications of `topic` and passing them to
+    /// `callback`.
+    ///
+    /// The threads exit once the dispatcher is dropped and its queue drained.
+    pub(crate) fn spawn<F>(
+        topic: Topic,
+        options: DispatchOptions,
+        dropped: Arc<AtomicU64>,
+        callback: Arc<F>,
+    ) -> std::io::Result<Self>
+    where
+        F: Fn(SparkScanMessage) + Send + Sync + 'static,
+    {
+        let (sender, receiver) = sync_channel(options.queue_size.max(1));
+        let pending = Arc::new(AtomicUsize::new(0));
+        match options.ordering {
+            HandlerOrdering::KeyedOrdering if options.concurrency > 1 => {
+                spawn_keyed(&topic, options, receiver, &pending, callback)?
+            }
+            _ => {
+                let receiver = Arc::new(Mutex::new(receiver));
+                for _ in 0..options.concurrency.max(1) {
+                    let thread_topic = topic.clone();
+                    let receiver = Arc::clone(&receiver);
+                    let pending = Arc::clone(&pending);
+                    let callback = Arc::clone(&callback);
+                    spawn_thread("dispatch", &topic, move || {
+                        run(
+                            &thread_topic,
+                            &options.limits,
+                            &receiver,
+                            &pending,
+                            &*callback,
+                        )
+                    })?;
+                }
+            }
+        }
+
+        Ok(Self {
+            sender,
+            topic,
+            dropped,
+            pending,
+        })
+    }
+
+    /// Get the counter of publications queued or being handled.
+    pub(crate) fn pending(&self) -> &Arc<AtomicUsize> {
+        &self.pending
+    }
+
+    /// Queue a publication without blocking, dropping it if the queue is full.
+    pub(crate) fn push(&self, publication: Publication) {
+        // Counted before sending, so that a dispatch thread never releases it first
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        let result = self.sender.try_send(publication);
+        if result.is_err() {
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+        }
+        if let Err(TrySendError::Full(_)) = result {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            // Log the first drops, then at exponentially growing intervals
+            if dropped.is_power_of_two() {
+                logging::log(
+                    LogCategory::Dispatch,
+                    Level::Warn,
+                    format_args!(
+                        "Dispatch queue for topic {:?} is full, {} messages dropped",
+                        self.topic, dropped
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Start a thread taking part in the dispatch of `topic`, named after its `role`.
+fn spawn_thread(
+    role: &'static str,
+    topic: &Topic,
+    f: impl FnOnce() + Send + 'static,
+) -> std::io::Result<()> {
+    let channel = topic.as_str();
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!("sparkscan_dispatch", role, topic = %channel);
+
+    std::thread::Builder::new()
+        .name(format!("sparkscan-{}:{}", role, channel))
+        .spawn(move || {
+            #[cfg(feature = "tracing")]
+            let _span = span.entered();
+            f()
+        })
+        .map(drop)
+}
+
+/// Start a Tokio task taking part in the client, named after its `role` and `name`.
+pub(crate) fn spawn_task<F>(role: &'static str, name: &str, future: F) -> std::io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(feature = "tracing")]
+    let future = tracing::Instrument::instrument(
+        future,
+        tracing::info_span!("sparkscan_task", role, name = %name),
+    );
+
+    #[cfg(all(tokio_unstable, feature = "tracing"))]
+    return tokio::task::Builder::new()
+        .name(&format!("sparkscan-{}:{}", role, name))
+        .spawn(future)
+        .map(drop);
+
+    #[cfg(not(all(tokio_unstable, feature = "tracing")))]
+    {
+        let _ = (role, name);
+        tokio::spawn(future);
+        Ok(())
+    }
+}
+
+/// Start one handler thread per unit of concurrency, fed by a thread routing messages by key.
+///
+/// The queue size is shared between the handler threads. The routing thread waits for a full
+/// handler queue, so publications back up into the main queue and are dropped there.
+fn spawn_keyed<F>(
+    topic: &Topic,
+    options: DispatchOptions,
+    receiver: Receiver<Publication>,
+    pending: &Arc<AtomicUsize>,
+    callback: Arc<F>,
+) -> std::io::Result<()>
+where
+    F: Fn(SparkScanMessage) + Send + Sync + 'static,
+{
+    let queue_size = options.queue_size.div_ceil(options.concurrency).max(1);
+    let mut handlers = Vec::with_capacity(options.concurrency);
+    for _ in 0..options.concurrency {
+        let (sender, messages) = sync_channel::<Parsed>(queue_size);
+        let thread_topic = topic.clone();
+        let pending = Arc::clone(pending);
+        let callback = Arc::clone(&callback);
+        spawn_thread("handler", topic, move || {
+            for parsed in messages {
+                deliver(&thread_topic, parsed, &*callback);
+                pending.fetch_sub(1, Ordering::AcqRel);
+            }
+        })?;
+        handlers.push(sender);
+    }
+
+    let thread_topic = topic.clone();
+    let pending = Arc::clone(pending);
+    spawn_thread("route", topic, move || {
+        route(
+            &thread_topic,
+            &options.limits,
+            &receiver,
+            &pending,
+            &handlers,
+        )
+    })
+}
+
+fn run<F>(
+    topic: &Topic,
+    limits: &PayloadLimits,
+    receiver: &Mutex<Receiver<Publication>>,
+    pending: &AtomicUsize,
+    callback: &F,
+) where
+    F: Fn(SparkScanMessage),
+{
+    while let Ok(publication) = next(receiver) {
+        dispatch(topic, limits, &publication, callback);
+        pending.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Parse publications and pass each message to the handler thread owning its key.
+fn route(
+    topic: &Topic,
+    limits: &PayloadLimits,
+    receiver: &Receiver<Publication>,
+    pending: &AtomicUsize,
+    handlers: &[SyncSender<Parsed>],
+) {
+    for publication in receiver {
+        let Some(parsed) = parse(topic, limits, &publication) else {
+            pending.fetch_sub(1, Ordering::AcqRel);
+            continue;
+        };
+        let mut hasher = DefaultHasher::new();
+        parsed.message.ordering_key().hash(&mut hasher);
+        let handler = &handlers[(hasher.finish() % handlers.len() as u64) as usize];
+        if handler.send(parsed).is_err() {
+            pending.fetch_sub(1, Ordering::AcqRel);
+            return;
+        }
+    }
+}
+
+/// Take the next publication, releasing the queue before it is dispatched.
+fn next(receiver: &Mutex<Receiver<Publication>>) -> Result<Publication, RecvError> {
+    receiver
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .recv()
+}
+
+/// Parse a publication and pass it to `callback`, logging parse errors and panics.
+pub(crate) fn dispatch<F>(
+    topic: &Topic,
+    limits: &PayloadLimits,
+    publication: &Publication,
+    callback: &F,
+) where
+    F: Fn(SparkScanMessage),
+{
+    if let Some(parsed) = parse(topic, limits, publication) {
+        deliver(topic, parsed, callback);
+    }
+}
+
+/// Parsed publication, with the span of its dispatch under the `tracing` feature.
+struct Parsed {
+    message: SparkScanMessage,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+#[cfg(feature = "tracing")]
+fn message_span(topic: &Topic, offset: u64) -> tracing::Span {
+    let span = tracing::info_span!(
+        "sparkscan_message",
+        topic = %topic.as_str(),
+        offset = tracing::field::Empty,
+        message_type = tracing::field::Empty,
+        parse_us = tracing::field::Empty,
+    );
+    // The server only sets offsets on channels with history
+    if offset > 0 {
+        span.record("offset", offset);
+    }
+    span
+}
+
+fn parse(topic: &Topic, limits: &PayloadLimits, publication: &Publication) -> Option<Parsed> {
+    #[cfg(feature = "tracing")]
+    let span = message_span(topic, publication.offset);
+    #[cfg(feature = "tracing")]
+    let _entered = span.enter();
+    #[cfg(feature = "tracing")]
+    let started = std::time::Instant::now();
+
+    match parse_message_for_topic_with_limits(topic, &publication.data, limits) {
+        Ok(message) => {
+            #[cfg(feature = "tracing")]
+            {
+                span.record("message_type", message.message_type());
+                span.record("parse_us", started.elapsed().as_micros() as u64);
+            }
+
+            Some(Parsed {
+                message,
+                #[cfg(feature = "tracing")]
+                span: span.clone(),
+            })
+        }
+        Err(e) => {
+            logging::log(
+                LogCategory::Parse,
+                Level::Error,
+                format_args!("Failed to parse message for topic {:?}: {}", topic, e),
+            );
+
+            None
+        }
+    }
+}
+
+fn deliver<F>(topic: &Topic, parsed: Parsed, callback: &F)
+where
+    F: Fn(SparkScanMessage),
+{
+    // Spans and events of the callback nest in the span of the message
+    #[cfg(feature = "tracing")]
+    let _entered = parsed.span.enter();
+
+    // Keep the dispatch thread alive for the next messages
+    if catch_unwind(AssertUnwindSafe(|| callback(parsed.message))).is_err() {
+        logging::log(
+            LogCategory::Dispatch,
+            Level::Error,
+            format_args!("Message callback for topic {:?} panicked", topic),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    const BALANCE: &str = r#"{
+        "address": "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s",
+        "network": "MAINNET",
+        "soft_balance": "100",
+        "hard_balance": "90",
+        "processed_at": "2025-08-06T16:28:42.955000Z"
+    }"#;
+
+    fn publication(data: &[u8]) -> Publication {
+        Publication {
+            data: data.to_vec(),
+            ..Publication::default()
+        }
+    }
+
+    #[test]
+    fn test_dispatch_runs_off_the_caller_thread() {
+        let caller = std::thread::current().id();
+        let (sender, receiver) = channel();
+        let dispatcher = Dispatcher::spawn(
+            Topic::Balances,
+            DispatchOptions::default(),
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(move |message: SparkScanMessage| {
+                let thread = std::thread::current();
+                let name = thread.name().map(str::to_string);
+                sender
+                    .send((thread.id(), name, message.message_type()))
+                    .unwrap();
+            }),
+        )
+        .unwrap();
+
+        dispatcher.push(publication(b"not json"));
+        dispatcher.push(publication(BALANCE.as_bytes()));
+
+        let (thread, name, message_type) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_ne!(thread, caller);
+        assert_eq!(name.as_deref(), Some("sparkscan-dispatch:balances"));
+        assert_eq!(message_type, "balance");
+    }
+
+    #[test]
+    fn test_full_queue_drops_messages() {
+        let (release, blocked) = channel::<()>();
+        let blocked = std::sync::Mutex::new(blocked);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let callback = Arc::new(move |_: SparkScanMessage| {
+            let _ = blocked.lock().unwrap().recv();
+        });
+        let options = DispatchOptions {
+            queue_size: 1,
+            ..DispatchOptions::default()
+        };
+        let dispatcher =
+            Dispatcher::spawn(Topic::Balances, options, Arc::clone(&dropped), callback).unwrap();
+
+        // The first message blocks the callback, the second fills the queue
+        for _ in 0..10 {
+            dispatcher.push(publication(BALANCE.as_bytes()));
+        }
+        assert!(dropped.load(Ordering::Relaxed) >= 8);
+        drop(release);
+    }
+
+    #[test]
+    fn test_panicking_callback_keeps_dispatching() {
+        let (sender, receiver) = channel();
+        let dispatcher = Dispatcher::spawn(
+            Topic::Balances,
+            DispatchOptions::default(),
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(move |_: SparkScanMessage| {
+                sender.send(()).unwrap();
+                panic!("callback failure");
+            }),
+        )
+        .unwrap();
+
+        dispatcher.push(publication(BALANCE.as_bytes()));
+        dispatcher.push(publication(BALANCE.as_bytes()));
+        for _ in 0..2 {
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_concurrent_handlers() {
+        let active = Arc::new(AtomicU64::new(0));
+        let max_active = Arc::new(AtomicU64::new(0));
+        let (sender, receiver) = channel();
+        let callback = {
+            let (active, max_active) = (Arc::clone(&active), Arc::clone(&max_active));
+            Arc::new(move |_: SparkScanMessage| {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                max_active.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(50));
+                active.fetch_sub(1, Ordering::SeqCst);
+                sender.send(()).unwrap();
+            })
+        };
+        let options = DispatchOptions {
+            queue_size: 16,
+            concurrency: 4,
+            ..DispatchOptions::default()
+        };
+        let dispatcher = Dispatcher::spawn(
+            Topic::Balances,
+            options,
+            Arc::new(AtomicU64::new(0)),
+            callback,
+        )
+        .unwrap();
+
+        for _ in 0..8 {
+            dispatcher.push(publication(BALANCE.as_bytes()));
+        }
+        for _ in 0..8 {
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+        assert!(max_active.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn test_pending_publications() {
+        let (release, blocked) = channel::<()>();
+        let blocked = std::sync::Mutex::new(blocked);
+        let callback = Arc::new(move |_: SparkScanMessage| {
+            let _ = blocked.lock().unwrap().recv();
+        });
+        let dispatcher = Dispatcher::spawn(
+            Topic::Balances,
+            DispatchOptions::default(),
+            Arc::new(AtomicU64::new(0)),
+            callback,
+        )
+        .unwrap();
+
+        dispatcher.push(publication(b"not json"));
+        for _ in 0..3 {
+            dispatcher.push(publication(BALANCE.as_bytes()));
+        }
+        assert!(dispatcher.pending().load(Ordering::Acquire) >= 3);
+
+        drop(release);
+        let pending = Arc::clone(dispatcher.pending());
+        for _ in 0..500 {
+            if pending.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("publications were not drained");
+    }
+
+    #[test]
+    fn test_keyed_ordering() {
+        let (sender, receiver) = channel();
+        let options = DispatchOptions {
+            queue_size: 64,
+            concurrency: 4,
+            ordering: HandlerOrdering::KeyedOrdering,
+            ..DispatchOptions::default()
+        };
+        let dispatcher = Dispatcher::spawn(
+            Topic::Balances,
+            options,
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(move |message: SparkScanMessage| {
+                let SparkScanMessage::Balance(balance) = message else {
+                    return;
+                };
+                std::thread::sleep(Duration::from_millis(1));
+                sender
+                    .send((balance.address.to_string(), balance.soft_balance))
+                    .unwrap();
+            }),
+        )
+        .unwrap();
+
+        let addresses = ["q", "p", "z", "r"].map(|c| format!("sp1{}", c.repeat(60)));
+        for sequence in 0..10 {
+            for address in &addresses {
+                let balance = BALANCE
+                    .replace(
+                        "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s",
+                        address,
+                    )
+                    .replace("\"100\"", &format!("\"{}\"", sequence));
+                dispatcher.push(publication(balance.as_bytes()));
+            }
+        }
+
+        let mut last = std::collections::HashMap::new();
+        for _ in 0..addresses.len() * 10 {
+            let (address, soft_balance) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+            let sequence: i64 = soft_balance.parse().unwrap();
+            let previous = last.insert(address, sequence).unwrap_or(-1);
+            assert_eq!(sequence, previous + 1);
+        }
+    }
+}
@@ -51,11 +51,182 @@ pub enum SparkScanWsError {
     #[error("Rate limit exceeded")]
     RateLimitError,
 
+    /// Publication rejected by the payload limits before parsing
+    #[error("Payload limit exceeded: {0}")]
+    PayloadLimit(#[from] PayloadLimitError),
+
     /// Generic error
     #[error("SparkScan WebSocket error: {0}")]
     Generic(#[from] anyhow::Error),
 }
 
+/// Limit of [`PayloadLimits`](crate::PayloadLimits) exceeded by a publication.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PayloadLimitError {
+    /// The publication is larger than the maximum payload size
+    #[error("payload of {size} bytes exceeds {max} bytes")]
+    TooLarge {
+        /// Size of the publication in bytes
+        size: usize,
+        /// Maximum payload size in bytes
+        max: usize,
+    },
+
+    /// The publication nests objects and arrays deeper than allowed
+    #[error("payload nested deeper than {max} levels")]
+    TooDeep {
+        /// Maximum nesting depth
+        max: usize,
+    },
+
+    /// The publication holds an array longer than allowed
+    #[error("array with more than {max} elements")]
+    ArrayTooLong {
+        /// Maximum array length
+        max: usize,
+    },
+}
+
+/// Publication rejected by the verifier of the client.
+///
+/// See [`SparkScanWsClient::verify_publications`](crate::SparkScanWsClient::verify_publications).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("publication on {channel} failed verification: {reason}")]
+pub struct VerificationError {
+    /// Channel of the publication
+    pub channel: String,
+    /// Why the verifier rejected the publication
+    pub reason: String,
+}
+
+/// Subscription failure reported by the server.
+///
+/// Classifies the Centrifugo error codes, so that clients can tell failures worth retrying
+/// from fatal ones.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SubscribeError {
+    /// Internal server error (code 100)
+    #[error("internal server error")]
+    Internal,
+
+    /// The client is not authorized (code 101)
+    #[error("unauthorized")]
+    Unauthorized,
+
+    /// The channel does not exist (code 102)
+    #[error("unknown channel")]
+    UnknownChannel,
+
+    /// The client may not subscribe to the channel (code 103)
+    #[error("permission denied")]
+    PermissionDenied,
+
+    /// The client has too many subscriptions (code 106)
+    #[error("subscription limit exceeded")]
+    LimitExceeded,
+
+    /// The subscription token expired (code 109)
+    #[error("token expired")]
+    TokenExpired,
+
+    /// The client sent too many requests (code 111)
+    #[error("too many requests")]
+    TooManyRequests,
+
+    /// Any other error code sent by the server
+    #[error("subscription error {code}: {message}")]
+    Server {
+        /// Centrifugo error code
+        code: u32,
+        /// Error message
+        message: String,
+    },
+
+    /// The server did not acknowledge the subscription in time
+    #[error("subscription not acknowledged within {0:?}")]
+    Timeout(std::time::Duration),
+
+    /// Failure without an error code, such as a transport error
+    #[error("{0}")]
+    Other(String),
+}
+
+impl SubscribeError {
+    /// Classify a Centrifugo error code and the message sent with it.
+    pub fn from_code<T: Into<String>>(code: u32, message: T) -> Self {
+        match code {
+            100 => Self::Internal,
+            101 => Self::Unauthorized,
+            102 => Self::UnknownChannel,
+            103 => Self::PermissionDenied,
+            106 => Self::LimitExceeded,
+            109 => Self::TokenExpired,
+            111 => Self::TooManyRequests,
+            _ => Self::Server {
+                code,
+                message: message.into(),
+            },
+        }
+    }
+
+    /// Classify an error reported by the underlying subscription, from the error code it
+    /// mentions if any.
+    pub fn parse<T: Into<String>>(error: T) -> Self {
+        let error = error.into();
+        match error_code(&error) {
+            Some(code) => Self::from_code(code, error),
+            None => Self::Other(error),
+        }
+    }
+
+    /// Get the Centrifugo error code, if the server sent one.
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            Self::Internal => Some(100),
+            Self::Unauthorized => Some(101),
+            Self::UnknownChannel => Some(102),
+            Self::PermissionDenied => Some(103),
+            Self::LimitExceeded => Some(106),
+            Self::TokenExpired => Some(109),
+            Self::TooManyRequests => Some(111),
+            Self::Server { code, .. } => Some(*code),
+            Self::Timeout(_) | Self::Other(_) => None,
+        }
+    }
+
+    /// Check whether subscribing again may succeed.
+    ///
+    /// Server errors are retryable when temporary, as are timeouts and failures without an
+    /// error code.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Internal
+            | Self::TokenExpired
+            | Self::TooManyRequests
+            | Self::Timeout(_)
+            | Self::Other(_) => true,
+            // Expired (110) and not available (108)
+            Self::Server { code, .. } => matches!(code, 108 | 110),
+            Self::Unauthorized
+            | Self::UnknownChannel
+            | Self::PermissionDenied
+            | Self::LimitExceeded => false,
+        }
+    }
+}
+
+/// Find the number following `code` in an error description, such as `code: 103`.
+fn error_code(error: &str) -> Option<u32> {
+    error.match_indices("code").find_map(|(index, _)| {
+        let rest = error[index + "code".len()..]
+            .trim_start_matches(|c: char| c == ':' || c == '=' || c == '"' || c.is_whitespace());
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        rest[..digits].parse().ok()
+    })
+}
+
 /// Result type alias for SparkScan WebSocket operations.
 pub type Result<T> = std::result::Result<T, SparkScanWsError>;
 
@@ -99,3 +270,49 @@ impl SparkScanWsError {
         Self::AuthError(msg.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribe_error_parse() {
+        assert_eq!(
+            SubscribeError::parse(r#"Error { code: 103, message: "permission denied" }"#),
+            SubscribeError::PermissionDenied
+        );
+        assert_eq!(
+            SubscribeError::parse(r#"{"code":102,"message":"unknown channel"}"#),
+            SubscribeError::UnknownChannel
+        );
+        assert_eq!(
+            SubscribeError::parse("ErrorCode { code: 112 }").code(),
+            Some(112)
+        );
+
+        let transport = SubscribeError::parse("connection reset");
+        assert_eq!(
+            transport,
+            SubscribeError::Other("connection reset".to_string())
+        );
+        assert_eq!(transport.code(), None);
+    }
+
+    #[test]
+    fn test_subscribe_error_is_retryable() {
+        assert!(SubscribeError::from_code(100, "internal").is_retryable());
+        assert!(SubscribeError::from_code(111, "too many requests").is_retryable());
+        assert!(SubscribeError::from_code(108, "not available").is_retryable());
+        assert!(!SubscribeError::from_code(103, "permission denied").is_retryable());
+        assert!(!SubscribeError::from_code(106, "limit exceeded").is_retryable());
+        assert!(!SubscribeError::from_code(107, "bad request").is_retryable());
+
+        let timeout = SubscribeError::Timeout(std::time::Duration::from_secs(5));
+        assert!(timeout.is_retryable());
+        assert_eq!(timeout.code(), None);
+        assert_eq!(
+            timeout.to_string(),
+            "subscription not acknowledged within 5s"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Conversion of prices and amounts to fiat currencies.
+//!
+//! The feed only carries values in satoshis. A [`FiatConverter`] supplies the price of one
+//! bitcoin in a fiat currency, from whichever rate source the application uses, and converts
+//! satoshi values with it. [`FiatMessage`] attaches the converted values to a message:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let converter = ManualRate::usd(100_000.0);
+//!
+//! let subscription = client.subscribe(Topic::TokenPrices).await?;
+//! subscription.on_message(move |message| {
+//!     let message = FiatMessage::new(message, &converter);
+//!     if let Some(price) = message.price {
+//!         println!("{}: {:.4} {}", message.message.ordering_key(), price, message.currency);
+//!     }
+//! });
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::types::SparkScanMessage;
+use sparkscan_types::Sats;
+use std::sync::{Arc, RwLock};
+
+/// Satoshis in one bitcoin.
+const SATS_PER_BTC: f64 = 100_000_000.0;
+
+/// Source of the bitcoin exchange rate, converting satoshi values to a fiat currency.
+///
+/// Implement [`btc_rate`](Self::btc_rate) on top of the rate source of the application; the
+/// conversions are derived from it.
+pub trait FiatConverter: Send + Sync {
+    /// Get the price of one bitcoin in the fiat currency, if known.
+    fn btc_rate(&self) -> Option<f64>;
+
+    /// Get the code of the fiat currency.
+    fn currency(&self) -> &str {
+        "USD"
+    }
+
+    /// Convert an amount in satoshis.
+    fn sats_to_fiat(&self, amount: Sats) -> Option<f64> {
+        Some(amount.value() as f64 * self.btc_rate()? / SATS_PER_BTC)
+    }
+
+    /// Convert a price in satoshis per unit, such as the price of a token.
+    fn price_to_fiat(&self, price_sats: f64) -> Option<f64> {
+        Some(price_sats * self.btc_rate()? / SATS_PER_BTC)
+    }
+}
+
+impl<T: FiatConverter + ?Sized> FiatConverter for Arc<T> {
+    fn btc_rate(&self) -> Option<f64> {
+        (**self).btc_rate()
+    }
+
+    fn currency(&self) -> &str {
+        (**self).currency()
+    }
+}
+
+/// Converter with a rate set by the application, and updated as it changes.
+#[derive(Debug)]
+pub struct ManualRate {
+    currency: String,
+    rate: RwLock<Option<f64>>,
+}
+
+impl ManualRate {
+    /// Create a converter to `currency` at `rate` per bitcoin.
+    pub fn new(currency: impl Into<String>, rate: f64) -> Self {
+        Self {
+            currency: currency.into(),
+            rate: RwLock::new(Some(rate)),
+        }
+    }
+
+    /// Create a converter to US dollars at `rate` per bitcoin.
+    pub fn usd(rate: f64) -> Self {
+        Self::new("USD", rate)
+    }
+
+    /// Replace the rate, or clear it when it is no longer known.
+    pub fn set_rate(&self, rate: Option<f64>) {
+        *self.rate.write().unwrap_or_else(|e| e.into_inner()) = rate;
+    }
+}
+
+impl FiatConverter for ManualRate {
+    fn btc_rate(&self) -> Option<f64> {
+        *self.rate.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn currency(&self) -> &str {
+        &self.currency
+    }
+}
+
+/// Message with its satoshi values converted to a fiat currency.
+#[derive(Debug, Clone)]
+pub struct FiatMessage {
+    /// Original message
+    pub message: SparkScanMessage,
+    /// Code of the fiat currency
+    pub currency: String,
+    /// Soft balance or transaction amount, if any and if the rate is known
+    pub amount: Option<f64>,
+    /// Token price per unit, if any and if the rate is known
+    pub price: Option<f64>,
+}
+
+impl FiatMessage {
+    /// Convert the values of `message` with `converter`.
+    pub fn new<C: FiatConverter + ?Sized>(message: SparkScanMessage, converter: &C) -> Self {
+        let amount = fiat_amount(&message, converter);
+        let price = fiat_price(&message, converter);
+        Self {
+            currency: converter.currency().to_string(),
+            message,
+            amount,
+            price,
+        }
+    }
+}
+
+/// Convert the soft balance of a balance update or the amount of a transaction.
+pub fn fiat_amount<C: FiatConverter + ?Sized>(
+    message: &SparkScanMessage,
+    converter: &C,
+) -> Option<f64> {
+    let amount = match message {
+        SparkScanMessage::Balance(data) => &data.soft_balance,
+        SparkScanMessage::Transaction(data) => data.amount_sats.as_ref()?,
+        _ => return None,
+    };
+    converter.sats_to_fiat(amount.parse().ok()?)
+}
+
+/// Convert the price of a token price or token update.
+pub fn fiat_price<C: FiatConverter + ?Sized>(
+    message: &SparkScanMessage,
+    converter: &C,
+) -> Option<f64> {
+    let price = match message {
+        SparkScanMessage::TokenPrice(data) => data.price_sats.as_str(),
+        SparkScanMessage::Token(data) => data.price_sats.as_ref()?.as_str(),
+        _ => return None,
+    };
+    converter.price_to_fiat(price.parse().ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{parse_message_for_topic, Topic};
+    use serde_json::json;
+
+    #[test]
+    fn test_fiat_conversion() {
+        let converter = ManualRate::usd(100_000.0);
+        assert_eq!(converter.sats_to_fiat(Sats(150_000)), Some(150.0));
+        assert_eq!(converter.price_to_fiat(250.0), Some(0.25));
+
+        converter.set_rate(None);
+        assert_eq!(converter.sats_to_fiat(Sats(150_000)), None);
+    }
+
+    #[test]
+    fn test_fiat_message() {
+        let converter = Arc::new(ManualRate::new("EUR", 50_000.0));
+        let price_json = json!({
+            "address": "btkn1qxqmw2gdhg8gqzd6cmxcwdz0mnh2xqqryd69y7sm6fpdjne2d6x8qqmzhc9",
+            "network": "MAINNET",
+            "protocol": "sparksat",
+            "price_sats": "200",
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+        let json_str = serde_json::to_string(&price_json).unwrap();
+        let message = parse_message_for_topic(&Topic::TokenPrices, json_str.as_bytes()).unwrap();
+
+        let message = FiatMessage::new(message, &converter);
+        assert_eq!(message.currency, "EUR");
+        assert_eq!(message.price, Some(0.1));
+        assert_eq!(message.amount, None);
+    }
+}
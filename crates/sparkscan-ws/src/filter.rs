@@ -0,0 +1,276 @@
+//! Composable message filters.
+//!
+//! A [`Filter`] is a predicate over [`SparkScanMessage`]s built from small pieces and combined
+//! with [`and`](Filter::and), [`or`](Filter::or) and `!`. The same filter can be
+//! applied to a subscription, a merged address subscription, or any other message callback:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let filter = Filter::topic(&Topic::TransactionNetwork("mainnet".to_string()))
+//!     .and(Filter::min_amount(Sats(100_000)))
+//!     .and(Filter::address_in(["sp1...", "sp1..."]));
+//!
+//! let subscription = client.subscribe(Topic::Transactions).await?;
+//! subscription.on_message_filtered(filter.clone(), |message| println!("{}", message));
+//! subscription.on_message(filter.apply(|message| println!("large: {}", message)));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::types::{SparkScanMessage, Topic};
+use sparkscan_types::{Network, Sats};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+type Predicate = Arc<dyn Fn(&SparkScanMessage) -> bool + Send + Sync>;
+
+/// Predicate selecting the messages delivered to a callback.
+///
+/// Filters are cheap to clone and share their predicate.
+#[derive(Clone)]
+pub struct Filter {
+    predicate: Predicate,
+}
+
+impl Filter {
+    /// Create a filter from a custom predicate.
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&SparkScanMessage) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    /// Match every message.
+    pub fn any() -> Self {
+        Self::new(|_| true)
+    }
+
+    /// Match the messages that would be published on `topic`.
+    ///
+    /// Networks in topics are compared case-insensitively, so both `mainnet` and `MAINNET`
+    /// match messages of the main network.
+    pub fn topic(topic: &Topic) -> Self {
+        let topic = topic.clone();
+        Self::new(move |message| topic_matches(&topic, message))
+    }
+
+    /// Match the messages of `network`.
+    pub fn network(network: Network) -> Self {
+        Self::new(move |message| message.spark_network() == network)
+    }
+
+    /// Match the messages of a type, as returned by [`SparkScanMessage::message_type`].
+    pub fn message_type(message_type: &'static str) -> Self {
+        Self::new(move |message| message.message_type() == message_type)
+    }
+
+    /// Match the balances and transactions carrying at least `amount` satoshis.
+    ///
+    /// Balances are compared by their soft balance. Messages without an amount in satoshis,
+    /// such as token updates, do not match.
+    pub fn min_amount(amount: Sats) -> Self {
+        Self::new(move |message| sats_amount(message).is_some_and(|sats| sats >= amount))
+    }
+
+    /// Match the messages involving one of `addresses`.
+    ///
+    /// This is the address of balance and token balance updates, and either side of
+    /// transactions. Token and price updates do not match.
+    pub fn address_in<I>(addresses: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: ToString,
+    {
+        let addresses: HashSet<String> = addresses.into_iter().map(|a| a.to_string()).collect();
+        Self::new(move |message| match message {
+            SparkScanMessage::Balance(data) => addresses.contains(data.address.as_str()),
+            SparkScanMessage::TokenBalance(data) => addresses.contains(data.address.as_str()),
+            SparkScanMessage::Transaction(data) => [&data.from_identifier, &data.to_identifier]
+                .into_iter()
+                .flatten()
+                .any(|identifier| addresses.contains(identifier)),
+            SparkScanMessage::TokenPrice(_) | SparkScanMessage::Token(_) => false,
+        })
+    }
+
+    /// Match the messages matched by both filters.
+    pub fn and(self, other: Filter) -> Self {
+        Self::new(move |message| self.matches(message) && other.matches(message))
+    }
+
+    /// Match the messages matched by either filter.
+    pub fn or(self, other: Filter) -> Self {
+        Self::new(move |message| self.matches(message) || other.matches(message))
+    }
+
+    /// Check whether `message` passes the filter.
+    pub fn matches(&self, message: &SparkScanMessage) -> bool {
+        (self.predicate)(message)
+    }
+
+    /// Wrap `callback` so that it is only called with the messages passing the filter.
+    pub fn apply<F>(self, callback: F) -> impl Fn(SparkScanMessage) + Send + Sync + 'static
+    where
+        F: Fn(SparkScanMessage) + Send + Sync + 'static,
+    {
+        move |message| {
+            if self.matches(&message) {
+                callback(message);
+            }
+        }
+    }
+}
+
+/// Match the messages not matched by the filter.
+impl std::ops::Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        Filter::new(move |message| !self.matches(message))
+    }
+}
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filter").finish_non_exhaustive()
+    }
+}
+
+/// Get the amount in satoshis carried by a balance or transaction.
+fn sats_amount(message: &SparkScanMessage) -> Option<Sats> {
+    let amount = match message {
+        SparkScanMessage::Balance(data) => &data.soft_balance,
+        SparkScanMessage::Transaction(data) => data.amount_sats.as_ref()?,
+        _ => return None,
+    };
+    amount.parse().ok()
+}
+
+fn topic_matches(topic: &Topic, message: &SparkScanMessage) -> bool {
+    let network = |network: &str| {
+        message
+            .spark_network()
+            .as_str()
+            .eq_ignore_ascii_case(network)
+    };
+    match (topic, message) {
+        (Topic::Balances, SparkScanMessage::Balance(_)) => true,
+        (Topic::BalanceNetwork(n), SparkScanMessage::Balance(_)) => network(n),
+        (Topic::BalanceAddress(a), SparkScanMessage::Balance(data)) => data.address.as_str() == a,
+
+        (Topic::TokenBalances, SparkScanMessage::TokenBalance(_)) => true,
+        (Topic::TokenBalanceNetwork(n), SparkScanMessage::TokenBalance(_)) => network(n),
+        (Topic::TokenBalanceIdentifier(i), SparkScanMessage::TokenBalance(data)) => {
+            data.token_address.as_str() == i
+        }
+        (Topic::TokenBalanceAddress(a), SparkScanMessage::TokenBalance(data)) => {
+            data.address.as_str() == a
+        }
+
+        (Topic::TokenPrices, SparkScanMessage::TokenPrice(_)) => true,
+        (Topic::TokenPriceNetwork(n), SparkScanMessage::TokenPrice(_)) => network(n),
+        (Topic::TokenPriceIdentifier(i), SparkScanMessage::TokenPrice(data)) => {
+            data.address.as_str() == i
+        }
+
+        (Topic::Transactions, SparkScanMessage::Transaction(_)) => true,
+        (Topic::TransactionNetwork(n), SparkScanMessage::Transaction(_)) => network(n),
+        (Topic::TransactionIn(n, field), SparkScanMessage::Transaction(data)) => {
+            network(n) && data.to_identifier.as_deref() == Some(field.as_str())
+        }
+        (Topic::TransactionOut(n, field), SparkScanMessage::Transaction(data)) => {
+            network(n) && data.from_identifier.as_deref() == Some(field.as_str())
+        }
+
+        (Topic::Tokens, SparkScanMessage::Token(_)) => true,
+        (Topic::TokenIdentifier(i), SparkScanMessage::Token(data)) => data.address.as_str() == i,
+        (Topic::TokenNetwork(n), SparkScanMessage::Token(_)) => network(n),
+        (Topic::TokenIssuer(issuer), SparkScanMessage::Token(data)) => {
+            data.issuer.as_str() == issuer
+        }
+
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::parse_message_for_topic;
+    use serde_json::json;
+
+    fn transaction(amount_sats: &str) -> SparkScanMessage {
+        let transaction_json = json!({
+            "id": "filter_test",
+            "network": "MAINNET",
+            "type": "spark_to_spark",
+            "status": "confirmed",
+            "amount_sats": amount_sats,
+            "from_identifier": "sender",
+            "to_identifier": "receiver",
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_topic_filter() {
+        let message = transaction("1000");
+
+        for topic in [
+            Topic::Transactions,
+            Topic::TransactionNetwork("mainnet".to_string()),
+            Topic::TransactionIn("mainnet".to_string(), "receiver".to_string()),
+            Topic::TransactionOut("MAINNET".to_string(), "sender".to_string()),
+        ] {
+            assert!(Filter::topic(&topic).matches(&message), "{:?}", topic);
+        }
+        for topic in [
+            Topic::Balances,
+            Topic::TransactionNetwork("regtest".to_string()),
+            Topic::TransactionIn("mainnet".to_string(), "sender".to_string()),
+        ] {
+            assert!(!Filter::topic(&topic).matches(&message), "{:?}", topic);
+        }
+    }
+
+    #[test]
+    fn test_combined_filters() {
+        let filter = Filter::topic(&Topic::Transactions)
+            .and(Filter::min_amount(Sats(1_000)))
+            .and(Filter::address_in(["receiver"]));
+
+        assert!(filter.matches(&transaction("1000")));
+        assert!(!filter.matches(&transaction("999")));
+        assert!(!filter.matches(&transaction("not a number")));
+        assert!(!Filter::address_in(["someone else"]).matches(&transaction("1000")));
+
+        let small = !Filter::min_amount(Sats(1_000));
+        assert!(small.matches(&transaction("999")));
+        assert!(small
+            .or(Filter::network(Network::Mainnet))
+            .matches(&transaction("1000")));
+    }
+
+    #[test]
+    fn test_apply_filter() {
+        let delivered = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&delivered);
+        let callback = Filter::min_amount(Sats(1_000)).apply(move |message| {
+            sink.lock()
+                .unwrap()
+                .push(message.ordering_key().to_string());
+        });
+
+        callback(transaction("10"));
+        callback(transaction("5000"));
+        assert_eq!(*delivered.lock().unwrap(), ["sender"]);
+    }
+}
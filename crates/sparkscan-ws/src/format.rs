@@ -0,0 +1,68 @@
+//! Human-friendly formatting of amounts carried by messages.
+//!
+//! Payloads carry amounts as decimal strings in their smallest unit; these helpers render
+//! them with thousands separators for logs and user interfaces.
+
+/// Format an amount of satoshis, e.g. `1,234,567 sats`.
+pub fn format_sats(sats: u64) -> String {
+    format!("{} sats", group_thousands(&sats.to_string()))
+}
+
+/// Format a token amount given in its smallest unit, applying the token's `decimals`.
+///
+/// Trailing zeros of the fractional part are dropped, so `2100000000000000` with 8 decimals
+/// is `21,000,000` and `150000` with 6 decimals is `0.15`.
+pub fn format_token_amount(amount: u128, decimals: u8) -> String {
+    let digits = amount.to_string();
+    let decimals = usize::from(decimals);
+    let (integer, fraction) = if digits.len() > decimals {
+        let (integer, fraction) = digits.split_at(digits.len() - decimals);
+        (integer.to_string(), fraction.to_string())
+    } else {
+        (
+            "0".to_string(),
+            format!("{:0>width$}", digits, width = decimals),
+        )
+    };
+
+    let integer = group_thousands(&integer);
+    match fraction.trim_end_matches('0') {
+        "" => integer,
+        fraction => format!("{}.{}", integer, fraction),
+    }
+}
+
+/// Insert a comma between every group of three digits.
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_sats() {
+        assert_eq!(format_sats(0), "0 sats");
+        assert_eq!(format_sats(999), "999 sats");
+        assert_eq!(format_sats(1_000), "1,000 sats");
+        assert_eq!(format_sats(1_234_567), "1,234,567 sats");
+    }
+
+    #[test]
+    fn test_format_token_amount() {
+        assert_eq!(format_token_amount(2_100_000_000_000_000, 8), "21,000,000");
+        assert_eq!(format_token_amount(150_000, 6), "0.15");
+        assert_eq!(format_token_amount(1, 8), "0.00000001");
+        assert_eq!(format_token_amount(123_456_789, 2), "1,234,567.89");
+        assert_eq!(format_token_amount(1_000, 0), "1,000");
+        assert_eq!(format_token_amount(0, 8), "0");
+    }
+}
@@ -0,0 +1,281 @@
+//! Human-readable labels of addresses.
+//!
+//! An [`AddressBook`] maps addresses to labels, loaded from CSV or JSON or inserted by the
+//! application. It annotates messages with the labels of their addresses, so that logs and
+//! alerts show "Exchange Hot Wallet" instead of a raw bech32 string:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # use std::sync::Arc;
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let book = Arc::new(AddressBook::from_csv(
+//!     "address,label\n\
+//!      sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s,Exchange Hot Wallet",
+//! )?);
+//!
+//! let subscription = client.subscribe(Topic::Transactions).await?;
+//! subscription.on_message(book.apply(|message| println!("{}", message)));
+//! subscription.subscribe();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, SparkScanWsError};
+use crate::types::SparkScanMessage;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Labels of addresses, updated while in use.
+#[derive(Debug, Default)]
+pub struct AddressBook {
+    labels: RwLock<HashMap<String, String>>,
+}
+
+impl AddressBook {
+    /// Create an empty address book.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load labels from CSV lines of `address,label`.
+    ///
+    /// An `address,label` header, empty lines and lines starting with `#` are skipped. Labels
+    /// may contain commas, and may be quoted.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a line has no label.
+    pub fn from_csv(csv: &str) -> Result<Self> {
+        let book = Self::new();
+        for (index, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line == "address,label" {
+                continue;
+            }
+            let Some((address, label)) = line.split_once(',') else {
+                return Err(SparkScanWsError::ConfigError(format!(
+                    "line {} of the address labels has no label",
+                    index + 1
+                )));
+            };
+            let label = label.trim();
+            let label = label
+                .strip_prefix('"')
+                .and_then(|label| label.strip_suffix('"'))
+                .map(|label| label.replace("\"\"", "\""))
+                .unwrap_or_else(|| label.to_string());
+            book.insert(address.trim(), label);
+        }
+        Ok(book)
+    }
+
+    /// Load labels from a JSON object of labels by address, or an array of objects with an
+    /// `address` and a `label`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the JSON has neither shape.
+    pub fn from_json(json: &str) -> Result<Self> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Labels {
+            Map(HashMap<String, String>),
+            List(Vec<Entry>),
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Entry {
+            address: String,
+            label: String,
+        }
+
+        let labels = match serde_json::from_str(json)? {
+            Labels::Map(labels) => labels,
+            Labels::List(entries) => entries
+                .into_iter()
+                .map(|entry| (entry.address, entry.label))
+                .collect(),
+        };
+        Ok(Self {
+            labels: RwLock::new(labels),
+        })
+    }
+
+    /// Label `address`, replacing its previous label.
+    pub fn insert(&self, address: impl Into<String>, label: impl Into<String>) {
+        self.labels
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(address.into(), label.into());
+    }
+
+    /// Remove the label of `address`, returning it.
+    pub fn remove(&self, address: &str) -> Option<String> {
+        self.labels
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(address)
+    }
+
+    /// Get the label of `address`.
+    pub fn label(&self, address: &str) -> Option<String> {
+        self.labels
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(address)
+            .cloned()
+    }
+
+    /// Get the number of labeled addresses.
+    pub fn len(&self) -> usize {
+        self.labels.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Check whether no address is labeled.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Annotate `message` with the labels of its addresses.
+    pub fn annotate(&self, message: SparkScanMessage) -> LabeledMessage {
+        let label = |address: Option<&str>| address.and_then(|address| self.label(address));
+        let (address, from, to) = match &message {
+            SparkScanMessage::Balance(data) => (Some(data.address.as_str()), None, None),
+            SparkScanMessage::TokenBalance(data) => (Some(data.address.as_str()), None, None),
+            SparkScanMessage::Transaction(data) => (
+                None,
+                data.from_identifier.as_deref(),
+                data.to_identifier.as_deref(),
+            ),
+            SparkScanMessage::TokenPrice(_) | SparkScanMessage::Token(_) => (None, None, None),
+        };
+        LabeledMessage {
+            address_label: label(address),
+            from_label: label(from),
+            to_label: label(to),
+            message,
+        }
+    }
+
+    /// Wrap `callback` so that it is called with the messages annotated with their labels.
+    pub fn apply<F>(
+        self: &Arc<Self>,
+        callback: F,
+    ) -> impl Fn(SparkScanMessage) + Send + Sync + 'static
+    where
+        F: Fn(LabeledMessage) + Send + Sync + 'static,
+    {
+        let book = Arc::clone(self);
+        move |message| callback(book.annotate(message))
+    }
+}
+
+/// Message annotated with the labels of its addresses.
+///
+/// Displays as the message, with the labeled addresses replaced by their label.
+#[derive(Debug, Clone)]
+pub struct LabeledMessage {
+    /// Original message
+    pub message: SparkScanMessage,
+    /// Label of the address of a balance or token balance update
+    pub address_label: Option<String>,
+    /// Label of the sender of a transaction
+    pub from_label: Option<String>,
+    /// Label of the recipient of a transaction
+    pub to_label: Option<String>,
+}
+
+impl fmt::Display for LabeledMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let address = match &self.message {
+            SparkScanMessage::Balance(data) => Some(data.address.as_str()),
+            SparkScanMessage::TokenBalance(data) => Some(data.address.as_str()),
+            _ => None,
+        };
+        match (address, &self.address_label) {
+            (Some(address), Some(label)) => {
+                write!(f, "{}", self.message.to_string().replace(address, label))
+            }
+            _ => write!(f, "{}", self.message),
+        }?;
+        if let Some(label) = &self.from_label {
+            write!(f, ", from {}", label)?;
+        }
+        if let Some(label) = &self.to_label {
+            write!(f, ", to {}", label)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{parse_message_for_topic, Topic};
+    use serde_json::json;
+
+    const ADDRESS: &str = "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s";
+
+    #[test]
+    fn test_load_labels() {
+        let csv = format!(
+            "address,label\n# exchanges\n{},\"Exchange, Hot Wallet\"\n\nsp1b, Treasury \n",
+            ADDRESS
+        );
+        let book = AddressBook::from_csv(&csv).unwrap();
+        assert_eq!(book.len(), 2);
+        assert_eq!(book.label(ADDRESS).as_deref(), Some("Exchange, Hot Wallet"));
+        assert_eq!(book.label("sp1b").as_deref(), Some("Treasury"));
+        assert!(AddressBook::from_csv("sp1c").is_err());
+
+        let book = AddressBook::from_json(r#"{"sp1a": "Alice"}"#).unwrap();
+        assert_eq!(book.label("sp1a").as_deref(), Some("Alice"));
+        let book = AddressBook::from_json(r#"[{"address": "sp1b", "label": "Bob"}]"#).unwrap();
+        assert_eq!(book.remove("sp1b").as_deref(), Some("Bob"));
+        assert!(book.is_empty());
+        assert!(AddressBook::from_json(r#"["sp1a"]"#).is_err());
+    }
+
+    #[test]
+    fn test_annotate() {
+        let book = AddressBook::new();
+        book.insert(ADDRESS, "Exchange Hot Wallet");
+
+        let transaction = json!({
+            "id": "tx",
+            "network": "MAINNET",
+            "type": "spark_to_spark",
+            "status": "confirmed",
+            "from_identifier": ADDRESS,
+            "to_identifier": "sp1unlabeled",
+            "processed_at": "2025-08-06T16:28:00Z"
+        });
+        let message =
+            parse_message_for_topic(&Topic::Transactions, transaction.to_string().as_bytes())
+                .unwrap();
+        let labeled = book.annotate(message);
+        assert_eq!(labeled.from_label.as_deref(), Some("Exchange Hot Wallet"));
+        assert_eq!(labeled.to_label, None);
+        assert!(labeled.to_string().ends_with(", from Exchange Hot Wallet"));
+
+        let balance = json!({
+            "address": ADDRESS,
+            "network": "MAINNET",
+            "soft_balance": "1000",
+            "hard_balance": "1000",
+            "processed_at": "2025-08-06T16:28:00Z"
+        });
+        let message =
+            parse_message_for_topic(&Topic::Balances, balance.to_string().as_bytes()).unwrap();
+        let labeled = book.annotate(message);
+        assert_eq!(
+            labeled.address_label.as_deref(),
+            Some("Exchange Hot Wallet")
+        );
+        assert!(labeled
+            .to_string()
+            .starts_with("balance of Exchange Hot Wallet on MAINNET"));
+    }
+}
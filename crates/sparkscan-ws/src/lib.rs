@@ -153,22 +153,110 @@
 //!
 //! let client = SparkScanWsClient::with_config(config);
 //! ```
+//!
+//! ## Types Only
+//!
+//! Applications that only parse payloads, without opening a WebSocket, can disable the default
+//! `client` feature. This builds the payload types, [`Topic`], the parsing functions of
+//! [`types`] and the message helpers, without tokio-centrifuge or the Tokio runtime:
+//!
+//! ```toml
+//! sparkscan-ws = { version = "0.5", default-features = false }
+//! ```
 
 #![deny(missing_docs)]
 #![warn(clippy::all)]
 
+#[cfg(feature = "client")]
+pub mod acked;
+#[cfg(feature = "client")]
+mod cache;
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "client")]
 pub mod client;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compression;
+pub mod conformance;
+#[cfg(feature = "client")]
+mod connections;
+pub mod debounce;
+#[cfg(feature = "client")]
+pub mod decoder;
+pub mod deposit;
+#[cfg(feature = "client")]
+mod dispatch;
 pub mod error;
+pub mod fiat;
+pub mod filter;
+mod format;
+pub mod labels;
+pub mod limits;
+#[cfg(feature = "client")]
+pub mod logging;
+pub mod price_history;
+#[cfg(feature = "client")]
+pub mod raw;
+pub mod redaction;
+#[cfg(feature = "client")]
+pub mod router;
+#[cfg(feature = "client")]
+mod schema_check;
+pub mod schemas;
+#[cfg(feature = "client")]
+mod shutdown;
+#[cfg(feature = "client")]
+mod skew;
+pub mod stats;
+#[cfg(feature = "client")]
 pub mod subscription;
+#[cfg(feature = "client")]
+pub mod tenants;
+pub mod timestamp;
+#[cfg(feature = "client")]
+pub mod verify;
+
+#[cfg(feature = "high-throughput")]
+mod pool;
+
+#[cfg(feature = "analytics")]
+pub mod analytics;
 
 // Allow missing docs for the types module since it contains generated code
 #[allow(missing_docs)]
 pub mod types;
 
 // Re-export main types for convenience
-pub use client::{ConnectionStats, SparkScanWsClient, SparkScanWsConfig};
-pub use error::{Result, SparkScanWsError};
-pub use subscription::{SparkScanSubscription, SubscriptionManager};
+#[cfg(feature = "client")]
+pub use acked::{AckedSubscription, Delivery};
+#[cfg(feature = "client")]
+pub use client::{ConnectionStats, DisconnectReason, SparkScanWsClient, SparkScanWsConfig};
+pub use debounce::Debouncer;
+#[cfg(feature = "client")]
+pub use decoder::DecodedSubscription;
+pub use deposit::{ConfirmationLevel, Deposit, DepositEvent, DepositMonitor};
+pub use error::{PayloadLimitError, Result, SparkScanWsError, SubscribeError, VerificationError};
+pub use fiat::{FiatConverter, FiatMessage, ManualRate};
+pub use filter::Filter;
+pub use format::{format_sats, format_token_amount};
+pub use labels::{AddressBook, LabeledMessage};
+pub use limits::PayloadLimits;
+pub use price_history::{PriceChange, PriceHistory, PricePoint};
+#[cfg(feature = "client")]
+pub use raw::{PublicationMeta, RawSubscription};
+#[cfg(feature = "client")]
+pub use router::TagRouter;
+pub use schemas::SchemaMismatch;
+#[cfg(feature = "client")]
+pub use shutdown::{DispatchStats, ShutdownReport};
+pub use stats::{NetworkStats, RollingStats, StatsSnapshot};
+#[cfg(feature = "client")]
+pub use subscription::{
+    AddressSubscription, AddressTopicKind, HandlerOrdering, MessageBroadcast, NetworkTopicKind,
+    Priority, Reconciliation, SparkScanSubscription, SubscriptionManager,
+};
+#[cfg(feature = "client")]
+pub use tenants::{TenantManager, TenantSubscription};
 pub use types::{SparkScanMessage, Topic};
 
 // Re-export generated types
@@ -180,6 +268,11 @@ pub use types::{
     transaction::{TransactionPayload, Type},
 };
 
+// Re-export the domain types shared with the `sparkscan` REST client
+pub use sparkscan_types::{
+    Network, Sats, SparkAddress, TokenAmount, TransactionStatus, TransactionType,
+};
+
 /// The current version of the SparkScan WebSocket SDK.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -200,10 +293,11 @@ pub const DEFAULT_MAINNET_URL: &str = "ws://updates.sparkscan.io/";
 /// ```
 pub mod prelude {
     pub use crate::{
-        BalancePayload, Result, SparkScanMessage, SparkScanSubscription, SparkScanWsClient,
-        SparkScanWsConfig, SparkScanWsError, TokenBalancePayload, TokenPayload, TokenPricePayload,
-        Topic, TransactionPayload,
+        BalancePayload, Result, SparkScanMessage, SparkScanWsError, TokenBalancePayload,
+        TokenPayload, TokenPricePayload, Topic, TransactionPayload,
     };
+    #[cfg(feature = "client")]
+    pub use crate::{SparkScanSubscription, SparkScanWsClient, SparkScanWsConfig};
 }
 
 #[cfg(test)]
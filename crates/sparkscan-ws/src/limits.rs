@@ -0,0 +1,214 @@
+//! Sanity limits on the size and shape of publications.
+//!
+//! Publications are checked against [`PayloadLimits`] before they are parsed, so that a single
+//! pathological publication, such as a transaction with millions of token outputs, is rejected
+//! with a [`PayloadLimitError`] instead of exhausting memory while building its payload.
+//!
+//! The check is a single pass over the bytes of the publication, without allocating per value;
+//! double-encoded payloads take a second pass over their decoded string.
+
+use crate::error::PayloadLimitError;
+
+/// Limits a publication must stay within to be parsed.
+///
+/// The defaults leave room for the largest publications of the feed. Configured for the client
+/// with [`SparkScanWsConfig::with_payload_limits`](crate::SparkScanWsConfig::with_payload_limits),
+/// or passed to [`types::parse_message_for_topic_with_limits`](crate::types).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadLimits {
+    /// Maximum size of a publication in bytes (default: 4 MiB)
+    pub max_payload_bytes: usize,
+    /// Maximum nesting depth of objects and arrays (default: 32)
+    pub max_depth: usize,
+    /// Maximum number of elements of any array, such as the `token_io_details` of a
+    /// transaction (default: 10000)
+    pub max_array_len: usize,
+}
+
+impl Default for PayloadLimits {
+    fn default() -> Self {
+        Self {
+            max_payload_bytes: 4 * 1024 * 1024,
+            max_depth: 32,
+            max_array_len: 10_000,
+        }
+    }
+}
+
+impl PayloadLimits {
+    /// Create limits with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create limits accepting every publication.
+    pub fn unlimited() -> Self {
+        Self {
+            max_payload_bytes: usize::MAX,
+            max_depth: usize::MAX,
+            max_array_len: usize::MAX,
+        }
+    }
+
+    /// Set the maximum size of a publication in bytes.
+    pub fn with_max_payload_bytes(mut self, max: usize) -> Self {
+        self.max_payload_bytes = max;
+        self
+    }
+
+    /// Set the maximum nesting depth of objects and arrays.
+    pub fn with_max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    /// Set the maximum number of elements of any array.
+    pub fn with_max_array_len(mut self, max: usize) -> Self {
+        self.max_array_len = max;
+        self
+    }
+
+    /// Check a JSON publication against the limits.
+    ///
+    /// Only the structure is inspected: a publication within the limits may still fail to
+    /// parse. Double-encoded payloads are decoded into a string, whose structure is checked
+    /// before it is parsed.
+    pub fn check(&self, data: &[u8]) -> Result<(), PayloadLimitError> {
+        self.check_size(data.len())?;
+
+        // Number of elements of each open array, `None` for objects
+        let mut open: Vec<Option<usize>> = Vec::new();
+        let mut in_string = false;
+        let mut escaped = false;
+        // Whether the string just opened is part of the envelope, and whether any such string
+        // holds an object or array, as double-encoded payloads do
+        let mut envelope_string = false;
+        let mut encoded = false;
+        for &byte in data {
+            if in_string {
+                encoded |= envelope_string && matches!(byte, b'{' | b'[');
+                envelope_string &= byte.is_ascii_whitespace();
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            if let Some(Some(len)) = open.last_mut() {
+                let first = *len == 0 && !byte.is_ascii_whitespace() && byte != b']';
+                if first || byte == b',' {
+                    *len += 1;
+                    if *len > self.max_array_len {
+                        return Err(PayloadLimitError::ArrayTooLong {
+                            max: self.max_array_len,
+                        });
+                    }
+                }
+            }
+            match byte {
+                b'"' => {
+                    in_string = true;
+                    envelope_string = open.len() <= 1;
+                }
+                b'{' | b'[' => {
+                    open.push((byte == b'[').then_some(0));
+                    if open.len() > self.max_depth {
+                        return Err(PayloadLimitError::TooDeep {
+                            max: self.max_depth,
+                        });
+                    }
+                }
+                b'}' | b']' => {
+                    open.pop();
+                }
+                _ => {}
+            }
+        }
+
+        if encoded {
+            let payload = crate::types::raw_payload(data).filter(|json| json.starts_with('"'));
+            if let Some(Ok(payload)) = payload.map(serde_json::from_str::<String>) {
+                return self.check(payload.as_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the size of a publication against the limits.
+    pub(crate) fn check_size(&self, size: usize) -> Result<(), PayloadLimitError> {
+        if size > self.max_payload_bytes {
+            return Err(PayloadLimitError::TooLarge {
+                size,
+                max: self.max_payload_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_limits() {
+        let limits = PayloadLimits::new()
+            .with_max_payload_bytes(256)
+            .with_max_depth(4)
+            .with_max_array_len(2);
+        let transaction = br#"{"data": {"id": "tx", "token_io_details": [{"a": 1}, {"b": "]["}]}}"#;
+        assert_eq!(limits.check(transaction), Ok(()));
+        assert_eq!(limits.check(br#"{"items": [ ]}"#), Ok(()));
+
+        assert_eq!(
+            limits.check(br#"{"data": {"token_io_details": [1, 2, 3]}}"#),
+            Err(PayloadLimitError::ArrayTooLong { max: 2 })
+        );
+        assert_eq!(
+            limits.check(br#"{"a": {"b": {"c": {"d": {}}}}}"#),
+            Err(PayloadLimitError::TooDeep { max: 4 })
+        );
+        assert_eq!(
+            limits.check(br#"{"escaped": "\"[[[[[[\"", "b": [[1]]}"#),
+            Ok(())
+        );
+        assert_eq!(
+            limits.check(&[b' '; 257]),
+            Err(PayloadLimitError::TooLarge {
+                size: 257,
+                max: 256
+            })
+        );
+        assert_eq!(PayloadLimits::unlimited().check(&[b'['; 64]), Ok(()));
+    }
+
+    #[test]
+    fn test_check_double_encoded() {
+        let limits = PayloadLimits::new().with_max_depth(4).with_max_array_len(2);
+        let encode = |payload: &str| serde_json::to_string(payload).unwrap();
+
+        let payload = encode(r#"{"id": "tx", "token_io_details": [{"a": "\"["}]}"#);
+        assert_eq!(limits.check(payload.as_bytes()), Ok(()));
+        let wrapped = format!(r#"{{"offset": 1, "data": {}}}"#, payload);
+        assert_eq!(limits.check(wrapped.as_bytes()), Ok(()));
+
+        let payload = encode(r#"{"token_io_details": [1, 2, 3]}"#);
+        let wrapped = format!(r#"{{"data": {}}}"#, payload);
+        assert_eq!(
+            limits.check(wrapped.as_bytes()),
+            Err(PayloadLimitError::ArrayTooLong { max: 2 })
+        );
+        let payload = encode(r#" {"a": {"b": {"c": {"d": {}}}}}"#);
+        assert_eq!(
+            limits.check(payload.as_bytes()),
+            Err(PayloadLimitError::TooDeep { max: 4 })
+        );
+        assert_eq!(
+            limits.check(encode("[[[[[]]]]]").as_bytes()),
+            Err(PayloadLimitError::TooDeep { max: 4 })
+        );
+    }
+}
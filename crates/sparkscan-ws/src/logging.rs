@@ -0,0 +1,272 @@
+//! Targets, levels and sampling of the messages logged by the crate.
+//!
+//! Every message is logged under a target naming its [`LogCategory`], such as
+//! `sparkscan_ws::parse`, which logger filters can select. [`configure`] additionally caps the
+//! level of each category, and samples repeated messages so that a payload the client keeps
+//! failing to parse is logged once per interval instead of once per message:
+//!
+//! ```rust,no_run
+//! use sparkscan_ws::logging::{self, LevelFilter, LogCategory, LogConfig};
+//! use std::time::Duration;
+//!
+//! logging::configure(
+//!     LogConfig::new()
+//!         .with_level(LogCategory::Connection, LevelFilter::Warn)
+//!         .with_sampling(LogCategory::Parse, Duration::from_secs(60)),
+//! );
+//! ```
+//!
+//! Addresses and amounts are masked from the messages once [`redaction`](crate::redaction) is
+//! enabled.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+pub use log::{Level, LevelFilter};
+
+/// Distinct messages remembered per category for sampling.
+const MAX_SAMPLED_MESSAGES: usize = 1024;
+
+/// Kind of the messages logged by the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogCategory {
+    /// Messages the client failed to parse
+    Parse,
+    /// Dispatch queues, message callbacks and redeliveries
+    Dispatch,
+    /// Connections, subscription limits and shutdown
+    Connection,
+    /// Clock skew with the servers
+    ClockSkew,
+}
+
+impl LogCategory {
+    /// Every category.
+    pub const ALL: [LogCategory; 4] = [
+        LogCategory::Parse,
+        LogCategory::Dispatch,
+        LogCategory::Connection,
+        LogCategory::ClockSkew,
+    ];
+
+    /// Get the target the messages of this category are logged under.
+    pub fn target(self) -> &'static str {
+        match self {
+            LogCategory::Parse => "sparkscan_ws::parse",
+            LogCategory::Dispatch => "sparkscan_ws::dispatch",
+            LogCategory::Connection => "sparkscan_ws::connection",
+            LogCategory::ClockSkew => "sparkscan_ws::clock_skew",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Levels and sampling of each log category, applied with [`configure`].
+///
+/// By default every message is passed on to the logger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogConfig {
+    levels: [LevelFilter; LogCategory::ALL.len()],
+    sampling: [Option<Duration>; LogCategory::ALL.len()],
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogConfig {
+    /// Create a configuration passing every message on to the logger.
+    pub const fn new() -> Self {
+        Self {
+            levels: [LevelFilter::Trace; LogCategory::ALL.len()],
+            sampling: [None; LogCategory::ALL.len()],
+        }
+    }
+
+    /// Set the most verbose level logged for `category`, or turn it off.
+    pub fn with_level(mut self, category: LogCategory, level: LevelFilter) -> Self {
+        self.levels[category.index()] = level;
+        self
+    }
+
+    /// Log each distinct message of `category` at most once per `interval`.
+    ///
+    /// The next message logged after the interval reports how many were suppressed.
+    pub fn with_sampling(mut self, category: LogCategory, interval: Duration) -> Self {
+        self.sampling[category.index()] = Some(interval);
+        self
+    }
+
+    /// Get the most verbose level logged for `category`.
+    pub fn level(&self, category: LogCategory) -> LevelFilter {
+        self.levels[category.index()]
+    }
+
+    /// Get the sampling interval of `category`, if sampled.
+    pub fn sampling(&self, category: LogCategory) -> Option<Duration> {
+        self.sampling[category.index()]
+    }
+}
+
+static CONFIG: RwLock<LogConfig> = RwLock::new(LogConfig::new());
+
+/// Apply `config` to the messages logged from now on, by every client.
+pub fn configure(config: LogConfig) {
+    *CONFIG.write().unwrap_or_else(PoisonError::into_inner) = config;
+}
+
+/// Get the configuration applied to logged messages.
+pub fn config() -> LogConfig {
+    *CONFIG.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Log `message` under `category`, unless filtered out or sampled away.
+pub(crate) fn log(category: LogCategory, level: Level, message: fmt::Arguments<'_>) {
+    let config = config();
+    if level > config.level(category) {
+        return;
+    }
+    let message = crate::redaction::redact_log(message.to_string());
+    let suppressed = match config.sampling(category) {
+        Some(interval) => {
+            static SAMPLERS: OnceLock<Mutex<HashMap<LogCategory, Sampler>>> = OnceLock::new();
+            let mut samplers = SAMPLERS
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let sampler = samplers.entry(category).or_default();
+            match sampler.admit(&message, interval, Instant::now()) {
+                Some(suppressed) => suppressed,
+                None => return,
+            }
+        }
+        None => 0,
+    };
+
+    if suppressed > 0 {
+        emit(
+            category,
+            level,
+            format_args!("{} ({} similar messages suppressed)", message, suppressed),
+        );
+    } else {
+        emit(category, level, format_args!("{}", message));
+    }
+}
+
+fn emit(category: LogCategory, level: Level, message: fmt::Arguments<'_>) {
+    #[cfg(feature = "tracing")]
+    {
+        // Targets and levels of tracing events are static
+        macro_rules! event {
+            ($target:literal) => {
+                match level {
+                    Level::Error => tracing::error!(target: $target, "{}", message),
+                    Level::Warn => tracing::warn!(target: $target, "{}", message),
+                    Level::Info => tracing::info!(target: $target, "{}", message),
+                    Level::Debug => tracing::debug!(target: $target, "{}", message),
+                    Level::Trace => tracing::trace!(target: $target, "{}", message),
+                }
+            };
+        }
+        match category {
+            LogCategory::Parse => event!("sparkscan_ws::parse"),
+            LogCategory::Dispatch => event!("sparkscan_ws::dispatch"),
+            LogCategory::Connection => event!("sparkscan_ws::connection"),
+            LogCategory::ClockSkew => event!("sparkscan_ws::clock_skew"),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    log::log!(target: category.target(), level, "{}", message);
+}
+
+/// Last time each distinct message of a category was logged.
+#[derive(Default)]
+struct Sampler {
+    seen: HashMap<String, Seen>,
+}
+
+struct Seen {
+    logged_at: Instant,
+    suppressed: u64,
+}
+
+impl Sampler {
+    /// Check whether to log `message` at `now`, returning how many copies were suppressed
+    /// since it was last logged.
+    fn admit(&mut self, message: &str, interval: Duration, now: Instant) -> Option<u64> {
+        if let Some(seen) = self.seen.get_mut(message) {
+            if now.duration_since(seen.logged_at) < interval {
+                seen.suppressed += 1;
+                return None;
+            }
+            let suppressed = seen.suppressed;
+            *seen = Seen {
+                logged_at: now,
+                suppressed: 0,
+            };
+            return Some(suppressed);
+        }
+
+        if self.seen.len() >= MAX_SAMPLED_MESSAGES {
+            self.seen
+                .retain(|_, seen| now.duration_since(seen.logged_at) < interval);
+            // Forget the oldest messages rather than growing without bound
+            if self.seen.len() >= MAX_SAMPLED_MESSAGES {
+                self.seen.clear();
+            }
+        }
+        self.seen.insert(
+            message.to_string(),
+            Seen {
+                logged_at: now,
+                suppressed: 0,
+            },
+        );
+        Some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_levels() {
+        let config = LogConfig::new()
+            .with_level(LogCategory::Parse, LevelFilter::Off)
+            .with_sampling(LogCategory::Dispatch, Duration::from_secs(60));
+        assert_eq!(config.level(LogCategory::Parse), LevelFilter::Off);
+        assert_eq!(config.level(LogCategory::Connection), LevelFilter::Trace);
+        assert_eq!(
+            config.sampling(LogCategory::Dispatch),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(config.sampling(LogCategory::Parse), None);
+        assert!(Level::Error > config.level(LogCategory::Parse));
+    }
+
+    #[test]
+    fn test_sampling_distinct_messages() {
+        let mut sampler = Sampler::default();
+        let interval = Duration::from_secs(60);
+        let start = Instant::now();
+
+        assert_eq!(sampler.admit("unknown variant", interval, start), Some(0));
+        assert_eq!(sampler.admit("missing field", interval, start), Some(0));
+        for _ in 0..3 {
+            assert_eq!(sampler.admit("unknown variant", interval, start), None);
+        }
+        let later = start + interval;
+        assert_eq!(sampler.admit("unknown variant", interval, later), Some(3));
+        assert_eq!(sampler.admit("missing field", interval, later), Some(0));
+    }
+}
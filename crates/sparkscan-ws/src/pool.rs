@@ -0,0 +1,149 @@
+//! Reused buffers for message parsing, enabled by the `high-throughput` feature.
+//!
+//! Double-encoded publications carry their payload as a JSON string, which has to be unescaped
+//! before the payload itself can be deserialized. By default that goes through a
+//! `serde_json::Value` and a new `String` per message; with this feature the string is
+//! unescaped into a buffer owned by the parsing thread and reused for every message.
+//!
+//! The payload types keep their `String` fields: they are part of the public API, and changing
+//! them to inline or compact strings behind a feature would break dependents that do not enable
+//! it.
+
+use std::cell::RefCell;
+
+use serde::de::DeserializeOwned;
+
+/// Capacity above which the buffer is released after use, so that one oversized message does
+/// not pin its memory for the lifetime of the thread.
+const MAX_RETAINED_CAPACITY: usize = 64 * 1024;
+
+thread_local! {
+    static SCRATCH: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Deserialize a payload encoded as the JSON string `literal` (quotes included).
+///
+/// Returns `None` if `literal` is not a valid JSON string.
+pub(crate) fn deserialize_encoded<T: DeserializeOwned>(
+    literal: &str,
+) -> Option<serde_json::Result<T>> {
+    SCRATCH.with(|scratch| {
+        let mut scratch = scratch.try_borrow_mut().ok()?;
+        scratch.clear();
+        unescape(literal, &mut scratch)?;
+        let result = serde_json::from_str(&scratch);
+        if scratch.capacity() > MAX_RETAINED_CAPACITY {
+            *scratch = String::new();
+        }
+        Some(result)
+    })
+}
+
+/// Append the contents of the JSON string `literal` to `out`.
+fn unescape(literal: &str, out: &mut String) -> Option<()> {
+    let mut rest = literal.strip_prefix('"')?.strip_suffix('"')?;
+    while let Some(index) = rest.find(['\\', '"']) {
+        out.push_str(&rest[..index]);
+        // An unescaped quote ends the string early
+        let escape = rest[index..].strip_prefix('\\')?;
+        let mut tail = escape.get(1..)?;
+        let unescaped = match escape.as_bytes().first()? {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => unescape_unicode(&mut tail)?,
+            _ => return None,
+        };
+        out.push(unescaped);
+        rest = tail;
+    }
+    out.push_str(rest);
+    Some(())
+}
+
+/// Decode the code point of a `\u` escape, including UTF-16 surrogate pairs.
+fn unescape_unicode(rest: &mut &str) -> Option<char> {
+    let high = hex4(rest)?;
+    if !(0xD800..0xDC00).contains(&high) {
+        return char::from_u32(high);
+    }
+    *rest = rest.strip_prefix("\\u")?;
+    let low = hex4(rest)?;
+    if !(0xDC00..0xE000).contains(&low) {
+        return None;
+    }
+    char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+}
+
+fn hex4(rest: &mut &str) -> Option<u32> {
+    let digits = rest.get(..4)?;
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    *rest = &rest[4..];
+    u32::from_str_radix(digits, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unescaped(literal: &str) -> Option<String> {
+        let mut out = String::new();
+        unescape(literal, &mut out).map(|()| out)
+    }
+
+    #[test]
+    fn test_unescape_matches_serde_json() {
+        let values = [
+            "",
+            r#"{"id":"test_id","type":"spark_to_spark"}"#,
+            "tab\tnewline\nquote\"backslash\\slash/",
+            "control \u{1} \u{8} \u{c} \r",
+            "unicode é ₿ 🦀",
+        ];
+        for value in values {
+            let literal = serde_json::to_string(value).unwrap();
+            assert_eq!(unescaped(&literal).as_deref(), Some(value));
+        }
+
+        assert_eq!(
+            unescaped(r#""\u00e9 \ud83e\udd80 \/""#).as_deref(),
+            Some("é 🦀 /")
+        );
+    }
+
+    #[test]
+    fn test_unescape_rejects_invalid_strings() {
+        for literal in [
+            "unquoted",
+            r#""unterminated"#,
+            r#""trailing backslash\""#,
+            r#""early " quote""#,
+            r#""\x""#,
+            r#""\u12""#,
+            r#""\u+123""#,
+            r#""\ud83e""#,
+            r#""\udd80""#,
+        ] {
+            assert_eq!(unescaped(literal), None, "{}", literal);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_encoded() {
+        let literal = serde_json::to_string(r#"{"id":"test_id"}"#).unwrap();
+        let value: serde_json::Value = deserialize_encoded(&literal).unwrap().unwrap();
+        assert_eq!(value["id"], "test_id");
+
+        assert!(deserialize_encoded::<serde_json::Value>("\"{\"")
+            .unwrap()
+            .is_err());
+        assert!(deserialize_encoded::<serde_json::Value>("{}").is_none());
+    }
+}
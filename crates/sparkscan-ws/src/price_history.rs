@@ -0,0 +1,249 @@
+//! Recent token prices, recorded from the price stream.
+//!
+//! [`PriceHistory`] keeps a bounded time series of the prices of each token, keyed by the time
+//! the server processed each update, so that displays such as the 24-hour change of a token
+//! need no extra REST calls:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # use std::sync::Arc;
+//! # use std::time::Duration;
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let history = Arc::new(PriceHistory::new());
+//!
+//! let subscription = client.subscribe(Topic::TokenPrices).await?;
+//! let sink = Arc::clone(&history);
+//! subscription.on_message(move |message| sink.record(&message));
+//! subscription.subscribe();
+//!
+//! // Later on
+//! let day = Duration::from_secs(24 * 60 * 60);
+//! if let Some(change) = history.change_over("btkn1...", day) {
+//!     println!("24h: {:+.2}%", change.percent());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::types::SparkScanMessage;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// Default number of prices kept per token.
+const DEFAULT_MAX_SAMPLES: usize = 10_000;
+
+/// Price of a token at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PricePoint {
+    /// Time the server processed the price update
+    pub at: DateTime<Utc>,
+    /// Price in satoshis per token
+    pub price_sats: f64,
+}
+
+/// Change of a token price over a period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceChange {
+    /// Price at the start of the period
+    pub start: PricePoint,
+    /// Price at the end of the period
+    pub end: PricePoint,
+}
+
+impl PriceChange {
+    /// Get the change in satoshis per token.
+    pub fn difference(&self) -> f64 {
+        self.end.price_sats - self.start.price_sats
+    }
+
+    /// Get the change as a percentage of the start price.
+    ///
+    /// This is infinite or NaN if the start price is zero.
+    pub fn percent(&self) -> f64 {
+        self.difference() / self.start.price_sats * 100.0
+    }
+}
+
+/// Bounded in-memory time series of token prices.
+///
+/// Feed it with [`record`](Self::record) from a [`TokenPrices`](crate::Topic::TokenPrices)
+/// subscription. For each token, prices older than the retention are dropped, except the last
+/// of them, which still tells the price at the start of the retention; at most a fixed number
+/// of prices is kept per token.
+pub struct PriceHistory {
+    retention: TimeDelta,
+    max_samples: usize,
+    prices: Mutex<HashMap<String, VecDeque<PricePoint>>>,
+}
+
+impl Default for PriceHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceHistory {
+    /// Create a history keeping a day of prices, and at most 10,000 prices per token.
+    pub fn new() -> Self {
+        Self {
+            retention: TimeDelta::days(1),
+            max_samples: DEFAULT_MAX_SAMPLES,
+            prices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set how long prices are kept.
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = TimeDelta::from_std(retention).unwrap_or(TimeDelta::MAX);
+        self
+    }
+
+    /// Set the maximum number of prices kept per token.
+    pub fn with_max_samples(mut self, max_samples: usize) -> Self {
+        self.max_samples = max_samples.max(1);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, VecDeque<PricePoint>>> {
+        // Prices are inserted whole, so a panicking caller leaves the series consistent
+        self.prices.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Add a price update to the history, ignoring other messages.
+    pub fn record(&self, message: &SparkScanMessage) {
+        if let SparkScanMessage::TokenPrice(data) = message {
+            if let Ok(price_sats) = data.price_sats.as_str().parse() {
+                self.insert(
+                    data.address.as_str(),
+                    PricePoint {
+                        at: data.processed_at,
+                        price_sats,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Add the price of `token` at a point in time.
+    pub fn insert(&self, token: &str, point: PricePoint) {
+        let mut prices = self.lock();
+        let series = prices.entry(token.to_string()).or_default();
+        // Updates may arrive out of order
+        let index = series.partition_point(|p| p.at <= point.at);
+        series.insert(index, point);
+
+        let latest = series.back().map_or(point.at, |p| p.at);
+        let start = latest
+            .checked_sub_signed(self.retention)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        while series.len() > self.max_samples || series.get(1).is_some_and(|p| p.at <= start) {
+            series.pop_front();
+        }
+    }
+
+    /// Get the latest price of `token`.
+    pub fn latest(&self, token: &str) -> Option<PricePoint> {
+        self.lock().get(token)?.back().copied()
+    }
+
+    /// Get the price of `token` at `at`, that is the latest price published until then.
+    ///
+    /// Returns `None` if no price of the token is known from that time.
+    pub fn price_at(&self, token: &str, at: DateTime<Utc>) -> Option<PricePoint> {
+        let prices = self.lock();
+        let series = prices.get(token)?;
+        let index = series.partition_point(|p| p.at <= at);
+        series.get(index.checked_sub(1)?).copied()
+    }
+
+    /// Get the change of the price of `token` over the `period` ending with its latest price.
+    ///
+    /// Returns `None` if the history does not reach back to the start of the period.
+    pub fn change_over(&self, token: &str, period: Duration) -> Option<PriceChange> {
+        let end = self.latest(token)?;
+        let start = end
+            .at
+            .checked_sub_signed(TimeDelta::from_std(period).ok()?)?;
+        Some(PriceChange {
+            start: self.price_at(token, start)?,
+            end,
+        })
+    }
+
+    /// Get the tokens with a known price.
+    pub fn tokens(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{parse_message_for_topic, Topic};
+    use serde_json::json;
+
+    const TOKEN: &str = "btkn1daywtenlww42njymqzyegvcwuy3p9f26zknme0srxa7tagewvuys86h553";
+
+    fn price(hour: u32, price_sats: &str) -> SparkScanMessage {
+        let price_json = json!({
+            "address": TOKEN,
+            "network": "MAINNET",
+            "protocol": "sparksat",
+            "price_sats": price_sats,
+            "processed_at": format!("2025-08-06T{:02}:00:00Z", hour)
+        });
+        let json_str = serde_json::to_string(&price_json).unwrap();
+        parse_message_for_topic(&Topic::TokenPrices, json_str.as_bytes()).unwrap()
+    }
+
+    fn hour(hour: u32) -> DateTime<Utc> {
+        format!("2025-08-06T{:02}:00:00Z", hour).parse().unwrap()
+    }
+
+    #[test]
+    fn test_price_at_and_change() {
+        let history = PriceHistory::new();
+        history.record(&price(2, "80"));
+        history.record(&price(0, "50"));
+        history.record(&price(10, "100"));
+
+        assert_eq!(history.price_at(TOKEN, hour(1)).unwrap().price_sats, 50.0);
+        assert_eq!(history.price_at(TOKEN, hour(2)).unwrap().price_sats, 80.0);
+        assert!(history
+            .price_at(TOKEN, hour(0) - TimeDelta::hours(1))
+            .is_none());
+        assert!(history.price_at("btkn1other", hour(1)).is_none());
+
+        let change = history
+            .change_over(TOKEN, Duration::from_secs(9 * 3600))
+            .unwrap();
+        assert_eq!(change.start.price_sats, 50.0);
+        assert_eq!(change.end.price_sats, 100.0);
+        assert_eq!(change.percent(), 100.0);
+        assert!(history
+            .change_over(TOKEN, Duration::from_secs(11 * 3600))
+            .is_none());
+    }
+
+    #[test]
+    fn test_retention() {
+        let history = PriceHistory::new()
+            .with_retention(Duration::from_secs(3 * 3600))
+            .with_max_samples(3);
+        for (hour, price_sats) in [(0, "1"), (1, "2"), (2, "3"), (5, "4")] {
+            history.record(&price(hour, price_sats));
+        }
+        // The price at the start of the retention is kept
+        assert_eq!(history.price_at(TOKEN, hour(2)).unwrap().price_sats, 3.0);
+        assert!(history.price_at(TOKEN, hour(1)).is_none());
+
+        for (hour, price_sats) in [(6, "5"), (7, "6"), (8, "7")] {
+            history.record(&price(hour, price_sats));
+        }
+        assert!(history.price_at(TOKEN, hour(5)).is_none());
+        assert_eq!(history.latest(TOKEN).unwrap().price_sats, 7.0);
+    }
+}
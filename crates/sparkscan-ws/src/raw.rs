@@ -0,0 +1,226 @@
+//! Subscriptions to arbitrary channels, without typed parsing.
+//!
+//! [`SparkScanWsClient::subscribe_raw`](crate::SparkScanWsClient::subscribe_raw) subscribes to a
+//! channel by name, including experimental channels [`Topic`](crate::Topic) does not know about
+//! yet, and hands every publication over as received:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let subscription = client.subscribe_raw("/experimental/mempool").await?;
+//! subscription.on_publication(|data, meta| {
+//!     println!("{} bytes at offset {:?} on {}", data.len(), meta.offset, meta.channel);
+//! });
+//! subscription.subscribe();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::router::TagRouter;
+use crate::{
+    connections::SubscriptionSlot, error::SubscribeError, shutdown::Lifecycle, verify::Verification,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use tokio_centrifuge::protocol::Publication;
+use tokio_centrifuge::subscription::Subscription;
+
+/// Metadata of a publication received by a [`RawSubscription`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicationMeta {
+    /// Channel the publication was received on
+    pub channel: String,
+    /// Offset of the publication in the stream of the channel, on channels with history
+    pub offset: Option<u64>,
+    /// Tags the server attached to the publication
+    pub tags: HashMap<String, String>,
+    /// Local time the publication was received at
+    pub received_at: DateTime<Utc>,
+}
+
+impl PublicationMeta {
+    pub(crate) fn new(channel: &str, publication: &Publication) -> Self {
+        Self {
+            channel: channel.to_string(),
+            // The server leaves the offset at zero on channels without history
+            offset: (publication.offset > 0).then_some(publication.offset),
+            tags: publication.tags.clone(),
+            received_at: Utc::now(),
+        }
+    }
+
+    /// Get the value of the tag `key`.
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags.get(key).map(String::as_str)
+    }
+}
+
+/// Subscription to a channel by name, delivering publications as raw bytes.
+///
+/// Unlike [`SparkScanSubscription`](crate::SparkScanSubscription), publications are not
+/// parsed, and callbacks run inline on the connection task rather than on a dispatch thread:
+/// callbacks doing more than a quick hand-off delay the other subscriptions of the connection.
+pub struct RawSubscription {
+    /// The underlying centrifuge subscription, shared with the shutdown of the client
+    inner: Arc<Subscription>,
+    /// The channel this subscription is for, as given
+    channel: String,
+    /// Shutdown state of the client
+    lifecycle: Option<Arc<Lifecycle>>,
+    /// Verifier of the client, checking publications before they are handed over
+    verification: Option<Arc<Verification>>,
+    /// Channel taken on the connection of the client, released on drop
+    _slot: Option<SubscriptionSlot>,
+}
+
+impl RawSubscription {
+    /// Create a raw subscription to `channel`.
+    pub(crate) fn new(inner: Subscription, channel: &str) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            channel: channel.to_string(),
+            lifecycle: None,
+            verification: None,
+            _slot: None,
+        }
+    }
+
+    /// Hold a channel on the connection of the client for the lifetime of the subscription.
+    pub(crate) fn with_slot(mut self, slot: SubscriptionSlot) -> Self {
+        self._slot = Some(slot);
+        self
+    }
+
+    /// Unsubscribe on the shutdown of the client.
+    pub(crate) fn with_lifecycle(mut self, lifecycle: Arc<Lifecycle>) -> Self {
+        // Publications are handed over inline, so none are ever dropped
+        lifecycle.track_subscription(&self.inner, &Arc::new(AtomicU64::new(0)));
+        self.lifecycle = Some(lifecycle);
+        self
+    }
+
+    /// Verify publications with the verifier of the client before handing them over.
+    pub(crate) fn with_verification(mut self, verification: Arc<Verification>) -> Self {
+        self.verification = Some(verification);
+        self
+    }
+
+    /// Get the channel of this subscription.
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Register callback for the publications of the channel.
+    ///
+    /// The callback receives the data of each publication as sent by the server, and its
+    /// [metadata](PublicationMeta). Publications received once the client shuts down, or
+    /// rejected by its [verifier](crate::SparkScanWsClient::verify_publications), are not
+    /// handed over.
+    pub fn on_publication<F>(&self, callback: F)
+    where
+        F: Fn(Vec<u8>, PublicationMeta) + Send + Sync + 'static,
+    {
+        let channel = self.channel.clone();
+        let lifecycle = self.lifecycle.clone();
+        let verification = self.verification.clone();
+        self.inner.on_publication(move |publication| {
+            if lifecycle.as_ref().is_some_and(|l| l.reject()) {
+                return;
+            }
+            if verification
+                .as_ref()
+                .is_some_and(|verification| !verification.accept(&channel, &publication))
+            {
+                return;
+            }
+            let meta = PublicationMeta::new(&channel, &publication);
+            callback(publication.data, meta);
+        });
+    }
+
+    /// Hand the publications of the channel to the handlers of `router`, by tag.
+    ///
+    /// See the [`router`](crate::router) module for an example.
+    pub fn on_publication_routed(&self, router: TagRouter) {
+        self.on_publication(move |data, meta| {
+            router.dispatch(data, meta);
+        });
+    }
+
+    /// Register callback for subscription establishment.
+    pub fn on_subscribed<F>(&self, callback: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.inner.on_subscribed(callback);
+    }
+
+    /// Register callback for subscription termination.
+    pub fn on_unsubscribed<F>(&self, callback: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.inner.on_unsubscribed(callback);
+    }
+
+    /// Register callback for classified subscription errors, such as an unknown channel.
+    ///
+    /// [`SubscribeError::is_retryable`] tells fatal failures apart from transient ones.
+    pub fn on_subscribe_error<F>(&self, callback: F)
+    where
+        F: Fn(SubscribeError) + Send + Sync + 'static,
+    {
+        self.inner.on_error(move |err| {
+            callback(SubscribeError::parse(format!("{:?}", err)));
+        });
+    }
+
+    /// Activate subscription to begin receiving publications.
+    pub fn subscribe(&self) {
+        self.inner.subscribe();
+    }
+
+    /// Deactivate subscription.
+    pub fn unsubscribe(&self) {
+        self.inner.unsubscribe();
+    }
+
+    /// Publish raw data to the channel.
+    ///
+    /// Note: Requires server support for client publishing.
+    pub fn publish(&self, data: Vec<u8>) {
+        self.inner.publish(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publication_meta() {
+        let before = Utc::now();
+        let publication = Publication {
+            data: br#"{"offset": 7, "tags": {"priority": "low"}, "data": {}}"#.to_vec(),
+            offset: 42,
+            tags: HashMap::from([("priority".to_string(), "high".to_string())]),
+            ..Publication::default()
+        };
+        let meta = PublicationMeta::new("/experimental/mempool", &publication);
+        assert_eq!(meta.channel, "/experimental/mempool");
+        assert_eq!(meta.offset, Some(42));
+        assert_eq!(meta.tag("priority"), Some("high"));
+        assert!(meta.received_at >= before);
+
+        let publication = Publication {
+            data: b"\x08\x01".to_vec(),
+            ..Publication::default()
+        };
+        let meta = PublicationMeta::new("/experimental/mempool", &publication);
+        assert_eq!(meta.offset, None);
+        assert!(meta.tags.is_empty());
+    }
+}
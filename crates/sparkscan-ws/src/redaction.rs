@@ -0,0 +1,322 @@
+//! Masking of customer data in logs and sinks.
+//!
+//! Once enabled with [`enable`], every message logged by the crate goes through a [`Redaction`]
+//! first, including the raw payloads logged at debug level with the `tracing` feature. The JSON
+//! in the messages is decoded, including payloads encoded as JSON strings, and the configured
+//! fields are masked in it, as are Spark addresses anywhere in the text, such as in the topics
+//! and channels of errors. This lets regulated users turn on debug logging
+//! without leaking addresses and amounts:
+//!
+//! ```rust
+//! use sparkscan_ws::redaction::{self, Redaction};
+//!
+//! redaction::enable(Redaction::new().with_field("bitcoin_txid"));
+//! ```
+//!
+//! Applications forwarding messages to their own logs or sinks can mask them the same way with
+//! [`Redaction::redact_message`].
+
+use crate::types::SparkScanMessage;
+use sparkscan_types::SparkAddress;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::sync::{PoisonError, RwLock};
+
+/// Replacement of masked values.
+pub const MASK: &str = "[redacted]";
+
+/// Payload fields holding addresses and amounts, masked by default.
+const DEFAULT_FIELDS: [&str; 10] = [
+    "address",
+    "from_identifier",
+    "to_identifier",
+    "issuer",
+    "soft_balance",
+    "hard_balance",
+    "balance",
+    "amount_sats",
+    "token_amount",
+    "amount",
+];
+
+/// Fields and values to mask.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redaction {
+    fields: BTreeSet<String>,
+    addresses: bool,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redaction {
+    /// Create a redaction of the address and amount fields of the payloads, and of Spark
+    /// addresses anywhere.
+    pub fn new() -> Self {
+        Self {
+            fields: DEFAULT_FIELDS
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+            addresses: true,
+        }
+    }
+
+    /// Create a redaction masking nothing, to configure from scratch.
+    pub fn empty() -> Self {
+        Self {
+            fields: BTreeSet::new(),
+            addresses: false,
+        }
+    }
+
+    /// Mask the values of the field `field`, at any depth.
+    pub fn with_field<T: Into<String>>(mut self, field: T) -> Self {
+        self.fields.insert(field.into());
+        self
+    }
+
+    /// Stop masking the values of the field `field`.
+    pub fn without_field(mut self, field: &str) -> Self {
+        self.fields.remove(field);
+        self
+    }
+
+    /// Set whether to mask Spark addresses outside of the masked fields.
+    pub fn with_addresses(mut self, addresses: bool) -> Self {
+        self.addresses = addresses;
+        self
+    }
+
+    /// Mask `value` in place.
+    ///
+    /// Strings holding JSON objects or arrays, such as double-encoded payloads, are decoded and
+    /// masked as well.
+    pub fn redact_json(&self, value: &mut serde_json::Value) {
+        self.redact_value(value);
+    }
+
+    /// Mask `value` in place, returning whether anything was masked.
+    fn redact_value(&self, value: &mut serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::Object(object) => {
+                let mut redacted = false;
+                for (key, value) in object.iter_mut() {
+                    if self.fields.contains(key) && !value.is_null() {
+                        *value = serde_json::Value::String(MASK.to_string());
+                        redacted = true;
+                    } else {
+                        redacted |= self.redact_value(value);
+                    }
+                }
+                redacted
+            }
+            serde_json::Value::Array(values) => values
+                .iter_mut()
+                .fold(false, |redacted, value| self.redact_value(value) | redacted),
+            serde_json::Value::String(string) => {
+                if let Some(redacted) = self.redact_encoded(string) {
+                    *string = redacted;
+                    return true;
+                }
+                match self.redact_addresses(string) {
+                    Some(redacted) => {
+                        *string = redacted;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Mask the JSON object or array encoded in `string`, returning `None` if there is none or
+    /// nothing was masked.
+    fn redact_encoded(&self, string: &str) -> Option<String> {
+        if !string.trim_start().starts_with(['{', '[']) {
+            return None;
+        }
+        let mut value = serde_json::from_str(string).ok()?;
+        self.redact_value(&mut value).then(|| value.to_string())
+    }
+
+    /// Get `message` as JSON, masked.
+    pub fn redact_message(&self, message: &SparkScanMessage) -> serde_json::Value {
+        let mut value = serde_json::to_value(message).expect("messages serialize to JSON");
+        self.redact_json(&mut value);
+        value
+    }
+
+    /// Mask the fields of the JSON objects and the addresses in `text`.
+    ///
+    /// The text does not have to be JSON: the JSON values embedded in it, such as a payload
+    /// after a log message, are decoded and masked like [`redact_json`](Self::redact_json).
+    /// Values with masked fields are written back compactly.
+    pub fn redact_text(&self, text: &str) -> String {
+        let mut redacted = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(['{', '[', '"']) {
+            redacted.push_str(&rest[..start]);
+            let (len, json) = self
+                .redact_embedded(&rest[start..])
+                .unwrap_or((1, Cow::Borrowed(&rest[start..start + 1])));
+            redacted.push_str(&json);
+            rest = &rest[start + len..];
+        }
+        redacted.push_str(rest);
+        self.redact_addresses(&redacted).unwrap_or(redacted)
+    }
+
+    /// Mask the JSON value at the start of `text`, returning its length and masked JSON.
+    fn redact_embedded<'a>(&self, text: &'a str) -> Option<(usize, Cow<'a, str>)> {
+        let mut values = serde_json::Deserializer::from_str(text).into_iter();
+        let mut value: serde_json::Value = values.next()?.ok()?;
+        let len = values.byte_offset();
+        if self.redact_value(&mut value) {
+            Some((len, Cow::Owned(value.to_string())))
+        } else {
+            Some((len, Cow::Borrowed(&text[..len])))
+        }
+    }
+
+    /// Mask the Spark addresses in `text`, returning `None` if there is none.
+    fn redact_addresses(&self, text: &str) -> Option<String> {
+        if !self.addresses {
+            return None;
+        }
+        let mut redacted = String::with_capacity(text.len());
+        let mut found = false;
+        let mut rest = text;
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+            let len = rest[start..]
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len() - start);
+            let word = &rest[start..start + len];
+            redacted.push_str(&rest[..start]);
+            if word.parse::<SparkAddress>().is_ok() {
+                redacted.push_str(MASK);
+                found = true;
+            } else {
+                redacted.push_str(word);
+            }
+            rest = &rest[start + len..];
+        }
+        redacted.push_str(rest);
+        found.then_some(redacted)
+    }
+}
+
+static REDACTION: RwLock<Option<Redaction>> = RwLock::new(None);
+
+/// Mask the messages logged from now on, by every client, with `redaction`.
+pub fn enable(redaction: Redaction) {
+    *REDACTION.write().unwrap_or_else(PoisonError::into_inner) = Some(redaction);
+}
+
+/// Stop masking the messages logged.
+pub fn disable() {
+    *REDACTION.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Get the redaction applied to logged messages, if enabled.
+pub fn enabled() -> Option<Redaction> {
+    REDACTION
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Mask a message about to be logged, if enabled.
+#[cfg(any(feature = "client", feature = "tracing"))]
+pub(crate) fn redact_log(message: String) -> String {
+    match &*REDACTION.read().unwrap_or_else(PoisonError::into_inner) {
+        Some(redaction) => redaction.redact_text(&message),
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s";
+
+    #[test]
+    fn test_redact_text() {
+        let redaction = Redaction::new();
+        let payload = format!(
+            r#"{{"address": "{}", "network": "MAINNET", "soft_balance": "379", {}}}"#,
+            ADDRESS, r#""hard_balance":379, "token_amount": null"#
+        );
+        assert_eq!(
+            redaction.redact_text(&format!("Raw payload: {}", payload)),
+            concat!(
+                r#"Raw payload: {"address":"[redacted]","hard_balance":"[redacted]","#,
+                r#""network":"MAINNET","soft_balance":"[redacted]","token_amount":null}"#
+            )
+        );
+
+        let error = format!(
+            "Failed to parse message for topic BalanceAddress(\"{}\")",
+            ADDRESS
+        );
+        assert_eq!(
+            redaction.redact_text(&error),
+            "Failed to parse message for topic BalanceAddress(\"[redacted]\")"
+        );
+        assert_eq!(
+            redaction.redact_text(r#"{"escaped": "a\"b", "amount_sats": "1\"0"}"#),
+            r#"{"amount_sats":"[redacted]","escaped":"a\"b"}"#
+        );
+        assert_eq!(
+            redaction.redact_text(r#"{"network": "MAINNET"} [redacted] {not json"#),
+            r#"{"network": "MAINNET"} [redacted] {not json"#
+        );
+        assert_eq!(Redaction::empty().redact_text(&payload), payload);
+    }
+
+    #[test]
+    fn test_redact_double_encoded() {
+        let redaction = Redaction::new();
+        let payload = r#"{"id": "tx", "amount_sats": "5000"}"#;
+        let wrapped = serde_json::json!({ "offset": 3, "data": payload }).to_string();
+        assert_eq!(
+            redaction.redact_text(&format!("Raw payload: {}", wrapped)),
+            r#"Raw payload: {"data":"{\"amount_sats\":\"[redacted]\",\"id\":\"tx\"}","offset":3}"#
+        );
+
+        // Payloads logged as a string literal
+        let logged = format!("Failed to parse {:?}", payload);
+        assert_eq!(
+            redaction.redact_text(&logged),
+            r#"Failed to parse "{\"amount_sats\":\"[redacted]\",\"id\":\"tx\"}""#
+        );
+
+        let mut value = serde_json::json!({ "data": payload });
+        redaction.redact_json(&mut value);
+        assert_eq!(value["data"], r#"{"amount_sats":"[redacted]","id":"tx"}"#);
+    }
+
+    #[test]
+    fn test_redact_json() {
+        let redaction = Redaction::empty().with_field("amount").with_addresses(true);
+        let mut value = serde_json::json!({
+            "id": "tx",
+            "from_identifier": ADDRESS,
+            "token_io_details": {"outputs": [{"address": ADDRESS, "amount": "150"}]},
+        });
+        redaction.redact_json(&mut value);
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "id": "tx",
+                "from_identifier": MASK,
+                "token_io_details": {"outputs": [{"address": MASK, "amount": MASK}]},
+            })
+        );
+    }
+}
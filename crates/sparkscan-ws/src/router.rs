@@ -0,0 +1,159 @@
+//! Routing of publications to handlers by their tags.
+//!
+//! The server can tag publications, e.g. with a priority or the source of the event, so that
+//! clients tell them apart without subscribing to separate channels. A [`TagRouter`] hands each
+//! publication to the handler of the first route its tags match:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let router = TagRouter::new()
+//!     .with_route("priority", "high", |data: Vec<u8>, meta| {
+//!         println!("urgent: {} bytes on {}", data.len(), meta.channel);
+//!     })
+//!     .with_fallback(|data, _meta| println!("{} bytes", data.len()));
+//!
+//! let subscription = client.subscribe_raw("/experimental/mempool").await?;
+//! subscription.on_publication_routed(router);
+//! subscription.subscribe();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Tags are set by the server on the publication, next to its data, and exposed in
+//! [`PublicationMeta::tags`].
+
+use crate::raw::PublicationMeta;
+use std::fmt;
+
+type Handler<T> = Box<dyn Fn(T, PublicationMeta) + Send + Sync>;
+
+struct Route<T> {
+    key: String,
+    value: String,
+    handler: Handler<T>,
+}
+
+/// Dispatcher of publications to handlers, by tag value.
+///
+/// Routes are tried in the order they were added. Publications matching no route go to the
+/// [fallback](Self::with_fallback) handler, or are dropped if there is none.
+pub struct TagRouter<T = Vec<u8>> {
+    routes: Vec<Route<T>>,
+    fallback: Option<Handler<T>>,
+}
+
+impl<T> Default for TagRouter<T> {
+    fn default() -> Self {
+        Self {
+            routes: Vec::new(),
+            fallback: None,
+        }
+    }
+}
+
+impl<T> TagRouter<T> {
+    /// Create a router without routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Route the publications whose tag `key` is `value` to `handler`.
+    pub fn with_route<K, V, F>(mut self, key: K, value: V, handler: F) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+        F: Fn(T, PublicationMeta) + Send + Sync + 'static,
+    {
+        self.routes.push(Route {
+            key: key.into(),
+            value: value.into(),
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// Route the publications matching no route to `handler`.
+    pub fn with_fallback<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(T, PublicationMeta) + Send + Sync + 'static,
+    {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    /// Hand a publication to the handler of its route.
+    ///
+    /// Returns `false` if the publication matched no route and there is no fallback handler.
+    pub fn dispatch(&self, message: T, meta: PublicationMeta) -> bool {
+        let route = self
+            .routes
+            .iter()
+            .find(|route| meta.tag(&route.key) == Some(route.value.as_str()));
+        match route.map(|route| &route.handler).or(self.fallback.as_ref()) {
+            Some(handler) => {
+                handler(message, meta);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TagRouter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let routes: Vec<_> = self
+            .routes
+            .iter()
+            .map(|route| format!("{}={}", route.key, route.value))
+            .collect();
+        f.debug_struct("TagRouter")
+            .field("routes", &routes)
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio_centrifuge::protocol::Publication;
+
+    #[test]
+    fn test_dispatch_by_tag() {
+        let routed = Arc::new(Mutex::new(Vec::new()));
+        let route = |name: &'static str| {
+            let routed = Arc::clone(&routed);
+            move |message: u32, _meta: PublicationMeta| {
+                routed.lock().unwrap().push((name, message));
+            }
+        };
+        let router = TagRouter::new()
+            .with_route("priority", "high", route("high"))
+            .with_route("source", "l1", route("l1"));
+
+        let meta = |tags: &[(&str, &str)]| {
+            let publication = Publication {
+                data: b"{}".to_vec(),
+                tags: tags
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+                ..Publication::default()
+            };
+            PublicationMeta::new("/custom", &publication)
+        };
+        assert!(router.dispatch(1, meta(&[("source", "l1"), ("priority", "high")])));
+        assert!(router.dispatch(2, meta(&[("source", "l1")])));
+        assert!(!router.dispatch(3, meta(&[("priority", "low")])));
+
+        let router = router.with_fallback(route("fallback"));
+        assert!(router.dispatch(4, meta(&[])));
+        assert_eq!(
+            *routed.lock().unwrap(),
+            [("high", 1), ("l1", 2), ("fallback", 4)]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Comparison of the schema version advertised by the server with the bundled one.
+
+use crate::logging::{self, Level, LogCategory};
+use crate::schemas::{SchemaMismatch, SCHEMA_VERSION, SCHEMA_VERSION_TAG};
+use std::sync::{Mutex, PoisonError};
+use tokio_centrifuge::protocol::Publication;
+
+type MismatchCallback = Box<dyn Fn(&SchemaMismatch) + Send + Sync>;
+
+/// Schema version check, shared by the subscriptions of a client.
+#[derive(Default)]
+pub(crate) struct SchemaCheck {
+    /// Latest version advertised by the server, so that each mismatch is reported once
+    advertised: Mutex<Option<String>>,
+    callbacks: Mutex<Vec<MismatchCallback>>,
+}
+
+impl SchemaCheck {
+    /// Register a callback notified when the server advertises another schema version.
+    pub(crate) fn on_mismatch<F>(&self, callback: F)
+    where
+        F: Fn(&SchemaMismatch) + Send + Sync + 'static,
+    {
+        self.callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Look for the advertised schema version in the tags of a publication.
+    pub(crate) fn inspect(&self, publication: &Publication) {
+        if let Some(version) = publication.tags.get(SCHEMA_VERSION_TAG) {
+            self.advertise(version);
+        }
+    }
+
+    /// Compare an advertised schema version with the bundled one.
+    ///
+    /// Returns the mismatch the first time a differing version is advertised, after logging
+    /// it and notifying the callbacks.
+    pub(crate) fn advertise(&self, version: &str) -> Option<SchemaMismatch> {
+        {
+            let mut advertised = self
+                .advertised
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if advertised.as_deref() == Some(version) {
+                return None;
+            }
+            *advertised = Some(version.to_string());
+        }
+        if version == SCHEMA_VERSION {
+            return None;
+        }
+
+        let mismatch = SchemaMismatch {
+            expected: SCHEMA_VERSION,
+            advertised: version.to_string(),
+        };
+        logging::log(
+            LogCategory::Connection,
+            Level::Warn,
+            format_args!(
+                "{}; payloads may fail to parse until sparkscan-ws is upgraded",
+                mismatch
+            ),
+        );
+        for callback in self
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            callback(&mismatch);
+        }
+        Some(mismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_mismatch_reported_once() {
+        let check = SchemaCheck::default();
+        let notified = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&notified);
+        check.on_mismatch(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let tagged = |version: Option<&str>| Publication {
+            data: br#"{"address": "sp1a"}"#.to_vec(),
+            tags: version
+                .map(|version| (SCHEMA_VERSION_TAG.to_string(), version.to_string()))
+                .into_iter()
+                .collect(),
+            ..Publication::default()
+        };
+        check.inspect(&tagged(None));
+        check.inspect(&tagged(Some(SCHEMA_VERSION)));
+        assert_eq!(notified.load(Ordering::Relaxed), 0);
+
+        // Tags in the payload are data, not publication tags
+        let mut spoofed = tagged(None);
+        spoofed.data = br#"{"tags": {"schema_version": "v3"}, "data": {}}"#.to_vec();
+        check.inspect(&spoofed);
+        assert_eq!(notified.load(Ordering::Relaxed), 0);
+
+        let upgraded = tagged(Some("v2"));
+        check.inspect(&upgraded);
+        check.inspect(&upgraded);
+        assert_eq!(notified.load(Ordering::Relaxed), 1);
+        assert_eq!(check.advertise("v2"), None);
+
+        // Reported again after the server went back to the bundled version and upgraded
+        assert_eq!(check.advertise(SCHEMA_VERSION), None);
+        assert_eq!(
+            check.advertise("v2").map(|mismatch| mismatch.advertised),
+            Some("v2".to_string())
+        );
+        assert_eq!(notified.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,111 @@
+//! JSON schemas of the payloads, as bundled with the crate.
+//!
+//! The payload types of [`types`](crate::types) are generated from these schemas at build time.
+//! They are exposed as they are, for validators and documentation tooling:
+//!
+//! ```rust
+//! use sparkscan_ws::{schemas, Topic};
+//!
+//! let schema: serde_json::Value =
+//!     serde_json::from_str(schemas::schema_for(&Topic::Balances)).unwrap();
+//! assert_eq!(schema["title"], "BalancePayload");
+//! ```
+//!
+//! The schemas are versioned by [`SCHEMA_VERSION`]. Servers advertising the version of their
+//! own schemas in the [`SCHEMA_VERSION_TAG`] tag of their publications are compared with it, and
+//! a mismatch is reported once to
+//! [`SparkScanWsClient::on_schema_mismatch`](crate::SparkScanWsClient::on_schema_mismatch)
+//! callbacks, before the payloads it affects start failing to parse.
+
+use crate::Topic;
+use std::fmt;
+
+/// Version of the bundled schemas, computed at build time.
+///
+/// The FNV-1a hash of the compact JSON of the schemas with sorted keys, in the order balance,
+/// token balance, token price, token and transaction, as 16 hexadecimal digits.
+pub const SCHEMA_VERSION: &str = env!("SPARKSCAN_WS_SCHEMA_HASH");
+
+/// Publication tag the server advertises the version of its schemas in.
+pub const SCHEMA_VERSION_TAG: &str = "schema_version";
+
+/// Schemas of the server differing from the ones bundled with the crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaMismatch {
+    /// Version of the bundled schemas, [`SCHEMA_VERSION`]
+    pub expected: &'static str,
+    /// Version advertised by the server
+    pub advertised: String,
+}
+
+impl fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "server schema version {} differs from the bundled version {}",
+            self.advertised, self.expected
+        )
+    }
+}
+
+/// Schema of the payloads of the balance topics.
+pub const BALANCE_SCHEMA: &str = include_str!("../schemas/balance_schema.json");
+
+/// Schema of the payloads of the token balance topics.
+pub const TOKEN_BALANCE_SCHEMA: &str = include_str!("../schemas/token_balance_schema.json");
+
+/// Schema of the payloads of the token price topics.
+pub const TOKEN_PRICE_SCHEMA: &str = include_str!("../schemas/token_price_schema.json");
+
+/// Schema of the payloads of the token topics.
+pub const TOKEN_SCHEMA: &str = include_str!("../schemas/token_schema.json");
+
+/// Schema of the payloads of the transaction topics.
+pub const TRANSACTION_SCHEMA: &str = include_str!("../schemas/transaction_schema.json");
+
+/// Get the schema of the payloads published on `topic`.
+pub fn schema_for(topic: &Topic) -> &'static str {
+    match topic {
+        Topic::Balances | Topic::BalanceNetwork(_) | Topic::BalanceAddress(_) => BALANCE_SCHEMA,
+        Topic::TokenBalances
+        | Topic::TokenBalanceNetwork(_)
+        | Topic::TokenBalanceIdentifier(_)
+        | Topic::TokenBalanceAddress(_) => TOKEN_BALANCE_SCHEMA,
+        Topic::TokenPrices | Topic::TokenPriceNetwork(_) | Topic::TokenPriceIdentifier(_) => {
+            TOKEN_PRICE_SCHEMA
+        }
+        Topic::Tokens
+        | Topic::TokenIdentifier(_)
+        | Topic::TokenNetwork(_)
+        | Topic::TokenIssuer(_) => TOKEN_SCHEMA,
+        Topic::Transactions
+        | Topic::TransactionNetwork(_)
+        | Topic::TransactionIn(_, _)
+        | Topic::TransactionOut(_, _) => TRANSACTION_SCHEMA,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_for() {
+        let titles = [
+            (Topic::BalanceAddress("sp1a".to_string()), "BalancePayload"),
+            (Topic::TokenBalances, "TokenBalancePayload"),
+            (
+                Topic::TokenPriceNetwork("MAINNET".to_string()),
+                "TokenPricePayload",
+            ),
+            (Topic::TokenIssuer("sp1a".to_string()), "TokenPayload"),
+            (Topic::Transactions, "TransactionPayload"),
+        ];
+        for (topic, title) in titles {
+            let schema: serde_json::Value = serde_json::from_str(schema_for(&topic)).unwrap();
+            assert_eq!(schema["title"], title, "{:?}", topic);
+        }
+        assert_eq!(SCHEMA_VERSION.len(), 16);
+        assert!(SCHEMA_VERSION.bytes().all(|byte| byte.is_ascii_hexdigit()));
+    }
+}
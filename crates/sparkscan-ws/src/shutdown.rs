@@ -0,0 +1,280 @@
+//! Coordinated shutdown of a client and its subscriptions.
+//!
+//! The client shares a [`Lifecycle`] with every subscription it creates. Subscriptions register
+//! their channel, the queues of their message callbacks and their drop counter, so that
+//! [`SparkScanWsClient::shutdown`](crate::SparkScanWsClient::shutdown) can stop new messages,
+//! unsubscribe the channels and wait for the queued messages to be handled.
+//!
+//! The queued messages of all subscriptions also measure the load of the client, from which
+//! subscriptions of lower [`Priority`] shed their messages first.
+
+use crate::logging::{self, Level, LogCategory};
+use crate::subscription::Priority;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use tokio_centrifuge::subscription::Subscription;
+use tokio_util::sync::CancellationToken;
+
+/// Outcome of [`SparkScanWsClient::shutdown`](crate::SparkScanWsClient::shutdown).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Channels unsubscribed
+    pub unsubscribed: usize,
+    /// Messages still queued or being handled when the grace period ended
+    pub abandoned: usize,
+    /// Messages dropped by full dispatch queues of the live subscriptions
+    pub dropped: u64,
+    /// Messages received after the shutdown started, and not handled
+    pub rejected: u64,
+}
+
+impl ShutdownReport {
+    /// Check whether every message queued when the shutdown started was handled.
+    pub fn is_drained(&self) -> bool {
+        self.abandoned == 0
+    }
+}
+
+/// Dispatch counters of a client, from
+/// [`SparkScanWsClient::dispatch_stats`](crate::SparkScanWsClient::dispatch_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DispatchStats {
+    /// Messages queued or being handled
+    pub pending: usize,
+    /// Messages dropped by full dispatch queues of the live subscriptions
+    pub dropped: u64,
+    /// Messages shed by the live subscriptions while the client was overloaded
+    pub shed: u64,
+}
+
+/// Shutdown state shared by a client and its subscriptions.
+#[derive(Default)]
+pub(crate) struct Lifecycle {
+    closing: AtomicBool,
+    rejected: AtomicU64,
+    tracked: Mutex<Tracked>,
+    /// Cancelled when the shutdown starts, ending the watch of the cancellation token
+    closed: CancellationToken,
+    /// Whether the cancellation token of the configuration is watched
+    watching: AtomicBool,
+}
+
+#[derive(Default)]
+struct Tracked {
+    subscriptions: Vec<(Weak<Subscription>, Weak<AtomicU64>)>,
+    /// Messages queued or being handled by each message callback
+    queues: Vec<Weak<AtomicUsize>>,
+    /// Messages shed by each subscription
+    shed: Vec<Weak<AtomicU64>>,
+}
+
+impl Lifecycle {
+    fn lock(&self) -> MutexGuard<'_, Tracked> {
+        // Entries are pushed and pruned whole, so a panicking caller leaves them consistent
+        self.tracked.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Track a subscription and its drop counter until they are dropped.
+    pub(crate) fn track_subscription(
+        &self,
+        subscription: &Arc<Subscription>,
+        dropped: &Arc<AtomicU64>,
+    ) {
+        let mut tracked = self.lock();
+        tracked
+            .subscriptions
+            .retain(|(subscription, _)| subscription.strong_count() > 0);
+        tracked
+            .subscriptions
+            .push((Arc::downgrade(subscription), Arc::downgrade(dropped)));
+    }
+
+    /// Track the queue of a message callback until it is dropped and drained.
+    pub(crate) fn track_queue(&self, pending: &Arc<AtomicUsize>) {
+        let mut tracked = self.lock();
+        tracked.queues.retain(|queue| queue.strong_count() > 0);
+        tracked.queues.push(Arc::downgrade(pending));
+    }
+
+    /// Track the shed counter of a subscription until it is dropped.
+    pub(crate) fn track_shed(&self, shed: &Arc<AtomicU64>) {
+        let mut tracked = self.lock();
+        tracked.shed.retain(|shed| shed.strong_count() > 0);
+        tracked.shed.push(Arc::downgrade(shed));
+    }
+
+    /// Check whether the shutdown started.
+    pub(crate) fn is_closing(&self) -> bool {
+        self.closing.load(Ordering::Relaxed)
+    }
+
+    /// Get a token cancelled when the shutdown starts.
+    pub(crate) fn closed(&self) -> CancellationToken {
+        self.closed.clone()
+    }
+
+    /// Mark the cancellation token as watched, returning whether it was not yet.
+    pub(crate) fn start_watching(&self) -> bool {
+        !self.watching.swap(true, Ordering::Relaxed)
+    }
+
+    /// Count a message received after the shutdown started, returning whether to reject it.
+    pub(crate) fn reject(&self) -> bool {
+        if !self.is_closing() {
+            return false;
+        }
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Count a message of a subscription of `priority` shed, returning whether to shed it.
+    ///
+    /// Messages are shed once `threshold` messages are pending over the client for
+    /// [`Priority::Low`], twice as many for [`Priority::Normal`], and never for
+    /// [`Priority::High`] or without a threshold.
+    pub(crate) fn shed(
+        &self,
+        priority: Priority,
+        threshold: Option<usize>,
+        shed: &AtomicU64,
+    ) -> bool {
+        let limit = match (priority, threshold) {
+            (Priority::High, _) | (_, None) => return false,
+            (Priority::Normal, Some(threshold)) => threshold.saturating_mul(2),
+            (Priority::Low, Some(threshold)) => threshold,
+        };
+        if self.pending() < limit {
+            return false;
+        }
+        let count = shed.fetch_add(1, Ordering::Relaxed) + 1;
+        // Log the first messages shed, then at exponentially growing intervals
+        if count.is_power_of_two() {
+            logging::log(
+                LogCategory::Dispatch,
+                Level::Warn,
+                format_args!(
+                    "Client overloaded, {} messages of a {:?} priority subscription shed",
+                    count, priority
+                ),
+            );
+        }
+        true
+    }
+
+    /// Start the shutdown and unsubscribe the live subscriptions, returning how many there were.
+    pub(crate) fn close(&self) -> usize {
+        self.closing.store(true, Ordering::Relaxed);
+        self.closed.cancel();
+        let subscriptions: Vec<_> = self
+            .lock()
+            .subscriptions
+            .iter()
+            .filter_map(|(subscription, _)| subscription.upgrade())
+            .collect();
+        for subscription in &subscriptions {
+            subscription.unsubscribe();
+        }
+        subscriptions.len()
+    }
+
+    /// Get the number of messages queued or being handled.
+    pub(crate) fn pending(&self) -> usize {
+        self.lock()
+            .queues
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|pending| pending.load(Ordering::Acquire))
+            .sum()
+    }
+
+    /// Get the report of the shutdown, with `abandoned` messages left.
+    pub(crate) fn report(&self, unsubscribed: usize, abandoned: usize) -> ShutdownReport {
+        ShutdownReport {
+            unsubscribed,
+            abandoned,
+            dropped: self.stats().dropped,
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Get the dispatch counters of the live subscriptions.
+    pub(crate) fn stats(&self) -> DispatchStats {
+        let pending = self.pending();
+        let tracked = self.lock();
+        let dropped = tracked.subscriptions.iter().map(|(_, dropped)| dropped);
+        DispatchStats {
+            pending,
+            dropped: total(dropped),
+            shed: total(tracked.shed.iter()),
+        }
+    }
+}
+
+/// Sum the counters still alive among `counters`.
+fn total<'a>(counters: impl Iterator<Item = &'a Weak<AtomicU64>>) -> u64 {
+    counters
+        .filter_map(Weak::upgrade)
+        .map(|counter| counter.load(Ordering::Relaxed))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_and_rejected_messages() {
+        let lifecycle = Lifecycle::default();
+        let queue = Arc::new(AtomicUsize::new(2));
+        lifecycle.track_queue(&queue);
+        lifecycle.track_queue(&Arc::new(AtomicUsize::new(5)));
+        // Dropped queues have nothing left to handle
+        assert_eq!(lifecycle.pending(), 2);
+
+        assert!(!lifecycle.reject());
+        assert_eq!(lifecycle.close(), 0);
+        assert!(lifecycle.reject());
+
+        queue.store(0, Ordering::Release);
+        let report = lifecycle.report(0, lifecycle.pending());
+        assert!(report.is_drained());
+        assert_eq!(report.rejected, 1);
+    }
+
+    #[test]
+    fn test_close_ends_cancellation_watch() {
+        let lifecycle = Lifecycle::default();
+        assert!(lifecycle.start_watching());
+        assert!(!lifecycle.start_watching());
+
+        let closed = lifecycle.closed();
+        assert!(!closed.is_cancelled());
+        lifecycle.close();
+        assert!(closed.is_cancelled());
+    }
+
+    #[test]
+    fn test_shed_by_priority() {
+        let lifecycle = Lifecycle::default();
+        let queue = Arc::new(AtomicUsize::new(5));
+        lifecycle.track_queue(&queue);
+        let shed = Arc::new(AtomicU64::new(0));
+        lifecycle.track_shed(&shed);
+
+        assert!(lifecycle.shed(Priority::Low, Some(4), &shed));
+        assert!(!lifecycle.shed(Priority::Normal, Some(4), &shed));
+        assert!(!lifecycle.shed(Priority::High, Some(1), &shed));
+        assert!(!lifecycle.shed(Priority::Low, None, &shed));
+
+        queue.store(8, Ordering::Release);
+        assert!(lifecycle.shed(Priority::Normal, Some(4), &shed));
+        assert_eq!(
+            lifecycle.stats(),
+            DispatchStats {
+                pending: 8,
+                dropped: 0,
+                shed: 2,
+            }
+        );
+    }
+}
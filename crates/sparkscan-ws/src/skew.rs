@@ -0,0 +1,181 @@
+//! Clock skew between the SparkScan servers and this host.
+//!
+//! Every message carries the time the server processed it. The difference to the local time
+//! at which the message is handled is the sum of the clock skew and the delivery delay; the
+//! smallest difference over recent messages is the closest estimate of the skew alone, since
+//! the delay is never negative and occasionally close to zero.
+
+use crate::logging::{self, Level, LogCategory};
+use crate::types::SparkScanMessage;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// Number of recent messages the skew is estimated from.
+const WINDOW: usize = 128;
+
+type SkewCallback = Box<dyn Fn(Duration) + Send + Sync>;
+
+/// Rolling estimate of the clock skew, shared by the subscriptions of a client.
+pub(crate) struct ClockSkew {
+    /// Skew above which callbacks are notified, in milliseconds
+    threshold_ms: AtomicU64,
+    state: Mutex<State>,
+    callbacks: Mutex<Vec<SkewCallback>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Offsets of the latest messages, in milliseconds
+    offsets: VecDeque<i64>,
+    /// Whether the estimate was above the threshold at the last message
+    exceeded: bool,
+}
+
+impl State {
+    fn offset(&self) -> Option<Duration> {
+        self.offsets
+            .iter()
+            .min()
+            .copied()
+            .map(Duration::milliseconds)
+    }
+}
+
+impl ClockSkew {
+    pub(crate) fn new(threshold_ms: u64) -> Self {
+        Self {
+            threshold_ms: AtomicU64::new(threshold_ms),
+            state: Mutex::new(State::default()),
+            callbacks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Get the estimated offset of the local clock from the server clock.
+    ///
+    /// Positive when the local clock is ahead. `None` before the first message.
+    pub(crate) fn offset(&self) -> Option<Duration> {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .offset()
+    }
+
+    /// Change the skew above which callbacks are notified.
+    pub(crate) fn set_threshold(&self, threshold_ms: u64) {
+        self.threshold_ms.store(threshold_ms, Ordering::Relaxed);
+    }
+
+    fn threshold(&self) -> Duration {
+        let threshold_ms = self.threshold_ms.load(Ordering::Relaxed);
+        Duration::milliseconds(i64::try_from(threshold_ms).unwrap_or(i64::MAX))
+    }
+
+    /// Register a callback notified when the estimated skew exceeds the threshold.
+    pub(crate) fn on_exceeded<F>(&self, callback: F)
+    where
+        F: Fn(Duration) + Send + Sync + 'static,
+    {
+        self.callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Record a message handled now.
+    pub(crate) fn observe(&self, message: &SparkScanMessage) {
+        if let Some(processed_at) = message.processed_at() {
+            self.record(processed_at, Utc::now());
+        }
+    }
+
+    fn record(&self, processed_at: DateTime<Utc>, received_at: DateTime<Utc>) {
+        let threshold = self.threshold();
+        let offset = {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            if state.offsets.len() == WINDOW {
+                state.offsets.pop_front();
+            }
+            state
+                .offsets
+                .push_back((received_at - processed_at).num_milliseconds());
+
+            let offset = state.offset().unwrap_or_else(Duration::zero);
+            let exceeded = offset.abs() > threshold;
+            // Notify once per excursion above the threshold
+            let notify = exceeded && !state.exceeded;
+            state.exceeded = exceeded;
+            if !notify {
+                return;
+            }
+            offset
+        };
+
+        logging::log(
+            LogCategory::ClockSkew,
+            Level::Warn,
+            format_args!(
+                "Clock skew of {} ms with the SparkScan servers exceeds {} ms",
+                offset.num_milliseconds(),
+                threshold.num_milliseconds()
+            ),
+        );
+
+        for callback in self
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            callback(offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_offset_is_the_smallest_recent_delay() {
+        let skew = ClockSkew::new(1000);
+        assert_eq!(skew.offset(), None);
+
+        let processed_at = Utc::now();
+        for delay in [250, 40, 900] {
+            skew.record(processed_at, processed_at + Duration::milliseconds(delay));
+        }
+        assert_eq!(skew.offset(), Some(Duration::milliseconds(40)));
+
+        // A local clock behind the server yields a negative offset
+        skew.record(processed_at, processed_at - Duration::milliseconds(300));
+        assert_eq!(skew.offset(), Some(Duration::milliseconds(-300)));
+    }
+
+    #[test]
+    fn test_callbacks_fire_once_per_excursion() {
+        let skew = ClockSkew::new(1000);
+        let notified = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&notified);
+        skew.on_exceeded(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let processed_at = Utc::now();
+        for _ in 0..3 {
+            skew.record(processed_at, processed_at + Duration::seconds(5));
+        }
+        assert_eq!(notified.load(Ordering::Relaxed), 1);
+
+        // The skew recovers, then drifts the other way
+        skew.record(processed_at, processed_at);
+        assert_eq!(skew.offset(), Some(Duration::zero()));
+        for _ in 0..3 {
+            skew.record(processed_at, processed_at - Duration::seconds(5));
+        }
+        assert_eq!(notified.load(Ordering::Relaxed), 2);
+    }
+}
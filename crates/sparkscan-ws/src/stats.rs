@@ -0,0 +1,395 @@
+//! Rolling aggregates over the message stream.
+//!
+//! [`RollingStats`] keeps the samples of a few sliding windows, keyed by the time the server
+//! processed each message:
+//!
+//! - transaction count and volume per network, over a minute by default;
+//! - distinct addresses sending or receiving transactions per network, over an hour;
+//! - volume-weighted average price (VWAP) per token over an hour, weighting the latest known
+//!   price of the token by the amount of each token transfer.
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::*;
+//! # use futures::StreamExt;
+//! # use std::sync::Arc;
+//! # use std::time::Duration;
+//! # async fn example() -> Result<()> {
+//! # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! let stats = Arc::new(RollingStats::new());
+//! for topic in [Topic::Transactions, Topic::TokenPrices] {
+//!     let subscription = client.subscribe(topic).await?;
+//!     let stats = Arc::clone(&stats);
+//!     subscription.on_message(move |message| stats.record(&message));
+//!     subscription.subscribe();
+//! }
+//!
+//! let mut snapshots = Box::pin(stats.snapshots(Duration::from_secs(10)));
+//! while let Some(snapshot) = snapshots.next().await {
+//!     println!("{:?}", snapshot.networks);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::types::SparkScanMessage;
+use chrono::{DateTime, TimeDelta, Utc};
+#[cfg(feature = "client")]
+use futures::Stream;
+use sparkscan_types::Network;
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "client")]
+use std::sync::Arc;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// Aggregates of one network.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    /// Transactions in the volume window
+    pub transactions: u64,
+    /// Amount in satoshis of the transactions in the volume window
+    pub volume_sats: u64,
+    /// Distinct senders and receivers of transactions in the activity window
+    pub active_addresses: usize,
+}
+
+/// Aggregates at a point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsSnapshot {
+    /// End of the windows
+    pub at: DateTime<Utc>,
+    /// Aggregates of each network with activity in its windows
+    pub networks: HashMap<Network, NetworkStats>,
+    /// Volume-weighted average price in satoshis of each token traded in the price window
+    pub vwap: HashMap<String, f64>,
+}
+
+/// Sliding-window aggregates of transactions and token prices.
+///
+/// Feed it with [`record`](Self::record) from any number of subscriptions, and read the
+/// aggregates with [`snapshot`](Self::snapshot) or [`snapshots`](Self::snapshots).
+pub struct RollingStats {
+    volume_window: TimeDelta,
+    activity_window: TimeDelta,
+    price_window: TimeDelta,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    transactions: VecDeque<TransactionSample>,
+    /// Last time each address of each network was active
+    active: HashMap<(Network, String), DateTime<Utc>>,
+    /// Activity of addresses in the order it was seen, to evict them from `active`
+    activity: VecDeque<(DateTime<Utc>, (Network, String))>,
+    /// Latest price in satoshis of each token
+    prices: HashMap<String, f64>,
+    trades: VecDeque<Trade>,
+    /// Latest processing time seen, from which old samples are evicted
+    latest: Option<DateTime<Utc>>,
+}
+
+struct TransactionSample {
+    at: DateTime<Utc>,
+    network: Network,
+    amount_sats: u64,
+}
+
+struct Trade {
+    at: DateTime<Utc>,
+    token: String,
+    price: f64,
+    volume: f64,
+}
+
+impl Default for RollingStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RollingStats {
+    /// Create aggregates with one-minute volume windows and one-hour activity and price
+    /// windows.
+    pub fn new() -> Self {
+        Self {
+            volume_window: TimeDelta::minutes(1),
+            activity_window: TimeDelta::hours(1),
+            price_window: TimeDelta::hours(1),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Set the window of transaction counts and volumes.
+    pub fn with_volume_window(mut self, window: Duration) -> Self {
+        self.volume_window = to_time_delta(window);
+        self
+    }
+
+    /// Set the window of active addresses.
+    pub fn with_activity_window(mut self, window: Duration) -> Self {
+        self.activity_window = to_time_delta(window);
+        self
+    }
+
+    /// Set the window of token VWAPs.
+    pub fn with_price_window(mut self, window: Duration) -> Self {
+        self.price_window = to_time_delta(window);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // Samples are pushed whole, so a panicking caller leaves the state consistent
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Add a message to the aggregates.
+    ///
+    /// Transactions count towards volumes, active addresses and, for token transfers, the
+    /// VWAP of the token; price updates set the price of later transfers. Other messages are
+    /// ignored.
+    pub fn record(&self, message: &SparkScanMessage) {
+        let mut state = self.lock();
+        match message {
+            SparkScanMessage::Transaction(data) => {
+                let at = data.processed_at;
+                let network = message.spark_network();
+                state.transactions.push_back(TransactionSample {
+                    at,
+                    network,
+                    amount_sats: data
+                        .amount_sats
+                        .as_deref()
+                        .and_then(|amount| amount.parse().ok())
+                        .unwrap_or(0),
+                });
+                for address in [&data.from_identifier, &data.to_identifier]
+                    .into_iter()
+                    .flatten()
+                {
+                    let key = (network, address.clone());
+                    state.active.insert(key.clone(), at);
+                    state.activity.push_back((at, key));
+                }
+
+                let trade = data
+                    .token_address
+                    .as_ref()
+                    .zip(data.token_amount.as_deref());
+                if let Some((token, amount)) = trade {
+                    let price = state.prices.get(token.as_str()).copied();
+                    if let (Some(price), Ok(volume)) = (price, amount.parse::<f64>()) {
+                        state.trades.push_back(Trade {
+                            at,
+                            token: token.to_string(),
+                            price,
+                            volume,
+                        });
+                    }
+                }
+                self.evict(&mut state, at);
+            }
+            SparkScanMessage::TokenPrice(data) => {
+                if let Ok(price) = data.price_sats.to_string().parse() {
+                    state.prices.insert(data.address.to_string(), price);
+                }
+                self.evict(&mut state, data.processed_at);
+            }
+            _ => {}
+        }
+    }
+
+    /// Drop the samples outside of every window ending at the latest time seen.
+    fn evict(&self, state: &mut State, at: DateTime<Utc>) {
+        let latest = state.latest.map_or(at, |latest| latest.max(at));
+        state.latest = Some(latest);
+
+        let volume_start = window_start(latest, self.volume_window);
+        while state
+            .transactions
+            .front()
+            .is_some_and(|sample| sample.at <= volume_start)
+        {
+            state.transactions.pop_front();
+        }
+        let price_start = window_start(latest, self.price_window);
+        while state
+            .trades
+            .front()
+            .is_some_and(|trade| trade.at <= price_start)
+        {
+            state.trades.pop_front();
+        }
+        let activity_start = window_start(latest, self.activity_window);
+        while let Some((at, key)) = state.activity.pop_front() {
+            if at > activity_start {
+                state.activity.push_front((at, key));
+                break;
+            }
+            // The address may have been active again since
+            if state
+                .active
+                .get(&key)
+                .is_some_and(|last| *last <= activity_start)
+            {
+                state.active.remove(&key);
+            }
+        }
+    }
+
+    /// Get the aggregates of the windows ending now.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        self.snapshot_at(Utc::now())
+    }
+
+    /// Get the aggregates of the windows ending at `at`.
+    pub fn snapshot_at(&self, at: DateTime<Utc>) -> StatsSnapshot {
+        let state = self.lock();
+        let in_window =
+            |time: DateTime<Utc>, window: TimeDelta| time > window_start(at, window) && time <= at;
+
+        let mut networks: HashMap<Network, NetworkStats> = HashMap::new();
+        for sample in &state.transactions {
+            if in_window(sample.at, self.volume_window) {
+                let stats = networks.entry(sample.network).or_default();
+                stats.transactions += 1;
+                stats.volume_sats = stats.volume_sats.saturating_add(sample.amount_sats);
+            }
+        }
+        for ((network, _), last) in &state.active {
+            if in_window(*last, self.activity_window) {
+                networks.entry(*network).or_default().active_addresses += 1;
+            }
+        }
+
+        let mut totals: HashMap<&str, (f64, f64)> = HashMap::new();
+        for trade in &state.trades {
+            if in_window(trade.at, self.price_window) {
+                let (value, volume) = totals.entry(&trade.token).or_default();
+                *value += trade.price * trade.volume;
+                *volume += trade.volume;
+            }
+        }
+        let vwap = totals
+            .into_iter()
+            .filter(|(_, (_, volume))| *volume > 0.0)
+            .map(|(token, (value, volume))| (token.to_string(), value / volume))
+            .collect();
+
+        StatsSnapshot { at, networks, vwap }
+    }
+
+    /// Emit a snapshot every `period`, starting right away.
+    ///
+    /// The stream must be polled within a Tokio runtime.
+    #[cfg(feature = "client")]
+    pub fn snapshots(self: &Arc<Self>, period: Duration) -> impl Stream<Item = StatsSnapshot> {
+        let stats = Arc::clone(self);
+        futures::stream::unfold(None, move |interval| {
+            let stats = Arc::clone(&stats);
+            async move {
+                let mut interval = interval.unwrap_or_else(|| tokio::time::interval(period));
+                interval.tick().await;
+                Some((stats.snapshot(), Some(interval)))
+            }
+        })
+    }
+}
+
+fn to_time_delta(duration: Duration) -> TimeDelta {
+    TimeDelta::from_std(duration).unwrap_or(TimeDelta::MAX)
+}
+
+/// Get the start of the window of length `window` ending at `end`.
+fn window_start(end: DateTime<Utc>, window: TimeDelta) -> DateTime<Utc> {
+    end.checked_sub_signed(window)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{parse_message_for_topic, Topic};
+    use serde_json::json;
+
+    const TOKEN: &str = "btkn1daywtenlww42njymqzyegvcwuy3p9f26zknme0srxa7tagewvuys86h553";
+
+    fn transaction(
+        id: &str,
+        network: &str,
+        seconds: u32,
+        token_amount: Option<&str>,
+    ) -> SparkScanMessage {
+        let mut transaction_json = json!({
+            "id": id,
+            "network": network,
+            "type": "spark_to_spark",
+            "status": "confirmed",
+            "amount_sats": "1000",
+            "from_identifier": format!("sender_{}", id),
+            "to_identifier": "receiver",
+            "processed_at": format!("2025-08-06T16:{:02}:{:02}Z", seconds / 60, seconds % 60)
+        });
+        if let Some(amount) = token_amount {
+            transaction_json["token_address"] = json!(TOKEN);
+            transaction_json["token_amount"] = json!(amount);
+        }
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap()
+    }
+
+    fn price(price_sats: &str, seconds: u32) -> SparkScanMessage {
+        let price_json = json!({
+            "address": TOKEN,
+            "network": "MAINNET",
+            "protocol": "flashnet",
+            "price_sats": price_sats,
+            "processed_at": format!("2025-08-06T16:{:02}:{:02}Z", seconds / 60, seconds % 60)
+        });
+        let json_str = serde_json::to_string(&price_json).unwrap();
+        parse_message_for_topic(&Topic::TokenPrices, json_str.as_bytes()).unwrap()
+    }
+
+    fn at(seconds: u32) -> DateTime<Utc> {
+        format!("2025-08-06T16:{:02}:{:02}Z", seconds / 60, seconds % 60)
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_transaction_windows() {
+        let stats = RollingStats::new();
+        stats.record(&transaction("a", "MAINNET", 0, None));
+        stats.record(&transaction("b", "MAINNET", 30, None));
+        stats.record(&transaction("c", "REGTEST", 45, None));
+
+        let snapshot = stats.snapshot_at(at(50));
+        let mainnet = &snapshot.networks[&Network::Mainnet];
+        assert_eq!(mainnet.transactions, 2);
+        assert_eq!(mainnet.volume_sats, 2000);
+        assert_eq!(mainnet.active_addresses, 3);
+        assert_eq!(snapshot.networks[&Network::Regtest].transactions, 1);
+
+        // The first transaction leaves the volume window but its addresses stay active
+        let snapshot = stats.snapshot_at(at(70));
+        let mainnet = &snapshot.networks[&Network::Mainnet];
+        assert_eq!(mainnet.transactions, 1);
+        assert_eq!(mainnet.active_addresses, 3);
+    }
+
+    #[test]
+    fn test_vwap() {
+        let trade = |id, seconds, amount| transaction(id, "MAINNET", seconds, Some(amount));
+
+        let stats = RollingStats::new();
+        // Transfers before the first price are not weighted
+        stats.record(&trade("a", 0, "500"));
+        stats.record(&price("10", 1));
+        stats.record(&trade("b", 2, "100"));
+        stats.record(&price("20", 3));
+        stats.record(&trade("c", 4, "300"));
+
+        let snapshot = stats.snapshot_at(at(5));
+        assert_eq!(snapshot.vwap[TOKEN], (10.0 * 100.0 + 20.0 * 300.0) / 400.0);
+    }
+}
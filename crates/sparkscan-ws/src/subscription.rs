@@ -1,21 +1,211 @@
 //! WebSocket subscription management for SparkScan.
 
 use crate::{
-    error::Result,
-    types::{parse_message_for_topic, SparkScanMessage, Topic},
+    acked::AckedSubscription,
+    cache::StateCache,
+    connections::SubscriptionSlot,
+    dispatch::{self, DispatchOptions, Dispatcher},
+    error::{Result, SparkScanWsError, SubscribeError},
+    filter::Filter,
+    limits::PayloadLimits,
+    logging::{self, Level, LogCategory},
+    schema_check::SchemaCheck,
+    shutdown::Lifecycle,
+    skew::ClockSkew,
+    types::{SparkScanMessage, Topic},
+    verify::Verification,
 };
-use std::sync::Arc;
+use sparkscan_types::{Network, SparkAddress};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot, watch};
+use tokio_centrifuge::protocol::Publication;
 use tokio_centrifuge::subscription::Subscription;
+use tokio_util::sync::CancellationToken;
+
+/// Ordering of messages processed concurrently by a message callback.
+///
+/// See [`crate::SparkScanWsConfig::with_handler_ordering`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HandlerOrdering {
+    /// Messages may complete in any order
+    #[default]
+    Unordered,
+    /// Messages sharing an [ordering key](SparkScanMessage::ordering_key) are processed one at a
+    /// time and in order
+    KeyedOrdering,
+}
+
+/// Importance of a subscription, deciding which messages are shed first while the client is
+/// overloaded.
+///
+/// See [`crate::SparkScanWsConfig::with_load_shedding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    /// Shed first, such as token prices when only the latest one matters
+    Low,
+    /// Shed once the load reaches twice the threshold of low-priority subscriptions
+    #[default]
+    Normal,
+    /// Never shed, such as the transactions of your own addresses
+    High,
+}
+
+/// Error callback, given the description of the error and its classification.
+type ErrorCallback = Arc<dyn Fn(&str, &SubscribeError) + Send + Sync>;
+type PublicationHandler = Arc<dyn Fn(&Publication) + Send + Sync>;
+type StateCallback = Arc<dyn Fn() + Send + Sync>;
+
+/// Outcome of a subscription request, settled by the first acknowledgement or error.
+type Pending = Arc<Mutex<Option<oneshot::Sender<std::result::Result<(), SubscribeError>>>>>;
+
+/// Callbacks of a subscription.
+///
+/// The centrifuge subscription holds a single callback per event, so each subscription
+/// registers its own on creation, calling every callback registered here. Registering a
+/// callback therefore never replaces another one.
+#[derive(Default)]
+struct Callbacks {
+    /// Checks deciding whether a publication reaches the message handlers
+    admission: Admission,
+    /// Handlers of the raw publications, called before any check
+    raw: Vec<PublicationHandler>,
+    /// Handlers of the admitted publications, one per message callback
+    messages: Vec<PublicationHandler>,
+    /// Error callbacks, also notified of the errors raised by this crate such as timeouts
+    errors: Vec<ErrorCallback>,
+    /// Callbacks of the subscription establishment
+    subscribed: Vec<StateCallback>,
+    /// Requests of [`SparkScanSubscription::subscribe_and_wait`] awaiting their outcome
+    pending: Vec<Pending>,
+}
+
+/// Checks applied once to every publication, before the message handlers.
+#[derive(Clone, Default)]
+struct Admission {
+    /// Schema version check of the client, fed with every publication
+    schema_check: Option<Arc<SchemaCheck>>,
+    /// Verifier of the client, checking every publication before dispatch
+    verification: Option<Arc<Verification>>,
+    /// Shutdown state of the client, rejecting messages once it shuts down
+    lifecycle: Option<Arc<Lifecycle>>,
+    /// Importance of the messages while the client is overloaded
+    priority: Priority,
+    /// Messages pending over the client from which low-priority messages are shed
+    load_shedding: Option<usize>,
+}
+
+impl Callbacks {
+    fn lock(callbacks: &Mutex<Self>) -> MutexGuard<'_, Self> {
+        callbacks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Pass a publication of `channel` to the callbacks.
+///
+/// The callbacks are called without holding the lock, so that they may register others.
+fn route(callbacks: &Mutex<Callbacks>, channel: &str, shed: &AtomicU64, publication: Publication) {
+    let (admission, raw, messages) = {
+        let callbacks = Callbacks::lock(callbacks);
+        (
+            callbacks.admission.clone(),
+            callbacks.raw.clone(),
+            callbacks.messages.clone(),
+        )
+    };
+    for handler in &raw {
+        handler(&publication);
+    }
+    if messages.is_empty() {
+        return;
+    }
+
+    if let Some(schema_check) = &admission.schema_check {
+        schema_check.inspect(&publication);
+    }
+    if let Some(verification) = &admission.verification {
+        if !verification.accept(channel, &publication) {
+            return;
+        }
+    }
+    // Messages arriving once the client shuts down are counted and not handled, as are the
+    // messages shed while it is overloaded
+    if let Some(lifecycle) = &admission.lifecycle {
+        if lifecycle.reject() || lifecycle.shed(admission.priority, admission.load_shedding, shed) {
+            return;
+        }
+    }
+    for handler in &messages {
+        handler(&publication);
+    }
+}
+
+/// Settle the pending subscription requests and call the callbacks of the subscription
+/// establishment.
+fn subscribed(callbacks: &Mutex<Callbacks>) {
+    let (subscribed, pending) = {
+        let mut callbacks = Callbacks::lock(callbacks);
+        (
+            callbacks.subscribed.clone(),
+            std::mem::take(&mut callbacks.pending),
+        )
+    };
+    for pending in &pending {
+        settle(pending, Ok(()));
+    }
+    for callback in &subscribed {
+        callback();
+    }
+}
+
+/// Fail the pending subscription requests with an error reported by the server, and pass it
+/// to the error callbacks.
+fn failed(callbacks: &Mutex<Callbacks>, description: &str) {
+    let error = SubscribeError::parse(description);
+    let pending = std::mem::take(&mut Callbacks::lock(callbacks).pending);
+    for pending in &pending {
+        settle(pending, Err(error.clone()));
+    }
+    report_error(callbacks, description, &error);
+}
+
+/// Pass an error described by `description` to the error callbacks.
+fn report_error(callbacks: &Mutex<Callbacks>, description: &str, error: &SubscribeError) {
+    let errors = Callbacks::lock(callbacks).errors.clone();
+    for callback in &errors {
+        callback(description, error);
+    }
+}
 
 /// Typed WebSocket subscription handler.
 ///
 /// Wraps tokio-centrifuge subscription with type-safe message deserialization
 /// based on topic-specific message types.
 pub struct SparkScanSubscription {
-    /// The underlying centrifuge subscription
-    inner: Subscription,
+    /// The underlying centrifuge subscription, shared with the shutdown of the client
+    inner: Arc<Subscription>,
     /// The topic this subscription is for
     topic: Topic,
+    /// Queue size, concurrency and ordering of message callbacks
+    dispatch: DispatchOptions,
+    /// Messages dropped because a dispatch queue was full
+    dropped_messages: Arc<AtomicU64>,
+    /// Messages shed while the client was overloaded
+    shed_messages: Arc<AtomicU64>,
+    /// Latest message per key, if enabled
+    state_cache: Option<Arc<StateCache>>,
+    /// Clock skew estimate of the client, fed with every message
+    clock_skew: Option<Arc<ClockSkew>>,
+    /// Callbacks of the subscription
+    callbacks: Arc<Mutex<Callbacks>>,
+    /// Shutdown state of the client, tracking the message callbacks
+    lifecycle: Option<Arc<Lifecycle>>,
+    /// Time the server has to acknowledge [`subscribe_and_wait`](Self::subscribe_and_wait)
+    subscribe_timeout: Option<Duration>,
+    /// Channel taken on the connection of the client, released on drop
+    _slot: Option<SubscriptionSlot>,
 }
 
 impl SparkScanSubscription {
@@ -23,7 +213,209 @@ impl SparkScanSubscription {
     ///
     /// Typically called internally by client.
     pub fn new(inner: Subscription, topic: Topic) -> Self {
-        Self { inner, topic }
+        let callbacks = Arc::new(Mutex::new(Callbacks::default()));
+        let shed_messages = Arc::new(AtomicU64::new(0));
+
+        let publications = Arc::clone(&callbacks);
+        let shed = Arc::clone(&shed_messages);
+        let channel = topic.as_str();
+        inner.on_publication(move |publication| {
+            route(&publications, &channel, &shed, publication);
+        });
+        let established = Arc::clone(&callbacks);
+        inner.on_subscribed(move || subscribed(&established));
+        let errors = Arc::clone(&callbacks);
+        inner.on_error(move |err| failed(&errors, &format!("{:?}", err)));
+
+        Self {
+            inner: Arc::new(inner),
+            topic,
+            dispatch: DispatchOptions::default(),
+            dropped_messages: Arc::new(AtomicU64::new(0)),
+            shed_messages,
+            state_cache: None,
+            clock_skew: None,
+            callbacks,
+            lifecycle: None,
+            subscribe_timeout: None,
+            _slot: None,
+        }
+    }
+
+    /// Hold a channel on the connection of the client for the lifetime of the subscription.
+    pub(crate) fn with_slot(mut self, slot: SubscriptionSlot) -> Self {
+        self._slot = Some(slot);
+        self
+    }
+
+    /// Feed the clock skew estimate of the client with the messages of this subscription.
+    pub(crate) fn with_clock_skew(mut self, clock_skew: Arc<ClockSkew>) -> Self {
+        self.clock_skew = Some(clock_skew);
+        self
+    }
+
+    /// Compare the schema version advertised in the publications of this subscription.
+    pub(crate) fn with_schema_check(self, schema_check: Arc<SchemaCheck>) -> Self {
+        self.callbacks().admission.schema_check = Some(schema_check);
+        self
+    }
+
+    /// Verify the publications of this subscription before dispatching them.
+    pub(crate) fn with_verification(self, verification: Arc<Verification>) -> Self {
+        self.callbacks().admission.verification = Some(verification);
+        self
+    }
+
+    /// Stop and drain the message callbacks on the shutdown of the client.
+    pub(crate) fn with_lifecycle(mut self, lifecycle: Arc<Lifecycle>) -> Self {
+        lifecycle.track_subscription(&self.inner, &self.dropped_messages);
+        lifecycle.track_shed(&self.shed_messages);
+        self.callbacks().admission.lifecycle = Some(Arc::clone(&lifecycle));
+        self.lifecycle = Some(lifecycle);
+        self
+    }
+
+    fn callbacks(&self) -> MutexGuard<'_, Callbacks> {
+        Callbacks::lock(&self.callbacks)
+    }
+
+    /// Unsubscribe when `token` is cancelled.
+    ///
+    /// Ties the subscription into the shutdown orchestration of the application instead of its
+    /// drop. The token is watched by a task, so this must be called within a Tokio runtime.
+    pub fn with_cancellation_token(self, token: CancellationToken) -> Self {
+        let inner = Arc::downgrade(&self.inner);
+        let watch = async move {
+            token.cancelled().await;
+            if let Some(inner) = inner.upgrade() {
+                inner.unsubscribe();
+            }
+        };
+        if let Err(e) = dispatch::spawn_task("unsubscribe", &self.topic.as_str(), watch) {
+            logging::log(
+                LogCategory::Connection,
+                Level::Error,
+                format_args!("Failed to start the cancellation watch: {}", e),
+            );
+        }
+        self
+    }
+
+    /// Set the number of messages queued per message callback.
+    ///
+    /// See [`crate::SparkScanWsConfig::with_dispatch_queue_size`].
+    pub fn with_dispatch_queue_size(mut self, size: usize) -> Self {
+        self.dispatch.queue_size = size;
+        self
+    }
+
+    /// Set the number of messages processed concurrently by each message callback.
+    ///
+    /// See [`crate::SparkScanWsConfig::with_handler_concurrency`].
+    pub fn with_handler_concurrency(mut self, concurrency: usize) -> Self {
+        self.dispatch.concurrency = concurrency;
+        self
+    }
+
+    /// Set the ordering of messages processed concurrently by each message callback.
+    ///
+    /// See [`crate::SparkScanWsConfig::with_handler_ordering`].
+    pub fn with_handler_ordering(mut self, ordering: HandlerOrdering) -> Self {
+        self.dispatch.ordering = ordering;
+        self
+    }
+
+    /// Set the limits publications must stay within to be parsed.
+    ///
+    /// See [`crate::SparkScanWsConfig::with_payload_limits`].
+    pub fn with_payload_limits(mut self, limits: PayloadLimits) -> Self {
+        self.dispatch.limits = limits;
+        self
+    }
+
+    /// Set the importance of the messages of this subscription while the client is overloaded.
+    ///
+    /// Has no effect without [load shedding](Self::with_load_shedding).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::*;
+    /// # async fn example() -> Result<()> {
+    /// let config = SparkScanWsConfig::new("ws://updates.sparkscan.io/").with_load_shedding(4096);
+    /// let client = SparkScanWsClient::with_config(config);
+    ///
+    /// let prices = client
+    ///     .subscribe(Topic::TokenPrices)
+    ///     .await?
+    ///     .with_priority(Priority::Low);
+    /// let treasury = client
+    ///     .subscribe(Topic::BalanceAddress("sp1abc123...".to_string()))
+    ///     .await?
+    ///     .with_priority(Priority::High);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_priority(self, priority: Priority) -> Self {
+        self.callbacks().admission.priority = priority;
+        self
+    }
+
+    /// Shed the messages of this subscription while `threshold` messages are pending over the
+    /// client.
+    ///
+    /// See [`crate::SparkScanWsConfig::with_load_shedding`].
+    pub fn with_load_shedding(self, threshold: usize) -> Self {
+        self.callbacks().admission.load_shedding = Some(threshold);
+        self
+    }
+
+    /// Fail [`subscribe_and_wait`](Self::subscribe_and_wait) if the server does not
+    /// acknowledge the subscription within `timeout`.
+    ///
+    /// See [`crate::SparkScanWsConfig::with_subscribe_timeout`].
+    pub fn with_subscribe_timeout(mut self, timeout: Duration) -> Self {
+        self.subscribe_timeout = Some(timeout);
+        self
+    }
+
+    /// Keep the latest message of up to `capacity` addresses or tokens, for
+    /// [`latest_for`](Self::latest_for).
+    ///
+    /// Registers a message callback, alongside any other message callback, storing each
+    /// message under its [ordering key](SparkScanMessage::ordering_key), evicting the least
+    /// recently used key once `capacity` keys are cached. With a handler concurrency above one, use
+    /// [`HandlerOrdering::KeyedOrdering`] so that an older message cannot replace a newer one.
+    pub fn with_state_cache(mut self, capacity: usize) -> Self {
+        let cache = Arc::new(StateCache::new(capacity));
+        let sink = Arc::clone(&cache);
+        self.on_message(move |message| sink.insert(message));
+        self.state_cache = Some(cache);
+        self
+    }
+
+    /// Get the latest message for an address or token identifier.
+    ///
+    /// Returns `None` if no message was received for `key`, or if the state cache is not
+    /// enabled with [`with_state_cache`](Self::with_state_cache).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::*;
+    /// # async fn example() -> Result<()> {
+    /// # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let subscription = client.subscribe(Topic::TokenPrices).await?.with_state_cache(1000);
+    /// subscription.subscribe();
+    ///
+    /// // Later, from a request handler
+    /// let token = "btkn1daywtenlww42njymqzyegvcwuy3p9f26zknme0srxa7tagewvuys86h553";
+    /// if let Some(SparkScanMessage::TokenPrice(price)) = subscription.latest_for(token) {
+    ///     println!("Price: {} sats", price.price_sats.as_str());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn latest_for(&self, key: &str) -> Option<SparkScanMessage> {
+        self.state_cache.as_ref()?.get(key)
     }
 
     /// Get the topic for this subscription.
@@ -33,6 +425,9 @@ impl SparkScanSubscription {
 
     /// Register callback for subscription establishment.
     ///
+    /// Called on every establishment, resubscriptions included, alongside the other callbacks
+    /// registered and those of [`subscribe_and_wait`](Self::subscribe_and_wait).
+    ///
     /// # Example
     /// ```rust,no_run
     /// # use sparkscan_ws::*;
@@ -50,7 +445,7 @@ impl SparkScanSubscription {
     where
         F: Fn() + Send + Sync + 'static,
     {
-        self.inner.on_subscribed(callback);
+        self.callbacks().subscribed.push(Arc::new(callback));
     }
 
     /// Register callback for subscription termination.
@@ -74,6 +469,17 @@ impl SparkScanSubscription {
     /// Primary method for processing incoming messages. Callback receives
     /// parsed SparkScanMessage enum with topic-appropriate payload.
     ///
+    /// Callbacks add up: every callback registered, including those of
+    /// [`broadcast`](Self::broadcast), [`latest`](Self::latest) and the other message handlers,
+    /// receives every message.
+    /// Messages are parsed and the callback invoked on a dedicated thread, in order, or on
+    /// several threads with a [handler concurrency](Self::with_handler_concurrency) above one,
+    /// optionally [in order per key](Self::with_handler_ordering).
+    /// Messages arriving while the callback is behind by the configured dispatch queue size are
+    /// dropped and counted by [`dropped_messages`](Self::dropped_messages).
+    /// With the `tracing` feature, the callback runs in the `sparkscan_message` span of the
+    /// message, so that its own spans join the trace of the message.
+    ///
     /// # Example
     /// ```rust,no_run
     /// # use sparkscan_ws::*;
@@ -99,25 +505,167 @@ impl SparkScanSubscription {
         F: Fn(SparkScanMessage) + Send + Sync + 'static,
     {
         let topic = self.topic.clone();
-        let callback = Arc::new(callback);
-
-        self.inner.on_publication(move |data| {
-            let topic = topic.clone();
-            let callback = callback.clone();
+        let dropped_messages = Arc::clone(&self.dropped_messages);
+        let clock_skew = self.clock_skew.clone();
+        let callback = Arc::new(move |message: SparkScanMessage| {
+            if let Some(clock_skew) = &clock_skew {
+                clock_skew.observe(&message);
+            }
+            callback(message);
+        });
 
-            match parse_message_for_topic(&topic, &data.data) {
-                Ok(message) => {
-                    callback(message);
+        let handler: PublicationHandler = match Dispatcher::spawn(
+            topic,
+            self.dispatch,
+            dropped_messages,
+            Arc::clone(&callback),
+        ) {
+            Ok(dispatcher) => {
+                if let Some(lifecycle) = &self.lifecycle {
+                    lifecycle.track_queue(dispatcher.pending());
                 }
-                Err(e) => {
-                    #[cfg(feature = "tracing")]
-                    tracing::error!("Failed to parse message for topic {:?}: {}", topic, e);
+                Arc::new(move |publication: &Publication| dispatcher.push(publication.clone()))
+            }
+            Err(e) => {
+                logging::log(
+                    LogCategory::Dispatch,
+                    Level::Error,
+                    format_args!(
+                        "Failed to start the dispatch thread, dispatching inline: {}",
+                        e
+                    ),
+                );
 
-                    #[cfg(not(feature = "tracing"))]
-                    log::error!("Failed to parse message for topic {:?}: {}", topic, e);
-                }
+                let topic = self.topic.clone();
+                let limits = self.dispatch.limits;
+                Arc::new(move |publication: &Publication| {
+                    dispatch::dispatch(&topic, &limits, publication, &*callback);
+                })
             }
+        };
+        self.callbacks().messages.push(handler);
+    }
+
+    /// Register callback for the messages passing `filter`.
+    ///
+    /// Messages are filtered on the dispatch thread, before reaching `callback`. Each filtered
+    /// callback is registered alongside the other message callbacks, so that one call per
+    /// filter routes the messages to several callbacks.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::*;
+    /// # async fn example() -> Result<()> {
+    /// # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let subscription = client.subscribe(Topic::Transactions).await?;
+    ///
+    /// let large = Filter::min_amount(Sats(1_000_000));
+    /// subscription.on_message_filtered(large, |message| println!("{}", message));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_message_filtered<F>(&self, filter: Filter, callback: F)
+    where
+        F: Fn(SparkScanMessage) + Send + Sync + 'static,
+    {
+        self.on_message(filter.apply(callback));
+    }
+
+    /// Deliver the messages of this subscription to `acked`, until acknowledged.
+    ///
+    /// Other message callbacks, registered before or after, do not interrupt the deliveries.
+    /// See [`AckedSubscription`] for an example.
+    pub fn on_message_acked(&self, acked: &AckedSubscription) {
+        let acked = acked.clone();
+        self.on_message(move |message| acked.deliver(message));
+    }
+
+    /// Fan the messages of this subscription out to any number of consumers.
+    ///
+    /// Registers a message callback forwarding every message to a broadcast channel holding
+    /// the last `capacity` messages, alongside any other message callback; each call to
+    /// [`MessageBroadcast::subscribe`] creates an independent receiver, so several components
+    /// can share one subscription. Receivers lagging behind by more than `capacity` messages
+    /// skip the oldest ones, as reported by [`broadcast::error::RecvError::Lagged`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::*;
+    /// # async fn example() -> Result<()> {
+    /// # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let subscription = client.subscribe(Topic::Balances).await?;
+    /// let broadcast = subscription.broadcast(256);
+    ///
+    /// let mut audit = broadcast.subscribe();
+    /// tokio::spawn(async move {
+    ///     while let Ok(message) = audit.recv().await {
+    ///         println!("audit: {:?}", message);
+    ///     }
+    /// });
+    ///
+    /// let mut balances = broadcast.subscribe();
+    /// subscription.subscribe();
+    ///
+    /// while let Ok(message) = balances.recv().await {
+    ///     println!("{:?}", message);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn broadcast(&self, capacity: usize) -> MessageBroadcast {
+        let broadcast = MessageBroadcast::new(capacity);
+        let sender = broadcast.sender.clone();
+        self.on_message(move |message| {
+            // Sending only fails while nobody is listening
+            let _ = sender.send(message);
         });
+        broadcast
+    }
+
+    /// Track the latest message of this subscription.
+    ///
+    /// Registers a message callback storing every message in a watch channel, alongside any
+    /// other message callback, for topics where only the current state matters, such as the
+    /// price of a token or the balance of an address. The receiver holds `None` until the first
+    /// message arrives; consumers can sample it at any time or wait for
+    /// [changes](watch::Receiver::changed) without processing every intermediate update.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::*;
+    /// # async fn example() -> Result<()> {
+    /// # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let subscription = client.subscribe(Topic::TokenPrices).await?;
+    /// let mut latest = subscription.latest();
+    /// subscription.subscribe();
+    ///
+    /// while latest.changed().await.is_ok() {
+    ///     if let Some(SparkScanMessage::TokenPrice(price)) = &*latest.borrow_and_update() {
+    ///         println!("Price: {} sats", price.price_sats.as_str());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn latest(&self) -> watch::Receiver<Option<SparkScanMessage>> {
+        let (sender, receiver) = watch::channel(None);
+        self.on_message(move |message| {
+            // Keep the value current even while no receiver is left
+            sender.send_replace(Some(message));
+        });
+        receiver
+    }
+
+    /// Get the number of messages dropped because a message callback fell behind.
+    pub fn dropped_messages(&self) -> u64 {
+        self.dropped_messages.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of messages shed because the client was overloaded.
+    ///
+    /// See [`with_priority`](Self::with_priority).
+    pub fn shed_messages(&self) -> u64 {
+        self.shed_messages.load(Ordering::Relaxed)
     }
 
     /// Register callback for raw message data.
@@ -127,19 +675,62 @@ impl SparkScanSubscription {
     where
         F: Fn(&[u8]) + Send + Sync + 'static,
     {
-        self.inner.on_publication(move |data| {
-            callback(&data.data);
-        });
+        self.callbacks()
+            .raw
+            .push(Arc::new(move |publication: &Publication| {
+                callback(&publication.data);
+            }));
     }
 
     /// Register callback for subscription errors.
+    ///
+    /// Callbacks add up with those of [`on_subscribe_error`](Self::on_subscribe_error), every
+    /// one of them being called for each error. Also called when the server does not acknowledge
+    /// [`subscribe_and_wait`](Self::subscribe_and_wait) in time.
     pub fn on_error<F>(&self, callback: F)
     where
         F: Fn(String) + Send + Sync + 'static,
     {
-        self.inner.on_error(move |err| {
-            callback(format!("{:?}", err));
-        });
+        self.callbacks()
+            .errors
+            .push(Arc::new(move |description, _| {
+                callback(description.to_string())
+            }));
+    }
+
+    /// Register callback for classified subscription errors.
+    ///
+    /// Like [`on_error`](Self::on_error), with the error mapped to a [`SubscribeError`] from
+    /// the server error code, so that fatal failures such as a denied permission can be told
+    /// apart from [retryable](SubscribeError::is_retryable) ones.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::*;
+    /// # async fn example() -> Result<()> {
+    /// # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let subscription = client.subscribe(Topic::Balances).await?;
+    ///
+    /// subscription.on_subscribe_error(|error| {
+    ///     if !error.is_retryable() {
+    ///         eprintln!("Subscription failed for good: {}", error);
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_subscribe_error<F>(&self, callback: F)
+    where
+        F: Fn(SubscribeError) + Send + Sync + 'static,
+    {
+        self.callbacks()
+            .errors
+            .push(Arc::new(move |_, error| callback(error.clone())));
+    }
+
+    /// Call the error callbacks with an error raised by this crate rather than the server.
+    fn emit_error(&self, error: SubscribeError) {
+        report_error(&self.callbacks, &error.to_string(), &error);
     }
 
     /// Activate subscription to begin receiving messages.
@@ -149,6 +740,71 @@ impl SparkScanSubscription {
         self.inner.subscribe();
     }
 
+    /// Activate subscription and wait for the server to acknowledge it.
+    ///
+    /// With a [subscribe timeout](Self::with_subscribe_timeout), fails with
+    /// [`SubscribeError::Timeout`] if no acknowledgement arrives in time, which is also passed
+    /// to the [error callbacks](Self::on_subscribe_error). The subscription stays active and
+    /// may still be acknowledged later; call [`unsubscribe`](Self::unsubscribe) to give up on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a subscription error if the server rejects the subscription or does not
+    /// acknowledge it in time.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::*;
+    /// # use std::time::Duration;
+    /// # async fn example() -> Result<()> {
+    /// # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let subscription = client
+    ///     .subscribe(Topic::Balances)
+    ///     .await?
+    ///     .with_subscribe_timeout(Duration::from_secs(10));
+    ///
+    /// subscription.on_message(|message| println!("{:?}", message));
+    /// subscription.subscribe_and_wait().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_and_wait(&self) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let pending: Pending = Arc::new(Mutex::new(Some(sender)));
+        // Settled by the callbacks of the subscription, leaving those of the caller in place
+        self.callbacks().pending.push(Arc::clone(&pending));
+        self.subscribe();
+
+        let outcome = match self.subscribe_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, receiver).await {
+                Ok(outcome) => outcome,
+                Err(_) => {
+                    // Later acknowledgements are ignored, as the caller got an answer
+                    pending
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .take();
+                    let error = SubscribeError::Timeout(timeout);
+                    logging::log(
+                        LogCategory::Connection,
+                        Level::Warn,
+                        format_args!("Subscription to {}: {}", self.topic.as_str(), error),
+                    );
+                    self.emit_error(error.clone());
+                    return Err(SparkScanWsError::subscription(error.to_string()));
+                }
+            },
+            None => receiver.await,
+        };
+        match outcome {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(error)) => Err(SparkScanWsError::subscription(error.to_string())),
+            Err(_) => Err(SparkScanWsError::subscription(
+                "the subscription was closed before being acknowledged",
+            )),
+        }
+    }
+
     /// Deactivate subscription.
     pub fn unsubscribe(&self) {
         self.inner.unsubscribe();
@@ -179,6 +835,52 @@ impl SparkScanSubscription {
     }
 }
 
+/// Settle a pending subscription request, unless already settled.
+fn settle(pending: &Pending, outcome: std::result::Result<(), SubscribeError>) {
+    if let Some(sender) = pending
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+    {
+        // The caller may have stopped waiting
+        let _ = sender.send(outcome);
+    }
+}
+
+/// Broadcast channel fed by a subscription, created by [`SparkScanSubscription::broadcast`].
+///
+/// Cloning the handle shares the same channel.
+#[derive(Debug, Clone)]
+pub struct MessageBroadcast {
+    sender: broadcast::Sender<SparkScanMessage>,
+}
+
+impl MessageBroadcast {
+    fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Create a receiver for the messages published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<SparkScanMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Get the number of receivers currently listening.
+    pub fn receiver_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+/// Topics changed by [`SubscriptionManager::reconcile`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Topics subscribed
+    pub added: Vec<Topic>,
+    /// Topics unsubscribed
+    pub removed: Vec<Topic>,
+}
+
 /// Subscription collection manager.
 ///
 /// Manages multiple subscriptions with bulk operation support.
@@ -230,6 +932,77 @@ impl SubscriptionManager {
         }
     }
 
+    /// Subscribe and unsubscribe so that the managed topics match `desired`.
+    ///
+    /// Subscriptions to topics missing from `desired` are deactivated and removed. Subscriptions
+    /// for new topics are created with `client`, passed to `setup` to register their callbacks,
+    /// then activated. Topics both managed and desired are left untouched, so services can
+    /// reload their watch lists without interrupting unchanged subscriptions.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error creating a subscription. Changes made before it are kept.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sparkscan_ws::*;
+    /// # use std::collections::HashSet;
+    /// # async fn example() -> Result<()> {
+    /// # let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+    /// let mut manager = SubscriptionManager::new();
+    /// let token = "btkn1daywtenlww42njymqzyegvcwuy3p9f26zknme0srxa7tagewvuys86h553";
+    /// let desired = HashSet::from([Topic::TokenPriceIdentifier(token.to_string())]);
+    ///
+    /// let changes = manager
+    ///     .reconcile(&client, desired, |subscription| {
+    ///         subscription.on_message(|message| println!("{:?}", message));
+    ///     })
+    ///     .await?;
+    /// println!("{} added, {} removed", changes.added.len(), changes.removed.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn reconcile<F>(
+        &mut self,
+        client: &crate::SparkScanWsClient,
+        desired: HashSet<Topic>,
+        mut setup: F,
+    ) -> Result<Reconciliation>
+    where
+        F: FnMut(&SparkScanSubscription),
+    {
+        let desired: HashMap<String, Topic> = desired
+            .into_iter()
+            .map(|topic| (topic.as_str(), topic))
+            .collect();
+
+        let stale: Vec<String> = self
+            .subscriptions
+            .keys()
+            .filter(|topic| !desired.contains_key(*topic))
+            .cloned()
+            .collect();
+        let mut changes = Reconciliation::default();
+        for topic in stale {
+            if let Some(subscription) = self.subscriptions.remove(&topic) {
+                subscription.unsubscribe();
+                changes.removed.push(subscription.topic.clone());
+            }
+        }
+
+        for (topic_str, topic) in desired {
+            if self.subscriptions.contains_key(&topic_str) {
+                continue;
+            }
+            let subscription = client.subscribe(topic.clone()).await?;
+            setup(&subscription);
+            subscription.subscribe();
+            self.subscriptions.insert(topic_str, subscription);
+            changes.added.push(topic);
+        }
+        Ok(changes)
+    }
+
     /// Get count of managed subscriptions.
     pub fn len(&self) -> usize {
         self.subscriptions.len()
@@ -241,9 +1014,272 @@ impl SubscriptionManager {
     }
 }
 
+/// Per-address topic subscribed by [`SparkScanWsClient::subscribe_addresses`].
+///
+/// [`SparkScanWsClient::subscribe_addresses`]: crate::SparkScanWsClient::subscribe_addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressTopicKind {
+    /// Balance updates of the address
+    Balance,
+    /// Token balance updates of the address
+    TokenBalance,
+    /// Transactions received by the address
+    TransactionsIn,
+    /// Transactions sent by the address
+    TransactionsOut,
+}
+
+impl AddressTopicKind {
+    /// Every per-address topic.
+    pub const ALL: &'static [AddressTopicKind] = &[
+        AddressTopicKind::Balance,
+        AddressTopicKind::TokenBalance,
+        AddressTopicKind::TransactionsIn,
+        AddressTopicKind::TransactionsOut,
+    ];
+
+    /// Get the topic of this kind for `address` on `network`.
+    pub fn topic(self, network: Network, address: &SparkAddress) -> Topic {
+        let address = address.to_string();
+        let network = network.as_str().to_lowercase();
+        match self {
+            AddressTopicKind::Balance => Topic::BalanceAddress(address),
+            AddressTopicKind::TokenBalance => Topic::TokenBalanceAddress(address),
+            AddressTopicKind::TransactionsIn => Topic::TransactionIn(network, address),
+            AddressTopicKind::TransactionsOut => Topic::TransactionOut(network, address),
+        }
+    }
+}
+
+/// Per-network topic partitioned by [`SparkScanWsClient::partitions_by_network`].
+///
+/// [`SparkScanWsClient::partitions_by_network`]: crate::SparkScanWsClient::partitions_by_network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NetworkTopicKind {
+    /// Balance updates of the network
+    Balances,
+    /// Token balance updates of the network
+    TokenBalances,
+    /// Token price updates of the network
+    TokenPrices,
+    /// Transactions of the network
+    Transactions,
+    /// Token information updates of the network
+    Tokens,
+}
+
+impl NetworkTopicKind {
+    /// Get the topic of this kind for `network`.
+    pub fn topic(self, network: Network) -> Topic {
+        let network = network.as_str().to_lowercase();
+        match self {
+            NetworkTopicKind::Balances => Topic::BalanceNetwork(network),
+            NetworkTopicKind::TokenBalances => Topic::TokenBalanceNetwork(network),
+            NetworkTopicKind::TokenPrices => Topic::TokenPriceNetwork(network),
+            NetworkTopicKind::Transactions => Topic::TransactionNetwork(network),
+            NetworkTopicKind::Tokens => Topic::TokenNetwork(network),
+        }
+    }
+}
+
+type MessageCallback = Arc<dyn Fn(SparkScanMessage) + Send + Sync>;
+
+/// Merged subscription to the per-address topics of a set of addresses.
+///
+/// Created by [`SparkScanWsClient::subscribe_addresses`]. Messages of every address and topic
+/// are delivered to the same callbacks, and addresses can be added and removed while the
+/// subscription is active.
+///
+/// [`SparkScanWsClient::subscribe_addresses`]: crate::SparkScanWsClient::subscribe_addresses
+pub struct AddressSubscription {
+    client: crate::SparkScanWsClient,
+    network: Network,
+    kinds: Vec<AddressTopicKind>,
+    /// Subscriptions of each address, one per topic kind
+    subscriptions: Mutex<HashMap<SparkAddress, Vec<SparkScanSubscription>>>,
+    /// Message callbacks shared by all subscriptions
+    callbacks: Arc<Mutex<Vec<MessageCallback>>>,
+    /// Whether the topics are subscribed
+    active: AtomicBool,
+}
+
+impl AddressSubscription {
+    pub(crate) fn new(
+        client: crate::SparkScanWsClient,
+        network: Network,
+        kinds: &[AddressTopicKind],
+    ) -> Self {
+        Self {
+            client,
+            network,
+            kinds: kinds.to_vec(),
+            subscriptions: Mutex::new(HashMap::new()),
+            callbacks: Arc::new(Mutex::new(Vec::new())),
+            active: AtomicBool::new(false),
+        }
+    }
+
+    fn subscriptions(
+        &self,
+    ) -> std::sync::MutexGuard<'_, HashMap<SparkAddress, Vec<SparkScanSubscription>>> {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Register callback for the messages of all addresses and topics.
+    pub fn on_message<F>(&self, callback: F)
+    where
+        F: Fn(SparkScanMessage) + Send + Sync + 'static,
+    {
+        self.callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::new(callback));
+    }
+
+    /// Register callback for the messages of all addresses and topics passing `filter`.
+    pub fn on_message_filtered<F>(&self, filter: Filter, callback: F)
+    where
+        F: Fn(SparkScanMessage) + Send + Sync + 'static,
+    {
+        self.on_message(filter.apply(callback));
+    }
+
+    /// Add the topics of `address`, subscribing them right away if the subscription is active.
+    ///
+    /// Adding an address twice has no effect.
+    pub async fn add_address(&self, address: SparkAddress) -> Result<()> {
+        if self.subscriptions().contains_key(&address) {
+            return Ok(());
+        }
+
+        let mut subscriptions = Vec::with_capacity(self.kinds.len());
+        for kind in &self.kinds {
+            let subscription = self
+                .client
+                .subscribe(kind.topic(self.network, &address))
+                .await?;
+            let callbacks = Arc::clone(&self.callbacks);
+            subscription.on_message(move |message| {
+                let callbacks = callbacks
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone();
+                for callback in callbacks {
+                    callback(message.clone());
+                }
+            });
+            subscriptions.push(subscription);
+        }
+
+        let mut current = self.subscriptions();
+        if current.contains_key(&address) {
+            // Added concurrently while the topics were created
+            return Ok(());
+        }
+        if self.active.load(Ordering::Relaxed) {
+            for subscription in &subscriptions {
+                subscription.subscribe();
+            }
+        }
+        current.insert(address, subscriptions);
+        Ok(())
+    }
+
+    /// Remove the topics of `address`, returning whether it was subscribed.
+    pub fn remove_address(&self, address: &SparkAddress) -> bool {
+        let Some(subscriptions) = self.subscriptions().remove(address) else {
+            return false;
+        };
+        for subscription in &subscriptions {
+            subscription.unsubscribe();
+        }
+        true
+    }
+
+    /// Get the subscribed addresses.
+    pub fn addresses(&self) -> Vec<SparkAddress> {
+        self.subscriptions().keys().cloned().collect()
+    }
+
+    /// Activate the topics of every address, including the ones added later.
+    pub fn subscribe(&self) {
+        let subscriptions = self.subscriptions();
+        self.active.store(true, Ordering::Relaxed);
+        for subscription in subscriptions.values().flatten() {
+            subscription.subscribe();
+        }
+    }
+
+    /// Deactivate the topics of every address.
+    pub fn unsubscribe(&self) {
+        let subscriptions = self.subscriptions();
+        self.active.store(false, Ordering::Relaxed);
+        for subscription in subscriptions.values().flatten() {
+            subscription.unsubscribe();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::mpsc::{channel, Receiver};
+
+    const BALANCE: &str = r#"{
+        "address": "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s",
+        "network": "MAINNET",
+        "soft_balance": "100",
+        "hard_balance": "90",
+        "processed_at": "2025-08-06T16:28:42.955000Z"
+    }"#;
+
+    async fn balances() -> SparkScanSubscription {
+        let client = crate::SparkScanWsClient::new("ws://sparkscan.io/");
+        client.subscribe(Topic::Balances).await.unwrap()
+    }
+
+    /// Pass a balance update to `subscription`, as the centrifuge subscription does.
+    fn publish(subscription: &SparkScanSubscription) {
+        let publication = Publication {
+            data: BALANCE.as_bytes().to_vec(),
+            ..Publication::default()
+        };
+        route(
+            &subscription.callbacks,
+            &subscription.topic.as_str(),
+            &subscription.shed_messages,
+            publication,
+        );
+    }
+
+    /// Register a message callback reporting the message types to the returned receiver.
+    fn message_types(subscription: &SparkScanSubscription) -> Receiver<&'static str> {
+        let (sender, receiver) = channel();
+        subscription.on_message(move |message| {
+            let _ = sender.send(message.message_type());
+        });
+        receiver
+    }
+
+    fn recv<T>(receiver: &Receiver<T>) -> T {
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_with_other_callbacks() {
+        let subscription = balances().await;
+        let before = message_types(&subscription);
+        let broadcast = subscription.broadcast(16);
+        let mut receiver = broadcast.subscribe();
+        let after = message_types(&subscription);
+
+        publish(&subscription);
+        assert_eq!(receiver.recv().await.unwrap().message_type(), "balance");
+        assert_eq!(recv(&before), "balance");
+        assert_eq!(recv(&after), "balance");
+    }
 
     #[test]
     fn test_subscription_manager() {
@@ -256,6 +1292,203 @@ mod tests {
         // be better for testing the full subscription functionality.
     }
 
+    #[tokio::test]
+    async fn test_reconcile() {
+        let client = crate::SparkScanWsClient::new("ws://sparkscan.io/");
+        let mut manager = SubscriptionManager::new();
+        let mut created = 0;
+
+        let changes = manager
+            .reconcile(
+                &client,
+                HashSet::from([Topic::Balances, Topic::Tokens]),
+                |_| created += 1,
+            )
+            .await
+            .unwrap();
+        assert_eq!(changes.added.len(), 2);
+        assert!(changes.removed.is_empty());
+
+        let changes = manager
+            .reconcile(
+                &client,
+                HashSet::from([Topic::Tokens, Topic::Transactions]),
+                |_| created += 1,
+            )
+            .await
+            .unwrap();
+        assert_eq!(changes.added, vec![Topic::Transactions]);
+        assert_eq!(changes.removed, vec![Topic::Balances]);
+        assert_eq!(created, 3);
+        assert_eq!(manager.len(), 2);
+        assert!(manager.get("tokens").is_some());
+        assert!(manager.get("balances").is_none());
+    }
+
+    #[test]
+    fn test_address_topic_kind() {
+        let address: SparkAddress =
+            "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            AddressTopicKind::Balance
+                .topic(Network::Mainnet, &address)
+                .as_str(),
+            format!("/balance/address/{}", address)
+        );
+        assert_eq!(
+            AddressTopicKind::TransactionsIn
+                .topic(Network::Regtest, &address)
+                .as_str(),
+            format!("/transaction/in/regtest/{}", address)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_message_broadcast() {
+        let broadcast = MessageBroadcast::new(0);
+        let mut first = broadcast.subscribe();
+        let mut second = broadcast.clone().subscribe();
+        assert_eq!(broadcast.receiver_count(), 2);
+
+        let message = crate::types::parse_message_for_topic(
+            &Topic::Balances,
+            br#"{
+                "address": "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s",
+                "network": "MAINNET",
+                "soft_balance": "100",
+                "hard_balance": "90",
+                "processed_at": "2025-08-06T16:28:42.955000Z"
+            }"#,
+        )
+        .unwrap();
+        broadcast.sender.send(message).unwrap();
+
+        assert_eq!(first.recv().await.unwrap().message_type(), "balance");
+        assert_eq!(second.recv().await.unwrap().message_type(), "balance");
+    }
+
+    #[tokio::test]
+    async fn test_latest_with_other_callbacks() {
+        let subscription = balances().await;
+        let mut latest = subscription.latest();
+        let types = message_types(&subscription);
+
+        publish(&subscription);
+        assert_eq!(recv(&types), "balance");
+        tokio::time::timeout(Duration::from_secs(5), latest.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        let message = latest.borrow_and_update().clone();
+        assert_eq!(message.unwrap().message_type(), "balance");
+    }
+
+    #[tokio::test]
+    async fn test_state_cache_with_other_callbacks() {
+        let subscription = balances().await.with_state_cache(16);
+        let types = message_types(&subscription);
+
+        publish(&subscription);
+        assert_eq!(recv(&types), "balance");
+        let address = "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s";
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while subscription.latest_for(address).is_none() {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "the message was not cached"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_callbacks_add_up() {
+        let subscription = balances().await;
+        let (sender, errors) = channel();
+        let described = sender.clone();
+        subscription.on_error(move |error| described.send(error).unwrap());
+        let classified = sender.clone();
+        subscription.on_subscribe_error(move |error| classified.send(error.to_string()).unwrap());
+        subscription.on_error(move |error| sender.send(error).unwrap());
+
+        let description = r#"Error { code: 103, message: "permission denied" }"#;
+        report_error(
+            &subscription.callbacks,
+            description,
+            &SubscribeError::parse(description),
+        );
+        assert_eq!(
+            errors.try_iter().collect::<Vec<_>>(),
+            [description, "permission denied", description]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filtered_callbacks_add_up() {
+        let subscription = balances().await;
+        let (sender, matched) = channel();
+        for (name, filter) in [
+            ("balance", Filter::message_type("balance")),
+            ("mainnet", Filter::network(Network::Mainnet)),
+            ("token", Filter::message_type("token")),
+        ] {
+            let sender = sender.clone();
+            subscription.on_message_filtered(filter, move |_| sender.send(name).unwrap());
+        }
+        drop(sender);
+
+        publish(&subscription);
+        let mut names = vec![recv(&matched), recv(&matched)];
+        names.sort_unstable();
+        assert_eq!(names, ["balance", "mainnet"]);
+        assert!(matched.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_acked_with_other_callbacks() {
+        let subscription = balances().await;
+        let (sender, deliveries) = channel();
+        let acked = AckedSubscription::new(move |delivery| {
+            sender.send(delivery.message().message_type()).unwrap();
+            delivery.ack();
+        });
+        subscription.on_message_acked(&acked);
+        let types = message_types(&subscription);
+
+        publish(&subscription);
+        assert_eq!(recv(&types), "balance");
+        assert_eq!(recv(&deliveries), "balance");
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_and_wait_keeps_callbacks() {
+        let subscription = Arc::new(balances().await);
+        let (sender, events) = channel();
+        let on_subscribed = sender.clone();
+        subscription.on_subscribed(move || on_subscribed.send("subscribed".to_string()).unwrap());
+        subscription.on_error(move |error| sender.send(error).unwrap());
+
+        let waiting = tokio::spawn({
+            let subscription = Arc::clone(&subscription);
+            async move { subscription.subscribe_and_wait().await }
+        });
+        while subscription.callbacks().pending.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let denied = r#"Error { code: 103, message: "permission denied" }"#;
+        failed(&subscription.callbacks, denied);
+        assert!(waiting.await.unwrap().is_err());
+        assert_eq!(recv(&events), denied);
+
+        // Later errors and resubscriptions still reach the callbacks of the caller
+        failed(&subscription.callbacks, "connection reset");
+        subscribed(&subscription.callbacks);
+        assert_eq!(recv(&events), "connection reset");
+        assert_eq!(recv(&events), "subscribed");
+    }
+
     #[test]
     fn test_topic_conversion() {
         let topic = Topic::Balances;
@@ -0,0 +1,403 @@
+//! Connections of several tenants, each authenticated with its own API key.
+//!
+//! A [`TenantManager`] keeps one client per tenant, connected with the API key of the tenant,
+//! and routes the messages of each subscription to the handlers of the tenant that made it.
+//! Products serving many customers from SparkScan can therefore keep the usage of each customer
+//! on their own key:
+//!
+//! ```rust,no_run
+//! # use sparkscan_ws::{tenants::TenantManager, SparkScanWsConfig, Topic};
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let manager = TenantManager::new(SparkScanWsConfig::new("ws://updates.sparkscan.io/"))
+//!     .with_deduplication(true);
+//! manager.add_tenant("acme", "acme-api-key").await?;
+//! manager.add_tenant("globex", "globex-api-key").await?;
+//!
+//! for tenant in ["acme", "globex"] {
+//!     let prices = manager.subscribe(tenant, Topic::TokenPrices).await?;
+//!     prices.on_message(move |message| println!("{}: {}", tenant, message));
+//!     prices.subscribe();
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With deduplication, tenants subscribing to the same topic share a single subscription, made
+//! on the client of the first of them: the server sends each publication once, and the manager
+//! hands it to every subscribed tenant. This shares the data received with the key of one
+//! tenant with the others, so it is disabled by default and should only be enabled when every
+//! tenant is allowed to see the topics of the others.
+
+use crate::client::{SparkScanWsClient, SparkScanWsConfig};
+use crate::error::{Result, SparkScanWsError};
+use crate::subscription::SparkScanSubscription;
+use crate::types::{SparkScanMessage, Topic};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+type MessageCallback = Arc<dyn Fn(SparkScanMessage) + Send + Sync>;
+
+/// Handlers of a tenant on a channel.
+#[derive(Default)]
+struct TenantHandlers {
+    /// Whether the tenant activated the subscription
+    active: bool,
+    callbacks: Vec<MessageCallback>,
+}
+
+type Handlers = Arc<Mutex<HashMap<String, TenantHandlers>>>;
+
+/// Subscription to a topic, shared by one or more tenants.
+struct Channel {
+    topic: Topic,
+    /// Tenant whose client holds the subscription, and the subscription
+    owner: Mutex<(String, SparkScanSubscription)>,
+    /// Handlers of every tenant subscribed to the topic
+    tenants: Handlers,
+}
+
+impl Channel {
+    fn owner(&self) -> MutexGuard<'_, (String, SparkScanSubscription)> {
+        self.owner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn tenants(&self) -> MutexGuard<'_, HashMap<String, TenantHandlers>> {
+        self.tenants.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether any tenant activated the subscription.
+    fn active(&self) -> bool {
+        self.tenants().values().any(|handlers| handlers.active)
+    }
+}
+
+/// Channel key: the channel of the topic, along with the tenant when not deduplicated.
+type ChannelKey = (Option<String>, String);
+
+/// Clients of several tenants, with the subscriptions of each routed back to it.
+///
+/// See the [module documentation](self) for an example.
+pub struct TenantManager {
+    config: SparkScanWsConfig,
+    deduplication: bool,
+    clients: Mutex<HashMap<String, SparkScanWsClient>>,
+    channels: Mutex<HashMap<ChannelKey, Arc<Channel>>>,
+}
+
+impl TenantManager {
+    /// Create a manager connecting the clients of the tenants with `config`.
+    ///
+    /// The API key of each tenant replaces the [token](SparkScanWsConfig::with_token) of the
+    /// configuration.
+    pub fn new(config: SparkScanWsConfig) -> Self {
+        Self {
+            config,
+            deduplication: false,
+            clients: Mutex::new(HashMap::new()),
+            channels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set whether tenants subscribing to the same topic share a subscription (default: false).
+    pub fn with_deduplication(mut self, deduplication: bool) -> Self {
+        self.deduplication = deduplication;
+        self
+    }
+
+    /// Get the configuration of the client of a tenant authenticated with `api_key`.
+    fn tenant_config(&self, api_key: &str) -> SparkScanWsConfig {
+        self.config.clone().with_token(api_key)
+    }
+
+    fn clients(&self) -> MutexGuard<'_, HashMap<String, SparkScanWsClient>> {
+        self.clients.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn channels(&self) -> MutexGuard<'_, HashMap<ChannelKey, Arc<Channel>>> {
+        self.channels.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Connect a client for `tenant`, authenticated with `api_key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tenant was already added or the connection fails to start.
+    pub async fn add_tenant<T, K>(&self, tenant: T, api_key: K) -> Result<()>
+    where
+        T: Into<String>,
+        K: AsRef<str>,
+    {
+        let tenant = tenant.into();
+        if self.clients().contains_key(&tenant) {
+            return Err(SparkScanWsError::ConfigError(format!(
+                "tenant {} already added",
+                tenant
+            )));
+        }
+
+        let client = SparkScanWsClient::with_config(self.tenant_config(api_key.as_ref()));
+        client.connect().await?;
+
+        let mut clients = self.clients();
+        if clients.contains_key(&tenant) {
+            // Added concurrently while connecting
+            return Err(SparkScanWsError::ConfigError(format!(
+                "tenant {} already added",
+                tenant
+            )));
+        }
+        clients.insert(tenant, client);
+        Ok(())
+    }
+
+    /// Remove `tenant`, returning whether it was added.
+    ///
+    /// The subscriptions of the tenant are unsubscribed, except for the ones shared with other
+    /// tenants, which are moved to the client of one of them. The client of the tenant is
+    /// closed once dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a shared subscription could not be moved to another client. The
+    /// tenant is removed regardless.
+    pub async fn remove_tenant(&self, tenant: &str) -> Result<bool> {
+        if self.clients().remove(tenant).is_none() {
+            return Ok(false);
+        }
+
+        let mut orphaned = Vec::new();
+        self.channels().retain(|_, channel| {
+            let remaining = {
+                let mut tenants = channel.tenants();
+                tenants.remove(tenant);
+                tenants.keys().next().cloned()
+            };
+            let owner = channel.owner();
+            match remaining {
+                None => {
+                    owner.1.unsubscribe();
+                    false
+                }
+                Some(successor) => {
+                    if owner.0 == tenant {
+                        orphaned.push((successor, Arc::clone(channel)));
+                    }
+                    true
+                }
+            }
+        });
+
+        let mut result = Ok(true);
+        for (successor, channel) in orphaned {
+            if let Err(e) = self.move_channel(&successor, &channel).await {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    /// Move the subscription of `channel` to the client of `tenant`.
+    async fn move_channel(&self, tenant: &str, channel: &Arc<Channel>) -> Result<()> {
+        let subscription = self
+            .subscription(tenant, &channel.topic, &channel.tenants)
+            .await?;
+        if channel.active() {
+            subscription.subscribe();
+        }
+        let previous = std::mem::replace(&mut *channel.owner(), (tenant.to_string(), subscription));
+        previous.1.unsubscribe();
+        Ok(())
+    }
+
+    /// Create a subscription to `topic` on the client of `tenant`, routed to `tenants`.
+    async fn subscription(
+        &self,
+        tenant: &str,
+        topic: &Topic,
+        tenants: &Handlers,
+    ) -> Result<SparkScanSubscription> {
+        let client = self.client(tenant).ok_or_else(|| unknown_tenant(tenant))?;
+        let subscription = client.subscribe(topic.clone()).await?;
+        let tenants = Arc::clone(tenants);
+        subscription.on_message(move |message| dispatch(&tenants, message));
+        Ok(subscription)
+    }
+
+    /// Subscribe `tenant` to `topic`.
+    ///
+    /// The subscription must be activated using its `subscribe()` method. Subscribing a tenant
+    /// to the same topic again returns the same subscription.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tenant was not added or the subscription cannot be created.
+    pub async fn subscribe(&self, tenant: &str, topic: Topic) -> Result<TenantSubscription> {
+        let key = (
+            (!self.deduplication).then(|| tenant.to_string()),
+            topic.as_str(),
+        );
+        let existing = self.channels().get(&key).cloned();
+        let channel = match existing {
+            Some(channel) => channel,
+            None => {
+                let tenants = Arc::new(Mutex::new(HashMap::new()));
+                let subscription = self.subscription(tenant, &topic, &tenants).await?;
+                let channel = Arc::new(Channel {
+                    topic,
+                    owner: Mutex::new((tenant.to_string(), subscription)),
+                    tenants,
+                });
+                let stored = Arc::clone(
+                    self.channels()
+                        .entry(key)
+                        .or_insert_with(|| Arc::clone(&channel)),
+                );
+                if !Arc::ptr_eq(&stored, &channel) {
+                    // Created concurrently while subscribing; drop the duplicate subscription
+                    channel.owner().1.unsubscribe();
+                }
+                stored
+            }
+        };
+
+        channel.tenants().entry(tenant.to_string()).or_default();
+        Ok(TenantSubscription {
+            tenant: tenant.to_string(),
+            channel,
+        })
+    }
+
+    /// Get the client of `tenant`, e.g. to register connection callbacks.
+    pub fn client(&self, tenant: &str) -> Option<SparkScanWsClient> {
+        self.clients().get(tenant).cloned()
+    }
+
+    /// Get the added tenants.
+    pub fn tenants(&self) -> Vec<String> {
+        self.clients().keys().cloned().collect()
+    }
+
+    /// Get the number of subscriptions made to the server, shared ones counting once.
+    pub fn subscription_count(&self) -> usize {
+        self.channels().len()
+    }
+}
+
+/// Subscription of a tenant to a topic.
+///
+/// Created by [`TenantManager::subscribe`]. Messages are only delivered to the callbacks of
+/// the tenant, even when the subscription to the server is shared with other tenants.
+pub struct TenantSubscription {
+    tenant: String,
+    channel: Arc<Channel>,
+}
+
+impl TenantSubscription {
+    /// Get the tenant of this subscription.
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    /// Get the topic of this subscription.
+    pub fn topic(&self) -> &Topic {
+        &self.channel.topic
+    }
+
+    /// Register callback for the messages of the topic.
+    ///
+    /// Has no effect once the tenant was removed.
+    pub fn on_message<F>(&self, callback: F)
+    where
+        F: Fn(SparkScanMessage) + Send + Sync + 'static,
+    {
+        if let Some(handlers) = self.channel.tenants().get_mut(&self.tenant) {
+            handlers.callbacks.push(Arc::new(callback));
+        }
+    }
+
+    /// Activate subscription to begin receiving messages.
+    pub fn subscribe(&self) {
+        match self.channel.tenants().get_mut(&self.tenant) {
+            Some(handlers) => handlers.active = true,
+            None => return,
+        }
+        self.channel.owner().1.subscribe();
+    }
+
+    /// Deactivate subscription, unsubscribing from the server once no tenant is active.
+    pub fn unsubscribe(&self) {
+        if let Some(handlers) = self.channel.tenants().get_mut(&self.tenant) {
+            handlers.active = false;
+        }
+        if !self.channel.active() {
+            self.channel.owner().1.unsubscribe();
+        }
+    }
+}
+
+/// Hand `message` to the callbacks of the active tenants.
+fn dispatch(tenants: &Mutex<HashMap<String, TenantHandlers>>, message: SparkScanMessage) {
+    let callbacks: Vec<MessageCallback> = tenants
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .values()
+        .filter(|handlers| handlers.active)
+        .flat_map(|handlers| handlers.callbacks.iter().cloned())
+        .collect();
+    for callback in callbacks {
+        callback(message.clone());
+    }
+}
+
+fn unknown_tenant(tenant: &str) -> SparkScanWsError {
+    SparkScanWsError::ConfigError(format!("unknown tenant {}", tenant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_tenant_config() {
+        let manager = TenantManager::new(
+            SparkScanWsConfig::new("ws://localhost:8000/?region=eu").with_token("shared"),
+        );
+        let config = manager.tenant_config("a+b/c=");
+        assert_eq!(config.url, "ws://localhost:8000/?region=eu");
+        assert_eq!(config.token.as_deref(), Some("a+b/c="));
+    }
+
+    #[test]
+    fn test_dispatch_to_active_tenants() {
+        let tenants = Mutex::new(HashMap::new());
+        let counters: Vec<_> = (0..3).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        for (index, counter) in counters.iter().enumerate() {
+            let counter = Arc::clone(counter);
+            let callback: MessageCallback = Arc::new(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+            tenants.lock().unwrap().insert(
+                format!("tenant-{}", index),
+                TenantHandlers {
+                    active: index != 2,
+                    callbacks: vec![callback],
+                },
+            );
+        }
+
+        let fixture = crate::conformance::fixture("balance").unwrap();
+        let message =
+            crate::types::parse_message_for_topic(&fixture.topic(), fixture.payload.as_bytes())
+                .unwrap();
+        dispatch(&tenants, message.clone());
+        tenants.lock().unwrap().remove("tenant-0");
+        dispatch(&tenants, message);
+
+        let counts: Vec<_> = counters
+            .iter()
+            .map(|counter| counter.load(Ordering::Relaxed))
+            .collect();
+        assert_eq!(counts, [1, 2, 0]);
+    }
+}
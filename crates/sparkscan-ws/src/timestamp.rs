@@ -0,0 +1,138 @@
+//! Lenient parsing of the timestamps of the payloads.
+//!
+//! Timestamps are sent in RFC 3339, but some fields, such as the `expired_time` of transactions,
+//! have been seen without a timezone (`2025-08-06T16:32:39.091001`). The timestamp fields of the
+//! payload types are deserialized with [`parse`], which also accepts those as UTC, so that such
+//! payloads do not go through the transaction fallback or fail to parse.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use std::fmt;
+
+/// Formats with an offset that RFC 3339 does not allow, such as `+0000`.
+const OFFSET_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z"];
+
+/// Formats without a timezone.
+const NAIVE_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// Parse a timestamp of a payload.
+///
+/// Accepts RFC 3339 timestamps, offsets without a colon and a space instead of the `T`
+/// separator, with any number of fractional digits. Timestamps without a timezone are assumed
+/// to be UTC.
+///
+/// # Example
+/// ```rust
+/// use sparkscan_ws::timestamp;
+///
+/// let naive = timestamp::parse("2025-08-06T16:32:39.091001").unwrap();
+/// let rfc3339 = timestamp::parse("2025-08-06T16:32:39.091001Z").unwrap();
+/// assert_eq!(naive, rfc3339);
+/// ```
+pub fn parse(timestamp: &str) -> Option<DateTime<Utc>> {
+    let timestamp = timestamp.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(timestamp) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    OFFSET_FORMATS
+        .iter()
+        .find_map(|format| DateTime::parse_from_str(timestamp, format).ok())
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .or_else(|| {
+            NAIVE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(timestamp, format).ok())
+                .map(|datetime| datetime.and_utc())
+        })
+}
+
+/// Timestamp deserialized with [`parse`].
+struct Lenient(DateTime<Utc>);
+
+impl<'de> Deserialize<'de> for Lenient {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct LenientVisitor;
+
+        impl Visitor<'_> for LenientVisitor {
+            type Value = Lenient;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a timestamp")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Lenient, E> {
+                parse(value)
+                    .map(Lenient)
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+        }
+
+        deserializer.deserialize_str(LenientVisitor)
+    }
+}
+
+/// Deserialize a timestamp field with [`parse`].
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    Lenient::deserialize(deserializer).map(|timestamp| timestamp.0)
+}
+
+/// Deserialize an optional timestamp field with [`parse`].
+pub(crate) fn deserialize_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    Option::<Lenient>::deserialize(deserializer).map(|timestamp| timestamp.map(|t| t.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse() {
+        let expected = Utc.with_ymd_and_hms(2025, 8, 6, 16, 32, 39).unwrap();
+        let millis = expected + chrono::Duration::milliseconds(91);
+        for (timestamp, expected) in [
+            ("2025-08-06T16:32:39Z", expected),
+            ("2025-08-06T18:32:39+02:00", expected),
+            ("2025-08-06T16:32:39.091Z", millis),
+            ("2025-08-06T16:32:39.091000000Z", millis),
+            ("2025-08-06 16:32:39.091+00:00", millis),
+            ("2025-08-06T16:32:39.091+0000", millis),
+            ("2025-08-06T16:32:39", expected),
+            ("2025-08-06T16:32:39.091", millis),
+            ("2025-08-06 16:32:39.091000", millis),
+        ] {
+            assert_eq!(parse(timestamp), Some(expected), "{}", timestamp);
+        }
+        assert_eq!(
+            parse("2025-08-06T16:32:39.091001"),
+            Some(expected + chrono::Duration::microseconds(91_001))
+        );
+        assert_eq!(parse("2025-08-06"), None);
+        assert_eq!(parse("yesterday"), None);
+    }
+
+    #[test]
+    fn test_naive_expired_time() {
+        let payload = r#"{
+            "id": "01987420-1c44-7a0b-8d2e-3f5a7c9e1b3d",
+            "network": "MAINNET",
+            "type": "spark_to_lightning",
+            "status": "expired",
+            "expired_time": "2025-08-06T16:32:39.091001",
+            "processed_at": "2025-08-06T16:32:40.004000Z"
+        }"#;
+        // Parsed without the transaction fallback, which would have lost the field
+        let transaction: crate::types::transaction::TransactionPayload =
+            serde_json::from_str(payload).unwrap();
+        assert_eq!(
+            transaction.expired_time,
+            parse("2025-08-06T16:32:39.091001Z")
+        );
+        assert_eq!(transaction.updated_at, None);
+    }
+}
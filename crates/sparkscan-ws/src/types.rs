@@ -3,9 +3,19 @@
 //! This module contains the generated types from JSON schemas and helper
 //! functions for message dispatching.
 
-use serde::{Deserialize, Serialize};
+use crate::format::format_sats;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::borrow::Cow;
+#[cfg(feature = "client")]
 use tokio_centrifuge::utils::decode_json;
 
+/// Decode a JSON publication, as the centrifuge client does.
+#[cfg(not(feature = "client"))]
+fn decode_json(data: &[u8]) -> serde_json::Result<serde_json::Value> {
+    serde_json::from_slice(data)
+}
+
 // Include the generated types from build.rs
 include!(concat!(env!("OUT_DIR"), "/types.rs"));
 
@@ -56,6 +66,218 @@ impl SparkScanMessage {
             SparkScanMessage::Transaction(data) => Some(format!("{:?}", data.network)),
         }
     }
+
+    /// Get the key under which the message is ordered with
+    /// [`HandlerOrdering::KeyedOrdering`](crate::HandlerOrdering::KeyedOrdering).
+    ///
+    /// This is the address for balance updates, the token identifier for token and price
+    /// updates, and the sender for transactions, falling back to the receiver and then the
+    /// transaction id.
+    pub fn ordering_key(&self) -> &str {
+        match self {
+            SparkScanMessage::Balance(data) => data.address.as_str(),
+            SparkScanMessage::TokenBalance(data) => data.address.as_str(),
+            SparkScanMessage::TokenPrice(data) => data.address.as_str(),
+            SparkScanMessage::Token(data) => data.address.as_str(),
+            SparkScanMessage::Transaction(data) => data
+                .from_identifier
+                .as_deref()
+                .or(data.to_identifier.as_deref())
+                .unwrap_or(&data.id),
+        }
+    }
+
+    /// Get the time the server processed the message.
+    ///
+    /// Token updates report the time their market data was calculated instead, if any.
+    pub fn processed_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self {
+            SparkScanMessage::Balance(data) => Some(data.processed_at),
+            SparkScanMessage::TokenBalance(data) => Some(data.processed_at),
+            SparkScanMessage::TokenPrice(data) => Some(data.processed_at),
+            SparkScanMessage::Token(data) => data.calculated_at,
+            SparkScanMessage::Transaction(data) => Some(data.processed_at),
+        }
+    }
+
+    /// Get the network of the message as the type shared with the `sparkscan` REST client.
+    pub fn spark_network(&self) -> sparkscan_types::Network {
+        match self {
+            SparkScanMessage::Balance(data) => data.network.into(),
+            SparkScanMessage::TokenBalance(data) => data.network.into(),
+            SparkScanMessage::TokenPrice(data) => data.network.into(),
+            SparkScanMessage::Token(data) => data.network.into(),
+            SparkScanMessage::Transaction(data) => data.network.into(),
+        }
+    }
+}
+
+/// One-line summary of the message, with amounts formatted by [`format_sats`].
+impl std::fmt::Display for SparkScanMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SparkScanMessage::Balance(data) => write!(
+                f,
+                "balance of {} on {}: {}",
+                data.address.as_str(),
+                data.network,
+                sats_or_raw(&data.soft_balance)
+            ),
+            SparkScanMessage::TokenBalance(data) => write!(
+                f,
+                "balance of token {} for {} on {}: {}",
+                data.token_address.as_str(),
+                data.address.as_str(),
+                data.network,
+                data.balance
+            ),
+            SparkScanMessage::TokenPrice(data) => write!(
+                f,
+                "price of token {} on {}: {} sats ({})",
+                data.address.as_str(),
+                data.network,
+                data.price_sats.as_str(),
+                data.protocol
+            ),
+            SparkScanMessage::Token(data) => write!(
+                f,
+                "token {} ({}) on {}: {} holders",
+                data.ticker,
+                data.address.as_str(),
+                data.network,
+                data.holders
+            ),
+            SparkScanMessage::Transaction(data) => {
+                write!(
+                    f,
+                    "transaction {} on {}: {} {}",
+                    data.id, data.network, data.type_, data.status
+                )?;
+                match &data.amount_sats {
+                    Some(amount) => write!(f, ", {}", sats_or_raw(amount)),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// Format a decimal string of satoshis, or keep it as sent if it does not parse.
+fn sats_or_raw(amount: &str) -> String {
+    match amount.parse::<sparkscan_types::Sats>() {
+        Ok(sats) => format_sats(sats.value()),
+        Err(_) => amount.to_string(),
+    }
+}
+
+// Every schema declares its own copy of the network enum; map them all onto the shared type.
+macro_rules! impl_shared_network {
+    ($($module:ident),+) => {
+        $(
+            impl From<$module::Network> for sparkscan_types::Network {
+                fn from(network: $module::Network) -> Self {
+                    match network {
+                        $module::Network::Mainnet => Self::Mainnet,
+                        $module::Network::Testnet => Self::Testnet,
+                        $module::Network::Signet => Self::Signet,
+                        $module::Network::Regtest => Self::Regtest,
+                        $module::Network::Loadtest => Self::Loadtest,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_shared_network!(balance, token_balance, token_price, token, transaction);
+
+impl From<transaction::Status> for sparkscan_types::TransactionStatus {
+    fn from(status: transaction::Status) -> Self {
+        match status {
+            transaction::Status::Confirmed => Self::Confirmed,
+            transaction::Status::Pending => Self::Pending,
+            transaction::Status::Sent => Self::Sent,
+            transaction::Status::Failed => Self::Failed,
+            transaction::Status::Expired => Self::Expired,
+        }
+    }
+}
+
+impl From<transaction::Type> for sparkscan_types::TransactionType {
+    fn from(type_: transaction::Type) -> Self {
+        match type_ {
+            transaction::Type::TokenMultiTransfer => Self::TokenMultiTransfer,
+            transaction::Type::BitcoinToSpark => Self::BitcoinToSpark,
+            transaction::Type::LightningToSpark => Self::LightningToSpark,
+            transaction::Type::SparkToBitcoin => Self::SparkToBitcoin,
+            transaction::Type::SparkToLightning => Self::SparkToLightning,
+            transaction::Type::SparkToSpark => Self::SparkToSpark,
+            transaction::Type::TokenTransfer => Self::TokenTransfer,
+            transaction::Type::Unknown => Self::Unknown,
+        }
+    }
+}
+
+// Generated enums serialize to their wire strings; expose them without going through serde.
+macro_rules! impl_wire_str {
+    ($($type:ty { $($variant:ident => $wire:literal),+ $(,)? })+) => {
+        $(
+            impl $type {
+                /// Get the string used for this value on the wire.
+                pub fn as_wire_str(&self) -> &'static str {
+                    match self {
+                        $(Self::$variant => $wire,)+
+                    }
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! impl_network_wire_str {
+    ($($module:ident),+) => {
+        impl_wire_str! {
+            $(
+                $module::Network {
+                    Mainnet => "MAINNET",
+                    Testnet => "TESTNET",
+                    Signet => "SIGNET",
+                    Regtest => "REGTEST",
+                    Loadtest => "LOADTEST",
+                }
+            )+
+        }
+    };
+}
+
+impl_network_wire_str!(balance, token_balance, token_price, token, transaction);
+
+impl_wire_str! {
+    token_price::Protocol {
+        Sparksat => "sparksat",
+        Flashnet => "flashnet",
+    }
+    token::PricingSource {
+        Sparksat => "sparksat",
+        Flashnet => "flashnet",
+    }
+    transaction::Status {
+        Confirmed => "confirmed",
+        Pending => "pending",
+        Sent => "sent",
+        Failed => "failed",
+        Expired => "expired",
+    }
+    transaction::Type {
+        TokenMultiTransfer => "token_multi_transfer",
+        BitcoinToSpark => "bitcoin_to_spark",
+        LightningToSpark => "lightning_to_spark",
+        SparkToBitcoin => "spark_to_bitcoin",
+        SparkToLightning => "spark_to_lightning",
+        SparkToSpark => "spark_to_spark",
+        TokenTransfer => "token_transfer",
+        Unknown => "unknown",
+    }
 }
 
 /// Topic names for WebSocket subscriptions.
@@ -140,112 +362,212 @@ impl Topic {
         }
     }
 
+    /// Get the channel of the topic on a server namespacing its channels with `prefix`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use sparkscan_ws::Topic;
+    /// assert_eq!(Topic::Balances.channel("spark:"), "spark:balances");
+    /// assert_eq!(Topic::Balances.channel(""), Topic::Balances.as_str());
+    /// ```
+    pub fn channel(&self, prefix: &str) -> String {
+        format!("{}{}", prefix, self.as_str())
+    }
+
+    /// Parse the channel of a server namespacing its channels with `prefix`.
+    ///
+    /// Channels without the prefix are parsed as they are.
+    ///
+    /// # Panics
+    ///
+    /// Panics like [`from_str`](Self::from_str) if the channel does not name a topic.
+    pub fn from_channel(channel: &str, prefix: &str) -> Self {
+        Self::from_str(channel.strip_prefix(prefix).unwrap_or(channel))
+    }
+
     /// Parse a topic string into a Topic enum.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `topic` does not name a topic; [`Topic::try_from`] returns an error instead.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(topic: &str) -> Self {
+        Self::try_from(topic).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl TryFrom<&str> for Topic {
+    type Error = crate::error::SparkScanWsError;
+
+    /// Parse a topic string, failing with a configuration error if it does not name a topic.
+    fn try_from(topic: &str) -> crate::error::Result<Self> {
         // Handle basic topics first
         match topic {
-            "balances" => return Topic::Balances,
-            "token_balances" => return Topic::TokenBalances,
-            "token_prices" => return Topic::TokenPrices,
-            "transactions" => return Topic::Transactions,
-            "tokens" => return Topic::Tokens,
+            "balances" => return Ok(Topic::Balances),
+            "token_balances" => return Ok(Topic::TokenBalances),
+            "token_prices" => return Ok(Topic::TokenPrices),
+            "transactions" => return Ok(Topic::Transactions),
+            "tokens" => return Ok(Topic::Tokens),
             _ => {}
         }
 
         // Handle path-based topics
         if let Some(rest) = topic.strip_prefix("/balance/network/") {
-            Topic::BalanceNetwork(rest.to_string())
+            Ok(Topic::BalanceNetwork(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/balance/address/") {
-            Topic::BalanceAddress(rest.to_string())
+            Ok(Topic::BalanceAddress(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/token_balance/network/") {
-            Topic::TokenBalanceNetwork(rest.to_string())
+            Ok(Topic::TokenBalanceNetwork(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/token_balance/identifier/") {
-            Topic::TokenBalanceIdentifier(rest.to_string())
+            Ok(Topic::TokenBalanceIdentifier(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/token_balance/address/") {
-            Topic::TokenBalanceAddress(rest.to_string())
+            Ok(Topic::TokenBalanceAddress(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/token_price/network/") {
-            Topic::TokenPriceNetwork(rest.to_string())
+            Ok(Topic::TokenPriceNetwork(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/token_price/identifier/") {
-            Topic::TokenPriceIdentifier(rest.to_string())
+            Ok(Topic::TokenPriceIdentifier(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/transaction/network/") {
-            Topic::TransactionNetwork(rest.to_string())
+            Ok(Topic::TransactionNetwork(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/transaction/in/") {
-            let parts: Vec<&str> = rest.splitn(2, '/').collect();
-            if parts.len() == 2 {
-                Topic::TransactionIn(parts[0].to_string(), parts[1].to_string())
-            } else {
-                panic!("Invalid transaction in topic format: {}. Expected /transaction/in/network/field", topic);
-            }
+            let (network, field) = rest.split_once('/').ok_or_else(|| {
+                crate::error::SparkScanWsError::config(format!(
+                    "Invalid transaction in topic format: {}. Expected /transaction/in/network/field",
+                    topic
+                ))
+            })?;
+            Ok(Topic::TransactionIn(network.to_string(), field.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/transaction/out/") {
-            let parts: Vec<&str> = rest.splitn(2, '/').collect();
-            if parts.len() == 2 {
-                Topic::TransactionOut(parts[0].to_string(), parts[1].to_string())
-            } else {
-                panic!("Invalid transaction out topic format: {}. Expected /transaction/out/network/field", topic);
-            }
+            let (network, field) = rest.split_once('/').ok_or_else(|| {
+                crate::error::SparkScanWsError::config(format!(
+                    "Invalid transaction out topic format: {}. Expected /transaction/out/network/field",
+                    topic
+                ))
+            })?;
+            Ok(Topic::TransactionOut(
+                network.to_string(),
+                field.to_string(),
+            ))
         } else if let Some(rest) = topic.strip_prefix("/token/identifier/") {
-            Topic::TokenIdentifier(rest.to_string())
+            Ok(Topic::TokenIdentifier(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/token/network/") {
-            Topic::TokenNetwork(rest.to_string())
+            Ok(Topic::TokenNetwork(rest.to_string()))
         } else if let Some(rest) = topic.strip_prefix("/token/issuer/") {
-            Topic::TokenIssuer(rest.to_string())
+            Ok(Topic::TokenIssuer(rest.to_string()))
         } else {
-            panic!(
+            Err(crate::error::SparkScanWsError::config(format!(
                 "Unknown topic: {}. Only predefined topics are supported.",
                 topic
-            );
+            )))
         }
     }
 }
 
 /// Extract payload data from potentially nested JSON structures
-fn extract_payload_data(json_value: serde_json::Value) -> crate::error::Result<serde_json::Value> {
+///
+/// Used for the publications [`direct_payload`] cannot borrow from, such as double-encoded
+/// payloads. Envelope fields are moved out of the value rather than cloned.
+fn extract_payload_data(
+    mut json_value: serde_json::Value,
+) -> crate::error::Result<serde_json::Value> {
     // Handle different JSON envelope patterns that Centrifugo/WebSocket servers might use
 
     // Case 1: Data is a double-encoded JSON string (most common case for Centrifugo)
-    if json_value.is_string() {
-        let json_str = json_value.as_str().unwrap();
+    if let serde_json::Value::String(json_str) = &json_value {
         return serde_json::from_str(json_str)
-            .map_err(|e| crate::error::SparkScanWsError::SerializationError(e));
+            .map_err(crate::error::SparkScanWsError::SerializationError);
     }
 
     // Case 2: Data is wrapped in a "data" field
-    if let Some(data_field) = json_value.get("data") {
-        if data_field.is_string() {
-            // Data field contains a JSON string
-            let data_str = data_field.as_str().unwrap();
-            return serde_json::from_str(data_str)
-                .map_err(|e| crate::error::SparkScanWsError::SerializationError(e));
-        } else {
-            // Data field is already a JSON object
-            return Ok(data_field.clone());
+    // Case 3: Data is wrapped in a "payload" field
+    // Case 4: Look for message envelope patterns
+    if let Some(object) = json_value.as_object_mut() {
+        let field = ["data", "payload", "message"]
+            .into_iter()
+            .find_map(|key| object.remove(key));
+        match field {
+            // The field contains a JSON string
+            Some(serde_json::Value::String(field_str)) => {
+                return serde_json::from_str(&field_str)
+                    .map_err(crate::error::SparkScanWsError::SerializationError);
+            }
+            // The field is already a JSON object
+            Some(field) => return Ok(field),
+            None => {}
         }
     }
 
-    // Case 3: Data is wrapped in a "payload" field
-    if let Some(payload_field) = json_value.get("payload") {
-        if payload_field.is_string() {
-            let payload_str = payload_field.as_str().unwrap();
-            return serde_json::from_str(payload_str)
-                .map_err(|e| crate::error::SparkScanWsError::SerializationError(e));
-        } else {
-            return Ok(payload_field.clone());
-        }
+    // Case 5: Use the entire JSON value as-is (direct payload)
+    Ok(json_value)
+}
+
+/// Envelope fields of a publication, borrowed from the raw bytes.
+///
+/// Other fields are skipped without being materialized.
+#[derive(Deserialize)]
+struct Envelope<'a> {
+    #[serde(borrow)]
+    data: Option<&'a RawValue>,
+    #[serde(borrow)]
+    payload: Option<&'a RawValue>,
+    #[serde(borrow)]
+    message: Option<&'a RawValue>,
+}
+
+/// Locate the raw JSON of the payload in `data` without building a `serde_json::Value`.
+///
+/// The envelope fields are checked in the same order as [`extract_payload_data`].
+pub(crate) fn raw_payload(data: &[u8]) -> Option<&str> {
+    let json = std::str::from_utf8(data).ok()?.trim();
+    if json.starts_with('"') {
+        return Some(json);
     }
+    if !json.starts_with('{') {
+        return None;
+    }
+    let envelope: Envelope = serde_json::from_str(json).ok()?;
+    Some(
+        envelope
+            .data
+            .or(envelope.payload)
+            .or(envelope.message)
+            .map_or(json, |field| field.get()),
+    )
+}
 
-    // Case 4: Look for message envelope patterns
-    if let Some(message_field) = json_value.get("message") {
-        if message_field.is_string() {
-            let message_str = message_field.as_str().unwrap();
-            return serde_json::from_str(message_str)
-                .map_err(|e| crate::error::SparkScanWsError::SerializationError(e));
-        } else {
-            return Ok(message_field.clone());
+/// Locate the payload object in `data`.
+///
+/// Returns `None` when the payload is double-encoded or the envelope is unusual, in which case
+/// [`extract_payload_data`] handles it.
+fn direct_payload(data: &[u8]) -> Option<&str> {
+    raw_payload(data).filter(|json| json.starts_with('{'))
+}
+
+/// Locate the JSON string holding a double-encoded payload in `data`.
+#[cfg(feature = "high-throughput")]
+fn encoded_payload(data: &[u8]) -> Option<&str> {
+    raw_payload(data).filter(|json| json.starts_with('"'))
+}
+
+/// Payload of a publication, either raw JSON or an already decoded value.
+enum PayloadData<'a> {
+    Raw(&'a str),
+    Value(serde_json::Value),
+}
+
+impl PayloadData<'_> {
+    fn deserialize<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        match self {
+            PayloadData::Raw(json) => serde_json::from_str(json),
+            PayloadData::Value(value) => T::deserialize(value),
         }
     }
 
-    // Case 5: Use the entire JSON value as-is (direct payload)
-    Ok(json_value)
+    fn into_value(self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            PayloadData::Raw(json) => serde_json::from_str(json),
+            PayloadData::Value(value) => Ok(value),
+        }
+    }
 }
 
 /// Create a fallback TransactionPayload from any JSON, putting unmappable fields into token_io_details
@@ -284,9 +606,8 @@ fn create_fallback_transaction_payload(
     let processed_at = obj
         .get("processed_at")
         .and_then(|v| v.as_str())
-        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
-        .map(|dt| dt.with_timezone(&chrono::Utc))
-        .unwrap_or_else(|| chrono::Utc::now());
+        .and_then(crate::timestamp::parse)
+        .unwrap_or_else(chrono::Utc::now);
 
     // Extract optional fields
     let amount_sats = obj
@@ -322,14 +643,12 @@ fn create_fallback_transaction_payload(
     let updated_at = obj
         .get("updated_at")
         .and_then(|v| v.as_str())
-        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
-        .map(|dt| dt.with_timezone(&chrono::Utc));
+        .and_then(crate::timestamp::parse);
 
     let expired_time = obj
         .get("expired_time")
         .and_then(|v| v.as_str())
-        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
-        .map(|dt| dt.with_timezone(&chrono::Utc));
+        .and_then(crate::timestamp::parse);
 
     // Create token_io_details containing all the original data for debugging/analysis
     let mut token_io_details = serde_json::Map::new();
@@ -393,64 +712,89 @@ fn create_fallback_transaction_payload(
     })
 }
 
+/// Undo the encodings of a publication: decompress it, then transcode it from CBOR.
+///
+/// Publications are returned as they are without the `gzip`, `zstd` and `cbor` features.
+/// Decompression stops past the maximum payload size of `limits`.
+pub(crate) fn decode_publication<'a>(
+    data: &'a [u8],
+    limits: &crate::PayloadLimits,
+) -> crate::error::Result<Cow<'a, [u8]>> {
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    let data = crate::compression::decompress(data, limits.max_payload_bytes)?;
+    #[cfg(not(any(feature = "gzip", feature = "zstd")))]
+    let _ = limits;
+    #[cfg(not(any(feature = "gzip", feature = "zstd")))]
+    let data = Cow::Borrowed(data);
+    #[cfg(feature = "cbor")]
+    if crate::cbor::is_cbor(&data) {
+        return Ok(Cow::Owned(crate::cbor::to_json(&data)?.into_owned()));
+    }
+    Ok(data)
+}
+
 /// Helper function to try parsing a message based on expected topic type.
+///
+/// Compressed publications are decompressed with the `gzip` and `zstd` features, and CBOR ones
+/// are decoded with the `cbor` feature. Publications exceeding the default
+/// [`PayloadLimits`](crate::PayloadLimits) are rejected.
 pub fn parse_message_for_topic(
     topic: &Topic,
     data: &[u8],
 ) -> crate::error::Result<SparkScanMessage> {
+    parse_message_for_topic_with_limits(topic, data, &crate::PayloadLimits::default())
+}
+
+/// Parse a message like [`parse_message_for_topic`], rejecting publications exceeding `limits`
+/// before parsing them.
+pub fn parse_message_for_topic_with_limits(
+    topic: &Topic,
+    data: &[u8],
+    limits: &crate::PayloadLimits,
+) -> crate::error::Result<SparkScanMessage> {
+    // Checked before decoding as well, so that oversized encoded publications are not decoded
+    limits.check_size(data.len())?;
+    let data = &*decode_publication(data, limits)?;
+    limits.check(data)?;
+
     // Debug: Log the raw data structure to understand the WebSocket message format
     #[cfg(feature = "tracing")]
     {
         if let Ok(raw_str) = std::str::from_utf8(data) {
-            tracing::debug!("Raw WebSocket data for topic {:?}: {}", topic, raw_str);
+            let raw = crate::redaction::redact_log(format!("{:?}: {}", topic, raw_str));
+            tracing::debug!(target: "sparkscan_ws::parse", "Raw WebSocket data for topic {}", raw);
         }
     }
 
-    // First, try to parse as a JSON value using tokio-centrifuge's decode_json
-    let json_value: serde_json::Value = decode_json(data).map_err(|e| {
-        crate::error::SparkScanWsError::InvalidMessageFormat(format!(
-            "Failed to decode JSON: {:?}",
-            e
-        ))
-    })?;
-
-    // Handle nested JSON scenarios more robustly
-    let payload_data = extract_payload_data(json_value)?;
-
     // Parse the message based on topic type, with transaction fallback
     match topic {
         Topic::Balances | Topic::BalanceNetwork(_) | Topic::BalanceAddress(_) => {
-            let payload: balance::BalancePayload = serde_json::from_value(payload_data)?;
-            Ok(SparkScanMessage::Balance(payload))
+            Ok(SparkScanMessage::Balance(deserialize_payload(data)?))
         }
         Topic::TokenBalances
         | Topic::TokenBalanceNetwork(_)
         | Topic::TokenBalanceIdentifier(_)
         | Topic::TokenBalanceAddress(_) => {
-            let payload: token_balance::TokenBalancePayload = serde_json::from_value(payload_data)?;
-            Ok(SparkScanMessage::TokenBalance(payload))
+            Ok(SparkScanMessage::TokenBalance(deserialize_payload(data)?))
         }
         Topic::TokenPrices | Topic::TokenPriceNetwork(_) | Topic::TokenPriceIdentifier(_) => {
-            let payload: token_price::TokenPricePayload = serde_json::from_value(payload_data)?;
-            Ok(SparkScanMessage::TokenPrice(payload))
+            Ok(SparkScanMessage::TokenPrice(deserialize_payload(data)?))
         }
         Topic::Tokens
         | Topic::TokenIdentifier(_)
         | Topic::TokenNetwork(_)
-        | Topic::TokenIssuer(_) => {
-            let payload: token::TokenPayload = serde_json::from_value(payload_data)?;
-            Ok(SparkScanMessage::Token(payload))
-        }
+        | Topic::TokenIssuer(_) => Ok(SparkScanMessage::Token(deserialize_payload(data)?)),
         Topic::Transactions
         | Topic::TransactionNetwork(_)
         | Topic::TransactionIn(_, _)
         | Topic::TransactionOut(_, _) => {
             // First try normal parsing, then fallback to field mapping
-            match serde_json::from_value::<transaction::TransactionPayload>(payload_data.clone()) {
+            match deserialize_payload::<transaction::TransactionPayload>(data) {
                 Ok(payload) => Ok(SparkScanMessage::Transaction(payload)),
                 Err(_) => {
                     // Create fallback transaction payload with unmappable fields in token_io_details
-                    let fallback_payload = create_fallback_transaction_payload(payload_data)?;
+                    let fallback_payload =
+                        create_fallback_transaction_payload(payload_data(data)?.into_value()?)?;
                     Ok(SparkScanMessage::Transaction(fallback_payload))
                 }
             }
@@ -458,6 +802,39 @@ pub fn parse_message_for_topic(
     }
 }
 
+/// Deserialize the payload of a publication.
+///
+/// Most publications are the payload itself, which is deserialized straight from the bytes;
+/// envelopes are only looked for when that fails.
+pub(crate) fn deserialize_payload<T: DeserializeOwned>(data: &[u8]) -> crate::error::Result<T> {
+    if let Ok(payload) = serde_json::from_slice(data) {
+        return Ok(payload);
+    }
+    #[cfg(feature = "high-throughput")]
+    if let Some(result) = encoded_payload(data).and_then(crate::pool::deserialize_encoded) {
+        return Ok(result?);
+    }
+    Ok(payload_data(data)?.deserialize()?)
+}
+
+/// Unwrap the payload of a publication from its envelope.
+///
+/// Payload objects are borrowed from the bytes; only double-encoded payloads and unusual
+/// envelopes go through an intermediate `serde_json::Value`.
+fn payload_data(data: &[u8]) -> crate::error::Result<PayloadData<'_>> {
+    if let Some(json) = direct_payload(data) {
+        return Ok(PayloadData::Raw(json));
+    }
+
+    let json_value: serde_json::Value = decode_json(data).map_err(|e| {
+        crate::error::SparkScanWsError::InvalidMessageFormat(format!(
+            "Failed to decode JSON: {:?}",
+            e
+        ))
+    })?;
+    Ok(PayloadData::Value(extract_payload_data(json_value)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -546,6 +923,24 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_direct_payload() {
+        let payload = r#"{"id":"test_id","status":"pending"}"#;
+        assert_eq!(direct_payload(payload.as_bytes()), Some(payload));
+
+        let wrapped = format!(r#"{{"offset": 5, "data": {}}}"#, payload);
+        assert_eq!(direct_payload(wrapped.as_bytes()), Some(payload));
+        let wrapped = format!(r#"{{"message": {}, "payload": {}}}"#, "{}", payload);
+        assert_eq!(direct_payload(wrapped.as_bytes()), Some(payload));
+
+        // Double-encoded payloads take the slow path
+        let double_encoded = serde_json::to_string(payload).unwrap();
+        assert_eq!(direct_payload(double_encoded.as_bytes()), None);
+        let wrapped = format!(r#"{{"data": {}}}"#, double_encoded);
+        assert_eq!(direct_payload(wrapped.as_bytes()), None);
+        assert_eq!(direct_payload(b"\xff{"), None);
+    }
+
     #[test]
     fn test_create_fallback_transaction_payload_minimal() {
         // Test with minimal required fields
@@ -734,6 +1129,173 @@ mod tests {
         assert_eq!(result.message_type(), "balance");
         assert!(result.network().is_some());
         assert!(result.network().unwrap().contains("Mainnet"));
+        assert_eq!(
+            result.ordering_key(),
+            "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s"
+        );
+    }
+
+    #[test]
+    fn test_transaction_ordering_key() {
+        let mut transaction_json = json!({
+            "id": "ordering_key_test",
+            "network": "MAINNET",
+            "type": "spark_to_spark",
+            "status": "confirmed",
+            "to_identifier": "receiver",
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        let result = parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap();
+        assert_eq!(result.ordering_key(), "receiver");
+
+        transaction_json["from_identifier"] = json!("sender");
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        let result = parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap();
+        assert_eq!(result.ordering_key(), "sender");
+    }
+
+    #[test]
+    fn test_wire_strings_round_trip() {
+        fn assert_round_trip<T>(value: T, wire: &str)
+        where
+            T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+        {
+            assert_eq!(serde_json::to_value(&value).unwrap(), json!(wire));
+            assert_eq!(serde_json::from_value::<T>(json!(wire)).unwrap(), value);
+        }
+
+        for (network, wire) in [
+            (transaction::Network::Mainnet, "MAINNET"),
+            (transaction::Network::Testnet, "TESTNET"),
+            (transaction::Network::Signet, "SIGNET"),
+            (transaction::Network::Regtest, "REGTEST"),
+            (transaction::Network::Loadtest, "LOADTEST"),
+        ] {
+            assert_eq!(network.as_wire_str(), wire);
+            assert_eq!(network.to_string(), wire);
+            assert_round_trip(network, wire);
+        }
+        assert_round_trip(
+            balance::Network::Mainnet,
+            balance::Network::Mainnet.as_wire_str(),
+        );
+
+        for status in [
+            transaction::Status::Confirmed,
+            transaction::Status::Pending,
+            transaction::Status::Sent,
+            transaction::Status::Failed,
+            transaction::Status::Expired,
+        ] {
+            assert_round_trip(status, status.as_wire_str());
+            assert_eq!(
+                status.as_wire_str(),
+                sparkscan_types::TransactionStatus::from(status).as_str()
+            );
+        }
+
+        for type_ in [
+            transaction::Type::TokenMultiTransfer,
+            transaction::Type::BitcoinToSpark,
+            transaction::Type::LightningToSpark,
+            transaction::Type::SparkToBitcoin,
+            transaction::Type::SparkToLightning,
+            transaction::Type::SparkToSpark,
+            transaction::Type::TokenTransfer,
+            transaction::Type::Unknown,
+        ] {
+            assert_round_trip(type_, type_.as_wire_str());
+            assert_eq!(type_.to_string(), type_.as_wire_str());
+            assert_eq!(
+                type_.as_wire_str(),
+                sparkscan_types::TransactionType::from(type_).as_str()
+            );
+        }
+
+        assert_round_trip(token_price::Protocol::Flashnet, "flashnet");
+        assert_round_trip(token::PricingSource::Sparksat, "sparksat");
+    }
+
+    #[test]
+    fn test_transaction_republishes_wire_strings() {
+        let transaction_json = json!({
+            "id": "republish_test",
+            "network": "MAINNET",
+            "type": "spark_to_lightning",
+            "status": "confirmed",
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        let result = parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap();
+        let SparkScanMessage::Transaction(tx) = result else {
+            panic!("expected a transaction message");
+        };
+
+        let republished = serde_json::to_value(&tx).unwrap();
+        assert_eq!(republished["network"], "MAINNET");
+        assert_eq!(republished["type"], "spark_to_lightning");
+        assert_eq!(republished["status"], "confirmed");
+    }
+
+    #[test]
+    fn test_message_display() {
+        let transaction_json = json!({
+            "id": "display_test",
+            "network": "MAINNET",
+            "type": "spark_to_lightning",
+            "status": "confirmed",
+            "amount_sats": "1234567",
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        let result = parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap();
+        assert_eq!(
+            result.to_string(),
+            "transaction display_test on MAINNET: spark_to_lightning confirmed, 1,234,567 sats"
+        );
+
+        let balance_json = json!({
+            "address": "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s",
+            "network": "REGTEST",
+            "soft_balance": "2500",
+            "hard_balance": "2500",
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+
+        let json_str = serde_json::to_string(&balance_json).unwrap();
+        let result = parse_message_for_topic(&Topic::Balances, json_str.as_bytes()).unwrap();
+        assert!(result.to_string().ends_with(" on REGTEST: 2,500 sats"));
+    }
+
+    #[test]
+    fn test_shared_domain_types() {
+        let transaction_json = json!({
+            "id": "shared_types_test",
+            "network": "REGTEST",
+            "type": "spark_to_lightning",
+            "status": "sent",
+            "processed_at": "2025-08-06T16:28:42.955000Z"
+        });
+
+        let json_str = serde_json::to_string(&transaction_json).unwrap();
+        let result = parse_message_for_topic(&Topic::Transactions, json_str.as_bytes()).unwrap();
+        assert_eq!(result.spark_network(), sparkscan_types::Network::Regtest);
+
+        let SparkScanMessage::Transaction(tx) = result else {
+            panic!("expected a transaction message");
+        };
+        assert_eq!(
+            sparkscan_types::TransactionType::from(tx.type_),
+            sparkscan_types::TransactionType::SparkToLightning
+        );
+        assert_eq!(
+            sparkscan_types::TransactionStatus::from(tx.status),
+            sparkscan_types::TransactionStatus::Sent
+        );
     }
 
     #[test]
@@ -780,6 +1342,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_topic_channel_prefix() {
+        let topic = Topic::TransactionIn("mainnet".to_string(), "sp1abc123".to_string());
+        let channel = topic.channel("spark:");
+        assert_eq!(channel, "spark:/transaction/in/mainnet/sp1abc123");
+        assert_eq!(Topic::from_channel(&channel, "spark:"), topic);
+        assert_eq!(Topic::from_channel("balances", "spark:"), Topic::Balances);
+    }
+
     #[test]
     fn test_topic_to_string() {
         // Basic topics
@@ -0,0 +1,209 @@
+//! Verification of signed publications before dispatch.
+//!
+//! A verifier registered with
+//! [`SparkScanWsClient::verify_publications`](crate::SparkScanWsClient::verify_publications)
+//! checks every publication of the subscriptions of the client before it is parsed, typed, raw
+//! and decoded alike. Publications it rejects are not dispatched: they are counted, logged and
+//! reported to [`on_verification_failure`](crate::SparkScanWsClient::on_verification_failure)
+//! callbacks as a [`VerificationError`].
+//!
+//! The signature scheme is left to the verifier, such as an HMAC or an ed25519 signature over
+//! the payload. [`signed_payload`] extracts the payload exactly as published, along with the
+//! signature from the [`SIGNATURE_TAG`] tag of the publication. Compare MACs in constant time,
+//! so that the time taken does not reveal how much of a forged signature is right:
+//!
+//! ```rust,no_run
+//! use sparkscan_ws::{verify, SparkScanWsClient};
+//! # fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> { unimplemented!() }
+//! # fn hex_decode(hex: &str) -> Option<Vec<u8>> { unimplemented!() }
+//!
+//! fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+//!     a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+//! }
+//!
+//! let key = b"shared secret".to_vec();
+//! let client = SparkScanWsClient::new("ws://updates.sparkscan.io/");
+//! client.verify_publications(move |publication| {
+//!     let signed = verify::signed_payload(publication).ok_or("missing signature")?;
+//!     let signature = hex_decode(&signed.signature).ok_or("malformed signature")?;
+//!     if !constant_time_eq(&hmac_sha256(&key, signed.payload.as_bytes()), &signature) {
+//!         return Err("signature mismatch".to_string());
+//!     }
+//!     Ok(())
+//! });
+//! client.on_verification_failure(|error| eprintln!("Tampered publication: {}", error));
+//! ```
+
+use crate::error::VerificationError;
+use crate::logging::{self, Level, LogCategory};
+use crate::types::raw_payload;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError, RwLock};
+use tokio_centrifuge::protocol::Publication;
+
+/// Publication tag the signature of a signed publication is read from.
+pub const SIGNATURE_TAG: &str = "signature";
+
+type Verifier = Box<dyn Fn(&UnverifiedPublication<'_>) -> Result<(), String> + Send + Sync>;
+type FailureCallback = Box<dyn Fn(&VerificationError) + Send + Sync>;
+
+/// Publication handed to the verifier, before it is dispatched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnverifiedPublication<'a> {
+    /// Channel the publication was received on
+    pub channel: &'a str,
+    /// Data of the publication, as published
+    pub data: &'a [u8],
+    /// Tags the server attached to the publication
+    pub tags: &'a HashMap<String, String>,
+}
+
+/// Payload of a signed publication, with its signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedPayload<'a> {
+    /// Payload as published, without its envelope
+    pub payload: &'a str,
+    /// Signature from the [`SIGNATURE_TAG`] tag
+    pub signature: String,
+}
+
+/// Get the payload and signature of a publication, `None` if it is not signed.
+///
+/// The payload is the JSON of the envelope field holding it, byte for byte, or the whole
+/// publication data when it has no envelope.
+pub fn signed_payload<'a>(publication: &UnverifiedPublication<'a>) -> Option<SignedPayload<'a>> {
+    let signature = publication.tags.get(SIGNATURE_TAG)?;
+    Some(SignedPayload {
+        payload: raw_payload(publication.data)?,
+        signature: signature.clone(),
+    })
+}
+
+/// Verifier and verification failures, shared by the subscriptions of a client.
+#[derive(Default)]
+pub(crate) struct Verification {
+    verifier: RwLock<Option<Verifier>>,
+    callbacks: Mutex<Vec<FailureCallback>>,
+    rejected: AtomicU64,
+}
+
+impl Verification {
+    /// Check the publications with `verifier` from now on.
+    pub(crate) fn set_verifier<F>(&self, verifier: F)
+    where
+        F: Fn(&UnverifiedPublication<'_>) -> Result<(), String> + Send + Sync + 'static,
+    {
+        *self
+            .verifier
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Box::new(verifier));
+    }
+
+    /// Register a callback notified of the rejected publications.
+    pub(crate) fn on_failure<F>(&self, callback: F)
+    where
+        F: Fn(&VerificationError) + Send + Sync + 'static,
+    {
+        self.callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Get the number of publications rejected by the verifier.
+    pub(crate) fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Check whether a publication on `channel` may be dispatched.
+    ///
+    /// Every publication is accepted until a verifier is set.
+    pub(crate) fn accept(&self, channel: &str, publication: &Publication) -> bool {
+        let result = match &*self.verifier.read().unwrap_or_else(PoisonError::into_inner) {
+            Some(verifier) => verifier(&UnverifiedPublication {
+                channel,
+                data: &publication.data,
+                tags: &publication.tags,
+            }),
+            None => return true,
+        };
+        let Err(reason) = result else {
+            return true;
+        };
+
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        let error = VerificationError {
+            channel: channel.to_string(),
+            reason,
+        };
+        logging::log(
+            LogCategory::Dispatch,
+            Level::Warn,
+            format_args!("Rejected publication: {}", error),
+        );
+        for callback in self
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            callback(&error);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn publication(data: &[u8], signature: Option<&str>) -> Publication {
+        Publication {
+            data: data.to_vec(),
+            tags: signature
+                .map(|signature| (SIGNATURE_TAG.to_string(), signature.to_string()))
+                .into_iter()
+                .collect(),
+            ..Publication::default()
+        }
+    }
+
+    #[test]
+    fn test_rejects_tampered_publications() {
+        let verification = Verification::default();
+        let signed = publication(br#"{"data": {"address": "sp1a"}}"#, Some("valid"));
+        assert!(verification.accept("balances", &signed));
+
+        // Stand-in for a MAC over the payload
+        verification.set_verifier(|publication| {
+            let signed = signed_payload(publication).ok_or("missing signature")?;
+            match (signed.payload, signed.signature.as_str()) {
+                (r#"{"address": "sp1a"}"#, "valid") => Ok(()),
+                _ => Err("signature mismatch".to_string()),
+            }
+        });
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let reported = Arc::clone(&failures);
+        verification.on_failure(move |error| reported.lock().unwrap().push(error.clone()));
+
+        let tampered = publication(br#"{"data": {"address": "sp1b"}}"#, Some("valid"));
+        // Signatures in the payload are data, not publication tags
+        let unsigned = publication(
+            br#"{"tags": {"signature": "valid"}, "data": {"address": "sp1a"}}"#,
+            None,
+        );
+        assert!(verification.accept("balances", &signed));
+        assert!(!verification.accept("balances", &tampered));
+        assert!(!verification.accept("balances", &unsigned));
+        assert_eq!(verification.rejected(), 2);
+
+        let failures = failures.lock().unwrap();
+        assert_eq!(
+            failures[0].to_string(),
+            "publication on balances failed verification: signature mismatch"
+        );
+        assert_eq!(failures[1].reason, "missing signature");
+    }
+}
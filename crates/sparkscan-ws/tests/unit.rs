@@ -7,9 +7,12 @@ mod tests {
             balance::Network as BalanceNetwork, parse_message_for_topic,
             token_balance::Network as TokenBalanceNetwork,
         },
-        SparkScanMessage, SparkScanWsClient, SparkScanWsConfig, Topic,
+        SparkScanMessage, Topic,
     };
+    #[cfg(feature = "client")]
+    use sparkscan_ws::{SparkScanWsClient, SparkScanWsConfig};
 
+    #[cfg(feature = "client")]
     #[tokio::test]
     async fn test_client_creation() {
         let client = SparkScanWsClient::new("ws://sparkscan.io/");
@@ -17,6 +20,7 @@ mod tests {
         assert!(!client.config().use_protobuf);
     }
 
+    #[cfg(feature = "client")]
     #[test]
     fn test_config_builder() {
         let config = SparkScanWsConfig::new("ws://sparkscan.io/")
@@ -165,6 +169,7 @@ mod tests {
     // require a test server and are better suited for separate integration
     // test files or end-to-end testing infrastructure.
 
+    #[cfg(feature = "client")]
     #[tokio::test]
     async fn test_subscription_creation() {
         // Test that we can create subscriptions without panicking
@@ -408,4 +413,73 @@ mod tests {
             assert_eq!(tx.to_identifier, Some("".to_string()));
         }
     }
+
+    #[test]
+    fn test_topic_try_from_rejects_invalid_topics() {
+        assert_eq!(
+            Topic::try_from("/transaction/out/mainnet/sp1abc123").unwrap(),
+            Topic::TransactionOut("mainnet".to_string(), "sp1abc123".to_string())
+        );
+        assert!(Topic::try_from("unknown_topic").is_err());
+        assert!(Topic::try_from("/transaction/in/mainnet").is_err());
+        assert!(Topic::try_from("").is_err());
+    }
+
+    #[test]
+    fn test_parsing_mutated_payloads_never_panics() {
+        use sparkscan_ws::types::parse_message_for_topic;
+
+        let transaction = serde_json::json!({
+            "id": "0196bb7e-1c4a-7b0e-9b1f-3c3a3e3d6f10",
+            "network": "MAINNET",
+            "type": "spark_to_spark",
+            "status": "confirmed",
+            "amount_sats": "1000",
+            "from_identifier": "sp1pgssx6rwqjer2xsmhe5x6mg6ng0cfu77q58vtcz9f0emuuzftnl7zvv6qujs5s",
+            "processed_at": "2025-08-06T16:28:42.955000Z",
+            "token_io_details": {"inputs": [{"amount": "1", "vout": 0}], "outputs": []}
+        });
+        let samples = [
+            transaction.to_string(),
+            serde_json::json!({"offset": 7, "data": transaction.to_string()}).to_string(),
+            r#"{"data": {"address": "sp1abc", "network": "MAINNET", "soft_balance": "1"}}"#
+                .to_string(),
+            r#"{"payload": "\ud83e\u00e9 [", "message": null}"#.to_string(),
+        ];
+        let topics = [
+            Topic::Balances,
+            Topic::TokenBalances,
+            Topic::TokenPrices,
+            Topic::Tokens,
+            Topic::Transactions,
+        ];
+
+        // Deterministic xorshift, so that a failing mutation reproduces
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for sample in &samples {
+            let bytes = sample.as_bytes();
+            for topic in &topics {
+                // Every truncation, including ones splitting multi-byte characters
+                for end in 0..=bytes.len() {
+                    let _ = parse_message_for_topic(topic, &bytes[..end]);
+                }
+                // Random byte substitutions
+                for _ in 0..500 {
+                    let mut mutated = bytes.to_vec();
+                    for _ in 0..=next() % 4 {
+                        let index = (next() % mutated.len() as u64) as usize;
+                        mutated[index] = next() as u8;
+                    }
+                    let _ = parse_message_for_topic(topic, &mutated);
+                }
+            }
+        }
+    }
 }
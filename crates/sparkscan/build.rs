@@ -1,6 +1,8 @@
 cfg_if::cfg_if! {
     if #[cfg(feature = "tracing")] {
         use syn::{ItemImpl, ItemStruct, parse_quote, visit_mut::VisitMut, ItemMod};
+    } else if #[cfg(feature = "middleware")] {
+        use syn::{ItemImpl, ItemStruct, parse_quote, visit_mut::VisitMut};
     } else {
         use syn::{ItemImpl, parse_quote, visit_mut::VisitMut};
     }
@@ -29,6 +31,9 @@ fn get_doc_filename_for_method(method_name: &str) -> Option<&'static str> {
         "new" => Some("new.md"),
         "new_with_client" => Some("new_with_client.md"),
         "new_with_api_key" => Some("new_with_api_key.md"),
+        "new_with_http_cache" => Some("new_with_http_cache.md"),
+        "new_with_middleware" => Some("new_with_middleware.md"),
+        "default_middleware_builder" => Some("default_middleware_builder.md"),
 
         // Root endpoint
         "root_get" => Some("root_get.md"),
@@ -65,13 +70,83 @@ fn get_doc_filename_for_method(method_name: &str) -> Option<&'static str> {
     }
 }
 
+/// Path prefix of each endpoint group, and the feature including it in the generated client.
+const ENDPOINT_GROUPS: &[(&str, &str)] = &[
+    ("/v1/address/", "address"),
+    ("/v1/tokens/", "tokens"),
+    ("/v1/stats/", "stats"),
+    ("/v1/bitcoin/", "bitcoin"),
+    ("/v1/tx/", "transactions"),
+];
+
+/// Remove the operations of the endpoint groups whose feature is disabled from the spec.
+///
+/// Paths outside of every group are always kept. The schemas are left untouched, so the `types`
+/// module is the same whatever the enabled groups.
+fn remove_disabled_endpoints(spec: &mut serde_json::Value) {
+    let Some(paths) = spec
+        .get_mut("paths")
+        .and_then(|paths| paths.as_object_mut())
+    else {
+        return;
+    };
+    paths.retain(|path, _| {
+        ENDPOINT_GROUPS
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix))
+            .is_none_or(|(_, feature)| {
+                let var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+                std::env::var_os(var).is_some()
+            })
+    });
+}
+
+/// Write the operations kept in the spec as a `&[Operation]` expression, and export the API
+/// version, for the `openapi` module.
+fn write_operations(spec: &serde_json::Value) {
+    let mut operations = String::from("&[\n");
+    for (path, methods) in spec["paths"].as_object().into_iter().flatten() {
+        for (method, operation) in methods.as_object().into_iter().flatten() {
+            let Some(operation_id) = operation["operationId"].as_str() else {
+                continue;
+            };
+            let tags: Vec<&str> = operation["tags"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|tag| tag.as_str())
+                .collect();
+            operations.push_str(&format!(
+                "    Operation {{ operation_id: {:?}, method: {:?}, path: {:?}, tags: &{:?}, \
+                 summary: {:?} }},\n",
+                operation_id,
+                method.to_uppercase(),
+                path,
+                tags,
+                operation["summary"].as_str().unwrap_or_default(),
+            ));
+        }
+    }
+    operations.push(']');
+
+    let out_file = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("operations.rs");
+    std::fs::write(out_file, operations).unwrap();
+    println!(
+        "cargo:rustc-env=SPARKSCAN_API_VERSION={}",
+        spec["info"]["version"].as_str().unwrap_or_default()
+    );
+}
+
 fn main() {
     let src = "./openapi.json";
     println!("cargo:rerun-if-changed={}", src);
     println!("cargo:rerun-if-changed=doc/");
 
-    let file = std::fs::File::open(src).unwrap();
-    let spec = serde_json::from_reader(file).unwrap();
+    let mut raw_spec: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(src).unwrap()).unwrap();
+    remove_disabled_endpoints(&mut raw_spec);
+    write_operations(&raw_spec);
+    let spec = serde_json::from_value(raw_spec.clone()).unwrap();
 
     let mut settings = progenitor::GenerationSettings::new();
     settings.with_interface(progenitor::InterfaceStyle::Builder);
@@ -117,21 +192,37 @@ fn main() {
     let mut headers_modifier = ClientHeadersModifier::new();
     headers_modifier.visit_file_mut(&mut ast);
 
-    let mut doc_modifier = ClientDocumentationModifier::new();
-    doc_modifier.visit_file_mut(&mut ast);
+    let mut override_fields_modifier = ClientOverrideFieldsModifier;
+    override_fields_modifier.visit_file_mut(&mut ast);
+
+    let mut validation_injector = BuilderValidationInjector::new(&raw_spec);
+    validation_injector.visit_file_mut(&mut ast);
+
+    let mut timeout_injector = BuilderTimeoutInjector::new();
+    timeout_injector.visit_file_mut(&mut ast);
+
+    let mut send_raw_injector = BuilderSendRawInjector::new();
+    send_raw_injector.visit_file_mut(&mut ast);
 
     let mut untagged_i128_injector = UntaggedI128Injector;
     untagged_i128_injector.visit_file_mut(&mut ast);
 
-    #[cfg(feature = "tracing")]
+    #[cfg(feature = "middleware")]
     {
-        let mut tracing_modifier = ClientTracingModifier;
-        tracing_modifier.visit_file_mut(&mut ast);
+        let mut middleware_modifier = ClientMiddlewareModifier;
+        middleware_modifier.visit_file_mut(&mut ast);
+    }
 
+    #[cfg(feature = "tracing")]
+    {
         let mut builder_instrumenter = BuilderSendInstrumenter::new();
         builder_instrumenter.visit_file_mut(&mut ast);
     }
 
+    // Documentation is applied last so that it also covers methods injected above
+    let mut doc_modifier = ClientDocumentationModifier::new();
+    doc_modifier.visit_file_mut(&mut ast);
+
     // Generate the code first
     let mut content = prettyplease::unparse(&ast);
 
@@ -256,11 +347,11 @@ fn main() {
 "#;
 
     // Insert the deserializer function inside the types module
-    if let Some(types_start) = content.find("pub mod types {") {
-        if let Some(insertion_point) = content[types_start..].find("/// Error types.") {
-            let full_insertion_point = types_start + insertion_point;
-            content.insert_str(full_insertion_point, i128_deserializer);
-        }
+    if let Some(types_start) = content.find("pub mod types {")
+        && let Some(insertion_point) = content[types_start..].find("/// Error types.")
+    {
+        let full_insertion_point = types_start + insertion_point;
+        content.insert_str(full_insertion_point, i128_deserializer);
     }
     let out_file = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("codegen.rs");
     std::fs::write(out_file, content).unwrap();
@@ -298,12 +389,13 @@ impl syn::visit_mut::VisitMut for ImportModifier {
 
     fn visit_path_mut(&mut self, path: &mut syn::Path) {
         // Handle fully qualified paths like progenitor_client::QueryParam
-        if path.leading_colon.is_none() && !path.segments.is_empty() {
-            if path.segments[0].ident == "progenitor_client" {
-                path.segments[0].ident =
-                    syn::Ident::new("sparkscan_client", path.segments[0].ident.span());
-                self.modified = true;
-            }
+        if path.leading_colon.is_none()
+            && !path.segments.is_empty()
+            && path.segments[0].ident == "progenitor_client"
+        {
+            path.segments[0].ident =
+                syn::Ident::new("sparkscan_client", path.segments[0].ident.span());
+            self.modified = true;
         }
 
         // Continue visiting the rest of the path
@@ -328,12 +420,12 @@ impl syn::visit_mut::VisitMut for ClientHeadersModifier {
 
         if is_client_impl && item.trait_.is_none() {
             for impl_item in &mut item.items {
-                if let syn::ImplItem::Fn(method) = impl_item {
-                    if method.sig.ident.to_string().as_str() == "new" {
-                        method.block = parse_quote! {{
-                            Self::new_with_client(baseurl, Self::base_client())
-                        }};
-                    }
+                if let syn::ImplItem::Fn(method) = impl_item
+                    && method.sig.ident.to_string().as_str() == "new"
+                {
+                    method.block = parse_quote! {{
+                        Self::new_with_client(baseurl, Self::base_client(None))
+                    }};
                 }
             }
 
@@ -344,33 +436,53 @@ impl syn::visit_mut::VisitMut for ClientHeadersModifier {
 
             if has_new_method {
                 // Add new method to the impl block
-                let get_base_url_method: syn::ImplItem = parse_quote! {
-                    /// Get the base URL of the client
-                    fn base_client() -> reqwest::Client {
-                        let user_agent = format!("sparkscan-rs/{}", env!("CARGO_PKG_VERSION"));
+                let base_client_builder_method: syn::ImplItem = parse_quote! {
+                    /// Prepare the default reqwest client builder, optionally authenticated with an API key
+                    fn base_client_builder(api_key: Option<&str>) -> reqwest::ClientBuilder {
+                        let user_agent = crate::user_agent::default_user_agent();
                         let mut headers = reqwest::header::HeaderMap::new();
                         headers.insert(
                             reqwest::header::USER_AGENT,
                             user_agent.parse().unwrap(),
                         );
+                        if let Some(api_key) = api_key {
+                            let auth_value = format!("Bearer {}", api_key);
+                            headers.insert(
+                                reqwest::header::AUTHORIZATION,
+                                auth_value.parse().unwrap(),
+                            );
+                        }
 
                         #[cfg(not(target_arch = "wasm32"))]
-                        let client = {
-                            let dur = std::time::Duration::from_secs(15);
+                        let builder = {
+                            let dur = crate::options::DEFAULT_TIMEOUT;
                             reqwest::ClientBuilder::new()
                                 .connect_timeout(dur)
                                 .timeout(dur)
                                 .default_headers(headers)
-                                .build()
-                                .unwrap()
                         };
                         #[cfg(target_arch = "wasm32")]
-                        let client = reqwest::ClientBuilder::new()
-                            .default_headers(headers)
-                            .build()
-                            .unwrap();
+                        let builder = reqwest::ClientBuilder::new().default_headers(headers);
+
+                        // rustls wins when both TLS features are enabled; OpenSSL is only kept
+                        // out of the build when the default `native-tls` feature is disabled
+                        #[cfg(all(feature = "rustls-tls", not(target_arch = "wasm32")))]
+                        let builder = builder.use_rustls_tls();
+                        #[cfg(all(
+                            feature = "native-tls",
+                            not(feature = "rustls-tls"),
+                            not(target_arch = "wasm32")
+                        ))]
+                        let builder = builder.use_native_tls();
+
+                        builder
+                    }
+                };
 
-                        client
+                let base_client_method: syn::ImplItem = parse_quote! {
+                    /// Build the default reqwest client, optionally authenticated with an API key
+                    fn base_client(api_key: Option<&str>) -> reqwest::Client {
+                        Self::base_client_builder(api_key).build().unwrap()
                     }
                 };
 
@@ -378,39 +490,12 @@ impl syn::visit_mut::VisitMut for ClientHeadersModifier {
                 let new_with_api_key_method: syn::ImplItem = parse_quote! {
                     /// Create a new client with an API key for production use with api.sparkscan.io
                     pub fn new_with_api_key(baseurl: &str, api_key: &str) -> Self {
-                        let user_agent = format!("sparkscan-rs/{}", env!("CARGO_PKG_VERSION"));
-                        let mut headers = reqwest::header::HeaderMap::new();
-                        headers.insert(
-                            reqwest::header::USER_AGENT,
-                            user_agent.parse().unwrap(),
-                        );
-                        let auth_value = format!("Bearer {}", api_key);
-                        headers.insert(
-                            reqwest::header::AUTHORIZATION,
-                            auth_value.parse().unwrap(),
-                        );
-
-                        #[cfg(not(target_arch = "wasm32"))]
-                        let client = {
-                            let dur = std::time::Duration::from_secs(15);
-                            reqwest::ClientBuilder::new()
-                                .connect_timeout(dur)
-                                .timeout(dur)
-                                .default_headers(headers)
-                                .build()
-                                .unwrap()
-                        };
-                        #[cfg(target_arch = "wasm32")]
-                        let client = reqwest::ClientBuilder::new()
-                            .default_headers(headers)
-                            .build()
-                            .unwrap();
-
-                        Self::new_with_client(baseurl, client)
+                        Self::new_with_client(baseurl, Self::base_client(Some(api_key)))
                     }
                 };
 
-                item.items.push(get_base_url_method);
+                item.items.push(base_client_builder_method);
+                item.items.push(base_client_method);
                 item.items.push(new_with_api_key_method);
                 self.modified = true;
             }
@@ -420,6 +505,470 @@ impl syn::visit_mut::VisitMut for ClientHeadersModifier {
     }
 }
 
+/// Add the per-client override fields backing `Client::with_api_key`,
+/// `Client::with_credential_provider` and `Client::with_app_identity`.
+///
+/// The overrides are applied by the `ClientHooks::pre` implementation in `src/hooks.rs`.
+struct ClientOverrideFieldsModifier;
+
+impl syn::visit_mut::VisitMut for ClientOverrideFieldsModifier {
+    fn visit_item_struct_mut(&mut self, item: &mut syn::ItemStruct) {
+        if item.ident == "Client"
+            && let syn::Fields::Named(fields) = &mut item.fields
+        {
+            let extra: syn::FieldsNamed = parse_quote!({
+                pub(crate) api_key_override: Option<reqwest::header::HeaderValue>,
+                pub(crate) credential_provider: Option<crate::credentials::SharedCredentialProvider>,
+                pub(crate) user_agent_override: Option<reqwest::header::HeaderValue>
+            });
+            fields.named.extend(extra.named);
+        }
+        syn::visit_mut::visit_item_struct_mut(self, item);
+    }
+
+    fn visit_item_impl_mut(&mut self, item: &mut ItemImpl) {
+        let is_client_impl = matches!(&item.self_ty.as_ref(),
+            syn::Type::Path(p) if p.path.is_ident("Client"));
+
+        if is_client_impl && item.trait_.is_none() {
+            for impl_item in &mut item.items {
+                if let syn::ImplItem::Fn(method) = impl_item
+                    && method.sig.ident == "new_with_client"
+                {
+                    for stmt in &mut method.block.stmts {
+                        if let syn::Stmt::Expr(syn::Expr::Struct(expr), _) = stmt {
+                            expr.fields.push(parse_quote!(api_key_override: None));
+                            expr.fields.push(parse_quote!(credential_provider: None));
+                            expr.fields.push(parse_quote!(user_agent_override: None));
+                        }
+                    }
+                }
+            }
+        }
+
+        syn::visit_mut::visit_item_impl_mut(self, item);
+    }
+}
+
+/// Range constraints of an integer query parameter, read from the OpenAPI spec.
+struct ParamRange {
+    name: String,
+    minimum: Option<i128>,
+    maximum: Option<i128>,
+}
+
+/// Collect the integer query parameter ranges of every operation, keyed by builder struct name.
+fn collect_param_ranges(
+    spec: &serde_json::Value,
+) -> std::collections::HashMap<String, Vec<ParamRange>> {
+    let mut ranges = std::collections::HashMap::new();
+    let Some(paths) = spec["paths"].as_object() else {
+        return ranges;
+    };
+
+    for operation in paths
+        .values()
+        .filter_map(|path| path.as_object())
+        .flat_map(|path| path.values())
+    {
+        let Some(operation_id) = operation["operationId"].as_str() else {
+            continue;
+        };
+        let params: Vec<ParamRange> = operation["parameters"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|param| param["in"] == "query" && param["schema"]["type"] == "integer")
+            .filter_map(|param| {
+                let schema = &param["schema"];
+                let range = ParamRange {
+                    name: param["name"].as_str()?.to_string(),
+                    minimum: schema["minimum"].as_i64().map(i128::from),
+                    maximum: schema["maximum"].as_i64().map(i128::from),
+                };
+                (range.minimum.is_some() || range.maximum.is_some()).then_some(range)
+            })
+            .collect();
+
+        if !params.is_empty() {
+            ranges.insert(builder_struct_name(operation_id), params);
+        }
+    }
+
+    ranges
+}
+
+/// Builder struct name generated by progenitor for an operation id.
+fn builder_struct_name(operation_id: &str) -> String {
+    operation_id
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Validate query parameters in the builder setters, before any request is sent.
+///
+/// Integer parameters are checked against the `minimum`/`maximum` of the spec through
+/// `crate::validation::check_range`, and every network parameter type gets a
+/// `TryFrom<crate::Network>` implementation so setters accept the typed network. Networks the
+/// REST API does not serve fail the request with `ConfigError::UnknownNetwork`.
+struct BuilderValidationInjector {
+    ranges: std::collections::HashMap<String, Vec<ParamRange>>,
+    network_types: Vec<syn::Path>,
+    in_builder_module: bool,
+}
+
+impl BuilderValidationInjector {
+    fn new(spec: &serde_json::Value) -> Self {
+        Self {
+            ranges: collect_param_ranges(spec),
+            network_types: Vec::new(),
+            in_builder_module: false,
+        }
+    }
+
+    /// Get the `T` of a `V: std::convert::TryInto<T>` setter bound.
+    fn setter_target_type(method: &syn::ImplItemFn) -> Option<syn::Path> {
+        let where_clause = method.sig.generics.where_clause.as_ref()?;
+        where_clause.predicates.iter().find_map(|predicate| {
+            let syn::WherePredicate::Type(predicate) = predicate else {
+                return None;
+            };
+            predicate.bounds.iter().find_map(|bound| {
+                let syn::TypeParamBound::Trait(bound) = bound else {
+                    return None;
+                };
+                let segment = bound.path.segments.last()?;
+                if segment.ident != "TryInto" {
+                    return None;
+                }
+                let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+                    return None;
+                };
+                match args.args.first()? {
+                    syn::GenericArgument::Type(syn::Type::Path(target)) => {
+                        Some(target.path.clone())
+                    }
+                    _ => None,
+                }
+            })
+        })
+    }
+
+    fn record_network_type(&mut self, mut path: syn::Path) {
+        // Setters live in the `builder` module; the impls are emitted at the crate root
+        if path
+            .segments
+            .first()
+            .is_some_and(|segment| segment.ident == "super")
+        {
+            path.segments = path.segments.into_iter().skip(1).collect();
+        }
+        // Setters of the `types::builder` module name the types relative to `types`
+        if path.segments.len() == 1 {
+            path.segments.insert(0, parse_quote!(types));
+        }
+        let key = path_key(&path);
+        if !self
+            .network_types
+            .iter()
+            .any(|known| path_key(known) == key)
+        {
+            self.network_types.push(path);
+        }
+    }
+}
+
+fn path_key(path: &syn::Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+impl syn::visit_mut::VisitMut for BuilderValidationInjector {
+    fn visit_file_mut(&mut self, file: &mut syn::File) {
+        syn::visit_mut::visit_file_mut(self, file);
+
+        for path in &self.network_types {
+            file.items.push(parse_quote! {
+                impl ::std::convert::TryFrom<crate::Network> for #path {
+                    type Error = crate::validation::ConfigError;
+
+                    fn try_from(network: crate::Network) -> Result<Self, Self::Error> {
+                        Self::try_from(network.as_str()).map_err(|_| {
+                            crate::validation::ConfigError::UnknownNetwork(network.to_string())
+                        })
+                    }
+                }
+            });
+        }
+    }
+
+    fn visit_item_mod_mut(&mut self, module: &mut syn::ItemMod) {
+        let old_state = self.in_builder_module;
+        self.in_builder_module = module.ident == "builder";
+        syn::visit_mut::visit_item_mod_mut(self, module);
+        self.in_builder_module = old_state;
+    }
+
+    fn visit_item_impl_mut(&mut self, item: &mut ItemImpl) {
+        if !self.in_builder_module || item.trait_.is_some() {
+            return;
+        }
+        let syn::Type::Path(self_ty) = item.self_ty.as_ref() else {
+            return;
+        };
+        let Some(builder) = self_ty.path.segments.last().map(|s| s.ident.to_string()) else {
+            return;
+        };
+
+        for impl_item in &mut item.items {
+            let syn::ImplItem::Fn(method) = impl_item else {
+                continue;
+            };
+            let setter = method.sig.ident.clone();
+
+            if setter == "network" {
+                if let Some(path) = Self::setter_target_type(method) {
+                    self.record_network_type(path);
+                }
+                continue;
+            }
+
+            let Some(range) = self
+                .ranges
+                .get(&builder)
+                .and_then(|params| params.iter().find(|param| setter == param.name.as_str()))
+            else {
+                continue;
+            };
+
+            let name = &range.name;
+            let minimum = option_expr(range.minimum);
+            let maximum = option_expr(range.maximum);
+            let check: syn::Stmt = parse_quote! {
+                self.#setter = self.#setter.and_then(|value| {
+                    crate::validation::check_range(#name, value, #minimum, #maximum)
+                        .map_err(|e| e.to_string())
+                });
+            };
+            // The setter body ends with the returned `self`
+            let position = method.block.stmts.len().saturating_sub(1);
+            method.block.stmts.insert(position, check);
+        }
+    }
+}
+
+fn option_expr(value: Option<i128>) -> syn::Expr {
+    match value {
+        Some(value) => parse_quote!(Some(#value)),
+        None => parse_quote!(None),
+    }
+}
+
+/// Add a per-request `timeout` setter to every builder.
+///
+/// The timeout is stored in an extra builder field and applied to the built `reqwest::Request`
+/// in `send`, overriding the client-wide timeout for that call only. Runs before
+/// `BuilderSendRawInjector` so that `send_raw` honors it as well.
+struct BuilderTimeoutInjector {
+    in_builder_module: bool,
+}
+
+impl BuilderTimeoutInjector {
+    fn new() -> Self {
+        Self {
+            in_builder_module: false,
+        }
+    }
+
+    fn is_builder_struct(item: &syn::ItemStruct) -> bool {
+        matches!(&item.fields, syn::Fields::Named(fields)
+            if fields.named.iter().any(|field| field.ident.as_ref().is_some_and(|ident| ident == "client")))
+    }
+
+    /// Initialize the field in `new`, which ends with `Self { client, .. }`.
+    fn patch_new(method: &mut syn::ImplItemFn) {
+        if let Some(syn::Stmt::Expr(syn::Expr::Struct(expr), _)) = method.block.stmts.last_mut() {
+            expr.fields.push(parse_quote!(timeout: None));
+        }
+    }
+
+    /// Destructure the field in `send` and apply it once the request is built.
+    fn patch_send(method: &mut syn::ImplItemFn) {
+        let mut request_position = None;
+        for (position, stmt) in method.block.stmts.iter_mut().enumerate() {
+            let syn::Stmt::Local(local) = stmt else {
+                continue;
+            };
+            match &mut local.pat {
+                syn::Pat::Struct(pat) if pat.path.is_ident("Self") => {
+                    if let syn::Pat::Struct(extra) = parse_quote!(Self { timeout }) {
+                        pat.fields.extend(extra.fields);
+                    }
+                }
+                syn::Pat::Ident(pat) if pat.ident == "request" => {
+                    request_position = Some(position);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(position) = request_position {
+            let apply: syn::Block = parse_quote!({
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(timeout) = timeout {
+                    *request.timeout_mut() = Some(timeout);
+                }
+                #[cfg(target_arch = "wasm32")]
+                let _ = timeout;
+            });
+            let tail = method.block.stmts.split_off(position + 1);
+            method.block.stmts.extend(apply.stmts);
+            method.block.stmts.extend(tail);
+        }
+    }
+}
+
+impl syn::visit_mut::VisitMut for BuilderTimeoutInjector {
+    fn visit_item_mod_mut(&mut self, module: &mut syn::ItemMod) {
+        let old_state = self.in_builder_module;
+        self.in_builder_module = module.ident == "builder";
+        syn::visit_mut::visit_item_mod_mut(self, module);
+        self.in_builder_module = old_state;
+    }
+
+    fn visit_item_struct_mut(&mut self, item: &mut syn::ItemStruct) {
+        if self.in_builder_module
+            && Self::is_builder_struct(item)
+            && let syn::Fields::Named(fields) = &mut item.fields
+        {
+            let extra: syn::FieldsNamed = parse_quote!({
+                timeout: Option<std::time::Duration>
+            });
+            fields.named.extend(extra.named);
+        }
+    }
+
+    fn visit_item_impl_mut(&mut self, item: &mut ItemImpl) {
+        if !self.in_builder_module || item.trait_.is_some() {
+            return;
+        }
+
+        let mut has_send = false;
+        for impl_item in &mut item.items {
+            let syn::ImplItem::Fn(method) = impl_item else {
+                continue;
+            };
+            if method.sig.ident == "new" {
+                Self::patch_new(method);
+            } else if method.sig.ident == "send" {
+                Self::patch_send(method);
+                has_send = true;
+            }
+        }
+
+        if has_send {
+            item.items.push(parse_quote! {
+                /// Override the client-wide timeout for this request only.
+                ///
+                /// Useful for slow calls such as large exports. Ignored on `wasm32`.
+                pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+                    self.timeout = Some(timeout);
+                    self
+                }
+            });
+        }
+    }
+}
+
+/// Add a `send_raw` method next to every builder `send`.
+///
+/// `send_raw` issues the same request but returns the status code and the body as untyped JSON,
+/// which keeps endpoints usable when the typed decoding breaks on upstream schema changes.
+struct BuilderSendRawInjector {
+    in_builder_module: bool,
+}
+
+impl BuilderSendRawInjector {
+    fn new() -> Self {
+        Self {
+            in_builder_module: false,
+        }
+    }
+
+    /// Derive `send_raw` from a generated `send` method.
+    fn send_raw(send: &syn::ImplItemFn) -> Option<syn::ImplItemFn> {
+        // `send` returns `Result<ResponseValue<T>, Error<E>>`; keep the error type
+        let syn::ReturnType::Type(_, output) = &send.sig.output else {
+            return None;
+        };
+        let syn::Type::Path(output) = output.as_ref() else {
+            return None;
+        };
+        let syn::PathArguments::AngleBracketed(args) = &output.path.segments.last()?.arguments
+        else {
+            return None;
+        };
+        let error_ty = match args.args.iter().nth(1)? {
+            syn::GenericArgument::Type(ty) => ty.clone(),
+            _ => return None,
+        };
+
+        let mut method = send.clone();
+        method.sig.ident = syn::Ident::new("send_raw", send.sig.ident.span());
+        method.sig.output = parse_quote! {
+            -> Result<(reqwest::StatusCode, serde_json::Value), #error_ty>
+        };
+        method.attrs = parse_quote! {
+            /// Send the request and return the status code and the body as untyped JSON.
+            ///
+            /// Unlike `send`, every status code is returned as a success and the body is not
+            /// decoded into the generated types.
+        };
+
+        // The body ends with the `match` dispatching on the status code of `response`
+        let last = method.block.stmts.last_mut()?;
+        *last = syn::Stmt::Expr(
+            parse_quote!(sparkscan_client::raw_json_response(response).await),
+            None,
+        );
+        Some(method)
+    }
+}
+
+impl syn::visit_mut::VisitMut for BuilderSendRawInjector {
+    fn visit_item_mod_mut(&mut self, module: &mut syn::ItemMod) {
+        let old_state = self.in_builder_module;
+        self.in_builder_module = module.ident == "builder";
+        syn::visit_mut::visit_item_mod_mut(self, module);
+        self.in_builder_module = old_state;
+    }
+
+    fn visit_item_impl_mut(&mut self, item: &mut ItemImpl) {
+        if !self.in_builder_module || item.trait_.is_some() {
+            return;
+        }
+
+        let send_raw = item.items.iter().find_map(|impl_item| match impl_item {
+            syn::ImplItem::Fn(method) if method.sig.ident == "send" => Self::send_raw(method),
+            _ => None,
+        });
+        if let Some(send_raw) = send_raw {
+            item.items.push(syn::ImplItem::Fn(send_raw));
+        }
+    }
+}
+
 struct ClientDocumentationModifier {
     modified: bool,
 }
@@ -458,18 +1007,40 @@ impl syn::visit_mut::VisitMut for ClientDocumentationModifier {
     }
 }
 
-#[cfg(feature = "tracing")]
-struct ClientTracingModifier;
+/// Build the middleware stack shared by every generated constructor.
+///
+/// Layers enabled through features (e.g. tracing) are added here so that constructors accepting
+/// extra middleware only need to append to the returned builder.
+#[cfg(feature = "middleware")]
+fn middleware_builder_expr() -> syn::Expr {
+    #[allow(unused_mut)]
+    let mut builder: syn::Expr = parse_quote!(reqwest_middleware::ClientBuilder::new(client));
 
-#[cfg(feature = "tracing")]
-impl syn::visit_mut::VisitMut for ClientTracingModifier {
+    #[cfg(feature = "tracing")]
+    {
+        builder = parse_quote!(#builder.with(reqwest_tracing::TracingMiddleware::default()));
+    }
+
+    #[cfg(feature = "request-id")]
+    {
+        builder = parse_quote!(#builder.with(crate::request_id::RequestIdMiddleware::default()));
+    }
+
+    builder
+}
+
+#[cfg(feature = "middleware")]
+struct ClientMiddlewareModifier;
+
+#[cfg(feature = "middleware")]
+impl syn::visit_mut::VisitMut for ClientMiddlewareModifier {
     fn visit_item_struct_mut(&mut self, item: &mut ItemStruct) {
-        if item.ident == "Client" {
-            if let syn::Fields::Named(fields) = &mut item.fields {
-                for field in &mut fields.named {
-                    if field.ident.as_ref().map(|i| i == "client").unwrap_or(false) {
-                        field.ty = parse_quote!(reqwest_middleware::ClientWithMiddleware);
-                    }
+        if item.ident == "Client"
+            && let syn::Fields::Named(fields) = &mut item.fields
+        {
+            for field in &mut fields.named {
+                if field.ident.as_ref().map(|i| i == "client").unwrap_or(false) {
+                    field.ty = parse_quote!(reqwest_middleware::ClientWithMiddleware);
                 }
             }
         }
@@ -494,54 +1065,21 @@ impl syn::visit_mut::VisitMut for ClientTracingModifier {
 
         if is_client_impl && item.trait_.is_none() {
             // Direct impl Client block
+            let mut has_new_method = false;
             for impl_item in &mut item.items {
                 if let syn::ImplItem::Fn(method) = impl_item {
                     match method.sig.ident.to_string().as_str() {
                         "new" => {
+                            has_new_method = true;
                             method.block = parse_quote! {{
-                                let client = Self::base_client();
-
-                                let client = reqwest_middleware::ClientBuilder::new(client)
-                                    .with(reqwest_tracing::TracingMiddleware::default())
-                                    .build();
-
+                                let client = Self::middleware_builder(Self::base_client(None)).build();
                                 Self::new_with_client(baseurl, client)
                             }};
                         }
                         "new_with_api_key" => {
                             method.block = parse_quote! {{
-                                let user_agent = format!("sparkscan-rs/{}", env!("CARGO_PKG_VERSION"));
-                                let mut headers = reqwest::header::HeaderMap::new();
-                                headers.insert(
-                                    reqwest::header::USER_AGENT,
-                                    user_agent.parse().unwrap(),
-                                );
-                                let auth_value = format!("Bearer {}", api_key);
-                                headers.insert(
-                                    reqwest::header::AUTHORIZATION,
-                                    auth_value.parse().unwrap(),
-                                );
-
-                                #[cfg(not(target_arch = "wasm32"))]
-                                let client = {
-                                    let dur = std::time::Duration::from_secs(15);
-                                    reqwest::ClientBuilder::new()
-                                        .connect_timeout(dur)
-                                        .timeout(dur)
-                                        .default_headers(headers)
-                                        .build()
-                                        .unwrap()
-                                };
-                                #[cfg(target_arch = "wasm32")]
-                                let client = reqwest::ClientBuilder::new()
-                                    .default_headers(headers)
-                                    .build()
-                                    .unwrap();
-
-                                let client = reqwest_middleware::ClientBuilder::new(client)
-                                    .with(reqwest_tracing::TracingMiddleware::default())
-                                    .build();
-
+                                let client =
+                                    Self::middleware_builder(Self::base_client(Some(api_key))).build();
                                 Self::new_with_client(baseurl, client)
                             }};
                         }
@@ -549,25 +1087,72 @@ impl syn::visit_mut::VisitMut for ClientTracingModifier {
                             if let Some(syn::FnArg::Typed(pat_type)) =
                                 method.sig.inputs.iter_mut().nth(1)
                             {
-                                pat_type.ty = Box::new(parse_quote!(
-                                    reqwest_middleware::ClientWithMiddleware
-                                ));
+                                *pat_type.ty =
+                                    parse_quote!(reqwest_middleware::ClientWithMiddleware);
                             }
                         }
                         _ => {}
                     }
                 }
             }
+
+            if has_new_method {
+                let builder = middleware_builder_expr();
+                let middleware_builder_method: syn::ImplItem = parse_quote! {
+                    /// Wrap a reqwest client with the middleware enabled through crate features
+                    fn middleware_builder(client: reqwest::Client) -> reqwest_middleware::ClientBuilder {
+                        #builder
+                    }
+                };
+                item.items.push(middleware_builder_method);
+
+                let default_middleware_builder_method: syn::ImplItem = parse_quote! {
+                    /// Get the middleware stack used by the default constructors, ready for extra layers
+                    pub fn default_middleware_builder(api_key: Option<&str>) -> reqwest_middleware::ClientBuilder {
+                        Self::middleware_builder(Self::base_client(api_key))
+                    }
+                };
+                item.items.push(default_middleware_builder_method);
+
+                let new_with_middleware_method: syn::ImplItem = parse_quote! {
+                    /// Create a new client sending requests through a custom middleware stack
+                    pub fn new_with_middleware(
+                        baseurl: &str,
+                        client: reqwest_middleware::ClientWithMiddleware,
+                    ) -> Self {
+                        Self::new_with_client(baseurl, client)
+                    }
+                };
+                item.items.push(new_with_middleware_method);
+
+                #[cfg(feature = "http-cache")]
+                {
+                    let new_with_http_cache_method: syn::ImplItem = parse_quote! {
+                        /// Create a new client whose GET requests go through an ETag-aware response cache
+                        pub fn new_with_http_cache(
+                            baseurl: &str,
+                            api_key: Option<&str>,
+                            cache: crate::http_cache::HttpCache,
+                        ) -> Self {
+                            let client = Self::middleware_builder(Self::base_client(api_key))
+                                .with(cache)
+                                .build();
+                            Self::new_with_client(baseurl, client)
+                        }
+                    };
+                    item.items.push(new_with_http_cache_method);
+                }
+            }
         } else if is_client_info_impl {
             // impl ClientInfo for Client
             for impl_item in &mut item.items {
-                if let syn::ImplItem::Fn(method) = impl_item {
-                    if method.sig.ident == "client" {
-                        // Change the return type to ClientWithMiddleware
-                        method.sig.output = parse_quote! {
-                            -> &reqwest_middleware::ClientWithMiddleware
-                        };
-                    }
+                if let syn::ImplItem::Fn(method) = impl_item
+                    && method.sig.ident == "client"
+                {
+                    // Change the return type to ClientWithMiddleware
+                    method.sig.output = parse_quote! {
+                        -> &reqwest_middleware::ClientWithMiddleware
+                    };
                 }
             }
         }
@@ -613,7 +1198,7 @@ impl syn::visit_mut::VisitMut for BuilderSendInstrumenter {
             for impl_item in &mut item.items {
                 if let syn::ImplItem::Fn(method) = impl_item {
                     // Check if this is a send method
-                    if method.sig.ident == "send" {
+                    if method.sig.ident == "send" || method.sig.ident == "send_raw" {
                         // Add the tracing attribute if it's not already there
                         let has_instrument = method.attrs.iter().any(|attr| {
                             attr.path().segments.len() == 2
@@ -679,21 +1264,27 @@ impl syn::visit_mut::VisitMut for UntaggedI128Injector {
                     };
 
                     if needs_custom_deserializer {
-                        // Check if it already has a deserialize_with attribute
-                        let already_has_custom = field.attrs.iter().any(|attr| {
-                            attr.path().is_ident("serde")
-                                && format!("{:?}", attr).contains("deserialize_with")
-                        });
+                        let is_option = matches!(&field.ty, syn::Type::Path(type_path)
+                            if type_path.path.segments.last().unwrap().ident == "Option");
+                        // Check if it already has custom (de)serialization attributes
+                        let has_serde_attr = |name: &str| {
+                            field.attrs.iter().any(|attr| {
+                                attr.path().is_ident("serde")
+                                    && attr.meta.require_list().is_ok_and(|list| {
+                                        list.tokens
+                                            .to_string()
+                                            .split(|c: char| !c.is_alphanumeric() && c != '_')
+                                            .any(|word| word == name)
+                                    })
+                            })
+                        };
+                        let already_has_deserializer = has_serde_attr("deserialize_with");
+                        let already_has_serializer = has_serde_attr("serialize_with");
 
-                        if !already_has_custom {
+                        if !already_has_deserializer {
                             // Determine which deserializer to use
-                            let deserializer_name = if let syn::Type::Path(type_path) = &field.ty {
-                                let last_segment = type_path.path.segments.last().unwrap();
-                                if last_segment.ident == "Option" {
-                                    "deserialize_option_i128"
-                                } else {
-                                    "deserialize_i128"
-                                }
+                            let deserializer_name = if is_option {
+                                "deserialize_option_i128"
                             } else {
                                 "deserialize_i128"
                             };
@@ -708,6 +1299,19 @@ impl syn::visit_mut::VisitMut for UntaggedI128Injector {
                                 deserializer_name, item.ident, field_name
                             );
                         }
+
+                        if !already_has_serializer {
+                            // Emit values beyond the range of JSON-safe integers as strings, the
+                            // way the API encodes them, so that responses round-trip
+                            let serializer_name = if is_option {
+                                "crate::numeric::serialize_option_i128"
+                            } else {
+                                "crate::numeric::serialize_i128"
+                            };
+                            field.attrs.push(parse_quote! {
+                                #[serde(serialize_with = #serializer_name)]
+                            });
+                        }
                     }
                 }
             }
@@ -0,0 +1,540 @@
+//! High-level facade over the generated client.
+//!
+//! The generated builders mirror the OpenAPI operation ids, which makes simple calls verbose
+//! (`get_token_holders_v1_tokens_identifier_holders_get().identifier(..).network(..).send()`).
+//! [`SparkScanApi`] binds a client to a network and groups the endpoints by resource:
+//!
+//! ```rust,no_run
+//! use sparkscan::{Client, Network, SparkAddress, SparkScanApi};
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::new_with_api_key("https://api.sparkscan.io", "api-key");
+//!     let api = SparkScanApi::new(client, Network::Mainnet);
+//!
+//!     let address: SparkAddress = "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k"
+//!         .parse()
+//!         .unwrap();
+//!     let summary = api.address(&address).summary().await.unwrap();
+//!     println!("Token count: {}", summary.token_count);
+//!
+//!     let leaderboard = api.stats().token_leaderboard().await.unwrap();
+//!     println!("Tracked tokens: {:?}", leaderboard.total);
+//! });
+//! ```
+//!
+//! The generated builders remain available through [`SparkScanApi::client`] for parameters the
+//! facade does not expose.
+//!
+//! Each endpoint group is only generated with its feature (`address`, `tokens`, `stats`, `bitcoin`
+//! and `transactions`, all enabled by default through `all-endpoints`), so size-constrained builds
+//! such as WASM can leave out the request code they do not use.
+
+#[cfg(feature = "bitcoin")]
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+#[cfg(feature = "export")]
+use futures::TryStreamExt;
+
+#[cfg(feature = "tokens")]
+use crate::cache::token_details_from_response;
+use crate::cache::{CacheConfig, CachedClient};
+use crate::pagination::{DEFAULT_PAGE_SIZE, Page};
+#[cfg(feature = "transactions")]
+use crate::polling::{TxWatermark, latest_transactions_since};
+use crate::{ApiError, Client, Network, SparkAddress, TokenIdentifier, types};
+#[cfg(feature = "bitcoin")]
+use crate::{BitcoinTxid, Error};
+
+/// Ergonomic entry point to the SparkScan REST API for a single network.
+///
+/// Cloning is cheap: clones share the underlying connection pool and lookup cache.
+#[derive(Debug, Clone)]
+pub struct SparkScanApi {
+    client: Client,
+    network: Network,
+    cache: Option<Arc<CachedClient>>,
+}
+
+impl SparkScanApi {
+    /// Create a facade issuing every request against `network`.
+    ///
+    /// The REST API serves [`Network::Mainnet`] and [`Network::Regtest`]; requests for other
+    /// networks fail with [`Error::InvalidRequest`](crate::Error::InvalidRequest).
+    pub fn new(client: Client, network: Network) -> Self {
+        Self {
+            client,
+            network,
+            cache: None,
+        }
+    }
+
+    /// Cache address summaries and token details in memory using `config`.
+    pub fn with_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(Arc::new(CachedClient::with_config(
+            self.client.clone(),
+            config,
+        )));
+        self
+    }
+
+    /// Get a facade for another network sharing this client and cache.
+    pub fn with_network(&self, network: Network) -> Self {
+        Self {
+            network,
+            ..self.clone()
+        }
+    }
+
+    /// Get the network requests are issued against.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Get the underlying generated client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Access the endpoints describing a Spark address.
+    ///
+    /// Requires the `address` feature.
+    #[cfg(feature = "address")]
+    pub fn address(&self, address: &SparkAddress) -> AddressApi<'_> {
+        AddressApi {
+            api: self,
+            address: address.to_string(),
+            force_refresh: false,
+        }
+    }
+
+    /// Access the endpoints describing a token.
+    ///
+    /// Requires the `tokens` feature.
+    #[cfg(feature = "tokens")]
+    pub fn token(&self, identifier: &TokenIdentifier) -> TokenApi<'_> {
+        TokenApi {
+            api: self,
+            identifier: identifier.to_string(),
+            force_refresh: false,
+        }
+    }
+
+    /// Access the network-wide statistics endpoints.
+    ///
+    /// Requires the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> StatsApi<'_> {
+        StatsApi { api: self }
+    }
+
+    /// Access the Bitcoin L1 endpoints.
+    ///
+    /// Requires the `bitcoin` feature.
+    #[cfg(feature = "bitcoin")]
+    pub fn bitcoin(&self) -> BitcoinApi<'_> {
+        BitcoinApi { api: self }
+    }
+
+    /// Get the first page of the most recent transactions on the network.
+    ///
+    /// Requires the `transactions` feature.
+    #[cfg(feature = "transactions")]
+    pub async fn latest_transactions(
+        &self,
+    ) -> Result<Page<types::LatestNetworkTransactionItem>, ApiError> {
+        self.latest_transactions_page(0, DEFAULT_PAGE_SIZE).await
+    }
+
+    /// Get a page of the most recent transactions on the network.
+    ///
+    /// Requires the `transactions` feature.
+    #[cfg(feature = "transactions")]
+    pub async fn latest_transactions_page(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<types::LatestNetworkTransactionItem>, ApiError> {
+        Page::latest_transactions(&self.client, self.network.as_str(), offset, limit).await
+    }
+
+    /// Get the recent transactions that `watermark` has not seen yet, oldest first.
+    ///
+    /// See [`latest_transactions_since`] for details. Requires the `transactions` feature.
+    #[cfg(feature = "transactions")]
+    pub async fn latest_transactions_since(
+        &self,
+        watermark: &mut TxWatermark,
+    ) -> Result<Vec<types::LatestNetworkTransactionItem>, ApiError> {
+        latest_transactions_since(&self.client, self.network.as_str(), watermark).await
+    }
+}
+
+/// Summary, token holdings and recent transactions of an address, fetched together.
+#[cfg(feature = "address")]
+#[derive(Debug, Clone)]
+pub struct AddressDossier {
+    /// Balance, holdings and activity summary
+    pub summary: types::AddressSummaryResponse,
+    /// Token holdings
+    pub tokens: types::AddressTokensResponse,
+    /// First page of the most recent transactions
+    pub transactions: Page<types::AddressTransaction>,
+}
+
+/// Endpoints scoped to a single Spark address.
+#[cfg(feature = "address")]
+#[derive(Debug, Clone)]
+pub struct AddressApi<'a> {
+    api: &'a SparkScanApi,
+    address: String,
+    force_refresh: bool,
+}
+
+#[cfg(feature = "address")]
+impl AddressApi<'_> {
+    /// Bypass the lookup cache for the next calls made through this handle.
+    pub fn force_refresh(mut self) -> Self {
+        self.force_refresh = true;
+        self
+    }
+
+    /// Get the address as passed to [`SparkScanApi::address`].
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Get the balance, holdings and activity summary of the address.
+    pub async fn summary(&self) -> Result<types::AddressSummaryResponse, ApiError> {
+        let network = self.api.network.as_str();
+        if let Some(cache) = &self.api.cache {
+            return cache
+                .address_summary(&self.address, network, self.force_refresh)
+                .await;
+        }
+
+        Ok(self
+            .api
+            .client
+            .address_summary_v1_address_address_get()
+            .address(self.address.as_str())
+            .network(network)
+            .send()
+            .await?
+            .into_inner())
+    }
+
+    /// Get the summary, token holdings and recent transactions of the address at once.
+    ///
+    /// The three requests are sent concurrently; the first failure is returned.
+    pub async fn dossier(&self) -> Result<AddressDossier, ApiError> {
+        let (summary, tokens, transactions) =
+            futures::try_join!(self.summary(), self.tokens(), self.transactions())?;
+        Ok(AddressDossier {
+            summary,
+            tokens,
+            transactions,
+        })
+    }
+
+    /// Get the token holdings of the address.
+    pub async fn tokens(&self) -> Result<types::AddressTokensResponse, ApiError> {
+        Ok(self
+            .api
+            .client
+            .get_address_tokens_v1_address_address_tokens_get()
+            .address(self.address.as_str())
+            .network(self.api.network)
+            .send()
+            .await?
+            .into_inner())
+    }
+
+    /// Get the first page of the most recent transactions of the address.
+    pub async fn transactions(&self) -> Result<Page<types::AddressTransaction>, ApiError> {
+        self.transactions_page(0, DEFAULT_PAGE_SIZE).await
+    }
+
+    /// Get a page of the most recent transactions of the address.
+    pub async fn transactions_page(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<types::AddressTransaction>, ApiError> {
+        let network = self.api.network.as_str();
+        Page::address_transactions(&self.api.client, &self.address, network, offset, limit).await
+    }
+}
+
+/// Endpoints scoped to a single token.
+#[cfg(feature = "tokens")]
+#[derive(Debug, Clone)]
+pub struct TokenApi<'a> {
+    api: &'a SparkScanApi,
+    identifier: String,
+    force_refresh: bool,
+}
+
+#[cfg(feature = "tokens")]
+impl TokenApi<'_> {
+    /// Bypass the lookup cache for the next calls made through this handle.
+    pub fn force_refresh(mut self) -> Self {
+        self.force_refresh = true;
+        self
+    }
+
+    /// Get the token identifier as passed to [`SparkScanApi::token`].
+    pub fn identifier(&self) -> &str {
+        &self.identifier
+    }
+
+    /// Get the metadata, supply and market data of the token.
+    pub async fn details(&self) -> Result<types::TokenDetailsResponse, ApiError> {
+        let network = self.api.network.as_str();
+        if let Some(cache) = &self.api.cache {
+            return cache
+                .token_details(&self.identifier, network, self.force_refresh)
+                .await;
+        }
+
+        let response = self
+            .api
+            .client
+            .get_token_info_by_identifier_v1_tokens_identifier_get()
+            .identifier(self.identifier.as_str())
+            .network(network)
+            .send()
+            .await?
+            .into_inner();
+        token_details_from_response(&response)
+    }
+
+    /// Get the first page of the largest holders of the token.
+    pub async fn holders(&self) -> Result<Page<types::TokenHolder>, ApiError> {
+        self.holders_page(0, DEFAULT_PAGE_SIZE).await
+    }
+
+    /// Get a page of the largest holders of the token.
+    pub async fn holders_page(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<types::TokenHolder>, ApiError> {
+        let network = self.api.network.as_str();
+        Page::token_holders(&self.api.client, &self.identifier, network, offset, limit).await
+    }
+
+    /// Get every holder of the token, paging through the holders endpoint.
+    ///
+    /// Requires the `export` feature.
+    #[cfg(feature = "export")]
+    pub async fn all_holders(&self) -> Result<Vec<types::TokenHolder>, ApiError> {
+        self.holders_stream(crate::export::ExportOptions::default())
+            .try_collect()
+            .await
+    }
+
+    /// Stream every holder of the token, fetching pages lazily.
+    ///
+    /// Requires the `export` feature.
+    #[cfg(feature = "export")]
+    pub fn holders_stream(
+        &self,
+        options: crate::export::ExportOptions,
+    ) -> impl futures::Stream<Item = Result<types::TokenHolder, ApiError>> + use<> {
+        let query = crate::pagination::PageQuery::TokenHolders {
+            identifier: self.identifier.clone(),
+            network: self.api.network.as_str().to_string(),
+        };
+        crate::export::stream_all(self.api.client.clone(), query, options)
+    }
+
+    /// Write every holder of the token to `writer` as CSV, returning the number of rows.
+    ///
+    /// Requires the `csv` feature.
+    #[cfg(feature = "csv")]
+    pub async fn export_holders_csv<W: std::io::Write>(
+        &self,
+        writer: W,
+        options: crate::export::ExportOptions,
+    ) -> Result<usize, crate::export::ExportError> {
+        crate::export::write_holders_csv(self.holders_stream(options), writer).await
+    }
+
+    /// Get the first page of the most recent transactions of the token.
+    pub async fn transactions(&self) -> Result<Page<types::TokenTransaction>, ApiError> {
+        self.transactions_page(0, DEFAULT_PAGE_SIZE).await
+    }
+
+    /// Get a page of the most recent transactions of the token.
+    pub async fn transactions_page(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<types::TokenTransaction>, ApiError> {
+        let network = self.api.network.as_str();
+        Page::token_transactions(&self.api.client, &self.identifier, network, offset, limit).await
+    }
+}
+
+/// Network-wide statistics endpoints.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone)]
+pub struct StatsApi<'a> {
+    api: &'a SparkScanApi,
+}
+
+#[cfg(feature = "stats")]
+impl StatsApi<'_> {
+    /// Get the network summary (TVL, active accounts, 24h transactions, BTC price).
+    pub async fn summary(&self) -> Result<types::NetworkStats, ApiError> {
+        Ok(self
+            .api
+            .client
+            .get_network_stats_v1_stats_summary_get()
+            .network(self.api.network)
+            .send()
+            .await?
+            .into_inner())
+    }
+
+    /// Get the first page of the token leaderboard ranked by market capitalization.
+    pub async fn token_leaderboard(&self) -> Result<Page<types::TokenLeaderboardEntry>, ApiError> {
+        self.token_leaderboard_page(0, DEFAULT_PAGE_SIZE).await
+    }
+
+    /// Get a page of the token leaderboard ranked by market capitalization.
+    pub async fn token_leaderboard_page(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<types::TokenLeaderboardEntry>, ApiError> {
+        Page::token_leaderboard(&self.api.client, self.api.network.as_str(), offset, limit).await
+    }
+
+    /// Get the wallet leaderboard ranked by total value.
+    pub async fn wallet_leaderboard(&self) -> Result<types::WalletLeaderboard, ApiError> {
+        Ok(self
+            .api
+            .client
+            .get_wallet_leaderboard_v1_stats_leaderboard_wallets_get()
+            .network(self.api.network)
+            .send()
+            .await?
+            .into_inner())
+    }
+}
+
+/// Maximum number of addresses accepted by the latest txid endpoint in one request.
+pub const LATEST_TXIDS_CHUNK_SIZE: usize = 100;
+
+/// Bitcoin L1 endpoints.
+#[cfg(feature = "bitcoin")]
+#[derive(Debug, Clone)]
+pub struct BitcoinApi<'a> {
+    api: &'a SparkScanApi,
+}
+
+#[cfg(feature = "bitcoin")]
+impl BitcoinApi<'_> {
+    /// Get the latest transaction id of each Bitcoin address.
+    ///
+    /// Lists longer than [`LATEST_TXIDS_CHUNK_SIZE`] are split into several sequential requests
+    /// and duplicates are only queried once. Addresses without any transaction are left out of
+    /// the returned map, and a txid that is not 64 hexadecimal characters fails the call with
+    /// [`Error::Custom`](crate::Error::Custom).
+    pub async fn latest_txids<S: AsRef<str>>(
+        &self,
+        addresses: &[S],
+    ) -> Result<HashMap<String, BitcoinTxid>, ApiError> {
+        let mut seen = HashSet::with_capacity(addresses.len());
+        let unique: Vec<String> = addresses
+            .iter()
+            .map(|address| address.as_ref())
+            .filter(|address| seen.insert(*address))
+            .map(str::to_string)
+            .collect();
+
+        let mut txids = HashMap::with_capacity(unique.len());
+        for chunk in unique.chunks(LATEST_TXIDS_CHUNK_SIZE) {
+            let response = self
+                .api
+                .client
+                .get_addresses_latest_txid_v1_bitcoin_addresses_latest_txid_post()
+                .network(self.api.network)
+                .body(chunk.to_vec())
+                .send()
+                .await?
+                .into_inner();
+            for (address, txid) in response {
+                if let Some(txid) = txid {
+                    let txid = txid
+                        .parse::<BitcoinTxid>()
+                        .map_err(|e| Error::Custom(e.to_string()))?;
+                    txids.insert(address, txid);
+                }
+            }
+        }
+
+        Ok(txids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(feature = "address", feature = "tokens"))]
+    fn test_facade_scoping() {
+        let api = SparkScanApi::new(Client::new("https://api.sparkscan.io"), Network::Regtest);
+        assert_eq!(api.network(), Network::Regtest);
+        assert_eq!(
+            api.with_network(Network::Mainnet).network(),
+            Network::Mainnet
+        );
+        let address: SparkAddress = "sp1pgssyv42njtx".parse().unwrap();
+        assert_eq!(api.address(&address).address(), "sp1pgssyv42njtx");
+        let token: TokenIdentifier = "btkn1qpzry9x8gf2".parse().unwrap();
+        assert_eq!(api.token(&token).identifier(), "btkn1qpzry9x8gf2");
+    }
+
+    #[cfg(feature = "bitcoin")]
+    #[tokio::test]
+    async fn test_latest_txids_are_validated() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const TXID: &str = "4A5E1E4BAAB89F3A32518A88C31BC87F618F76673E2CC77AB2127B7AFDEDA33B";
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/bitcoin/addresses/latest-txid"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "bc1qfunded": TXID,
+                "bc1qempty": null
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/bitcoin/addresses/latest-txid"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "bc1qfunded": "not-a-txid"
+            })))
+            .mount(&server)
+            .await;
+
+        let api = SparkScanApi::new(Client::new(&server.uri()), Network::Mainnet);
+        let txids = api
+            .bitcoin()
+            .latest_txids(&["bc1qfunded", "bc1qempty", "bc1qfunded"])
+            .await
+            .unwrap();
+        assert_eq!(txids.len(), 1);
+        assert_eq!(txids["bc1qfunded"].as_str(), TXID.to_ascii_lowercase());
+
+        let result = api.bitcoin().latest_txids(&["bc1qfunded"]).await;
+        assert!(matches!(result, Err(Error::Custom(_))));
+    }
+}
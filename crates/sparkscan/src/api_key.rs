@@ -0,0 +1,130 @@
+//! Per-client API key overrides.
+//!
+//! Multi-tenant services often call the API on behalf of several customers, each with their own
+//! key. The override is stored on the client and applied through
+//! [`ClientHooks::pre`](sparkscan_client::ClientHooks::pre), so it works with or without the
+//! `middleware` feature.
+
+use reqwest::header::{HeaderValue, InvalidHeaderValue};
+
+use crate::Client;
+
+impl Client {
+    /// Get a copy of this client authenticating every request with `api_key`.
+    ///
+    /// The copy shares the connection pool and middleware of `self`; an `HttpCache` in that stack
+    /// keeps the responses of each key apart. The key replaces any `Authorization` header
+    /// configured on the underlying client, including one set through
+    /// [`Client::new_with_api_key`].
+    ///
+    /// Fails if `api_key` contains characters that are not allowed in an HTTP header.
+    ///
+    /// ```rust,no_run
+    /// use sparkscan::Client;
+    ///
+    /// tokio_test::block_on(async {
+    ///     let shared = Client::new("https://api.sparkscan.io");
+    ///
+    ///     // Cheap enough to derive once per incoming request
+    ///     let tenant = shared.with_api_key("tenant-api-key").unwrap();
+    ///     let response = tenant
+    ///         .get_network_stats_v1_stats_summary_get()
+    ///         .network("MAINNET")
+    ///         .send()
+    ///         .await
+    ///         .unwrap();
+    ///     println!("Active accounts: {}", response.active_accounts);
+    /// });
+    /// ```
+    pub fn with_api_key(&self, api_key: &str) -> Result<Self, InvalidHeaderValue> {
+        let mut auth_value = HeaderValue::from_str(&format!("Bearer {}", api_key))?;
+        auth_value.set_sensitive(true);
+
+        let mut client = self.clone();
+        client.api_key_override = Some(auth_value);
+        client.credential_provider = None;
+        Ok(client)
+    }
+
+    /// Check if this client overrides the API key of the underlying HTTP client.
+    pub fn has_api_key_override(&self) -> bool {
+        self.api_key_override.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_api_key_sets_override() {
+        let client = Client::new("https://api.sparkscan.io");
+        assert!(!client.has_api_key_override());
+
+        let tenant = client.with_api_key("tenant-key").unwrap();
+        assert!(tenant.has_api_key_override());
+        assert!(!client.has_api_key_override());
+
+        let auth_value = tenant.api_key_override.as_ref().unwrap();
+        assert_eq!(auth_value, "Bearer tenant-key");
+        assert!(auth_value.is_sensitive());
+    }
+
+    #[test]
+    fn test_with_api_key_rejects_invalid_header() {
+        let client = Client::new("https://api.sparkscan.io");
+        assert!(client.with_api_key("bad\nkey").is_err());
+    }
+
+    #[cfg(all(feature = "http-cache", feature = "tokens"))]
+    #[tokio::test]
+    async fn test_with_api_key_does_not_share_cached_responses() {
+        use std::time::Duration;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for (key, balance) in [("first-key", 100), ("second-key", 200)] {
+            Mock::given(method("GET"))
+                .and(path("/v1/tokens/btkn1abc/holders"))
+                .and(header("authorization", format!("Bearer {}", key).as_str()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "meta": {"totalItems": 1, "limit": 1, "offset": 0},
+                    "data": [{
+                        "address": "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k",
+                        "pubkey": "02a3f1c0d2e4b5968778695a4b3c2d1e0f11223344556677889900aabbccddeeff",
+                        "balance": balance,
+                        "valueUsd": 1.25,
+                        "percentage": 1.25
+                    }]
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let cache = crate::http_cache::HttpCache::new(Duration::from_secs(60));
+        let shared = Client::new_with_http_cache(&server.uri(), None, cache.clone());
+        let balance = |client: Client| async move {
+            client
+                .get_token_holders_v1_tokens_identifier_holders_get()
+                .identifier("btkn1abc")
+                .network("MAINNET")
+                .limit(1)
+                .send()
+                .await
+                .unwrap()
+                .data[0]
+                .balance
+        };
+
+        // The second call of each key is answered from the cache
+        let first = shared.with_api_key("first-key").unwrap();
+        let second = shared.with_api_key("second-key").unwrap();
+        assert_eq!(balance(first.clone()).await, 100);
+        assert_eq!(balance(second.clone()).await, 200);
+        assert_eq!(balance(first).await, 100);
+        assert_eq!(balance(second).await, 200);
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,322 @@
+//! Typed in-process cache for expensive lookups.
+//!
+//! Unlike the `http-cache` feature, which works on raw HTTP responses, this module caches decoded
+//! values. [`CachedClient`] wraps a [`Client`] and keeps address summaries and token
+//! details in memory for a configurable time per endpoint, with a `force_refresh` flag on every
+//! lookup to bypass stale data on demand.
+//!
+//! ```rust,no_run
+//! use sparkscan::{Client, cache::{CacheConfig, CachedClient}};
+//! use std::time::Duration;
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::new_with_api_key("https://api.sparkscan.io", "api-key");
+//!     let cached = CachedClient::with_config(
+//!         client,
+//!         CacheConfig::default().with_address_summary_ttl(Duration::from_secs(10)),
+//!     );
+//!
+//!     let address = "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k";
+//!     // Served from the network, then from memory for the next 10 seconds
+//!     let summary = cached.address_summary(address, "MAINNET", false).await.unwrap();
+//!     let again = cached.address_summary(address, "MAINNET", false).await.unwrap();
+//!     assert_eq!(summary.transaction_count, again.transaction_count);
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{ApiError, Client, Error, types};
+
+/// A thread-safe map whose entries expire after a fixed time-to-live.
+///
+/// Expired entries are dropped lazily on lookup, or eagerly through [`TtlCache::purge_expired`].
+#[derive(Debug)]
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Eq + Hash,
+    V: Clone,
+{
+    /// Create an empty cache whose entries live for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Time-to-live applied to every entry.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Get a clone of the value stored for `key`, if present and not expired.
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries();
+        match entries.get(key) {
+            Some((stored_at, value)) if stored_at.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Store `value` for `key`, replacing any previous entry and resetting its age.
+    pub fn insert(&self, key: K, value: V) {
+        self.entries().insert(key, (Instant::now(), value));
+    }
+
+    /// Remove the entry for `key`, if any.
+    pub fn invalidate(&self, key: &K) {
+        self.entries().remove(key);
+    }
+
+    /// Remove every entry.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    /// Remove every expired entry.
+    pub fn purge_expired(&self) {
+        let ttl = self.ttl;
+        self.entries()
+            .retain(|_, (stored_at, _)| stored_at.elapsed() < ttl);
+    }
+
+    /// Number of stored entries, including expired ones not yet purged.
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    /// Check if the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<K, (Instant, V)>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Per-endpoint time-to-live settings for [`CachedClient`].
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// How long address summaries are reused (default: 30 seconds)
+    pub address_summary_ttl: Duration,
+    /// How long token details are reused (default: 5 minutes)
+    pub token_details_ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            address_summary_ttl: Duration::from_secs(30),
+            token_details_ttl: Duration::from_secs(300),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Configure how long address summaries are reused.
+    pub fn with_address_summary_ttl(mut self, ttl: Duration) -> Self {
+        self.address_summary_ttl = ttl;
+        self
+    }
+
+    /// Configure how long token details are reused.
+    pub fn with_token_details_ttl(mut self, ttl: Duration) -> Self {
+        self.token_details_ttl = ttl;
+        self
+    }
+}
+
+/// Cache key made of the looked-up identifier and the network wire name.
+type LookupKey = (String, String);
+
+/// Client wrapper caching the results of expensive lookups in memory.
+#[derive(Debug)]
+pub struct CachedClient {
+    client: Client,
+    address_summaries: TtlCache<LookupKey, types::AddressSummaryResponse>,
+    token_details: TtlCache<LookupKey, types::TokenDetailsResponse>,
+}
+
+impl CachedClient {
+    /// Wrap `client` using the default [`CacheConfig`].
+    pub fn new(client: Client) -> Self {
+        Self::with_config(client, CacheConfig::default())
+    }
+
+    /// Wrap `client` using custom per-endpoint TTLs.
+    pub fn with_config(client: Client, config: CacheConfig) -> Self {
+        Self {
+            client,
+            address_summaries: TtlCache::new(config.address_summary_ttl),
+            token_details: TtlCache::new(config.token_details_ttl),
+        }
+    }
+
+    /// Get the wrapped client for uncached calls.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Get the summary of a Spark address, reusing a cached value unless `force_refresh` is set.
+    ///
+    /// `network` is the API network name (`"MAINNET"` or `"REGTEST"`).
+    ///
+    /// Requires the `address` feature.
+    #[cfg(feature = "address")]
+    pub async fn address_summary(
+        &self,
+        address: &str,
+        network: &str,
+        force_refresh: bool,
+    ) -> Result<types::AddressSummaryResponse, ApiError> {
+        let key = (address.to_string(), network.to_string());
+        if !force_refresh && let Some(summary) = self.address_summaries.get(&key) {
+            return Ok(summary);
+        }
+
+        let summary = self
+            .client
+            .address_summary_v1_address_address_get()
+            .address(address)
+            .network(network)
+            .send()
+            .await?
+            .into_inner();
+        self.address_summaries.insert(key, summary.clone());
+        Ok(summary)
+    }
+
+    /// Get the details of a token, reusing a cached value unless `force_refresh` is set.
+    ///
+    /// `identifier` must resolve to a single token; free-text searches are rejected with
+    /// [`Error::Custom`] since their results are not cached.
+    ///
+    /// Requires the `tokens` feature.
+    #[cfg(feature = "tokens")]
+    pub async fn token_details(
+        &self,
+        identifier: &str,
+        network: &str,
+        force_refresh: bool,
+    ) -> Result<types::TokenDetailsResponse, ApiError> {
+        let key = (identifier.to_string(), network.to_string());
+        if !force_refresh && let Some(details) = self.token_details.get(&key) {
+            return Ok(details);
+        }
+
+        let response = self
+            .client
+            .get_token_info_by_identifier_v1_tokens_identifier_get()
+            .identifier(identifier)
+            .network(network)
+            .send()
+            .await?
+            .into_inner();
+        let details = token_details_from_response(&response)?;
+        self.token_details.insert(key, details.clone());
+        Ok(details)
+    }
+
+    /// Drop the cached summary of `address` on `network`.
+    pub fn invalidate_address_summary(&self, address: &str, network: &str) {
+        self.address_summaries
+            .invalidate(&(address.to_string(), network.to_string()));
+    }
+
+    /// Drop the cached details of `identifier` on `network`.
+    pub fn invalidate_token_details(&self, identifier: &str, network: &str) {
+        self.token_details
+            .invalidate(&(identifier.to_string(), network.to_string()));
+    }
+
+    /// Drop every cached value.
+    pub fn clear(&self) {
+        self.address_summaries.clear();
+        self.token_details.clear();
+    }
+}
+
+/// Extract token details from the token info endpoint, which answers either with the details of
+/// a single token or with a list of search results.
+#[cfg(feature = "tokens")]
+#[allow(clippy::result_large_err)]
+pub(crate) fn token_details_from_response<T: serde::Serialize>(
+    response: &T,
+) -> Result<types::TokenDetailsResponse, ApiError> {
+    // Round-trip through bytes rather than `serde_json::Value`, which cannot hold i128 amounts
+    let bytes = serde_json::to_vec(response).map_err(|e| Error::Custom(e.to_string()))?;
+    serde_json::from_slice(&bytes).map_err(|_| {
+        Error::Custom("token identifier matched a search result list, not a single token".into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ttl_cache_get_and_insert() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        assert!(cache.is_empty());
+
+        cache.insert("a", 1);
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"b"), None);
+
+        cache.insert("a", 2);
+        assert_eq!(cache.get(&"a"), Some(2));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_ttl_cache_expiry() {
+        let cache = TtlCache::new(Duration::ZERO);
+        cache.insert("a", 1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&"a"), None);
+        assert!(cache.is_empty());
+
+        cache.insert("b", 2);
+        cache.purge_expired();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_ttl_cache_invalidate_and_clear() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+
+        cache.invalidate(&"a");
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), Some(2));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_config_builder() {
+        let config = CacheConfig::default()
+            .with_address_summary_ttl(Duration::from_secs(5))
+            .with_token_details_ttl(Duration::from_secs(10));
+        assert_eq!(config.address_summary_ttl, Duration::from_secs(5));
+        assert_eq!(config.token_details_ttl, Duration::from_secs(10));
+    }
+}
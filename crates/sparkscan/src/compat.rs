@@ -0,0 +1,169 @@
+//! Compatibility of the server with the API version the client was generated from.
+//!
+//! [`Client::check_compatibility`] asks the root endpoint for the version of the server and
+//! compares it with [`API_VERSION`](crate::openapi::API_VERSION), so that a deployment talking to
+//! an incompatible server fails at startup rather than on the first response that does not
+//! decode:
+//!
+//! ```rust,no_run
+//! use sparkscan::Client;
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::mainnet("api-key");
+//!     let report = client.check_compatibility().await.unwrap();
+//!     println!("{}", report);
+//!     assert!(report.is_compatible());
+//! });
+//! ```
+//!
+//! Versions are compared as semantic versions: servers with the same major version are
+//! compatible. Servers that do not report a version are reported as
+//! [`Compatibility::Unknown`], which is not treated as incompatible.
+//!
+//! The WebSocket feed is not covered: its client does not expose the reply the server sends when
+//! connecting, where the server version would be found.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::openapi::API_VERSION;
+use crate::{Client, Error};
+
+/// Keys of the root response that may carry the server version.
+const VERSION_KEYS: &[&str] = &["version", "apiVersion", "api_version"];
+
+/// How the server version relates to the version the client was generated from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    /// Same version
+    Exact,
+    /// Same major version, newer server: responses may carry fields the client ignores
+    ServerNewer,
+    /// Same major version, older server: endpoints or fields the client expects may be missing
+    ServerOlder,
+    /// Different major version: responses are likely not to decode
+    Incompatible,
+    /// The server did not report a version that could be compared
+    Unknown,
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compatibility::Exact => "exact match",
+            Compatibility::ServerNewer => "compatible, server is newer",
+            Compatibility::ServerOlder => "compatible, server is older",
+            Compatibility::Incompatible => "incompatible",
+            Compatibility::Unknown => "unknown",
+        })
+    }
+}
+
+/// Result of [`Client::check_compatibility`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    /// API version the client was generated from
+    pub generated_version: &'static str,
+    /// API version reported by the server, if any
+    pub server_version: Option<String>,
+    /// How the two versions relate
+    pub compatibility: Compatibility,
+}
+
+impl CompatibilityReport {
+    fn new(server_version: Option<String>) -> Self {
+        let compatibility = match server_version.as_deref() {
+            Some(server) => compare(API_VERSION, server),
+            None => Compatibility::Unknown,
+        };
+        Self {
+            generated_version: API_VERSION,
+            server_version,
+            compatibility,
+        }
+    }
+
+    /// Check whether the client can be expected to work with the server.
+    ///
+    /// Only [`Compatibility::Incompatible`] fails the check.
+    pub fn is_compatible(&self) -> bool {
+        self.compatibility != Compatibility::Incompatible
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "server {}, client generated from {}: {}",
+            self.server_version.as_deref().unwrap_or("version unknown"),
+            self.generated_version,
+            self.compatibility
+        )
+    }
+}
+
+impl Client {
+    /// Compare the version reported by the server with the one the client was generated from.
+    ///
+    /// Fails only if the root endpoint cannot be reached; see [`CompatibilityReport`] for the
+    /// outcome of the comparison.
+    pub async fn check_compatibility(&self) -> Result<CompatibilityReport, Error> {
+        let root = self.root_get().send().await?.into_inner();
+        let server_version = VERSION_KEYS
+            .iter()
+            .find_map(|key| root[*key].as_str())
+            .map(str::to_string);
+        Ok(CompatibilityReport::new(server_version))
+    }
+}
+
+/// Compare two `major.minor.patch` versions, ignoring pre-release and build suffixes.
+fn compare(generated: &str, server: &str) -> Compatibility {
+    let (Some(generated), Some(server)) = (parse_version(generated), parse_version(server)) else {
+        return Compatibility::Unknown;
+    };
+    if generated[0] != server[0] {
+        return Compatibility::Incompatible;
+    }
+    match server.cmp(&generated) {
+        Ordering::Greater => Compatibility::ServerNewer,
+        Ordering::Less => Compatibility::ServerOlder,
+        Ordering::Equal => Compatibility::Exact,
+    }
+}
+
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let version = version.trim().trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some([major, minor, patch])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare("1.2.8", "1.2.8"), Compatibility::Exact);
+        assert_eq!(compare("1.2.8", "v1.2.8+build.5"), Compatibility::Exact);
+        assert_eq!(compare("1.2.8", "1.10.0"), Compatibility::ServerNewer);
+        assert_eq!(compare("1.2.8", "1.2"), Compatibility::ServerOlder);
+        assert_eq!(compare("1.2.8", "2.0.0-rc.1"), Compatibility::Incompatible);
+        assert_eq!(compare("1.2.8", "latest"), Compatibility::Unknown);
+
+        let report = CompatibilityReport::new(None);
+        assert!(report.is_compatible());
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "server version unknown, client generated from {}: unknown",
+                API_VERSION
+            )
+        );
+    }
+}
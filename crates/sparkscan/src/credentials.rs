@@ -0,0 +1,139 @@
+//! API keys resolved per request.
+//!
+//! A [`CredentialProvider`] is consulted before every request, so long-running services can
+//! rotate their key without rebuilding the client. Any `Fn() -> String` is a provider, and
+//! [`RotatingApiKey`] is a ready-made shared handle:
+//!
+//! ```rust
+//! use sparkscan::{Client, credentials::RotatingApiKey};
+//!
+//! let api_key = RotatingApiKey::new("first-key");
+//! let client = Client::new_with_credential_provider("https://api.sparkscan.io", api_key.clone());
+//!
+//! // Later, e.g. from a secret manager refresh task
+//! api_key.rotate("second-key");
+//! ```
+
+use std::sync::{Arc, RwLock};
+
+use reqwest::header::HeaderValue;
+
+use crate::Client;
+
+/// Source of the API key sent with each request.
+pub trait CredentialProvider: Send + Sync {
+    /// Get the API key for the next request.
+    fn api_key(&self) -> String;
+}
+
+impl<F> CredentialProvider for F
+where
+    F: Fn() -> String + Send + Sync,
+{
+    fn api_key(&self) -> String {
+        self()
+    }
+}
+
+/// Shared API key that can be replaced while clients are using it.
+///
+/// Clones share the same key.
+#[derive(Clone)]
+pub struct RotatingApiKey(Arc<RwLock<String>>);
+
+impl RotatingApiKey {
+    /// Create a handle holding `api_key`.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self(Arc::new(RwLock::new(api_key.into())))
+    }
+
+    /// Replace the key used by subsequent requests.
+    pub fn rotate(&self, api_key: impl Into<String>) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = api_key.into();
+    }
+
+    /// Get the current key.
+    pub fn current(&self) -> String {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl std::fmt::Debug for RotatingApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RotatingApiKey(..)")
+    }
+}
+
+impl CredentialProvider for RotatingApiKey {
+    fn api_key(&self) -> String {
+        self.current()
+    }
+}
+
+/// Credential provider stored on a [`Client`].
+#[derive(Clone)]
+pub(crate) struct SharedCredentialProvider(Arc<dyn CredentialProvider>);
+
+impl SharedCredentialProvider {
+    /// Build the `Authorization` header for the next request.
+    pub(crate) fn auth_value(&self) -> Result<HeaderValue, reqwest::header::InvalidHeaderValue> {
+        let mut auth_value = HeaderValue::from_str(&format!("Bearer {}", self.0.api_key()))?;
+        auth_value.set_sensitive(true);
+        Ok(auth_value)
+    }
+}
+
+impl std::fmt::Debug for SharedCredentialProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedCredentialProvider(..)")
+    }
+}
+
+impl Client {
+    /// Create a new client resolving its API key through `provider` before every request.
+    pub fn new_with_credential_provider(
+        baseurl: &str,
+        provider: impl CredentialProvider + 'static,
+    ) -> Self {
+        Self::new(baseurl).with_credential_provider(provider)
+    }
+
+    /// Get a copy of this client resolving its API key through `provider` before every request.
+    ///
+    /// The provider replaces any key set with [`Client::with_api_key`]. Requests fail with
+    /// [`Error::InvalidRequest`](crate::Error::InvalidRequest) if it returns a key that is not
+    /// a valid HTTP header value.
+    pub fn with_credential_provider(&self, provider: impl CredentialProvider + 'static) -> Self {
+        let mut client = self.clone();
+        client.api_key_override = None;
+        client.credential_provider = Some(SharedCredentialProvider(Arc::new(provider)));
+        client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotating_api_key() {
+        let api_key = RotatingApiKey::new("first-key");
+        let shared = api_key.clone();
+        api_key.rotate("second-key");
+        assert_eq!(shared.api_key(), "second-key");
+        assert_eq!(format!("{:?}", shared), "RotatingApiKey(..)");
+    }
+
+    #[test]
+    fn test_provider_replaces_static_key() {
+        let client = Client::new("https://api.sparkscan.io")
+            .with_api_key("static-key")
+            .unwrap()
+            .with_credential_provider(|| "dynamic-key".to_string());
+        assert!(!client.has_api_key_override());
+
+        let auth_value = client.credential_provider.unwrap().auth_value().unwrap();
+        assert_eq!(auth_value, "Bearer dynamic-key");
+        assert!(auth_value.is_sensitive());
+    }
+}
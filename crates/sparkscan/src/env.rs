@@ -0,0 +1,210 @@
+//! Client configuration from environment variables.
+//!
+//! [`Client::from_env`] reads the following variables:
+//!
+//! | Variable | Required | Description |
+//! |----------|----------|-------------|
+//! | `SPARKSCAN_API_KEY` | yes | API key sent as a bearer token |
+//! | `SPARKSCAN_BASE_URL` | no | API base URL (default: [`DEFAULT_BASE_URL`]) |
+//! | `SPARKSCAN_TIMEOUT_SECS` | no | Request timeout in seconds (default: 15) |
+//! | `SPARKSCAN_CONNECT_TIMEOUT_SECS` | no | Connect timeout in seconds (default: 15) |
+//!
+//! Timeout overrides are ignored on `wasm32`, where reqwest does not support them.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::Client;
+use crate::options::ClientOptions;
+
+/// Base URL of the hosted SparkScan API.
+pub const DEFAULT_BASE_URL: &str = crate::presets::MAINNET_BASE_URL;
+
+/// Environment variable holding the API key.
+pub const API_KEY_VAR: &str = "SPARKSCAN_API_KEY";
+/// Environment variable overriding the API base URL.
+pub const BASE_URL_VAR: &str = "SPARKSCAN_BASE_URL";
+/// Environment variable overriding the request timeout, in seconds.
+pub const TIMEOUT_VAR: &str = "SPARKSCAN_TIMEOUT_SECS";
+/// Environment variable overriding the connect timeout, in seconds.
+pub const CONNECT_TIMEOUT_VAR: &str = "SPARKSCAN_CONNECT_TIMEOUT_SECS";
+
+/// Error raised when the client cannot be configured from the environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvError {
+    /// A required variable is unset or empty.
+    Missing {
+        /// Name of the variable
+        var: &'static str,
+    },
+    /// A variable is set to a value that cannot be parsed.
+    Invalid {
+        /// Name of the variable
+        var: &'static str,
+        /// Value found in the environment
+        value: String,
+        /// What was expected instead
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::Missing { var } => write!(f, "environment variable {} is not set", var),
+            EnvError::Invalid { var, value, reason } => {
+                write!(f, "invalid value {:?} for {}: {}", value, var, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+/// Client settings collected from the environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EnvConfig {
+    api_key: String,
+    base_url: String,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+}
+
+impl EnvConfig {
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, EnvError> {
+        let var = |name: &str| lookup(name).filter(|value| !value.trim().is_empty());
+
+        let api_key = var(API_KEY_VAR).ok_or(EnvError::Missing { var: API_KEY_VAR })?;
+        let base_url = match var(BASE_URL_VAR) {
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+                url.trim_end_matches('/').to_string()
+            }
+            Some(url) => {
+                return Err(EnvError::Invalid {
+                    var: BASE_URL_VAR,
+                    value: url,
+                    reason: "expected an http:// or https:// URL",
+                });
+            }
+            None => DEFAULT_BASE_URL.to_string(),
+        };
+
+        Ok(Self {
+            api_key: api_key.trim().to_string(),
+            base_url,
+            timeout: parse_secs(TIMEOUT_VAR, var(TIMEOUT_VAR))?,
+            connect_timeout: parse_secs(CONNECT_TIMEOUT_VAR, var(CONNECT_TIMEOUT_VAR))?,
+        })
+    }
+}
+
+fn parse_secs(var: &'static str, value: Option<String>) -> Result<Option<Duration>, EnvError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.trim().parse::<u64>() {
+        Ok(secs) if secs > 0 => Ok(Some(Duration::from_secs(secs))),
+        _ => Err(EnvError::Invalid {
+            var,
+            value,
+            reason: "expected a positive number of seconds",
+        }),
+    }
+}
+
+impl Client {
+    /// Create a new client configured from `SPARKSCAN_*` environment variables.
+    ///
+    /// See the [`env`](crate::env) module for the supported variables.
+    ///
+    /// ```rust,no_run
+    /// let client = sparkscan::Client::from_env().expect("SPARKSCAN_API_KEY must be set");
+    /// ```
+    pub fn from_env() -> Result<Self, EnvError> {
+        let config = EnvConfig::from_lookup(|name| std::env::var(name).ok())?;
+        Ok(Self::from_env_config(config))
+    }
+
+    fn from_env_config(config: EnvConfig) -> Self {
+        let mut options = ClientOptions::default();
+        if let Some(timeout) = config.timeout {
+            options.request_timeout = timeout;
+        }
+        if let Some(connect_timeout) = config.connect_timeout {
+            options.connect_timeout = connect_timeout;
+        }
+
+        Self::from_options(&config.base_url, Some(&config.api_key), &options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = EnvConfig::from_lookup(lookup(&[(API_KEY_VAR, "key")])).unwrap();
+        assert_eq!(config.api_key, "key");
+        assert_eq!(config.base_url, DEFAULT_BASE_URL);
+        assert_eq!(config.timeout, None);
+        assert_eq!(config.connect_timeout, None);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let config = EnvConfig::from_lookup(lookup(&[
+            (API_KEY_VAR, "key"),
+            (BASE_URL_VAR, "http://localhost:8000/"),
+            (TIMEOUT_VAR, "30"),
+            (CONNECT_TIMEOUT_VAR, " 5 "),
+        ]))
+        .unwrap();
+        assert_eq!(config.base_url, "http://localhost:8000");
+        assert_eq!(config.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(config.connect_timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_missing_api_key() {
+        let err = EnvConfig::from_lookup(lookup(&[(API_KEY_VAR, "  ")])).unwrap_err();
+        assert_eq!(err, EnvError::Missing { var: API_KEY_VAR });
+        assert_eq!(
+            err.to_string(),
+            "environment variable SPARKSCAN_API_KEY is not set"
+        );
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let err = EnvConfig::from_lookup(lookup(&[(API_KEY_VAR, "key"), (TIMEOUT_VAR, "soon")]))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            EnvError::Invalid {
+                var: TIMEOUT_VAR,
+                ..
+            }
+        ));
+
+        let err =
+            EnvConfig::from_lookup(lookup(&[(API_KEY_VAR, "key"), (BASE_URL_VAR, "ftp://x")]))
+                .unwrap_err();
+        assert!(matches!(
+            err,
+            EnvError::Invalid {
+                var: BASE_URL_VAR,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+//! Full exports of paginated endpoints.
+//!
+//! [`stream_all`] walks every page of a list endpoint, pausing between requests to stay within
+//! rate limits, and yields the items one by one. [`collect_all`] gathers them into a `Vec`, and
+//! with the `csv` feature token holders can be written straight to a CSV file:
+//!
+//! ```rust,no_run
+//! use sparkscan::{Network, SparkScanApi, Client, TokenIdentifier};
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::new_with_api_key("https://api.sparkscan.io", "api-key");
+//!     let api = SparkScanApi::new(client, Network::Mainnet);
+//!
+//!     let token: TokenIdentifier = "btkn1...".parse().unwrap();
+//!     let holders = api.token(&token).all_holders().await.unwrap();
+//!     println!("{} holders", holders.len());
+//! });
+//! ```
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::{Stream, TryStreamExt};
+
+use crate::pagination::{MAX_PAGE_SIZE, PageItem, PageQuery};
+use crate::{ApiError, Client};
+
+/// Paging and throttling settings for exports.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Items requested per page (default: 100, the maximum accepted by most endpoints)
+    pub page_size: u64,
+    /// Pause between two page requests (default: 250 milliseconds)
+    pub page_delay: Duration,
+    /// Stop after this many items (default: no limit)
+    pub max_items: Option<usize>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            page_size: MAX_PAGE_SIZE,
+            page_delay: Duration::from_millis(250),
+            max_items: None,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Configure the number of items requested per page.
+    pub fn with_page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Configure the pause between two page requests.
+    pub fn with_page_delay(mut self, page_delay: Duration) -> Self {
+        self.page_delay = page_delay;
+        self
+    }
+
+    /// Stop the export after `max_items` items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+}
+
+struct ExportState<T> {
+    client: Client,
+    query: PageQuery,
+    options: ExportOptions,
+    offset: u64,
+    fetched_pages: usize,
+    yielded: usize,
+    done: bool,
+    buffer: VecDeque<T>,
+}
+
+/// Stream every item of `query`, fetching pages lazily.
+///
+/// The stream ends after the last page, after [`ExportOptions::max_items`] items, or right after
+/// yielding the first error.
+pub fn stream_all<T: PageItem>(
+    client: Client,
+    query: PageQuery,
+    options: ExportOptions,
+) -> impl Stream<Item = Result<T, ApiError>> {
+    let state = ExportState {
+        client,
+        query,
+        options,
+        offset: 0,
+        fetched_pages: 0,
+        yielded: 0,
+        done: false,
+        buffer: VecDeque::new(),
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if state
+                .options
+                .max_items
+                .is_some_and(|max_items| state.yielded >= max_items)
+            {
+                return None;
+            }
+            if let Some(item) = state.buffer.pop_front() {
+                state.yielded += 1;
+                return Some((Ok(item), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            if state.fetched_pages > 0 && !state.options.page_delay.is_zero() {
+                tokio::time::sleep(state.options.page_delay).await;
+            }
+
+            let page = T::fetch_page(
+                &state.client,
+                &state.query,
+                state.offset,
+                state.options.page_size,
+            )
+            .await;
+            match page {
+                Ok(page) => {
+                    state.fetched_pages += 1;
+                    state.offset = page.next_offset();
+                    state.done = !page.has_more || page.is_empty();
+                    state.buffer.extend(page.items);
+                }
+                Err(err) => {
+                    state.done = true;
+                    return Some((Err(err), state));
+                }
+            }
+        }
+    })
+}
+
+/// Collect every item of `query` into a `Vec`.
+pub async fn collect_all<T: PageItem>(
+    client: Client,
+    query: PageQuery,
+    options: ExportOptions,
+) -> Result<Vec<T>, ApiError> {
+    stream_all(client, query, options).try_collect().await
+}
+
+/// Error raised while writing an export.
+#[cfg(feature = "csv")]
+#[derive(Debug)]
+pub enum ExportError {
+    /// The API request failed
+    Api(ApiError),
+    /// The output could not be written
+    Csv(csv::Error),
+}
+
+#[cfg(feature = "csv")]
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Api(e) => write!(f, "API request failed: {}", e),
+            ExportError::Csv(e) => write!(f, "failed to write CSV: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+impl std::error::Error for ExportError {}
+
+#[cfg(feature = "csv")]
+impl From<ApiError> for ExportError {
+    fn from(e: ApiError) -> Self {
+        ExportError::Api(e)
+    }
+}
+
+#[cfg(feature = "csv")]
+impl From<csv::Error> for ExportError {
+    fn from(e: csv::Error) -> Self {
+        ExportError::Csv(e)
+    }
+}
+
+/// Write token holders as CSV with an `address,pubkey,balance,value_usd,percentage` header.
+///
+/// Returns the number of holders written. Rows are written as pages arrive, so exports of large
+/// tokens do not need to fit in memory.
+#[cfg(feature = "csv")]
+pub async fn write_holders_csv<W: std::io::Write>(
+    holders: impl Stream<Item = Result<crate::types::TokenHolder, ApiError>>,
+    writer: W,
+) -> Result<usize, ExportError> {
+    use futures::StreamExt;
+
+    let mut out = csv::Writer::from_writer(writer);
+    out.write_record(["address", "pubkey", "balance", "value_usd", "percentage"])?;
+
+    let mut holders = std::pin::pin!(holders);
+    let mut written = 0;
+    while let Some(holder) = holders.next().await {
+        let holder = holder?;
+        out.write_record([
+            holder.address,
+            holder.pubkey,
+            holder.balance.to_string(),
+            holder.value_usd.to_string(),
+            holder.percentage.to_string(),
+        ])?;
+        written += 1;
+    }
+    out.flush().map_err(csv::Error::from)?;
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_options() {
+        let options = ExportOptions::default();
+        assert_eq!(options.page_size, MAX_PAGE_SIZE);
+        assert_eq!(options.max_items, None);
+
+        let options = options
+            .with_page_size(0)
+            .with_page_delay(Duration::ZERO)
+            .with_max_items(10);
+        assert_eq!(options.page_size, 1);
+        assert_eq!(options.page_delay, Duration::ZERO);
+        assert_eq!(options.max_items, Some(10));
+    }
+}
@@ -0,0 +1,246 @@
+//! Hedged requests for latency-critical read paths.
+//!
+//! [`HedgeMiddleware`] sends a second, identical `GET` when the first one has not completed after
+//! a configurable delay, and returns whichever response arrives first. The slower request is
+//! dropped. Hedging trades a few extra requests for lower tail latency, so the number of hedges
+//! in flight at any time is capped to protect rate limits.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use sparkscan::{Client, hedge::HedgeMiddleware};
+//!
+//! let stack = Client::default_middleware_builder(Some("api-key"))
+//!     .with(HedgeMiddleware::new(Duration::from_millis(300)).with_max_in_flight(4))
+//!     .build();
+//! let client = Client::new_with_middleware("https://api.sparkscan.io", stack);
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::Extensions;
+use reqwest::{Method, Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use tokio::sync::Semaphore;
+
+/// Default number of hedged requests allowed in flight at once.
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 8;
+
+/// Middleware racing a delayed duplicate of slow `GET` requests.
+///
+/// Cloning is cheap and clones share the in-flight hedge limit.
+#[derive(Debug, Clone)]
+pub struct HedgeMiddleware {
+    delay: Duration,
+    permits: Arc<Semaphore>,
+    max_in_flight: usize,
+}
+
+impl HedgeMiddleware {
+    /// Hedge `GET` requests still pending after `delay`.
+    ///
+    /// Pick a delay around the 95th percentile latency of the endpoints being hedged, so that
+    /// only the slowest requests are duplicated.
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_IN_FLIGHT)),
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+        }
+    }
+
+    /// Limit the number of hedged requests in flight (default: [`DEFAULT_MAX_IN_FLIGHT`]).
+    ///
+    /// Slow requests are not hedged while the limit is reached. A limit of zero disables hedging.
+    pub fn with_max_in_flight(self, max_in_flight: usize) -> Self {
+        Self {
+            delay: self.delay,
+            permits: Arc::new(Semaphore::new(max_in_flight)),
+            max_in_flight,
+        }
+    }
+
+    /// Delay after which a pending request is hedged.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Maximum number of hedged requests in flight.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// Number of hedged requests that can currently be started.
+    pub fn available_hedges(&self) -> usize {
+        self.permits.available_permits()
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for HedgeMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if req.method() != Method::GET {
+            return next.run(req, extensions).await;
+        }
+        // Streaming bodies cannot be duplicated; GET requests never have one in practice
+        let Some(hedge_req) = req.try_clone() else {
+            return next.run(req, extensions).await;
+        };
+        let mut hedge_extensions = extensions.clone();
+
+        let primary = next.clone().run(req, extensions);
+        tokio::pin!(primary);
+
+        tokio::select! {
+            result = &mut primary => return result,
+            _ = tokio::time::sleep(self.delay) => {}
+        }
+
+        let Ok(_permit) = self.permits.clone().try_acquire_owned() else {
+            return primary.await;
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(url = %hedge_req.url(), delay = ?self.delay, "Hedging slow request");
+
+        let hedge = next.run(hedge_req, &mut hedge_extensions);
+        tokio::pin!(hedge);
+
+        // Prefer the first success; only surface an error once both attempts have failed
+        tokio::select! {
+            result = &mut primary => match result {
+                Ok(response) => Ok(response),
+                Err(_) => hedge.await,
+            },
+            result = &mut hedge => match result {
+                Ok(response) => Ok(response),
+                Err(_) => primary.await,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Respond, ResponseTemplate};
+
+    const SLOW: Duration = Duration::from_millis(1_000);
+
+    /// Answers the first request after [`SLOW`] and the later ones right away.
+    struct SlowFirst(AtomicUsize);
+
+    impl Respond for SlowFirst {
+        fn respond(&self, _: &wiremock::Request) -> ResponseTemplate {
+            if self.0.fetch_add(1, Ordering::SeqCst) == 0 {
+                ResponseTemplate::new(200)
+                    .set_body_string("primary")
+                    .set_delay(SLOW)
+            } else {
+                ResponseTemplate::new(200).set_body_string("hedge")
+            }
+        }
+    }
+
+    async fn server(delay: Option<Duration>) -> MockServer {
+        let server = MockServer::start().await;
+        let mock = Mock::given(method("GET")).and(path("/v1/stats"));
+        match delay {
+            Some(delay) => mock.respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("primary")
+                    .set_delay(delay),
+            ),
+            None => mock.respond_with(SlowFirst(AtomicUsize::new(0))),
+        }
+        .mount(&server)
+        .await;
+        server
+    }
+
+    async fn get(hedge: &HedgeMiddleware, server: &MockServer) -> (String, Duration) {
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(hedge.clone())
+            .build();
+        let started = std::time::Instant::now();
+        let response = client
+            .get(format!("{}/v1/stats", server.uri()))
+            .send()
+            .await
+            .unwrap();
+        (response.text().await.unwrap(), started.elapsed())
+    }
+
+    async fn requests(server: &MockServer) -> usize {
+        server.received_requests().await.unwrap().len()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let hedge = HedgeMiddleware::new(Duration::from_millis(250));
+        assert_eq!(hedge.delay(), Duration::from_millis(250));
+        assert_eq!(hedge.max_in_flight(), DEFAULT_MAX_IN_FLIGHT);
+        assert_eq!(hedge.available_hedges(), DEFAULT_MAX_IN_FLIGHT);
+    }
+
+    #[test]
+    fn test_max_in_flight() {
+        let hedge = HedgeMiddleware::new(Duration::from_millis(250)).with_max_in_flight(2);
+        assert_eq!(hedge.delay(), Duration::from_millis(250));
+        assert_eq!(hedge.available_hedges(), 2);
+
+        let disabled = hedge.with_max_in_flight(0);
+        assert_eq!(disabled.available_hedges(), 0);
+    }
+
+    #[test]
+    fn test_clones_share_limit() {
+        let hedge = HedgeMiddleware::new(Duration::from_millis(250)).with_max_in_flight(1);
+        let clone = hedge.clone();
+        let _permit = hedge.permits.clone().try_acquire_owned().unwrap();
+        assert_eq!(clone.available_hedges(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_fast_requests_are_not_hedged() {
+        let server = server(Some(Duration::from_millis(10))).await;
+        let hedge = HedgeMiddleware::new(Duration::from_millis(200));
+
+        let (body, _) = get(&hedge, &server).await;
+        assert_eq!(body, "primary");
+        assert_eq!(requests(&server).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_hedge_fires_after_delay_and_first_success_wins() {
+        let server = server(None).await;
+        let hedge = HedgeMiddleware::new(Duration::from_millis(50));
+
+        let (body, elapsed) = get(&hedge, &server).await;
+        assert_eq!(body, "hedge");
+        assert!(elapsed >= Duration::from_millis(50));
+        assert!(elapsed < SLOW);
+        assert_eq!(requests(&server).await, 2);
+        // The permit of the hedge is released once the request completes
+        assert_eq!(hedge.available_hedges(), DEFAULT_MAX_IN_FLIGHT);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_limit_suppresses_hedging() {
+        let server = server(None).await;
+        let hedge = HedgeMiddleware::new(Duration::from_millis(50)).with_max_in_flight(1);
+        let _permit = hedge.permits.clone().try_acquire_owned().unwrap();
+
+        let (body, elapsed) = get(&hedge, &server).await;
+        assert_eq!(body, "primary");
+        assert!(elapsed >= SLOW);
+        assert_eq!(requests(&server).await, 1);
+    }
+}
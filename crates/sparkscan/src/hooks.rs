@@ -0,0 +1,66 @@
+//! Hooks run by the generated builders around every request.
+//!
+//! Implementing [`ClientHooks`] for `Client` (rather than `&Client`) overrides the default,
+//! no-op hooks of `sparkscan-client`.
+
+use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use sparkscan_client::{ClientHooks, Error, OperationInfo};
+
+use crate::Client;
+
+impl ClientHooks<()> for Client {
+    async fn pre<E>(
+        &self,
+        request: &mut reqwest::Request,
+        _info: &OperationInfo,
+    ) -> Result<(), Error<E>> {
+        // Default headers of the underlying client are only applied when absent, so this wins
+        if let Some(auth_value) = &self.api_key_override {
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, auth_value.clone());
+        }
+        if let Some(provider) = &self.credential_provider {
+            let auth_value = provider.auth_value().map_err(|_| {
+                Error::InvalidRequest("credential provider returned an invalid API key".to_string())
+            })?;
+            request.headers_mut().insert(AUTHORIZATION, auth_value);
+        }
+        if let Some(user_agent) = &self.user_agent_override {
+            request.headers_mut().insert(USER_AGENT, user_agent.clone());
+        }
+        Ok(())
+    }
+
+    #[cfg(all(feature = "response-validation", not(target_arch = "wasm32")))]
+    async fn post<E>(
+        &self,
+        result: &reqwest::Result<reqwest::Response>,
+        _info: &OperationInfo,
+    ) -> Result<(), Error<E>> {
+        // Attached by `exec` before the response is decoded
+        if let Ok(response) = result
+            && let Some(report) = response.extensions().get::<crate::schema::SchemaReport>()
+        {
+            return Err(Error::Custom(report.to_string()));
+        }
+        Ok(())
+    }
+
+    #[cfg(all(
+        any(feature = "metrics", feature = "response-validation"),
+        not(target_arch = "wasm32")
+    ))]
+    async fn exec(
+        &self,
+        request: reqwest::Request,
+        info: &OperationInfo,
+    ) -> reqwest::Result<reqwest::Response> {
+        let response = sparkscan_client::execute_request::<Self, ()>(self, request);
+        #[cfg(feature = "metrics")]
+        let response = crate::metrics::record(info.operation_id, response);
+        #[cfg(feature = "response-validation")]
+        let response = crate::schema::check_response(response.await?, info.operation_id);
+        response.await
+    }
+}
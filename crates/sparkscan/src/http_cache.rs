@@ -0,0 +1,342 @@
+//! ETag-aware HTTP response caching middleware.
+//!
+//! [`HttpCache`] stores successful `GET` responses keyed by their full URL and `Authorization`
+//! header. While an entry is
+//! younger than the configured TTL it is served straight from memory; once it goes stale the
+//! request is revalidated with `If-None-Match`, and a `304 Not Modified` answer refreshes the
+//! entry without transferring the body again.
+//!
+//! The cache is attached through [`Client::new_with_http_cache`](crate::Client::new_with_http_cache)
+//! or, for custom stacks, added to any `reqwest_middleware::ClientBuilder`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use http::Extensions;
+use reqwest::header::{AUTHORIZATION, CACHE_CONTROL, ETAG, HeaderMap, HeaderValue, IF_NONE_MATCH};
+use reqwest::{Method, Request, Response, StatusCode};
+use reqwest_middleware::{Middleware, Next};
+
+/// Default number of responses kept before the oldest entry is evicted.
+pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// Identifies a stored response.
+///
+/// The `Authorization` header is part of the key so clients derived with
+/// [`Client::with_api_key`](crate::Client::with_api_key) never see each other's responses.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    url: String,
+    authorization: Option<HeaderValue>,
+}
+
+impl CacheKey {
+    fn for_request(req: &Request) -> Self {
+        Self {
+            url: req.url().to_string(),
+            authorization: req.headers().get(AUTHORIZATION).cloned(),
+        }
+    }
+}
+
+/// A cached response body together with the metadata needed to replay and revalidate it.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    etag: Option<HeaderValue>,
+    stored_at: Instant,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self, ttl: Duration, now: Instant) -> bool {
+        now.saturating_duration_since(self.stored_at) < ttl
+    }
+
+    fn to_response(&self) -> Response {
+        let mut builder = http::Response::builder().status(self.status);
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(self.headers.clone());
+        }
+        builder
+            .body(self.body.clone())
+            .expect("cached response parts are always valid")
+            .into()
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    ttl: Duration,
+    max_entries: usize,
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+}
+
+/// In-memory response cache with ETag revalidation and TTL expiry.
+///
+/// Cloning an `HttpCache` is cheap and every clone shares the same storage, so a handle can be
+/// kept around to inspect or clear the cache after it has been handed to a client.
+///
+/// Only `GET` requests answered with `200 OK` are stored. Responses carrying
+/// `Cache-Control: no-store` are never cached. Entries are keyed by URL and `Authorization`
+/// header, so one cache can safely serve clients using different API keys.
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    inner: Arc<Inner>,
+}
+
+impl HttpCache {
+    /// Create a cache serving stored responses without revalidation for `ttl`.
+    ///
+    /// A zero TTL revalidates every request, which still saves bandwidth whenever the server
+    /// answers `304 Not Modified`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                ttl,
+                max_entries: DEFAULT_MAX_ENTRIES,
+                entries: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Limit the number of stored responses (default: [`DEFAULT_MAX_ENTRIES`]).
+    ///
+    /// Must be called before the cache is shared, as it rebuilds the underlying storage.
+    pub fn with_max_entries(self, max_entries: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                ttl: self.inner.ttl,
+                max_entries: max_entries.max(1),
+                entries: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Time during which a stored response is served without contacting the server.
+    pub fn ttl(&self) -> Duration {
+        self.inner.ttl
+    }
+
+    /// Number of responses currently stored.
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    /// Check if the cache holds no responses.
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    /// Drop the stored responses for `url`, whichever API key fetched them.
+    pub fn invalidate(&self, url: &str) {
+        self.entries().retain(|key, _| key.url != url);
+    }
+
+    /// Drop every stored response.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<CacheKey, CacheEntry>> {
+        // A poisoned lock only means another request panicked mid-update; the map is still usable.
+        self.inner
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lookup(&self, key: &CacheKey) -> Option<CacheEntry> {
+        self.entries().get(key).cloned()
+    }
+
+    fn store(&self, key: CacheKey, entry: CacheEntry) {
+        let mut entries = self.entries();
+        if !entries.contains_key(&key) && entries.len() >= self.inner.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, entry);
+    }
+
+    fn touch(&self, key: &CacheKey, headers: &HeaderMap) -> Option<CacheEntry> {
+        let mut entries = self.entries();
+        let entry = entries.get_mut(key)?;
+        entry.stored_at = Instant::now();
+        // A 304 may carry updated validators; keep them for the next revalidation.
+        if let Some(etag) = headers.get(ETAG) {
+            entry.etag = Some(etag.clone());
+            entry.headers.insert(ETAG, etag.clone());
+        }
+        Some(entry.clone())
+    }
+}
+
+fn is_no_store(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-store"))
+}
+
+#[async_trait::async_trait]
+impl Middleware for HttpCache {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if req.method() != Method::GET {
+            return next.run(req, extensions).await;
+        }
+
+        let key = CacheKey::for_request(&req);
+        let cached = self.lookup(&key);
+
+        if let Some(entry) = &cached {
+            if entry.is_fresh(self.inner.ttl, Instant::now()) {
+                return Ok(entry.to_response());
+            }
+            if let Some(etag) = &entry.etag {
+                req.headers_mut().insert(IF_NONE_MATCH, etag.clone());
+            }
+        }
+
+        let response = next.run(req, extensions).await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+            match self.touch(&key, response.headers()) {
+                Some(entry) => return Ok(entry.to_response()),
+                // The entry was evicted or invalidated while the request was in flight
+                None => return Ok(response),
+            }
+        }
+
+        if response.status() != StatusCode::OK || is_no_store(response.headers()) {
+            return Ok(response);
+        }
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        let entry = CacheEntry {
+            status,
+            etag: headers.get(ETAG).cloned(),
+            headers,
+            body,
+            stored_at: Instant::now(),
+        };
+        let response = entry.to_response();
+        self.store(key, entry);
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(url: &str) -> CacheKey {
+        CacheKey {
+            url: url.to_string(),
+            authorization: None,
+        }
+    }
+
+    fn entry(stored_at: Instant) -> CacheEntry {
+        CacheEntry {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(b"{}"),
+            etag: Some(HeaderValue::from_static("\"v1\"")),
+            stored_at,
+        }
+    }
+
+    #[test]
+    fn test_entry_freshness() {
+        let now = Instant::now();
+        let entry = entry(now);
+        assert!(entry.is_fresh(Duration::from_secs(5), now + Duration::from_secs(4)));
+        assert!(!entry.is_fresh(Duration::from_secs(5), now + Duration::from_secs(5)));
+        assert!(!entry.is_fresh(Duration::ZERO, now));
+    }
+
+    #[test]
+    fn test_store_evicts_oldest_entry() {
+        let cache = HttpCache::new(Duration::from_secs(60)).with_max_entries(2);
+        let now = Instant::now();
+        cache.store(key("a"), entry(now));
+        cache.store(key("b"), entry(now + Duration::from_millis(1)));
+        cache.store(key("c"), entry(now + Duration::from_millis(2)));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.lookup(&key("a")).is_none());
+        assert!(cache.lookup(&key("b")).is_some());
+        assert!(cache.lookup(&key("c")).is_some());
+    }
+
+    #[test]
+    fn test_touch_refreshes_entry_and_etag() {
+        let cache = HttpCache::new(Duration::from_secs(60));
+        let stored_at = Instant::now() - Duration::from_secs(120);
+        cache.store(key("a"), entry(stored_at));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static("\"v2\""));
+        let touched = cache.touch(&key("a"), &headers).unwrap();
+
+        assert!(touched.stored_at > stored_at);
+        assert_eq!(touched.etag, Some(HeaderValue::from_static("\"v2\"")));
+    }
+
+    #[test]
+    fn test_invalidate_drops_every_key_for_url() {
+        let cache = HttpCache::new(Duration::from_secs(60));
+        let tenant = CacheKey {
+            authorization: Some(HeaderValue::from_static("Bearer tenant")),
+            ..key("a")
+        };
+        cache.store(key("a"), entry(Instant::now()));
+        cache.store(tenant, entry(Instant::now()));
+        cache.store(key("b"), entry(Instant::now()));
+
+        cache.invalidate("a");
+        assert_eq!(cache.len(), 1);
+        assert!(cache.lookup(&key("b")).is_some());
+    }
+
+    #[test]
+    fn test_no_store_detection() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("private, No-Store"));
+        assert!(is_no_store(&headers));
+
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("max-age=60"));
+        assert!(!is_no_store(&headers));
+    }
+
+    #[test]
+    fn test_cached_response_replays_parts() {
+        let mut entry = entry(Instant::now());
+        entry
+            .headers
+            .insert(ETAG, HeaderValue::from_static("\"v1\""));
+        let response = entry.to_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(ETAG).unwrap(), "\"v1\"");
+    }
+}
@@ -0,0 +1,267 @@
+//! Leaderboard snapshots and rank movements.
+//!
+//! [`LeaderboardTracker`] keeps the previous wallet and token leaderboards in memory and, on each
+//! refresh, reports how entries moved since the last snapshot:
+//!
+//! ```rust,no_run
+//! use sparkscan::{Client, leaderboard::{LeaderboardTracker, RankMovement}};
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::new_with_api_key("https://api.sparkscan.io", "api-key");
+//!     let mut tracker = LeaderboardTracker::new(client, "MAINNET");
+//!
+//!     // The first refresh reports every entry as new
+//!     tracker.refresh_tokens().await.unwrap();
+//!
+//!     let diff = tracker.refresh_tokens().await.unwrap();
+//!     for change in diff.movers() {
+//!         if let RankMovement::Up(positions) = change.movement {
+//!             println!("{} climbed {} places", change.key, positions);
+//!         }
+//!     }
+//! });
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{ApiError, Client};
+
+/// How an entry moved between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RankMovement {
+    /// Climbed by this many places
+    Up(u64),
+    /// Fell by this many places
+    Down(u64),
+    /// Kept its rank
+    Unchanged,
+    /// Entered the leaderboard
+    New,
+    /// Left the leaderboard
+    Dropped,
+}
+
+/// Rank change of a single leaderboard entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankChange {
+    /// Entry key: Spark address for wallets, token identifier for tokens
+    pub key: String,
+    /// Rank in the previous snapshot
+    pub previous_rank: Option<i128>,
+    /// Rank in the current snapshot
+    pub current_rank: Option<i128>,
+    /// Movement between the two snapshots
+    pub movement: RankMovement,
+}
+
+/// Rank changes between two leaderboard snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LeaderboardDiff {
+    /// Changes of every entry in either snapshot, in current rank order followed by dropped
+    /// entries
+    pub changes: Vec<RankChange>,
+}
+
+impl LeaderboardDiff {
+    /// Compute the rank changes from `previous` to `current`, given as `(key, rank)` pairs.
+    pub fn between(previous: &[(String, i128)], current: &[(String, i128)]) -> Self {
+        let previous_ranks: HashMap<&str, i128> = previous
+            .iter()
+            .map(|(key, rank)| (key.as_str(), *rank))
+            .collect();
+        let current_keys: HashSet<&str> = current.iter().map(|(key, _)| key.as_str()).collect();
+
+        let mut changes: Vec<RankChange> = current
+            .iter()
+            .map(|(key, rank)| {
+                let previous_rank = previous_ranks.get(key.as_str()).copied();
+                let movement = match previous_rank {
+                    None => RankMovement::New,
+                    Some(before) if before > *rank => RankMovement::Up((before - rank) as u64),
+                    Some(before) if before < *rank => RankMovement::Down((rank - before) as u64),
+                    Some(_) => RankMovement::Unchanged,
+                };
+                RankChange {
+                    key: key.clone(),
+                    previous_rank,
+                    current_rank: Some(*rank),
+                    movement,
+                }
+            })
+            .collect();
+        changes.sort_by_key(|change| change.current_rank);
+
+        changes.extend(
+            previous
+                .iter()
+                .filter(|(key, _)| !current_keys.contains(key.as_str()))
+                .map(|(key, rank)| RankChange {
+                    key: key.clone(),
+                    previous_rank: Some(*rank),
+                    current_rank: None,
+                    movement: RankMovement::Dropped,
+                }),
+        );
+
+        Self { changes }
+    }
+
+    /// Entries that climbed or fell.
+    pub fn movers(&self) -> impl Iterator<Item = &RankChange> {
+        self.changes
+            .iter()
+            .filter(|change| matches!(change.movement, RankMovement::Up(_) | RankMovement::Down(_)))
+    }
+
+    /// Entries that entered the leaderboard.
+    pub fn new_entries(&self) -> impl Iterator<Item = &RankChange> {
+        self.changes
+            .iter()
+            .filter(|change| change.movement == RankMovement::New)
+    }
+
+    /// Entries that left the leaderboard.
+    pub fn dropped(&self) -> impl Iterator<Item = &RankChange> {
+        self.changes
+            .iter()
+            .filter(|change| change.movement == RankMovement::Dropped)
+    }
+
+    /// Check if no entry moved, entered or left.
+    pub fn is_unchanged(&self) -> bool {
+        self.changes
+            .iter()
+            .all(|change| change.movement == RankMovement::Unchanged)
+    }
+}
+
+/// Fetches leaderboards and remembers the previous snapshot to report rank movements.
+#[derive(Debug, Clone)]
+pub struct LeaderboardTracker {
+    client: Client,
+    network: String,
+    limit: u64,
+    wallets: Option<Vec<(String, i128)>>,
+    tokens: Option<Vec<(String, i128)>>,
+}
+
+impl LeaderboardTracker {
+    /// Track the leaderboards of `network`, fetching the top 100 entries.
+    pub fn new(client: Client, network: &str) -> Self {
+        Self {
+            client,
+            network: network.to_string(),
+            limit: crate::pagination::MAX_PAGE_SIZE,
+            wallets: None,
+            tokens: None,
+        }
+    }
+
+    /// Configure how many top entries are fetched (at most 100).
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = limit.clamp(1, crate::pagination::MAX_PAGE_SIZE);
+        self
+    }
+
+    /// Fetch the wallet leaderboard and diff it against the previous snapshot.
+    ///
+    /// Requires the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub async fn refresh_wallets(&mut self) -> Result<LeaderboardDiff, ApiError> {
+        let leaderboard = self
+            .client
+            .get_wallet_leaderboard_v1_stats_leaderboard_wallets_get()
+            .network(self.network.as_str())
+            .limit(self.limit)
+            .send()
+            .await?
+            .into_inner();
+        let current: Vec<_> = leaderboard
+            .leaderboard
+            .into_iter()
+            .map(|entry| (entry.spark_address, entry.rank))
+            .collect();
+
+        let diff = LeaderboardDiff::between(self.wallets.as_deref().unwrap_or_default(), &current);
+        self.wallets = Some(current);
+        Ok(diff)
+    }
+
+    /// Fetch the token leaderboard and diff it against the previous snapshot.
+    ///
+    /// Requires the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub async fn refresh_tokens(&mut self) -> Result<LeaderboardDiff, ApiError> {
+        let leaderboard = self
+            .client
+            .get_token_leaderboard_v1_stats_leaderboard_tokens_get()
+            .network(self.network.as_str())
+            .limit(self.limit)
+            .send()
+            .await?
+            .into_inner();
+        let current: Vec<_> = leaderboard
+            .leaderboard
+            .into_iter()
+            .map(|entry| (entry.token_identifier, entry.rank))
+            .collect();
+
+        let diff = LeaderboardDiff::between(self.tokens.as_deref().unwrap_or_default(), &current);
+        self.tokens = Some(current);
+        Ok(diff)
+    }
+
+    /// Forget the stored snapshots, so the next refreshes report every entry as new.
+    pub fn reset(&mut self) {
+        self.wallets = None;
+        self.tokens = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranks(entries: &[(&str, i128)]) -> Vec<(String, i128)> {
+        entries.iter().map(|(k, r)| (k.to_string(), *r)).collect()
+    }
+
+    #[test]
+    fn test_diff_movements() {
+        let previous = ranks(&[("a", 1), ("b", 2), ("c", 3), ("d", 4)]);
+        let current = ranks(&[("b", 1), ("a", 2), ("c", 3), ("e", 4)]);
+        let diff = LeaderboardDiff::between(&previous, &current);
+
+        let movements: Vec<_> = diff
+            .changes
+            .iter()
+            .map(|change| (change.key.as_str(), change.movement))
+            .collect();
+        assert_eq!(
+            movements,
+            vec![
+                ("b", RankMovement::Up(1)),
+                ("a", RankMovement::Down(1)),
+                ("c", RankMovement::Unchanged),
+                ("e", RankMovement::New),
+                ("d", RankMovement::Dropped),
+            ]
+        );
+        assert_eq!(diff.movers().count(), 2);
+        assert_eq!(diff.new_entries().next().unwrap().key, "e");
+        assert_eq!(diff.dropped().next().unwrap().previous_rank, Some(4));
+        assert!(!diff.is_unchanged());
+    }
+
+    #[test]
+    fn test_first_snapshot_is_all_new() {
+        let diff = LeaderboardDiff::between(&[], &ranks(&[("a", 1), ("b", 2)]));
+        assert_eq!(diff.new_entries().count(), 2);
+    }
+
+    #[test]
+    fn test_identical_snapshots() {
+        let snapshot = ranks(&[("a", 1), ("b", 2)]);
+        assert!(LeaderboardDiff::between(&snapshot, &snapshot).is_unchanged());
+    }
+}
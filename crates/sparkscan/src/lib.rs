@@ -1 +1,54 @@
+// Helpers shared by several endpoint groups go unused when only some of them are enabled
+#![cfg_attr(not(feature = "all-endpoints"), allow(dead_code, unused_imports))]
+// The generated builders borrow the client without naming the lifetime
+#![allow(mismatched_lifetime_syntaxes)]
+
 include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
+
+pub mod api;
+mod api_key;
+pub mod cache;
+pub mod compat;
+pub mod credentials;
+pub mod env;
+mod hooks;
+pub mod leaderboard;
+pub mod numeric;
+pub mod openapi;
+pub mod options;
+pub mod pagination;
+pub mod polling;
+pub mod positional;
+pub mod presets;
+mod user_agent;
+pub mod validation;
+
+#[cfg(feature = "export")]
+pub mod export;
+
+#[cfg(feature = "hedging")]
+pub mod hedge;
+
+#[cfg(feature = "http-cache")]
+pub mod http_cache;
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
+#[cfg(feature = "request-id")]
+pub mod request_id;
+
+#[cfg(all(feature = "response-validation", not(target_arch = "wasm32")))]
+pub mod schema;
+
+#[cfg(feature = "poll-watcher")]
+pub mod watch;
+
+pub use api::SparkScanApi;
+pub use sparkscan_types::{
+    BitcoinTxid, Network, ParseAmountError, ParseEnumError, ParseIdError, Sats, SparkAddress,
+    TokenAmount, TokenIdentifier, TransactionStatus, TransactionType,
+};
+
+/// Error type returned by the SparkScan API endpoints.
+pub type ApiError = Error<types::HttpValidationError>;
@@ -0,0 +1,72 @@
+//! Request metrics through the [`metrics`] facade.
+//!
+//! With the `metrics` feature, every call made through the generated builders records:
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `sparkscan_http_requests_total` | counter | `operation`, `status` |
+//! | `sparkscan_http_request_errors_total` | counter | `operation`, `status` |
+//! | `sparkscan_http_request_duration_seconds` | histogram | `operation` |
+//!
+//! `operation` is the OpenAPI operation id (e.g.
+//! `get_token_holders_v1_tokens__identifier__holders_get`) and `status` the HTTP status code, or
+//! `error` when no response was received. Errors count transport failures and 4xx/5xx responses.
+//!
+//! The metrics are emitted to whichever recorder the application installs (Prometheus exporter,
+//! StatsD, ...). Call [`describe_metrics`] once after installing it to register units and
+//! descriptions. Metrics are not recorded on `wasm32`.
+
+/// Counter of requests sent.
+pub const REQUESTS_TOTAL: &str = "sparkscan_http_requests_total";
+
+/// Counter of failed requests.
+pub const REQUEST_ERRORS_TOTAL: &str = "sparkscan_http_request_errors_total";
+
+/// Histogram of request latencies, in seconds.
+pub const REQUEST_DURATION_SECONDS: &str = "sparkscan_http_request_duration_seconds";
+
+/// Register the units and descriptions of the SparkScan metrics with the installed recorder.
+pub fn describe_metrics() {
+    ::metrics::describe_counter!(REQUESTS_TOTAL, "Requests sent to the SparkScan API");
+    ::metrics::describe_counter!(
+        REQUEST_ERRORS_TOTAL,
+        "SparkScan API requests that failed or returned an error status"
+    );
+    ::metrics::describe_histogram!(
+        REQUEST_DURATION_SECONDS,
+        ::metrics::Unit::Seconds,
+        "Latency of SparkScan API requests"
+    );
+}
+
+/// Run `request` and record its outcome and latency under `operation_id`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn record(
+    operation_id: &'static str,
+    request: impl std::future::Future<Output = reqwest::Result<reqwest::Response>>,
+) -> reqwest::Result<reqwest::Response> {
+    let start = std::time::Instant::now();
+    let result = request.await;
+    ::metrics::histogram!(REQUEST_DURATION_SECONDS, "operation" => operation_id)
+        .record(start.elapsed().as_secs_f64());
+
+    let (status, failed) = match &result {
+        Ok(response) => (
+            response.status().as_u16().to_string(),
+            response.status().is_client_error() || response.status().is_server_error(),
+        ),
+        Err(_) => ("error".to_string(), true),
+    };
+    if failed {
+        ::metrics::counter!(
+            REQUEST_ERRORS_TOTAL,
+            "operation" => operation_id,
+            "status" => status.clone()
+        )
+        .increment(1);
+    }
+    ::metrics::counter!(REQUESTS_TOTAL, "operation" => operation_id, "status" => status)
+        .increment(1);
+
+    result
+}
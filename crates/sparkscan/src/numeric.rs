@@ -0,0 +1,175 @@
+//! Checked conversions of the integers returned by the REST API.
+//!
+//! The generated types decode every integer as `i128`, so that no amount can overflow while
+//! parsing. Most values fit in narrower types though, and many sinks (e.g. metrics or
+//! `serde_json::Value`) do not accept `i128`. [`I128Ext`] converts them without silent truncation,
+//! on plain fields as well as optional ones:
+//!
+//! ```rust
+//! use sparkscan::numeric::I128Ext;
+//! use sparkscan::{Sats, TokenAmount};
+//!
+//! let balance: i128 = 2229;
+//! assert_eq!(balance.as_u64_checked(), Some(2229));
+//! assert_eq!((-1i128).as_u128(), None);
+//! assert_eq!(Some(balance).as_u64_checked(), Some(2229));
+//! assert_eq!(None::<i128>.as_u64_checked(), None);
+//!
+//! // Amounts convert with `TryFrom` into their typed newtypes
+//! assert_eq!(Sats::try_from(balance), Ok(Sats(2229)));
+//! assert!(TokenAmount::try_from(-1i128).is_err());
+//! ```
+//!
+//! When serialized, the integer fields of the generated types are written as JSON numbers up to
+//! the range of `u64` and `i64`, and as decimal strings beyond it, the way the API encodes big
+//! integers. [`set_i128_format`] selects another [`I128Format`] for every serialization:
+//!
+//! ```rust
+//! use sparkscan::numeric::{self, I128Format};
+//!
+//! // Some sinks only accept numbers
+//! numeric::set_i128_format(I128Format::Number);
+//! ```
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::{Serialize, Serializer};
+
+/// Checked narrowing of `i128` API integers.
+pub trait I128Ext {
+    /// Get the value as `u64`, or `None` if it is negative or too large.
+    fn as_u64_checked(&self) -> Option<u64>;
+
+    /// Get the value as `i64`, or `None` if it is out of range.
+    fn as_i64_checked(&self) -> Option<i64>;
+
+    /// Get the value as `u128`, or `None` if it is negative.
+    fn as_u128(&self) -> Option<u128>;
+}
+
+impl I128Ext for i128 {
+    fn as_u64_checked(&self) -> Option<u64> {
+        u64::try_from(*self).ok()
+    }
+
+    fn as_i64_checked(&self) -> Option<i64> {
+        i64::try_from(*self).ok()
+    }
+
+    fn as_u128(&self) -> Option<u128> {
+        u128::try_from(*self).ok()
+    }
+}
+
+/// Missing values convert to `None`.
+impl I128Ext for Option<i128> {
+    fn as_u64_checked(&self) -> Option<u64> {
+        self.as_ref()?.as_u64_checked()
+    }
+
+    fn as_i64_checked(&self) -> Option<i64> {
+        self.as_ref()?.as_i64_checked()
+    }
+
+    fn as_u128(&self) -> Option<u128> {
+        self.as_ref()?.as_u128()
+    }
+}
+
+/// How the `i128` fields of the generated types are serialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum I128Format {
+    /// Numbers within the range of `u64` and `i64`, decimal strings beyond it
+    #[default]
+    LargeAsString,
+    /// Always numbers, which some formats cannot represent beyond `u64`
+    Number,
+    /// Always decimal strings
+    String,
+}
+
+impl I128Format {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => I128Format::Number,
+            2 => I128Format::String,
+            _ => I128Format::LargeAsString,
+        }
+    }
+}
+
+static I128_FORMAT: AtomicU8 = AtomicU8::new(I128Format::LargeAsString as u8);
+
+/// Serialize the `i128` fields of the generated types using `format` from now on.
+pub fn set_i128_format(format: I128Format) {
+    I128_FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+/// Get the format the `i128` fields of the generated types are serialized with.
+pub fn i128_format() -> I128Format {
+    I128Format::from_u8(I128_FORMAT.load(Ordering::Relaxed))
+}
+
+/// An API integer, serialized according to [`i128_format`].
+struct ApiInteger(i128);
+
+impl Serialize for ApiInteger {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = self.0;
+        match i128_format() {
+            I128Format::Number => serializer.serialize_i128(value),
+            I128Format::String => serializer.collect_str(&value),
+            I128Format::LargeAsString => {
+                if let Ok(value) = u64::try_from(value) {
+                    serializer.serialize_u64(value)
+                } else if let Ok(value) = i64::try_from(value) {
+                    serializer.serialize_i64(value)
+                } else {
+                    serializer.collect_str(&value)
+                }
+            }
+        }
+    }
+}
+
+/// Serializer of the `i128` fields, applied by the build script.
+pub(crate) fn serialize_i128<S: Serializer>(
+    value: &i128,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    ApiInteger(*value).serialize(serializer)
+}
+
+/// Serializer of the `Option<i128>` fields, applied by the build script.
+pub(crate) fn serialize_option_i128<S: Serializer>(
+    value: &Option<i128>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value.map(ApiInteger).serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_bounds() {
+        assert_eq!(i128::from(u64::MAX).as_u64_checked(), Some(u64::MAX));
+        assert_eq!((i128::from(u64::MAX) + 1).as_u64_checked(), None);
+        assert_eq!((-1i128).as_u64_checked(), None);
+        assert_eq!(i128::from(i64::MIN).as_i64_checked(), Some(i64::MIN));
+        assert_eq!((i128::from(i64::MIN) - 1).as_i64_checked(), None);
+        assert_eq!(i128::MAX.as_u128(), Some(i128::MAX as u128));
+        assert_eq!(Some(-5i128).as_u128(), None);
+    }
+
+    #[test]
+    fn test_large_values_as_strings() {
+        let json = |value: i128| serde_json::to_string(&ApiInteger(value)).unwrap();
+        assert_eq!(json(i128::from(u64::MAX)), u64::MAX.to_string());
+        assert_eq!(json(-42), "-42");
+        assert_eq!(json(i128::from(u64::MAX) + 1), "\"18446744073709551616\"");
+        assert_eq!(json(i128::MIN), format!("\"{}\"", i128::MIN));
+    }
+}
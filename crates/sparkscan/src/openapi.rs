@@ -0,0 +1,82 @@
+//! The OpenAPI document the client was generated from, and its operations.
+//!
+//! Tooling such as mock servers and contract tests can check against exactly what this build of
+//! the client expects:
+//!
+//! ```rust
+//! use sparkscan::openapi;
+//!
+//! for operation in openapi::operations() {
+//!     println!("{} {} ({})", operation.method, operation.path, operation.operation_id);
+//! }
+//!
+//! let latest = openapi::operation("get_latest_transactions_v1_tx_latest_get").unwrap();
+//! assert_eq!((latest.method, latest.path), ("GET", "/v1/tx/latest"));
+//! println!("Generated from API version {}", openapi::API_VERSION);
+//! ```
+
+use std::sync::OnceLock;
+
+/// Version of the API described by the bundled document (`info.version`).
+pub const API_VERSION: &str = env!("SPARKSCAN_API_VERSION");
+
+/// An operation of the generated client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operation {
+    /// Operation id, as reported by [`OperationInfo`](sparkscan_client::OperationInfo)
+    pub operation_id: &'static str,
+    /// HTTP method, in upper case
+    pub method: &'static str,
+    /// Path template, e.g. `/v1/address/{address}`
+    pub path: &'static str,
+    /// Tags grouping the operation, e.g. `Address`
+    pub tags: &'static [&'static str],
+    /// Short description of the operation
+    pub summary: &'static str,
+}
+
+const OPERATIONS: &[Operation] = include!(concat!(env!("OUT_DIR"), "/operations.rs"));
+
+/// Get the bundled OpenAPI document, as JSON.
+///
+/// The document is the one shipped with the crate: it also describes the endpoint groups left
+/// out of this build by their features, unlike [`operations`].
+pub fn openapi_json() -> &'static str {
+    include_str!("../openapi.json")
+}
+
+/// Get the bundled OpenAPI document, parsed once.
+pub fn openapi_document() -> &'static serde_json::Value {
+    static DOCUMENT: OnceLock<serde_json::Value> = OnceLock::new();
+    DOCUMENT.get_or_init(|| {
+        serde_json::from_str(openapi_json()).expect("the bundled OpenAPI document is valid JSON")
+    })
+}
+
+/// Get the operations of the generated client.
+pub fn operations() -> &'static [Operation] {
+    OPERATIONS
+}
+
+/// Get the operation with the id `operation_id`, if generated.
+pub fn operation(operation_id: &str) -> Option<&'static Operation> {
+    OPERATIONS
+        .iter()
+        .find(|operation| operation.operation_id == operation_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_match_document() {
+        let document = openapi_document();
+        assert_eq!(document["info"]["version"], API_VERSION);
+        for operation in operations() {
+            let described = &document["paths"][operation.path][operation.method.to_lowercase()];
+            assert_eq!(described["operationId"], operation.operation_id);
+        }
+        assert!(operation("unknown_operation").is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! Tuning of the bundled HTTP client.
+//!
+//! [`Client::new_with_options`] builds the same client as [`Client::new`], with timeouts and
+//! connection pooling taken from [`ClientOptions`]:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use sparkscan::{Client, options::ClientOptions};
+//!
+//! let options = ClientOptions::default()
+//!     .with_request_timeout(Duration::from_secs(60))
+//!     .with_pool_max_idle_per_host(4);
+//! let client = Client::new_with_options("https://api.sparkscan.io", options)
+//!     .with_api_key("api-key")
+//!     .unwrap();
+//! ```
+//!
+//! The options are ignored on `wasm32`, where reqwest does not support them.
+
+use std::time::Duration;
+
+use crate::Client;
+
+/// Connect and request timeout used by the default constructors.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long idle pooled connections are kept alive by default.
+pub const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Timeout and connection pool settings of the bundled reqwest client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientOptions {
+    /// Timeout for establishing a connection (default: 15 seconds)
+    pub connect_timeout: Duration,
+    /// Timeout for a whole request, from connecting until the body is read (default: 15 seconds)
+    pub request_timeout: Duration,
+    /// How long idle pooled connections are kept alive, `None` to keep them indefinitely
+    /// (default: 90 seconds)
+    pub pool_idle_timeout: Option<Duration>,
+    /// Maximum number of idle connections kept per host (default: unlimited)
+    pub pool_max_idle_per_host: usize,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: DEFAULT_TIMEOUT,
+            request_timeout: DEFAULT_TIMEOUT,
+            pool_idle_timeout: Some(DEFAULT_POOL_IDLE_TIMEOUT),
+            pool_max_idle_per_host: usize::MAX,
+        }
+    }
+}
+
+impl ClientOptions {
+    /// Configure the connect timeout.
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Configure the request timeout.
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Configure how long idle pooled connections are kept alive.
+    pub fn with_pool_idle_timeout(mut self, pool_idle_timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = pool_idle_timeout;
+        self
+    }
+
+    /// Configure the maximum number of idle connections kept per host.
+    pub fn with_pool_max_idle_per_host(mut self, pool_max_idle_per_host: usize) -> Self {
+        self.pool_max_idle_per_host = pool_max_idle_per_host;
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        builder
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.request_timeout)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        builder
+    }
+}
+
+impl Client {
+    /// Create a new client with custom timeouts and connection pooling.
+    ///
+    /// See the [`options`](crate::options) module for an example.
+    pub fn new_with_options(baseurl: &str, options: ClientOptions) -> Self {
+        Self::from_options(baseurl, None, &options)
+    }
+
+    pub(crate) fn from_options(
+        baseurl: &str,
+        api_key: Option<&str>,
+        options: &ClientOptions,
+    ) -> Self {
+        let client = options
+            .apply(Self::base_client_builder(api_key))
+            .build()
+            .unwrap();
+        #[cfg(feature = "middleware")]
+        let client = Self::middleware_builder(client).build();
+
+        Self::new_with_client(baseurl, client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_builders() {
+        let options = ClientOptions::default()
+            .with_connect_timeout(Duration::from_secs(5))
+            .with_request_timeout(Duration::from_secs(60))
+            .with_pool_idle_timeout(None)
+            .with_pool_max_idle_per_host(2);
+
+        assert_eq!(
+            options,
+            ClientOptions {
+                connect_timeout: Duration::from_secs(5),
+                request_timeout: Duration::from_secs(60),
+                pool_idle_timeout: None,
+                pool_max_idle_per_host: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,517 @@
+//! Typed pagination over the list endpoints.
+//!
+//! List endpoints take `limit`/`offset` parameters and answer with a batch of items, usually with
+//! a total count. [`Page`] keeps the items together with the query that produced them, so the
+//! following page is one call away and offsets never have to be tracked by hand:
+//!
+//! ```rust,no_run
+//! use sparkscan::{Client, pagination::Page};
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::new_with_api_key("https://api.sparkscan.io", "api-key");
+//!     let mut page = Page::token_holders(&client, "btkn1...", "MAINNET", 0, 100)
+//!         .await
+//!         .unwrap();
+//!
+//!     loop {
+//!         for holder in &page.items {
+//!             println!("{}: {}", holder.address, holder.balance);
+//!         }
+//!         match page.next(&client).await.unwrap() {
+//!             Some(next) => page = next,
+//!             None => break,
+//!         }
+//!     }
+//! });
+//! ```
+
+use crate::{ApiError, Client, types};
+use std::num::NonZeroU64;
+
+/// Default page size of the list endpoints.
+pub const DEFAULT_PAGE_SIZE: u64 = 25;
+
+/// Largest page size accepted by most list endpoints.
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Largest page size accepted by the latest transactions endpoint.
+pub const MAX_LATEST_TRANSACTIONS_PAGE_SIZE: u64 = 500;
+
+/// The list endpoint and its fixed parameters, used to fetch neighbouring pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageQuery {
+    /// Transactions of an address
+    AddressTransactions {
+        /// Spark address
+        address: String,
+        /// API network name
+        network: String,
+    },
+    /// Holders of a token
+    TokenHolders {
+        /// Token identifier
+        identifier: String,
+        /// API network name
+        network: String,
+    },
+    /// Transactions of a token
+    TokenTransactions {
+        /// Token identifier
+        identifier: String,
+        /// API network name
+        network: String,
+    },
+    /// Token leaderboard
+    TokenLeaderboard {
+        /// API network name
+        network: String,
+    },
+    /// Latest transactions on the network
+    LatestTransactions {
+        /// API network name
+        network: String,
+    },
+}
+
+/// Item type returned by a paginated endpoint.
+#[allow(async_fn_in_trait)]
+pub trait PageItem: Sized {
+    /// Fetch the items at `offset` for `query`.
+    ///
+    /// Returns [`Error::InvalidRequest`](crate::Error::InvalidRequest) if `query` targets an
+    /// endpoint returning another item type.
+    async fn fetch_page(
+        client: &Client,
+        query: &PageQuery,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<Self>, ApiError>;
+}
+
+/// A batch of items from a list endpoint, with the state needed to fetch its neighbours.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    /// Items of this page
+    pub items: Vec<T>,
+    /// Position of the first item in the full result set
+    pub offset: u64,
+    /// Requested page size
+    pub limit: u64,
+    /// Size of the full result set, when reported by the endpoint
+    pub total: Option<u64>,
+    /// Whether more items follow this page
+    pub has_more: bool,
+    /// Endpoint and parameters that produced this page
+    pub query: PageQuery,
+}
+
+impl<T> Page<T> {
+    fn new(query: PageQuery, items: Vec<T>, offset: u64, limit: u64, total: Option<i128>) -> Self {
+        let total = total.map(|total| u64::try_from(total).unwrap_or(0));
+        let end = offset + items.len() as u64;
+        let has_more = match total {
+            Some(total) => end < total,
+            // Without a total, a full page is the only hint that more items follow
+            None => !items.is_empty() && items.len() as u64 >= limit,
+        };
+
+        Self {
+            items,
+            offset,
+            limit,
+            total,
+            has_more,
+            query,
+        }
+    }
+
+    /// Check if this page holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Number of items in this page.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Offset of the following page.
+    pub fn next_offset(&self) -> u64 {
+        self.offset + self.items.len() as u64
+    }
+
+    /// Map the items of this page, keeping the pagination state.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            offset: self.offset,
+            limit: self.limit,
+            total: self.total,
+            has_more: self.has_more,
+            query: self.query,
+        }
+    }
+}
+
+impl<T> IntoIterator for Page<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<T: PageItem> Page<T> {
+    /// Fetch the first page of `query`.
+    pub async fn first(client: &Client, query: PageQuery, limit: u64) -> Result<Self, ApiError> {
+        T::fetch_page(client, &query, 0, limit).await
+    }
+
+    /// Fetch the following page, or `None` if this is the last one.
+    pub async fn next(&self, client: &Client) -> Result<Option<Self>, ApiError> {
+        if !self.has_more {
+            return Ok(None);
+        }
+        T::fetch_page(client, &self.query, self.next_offset(), self.limit)
+            .await
+            .map(Some)
+    }
+
+    /// Fetch the preceding page, or `None` if this is the first one.
+    pub async fn previous(&self, client: &Client) -> Result<Option<Self>, ApiError> {
+        if self.offset == 0 {
+            return Ok(None);
+        }
+        let offset = self.offset.saturating_sub(self.limit);
+        T::fetch_page(client, &self.query, offset, self.limit)
+            .await
+            .map(Some)
+    }
+}
+
+#[cfg(feature = "address")]
+impl Page<types::AddressTransaction> {
+    /// Fetch a page of transactions of `address`.
+    pub async fn address_transactions(
+        client: &Client,
+        address: &str,
+        network: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Self, ApiError> {
+        let query = PageQuery::AddressTransactions {
+            address: address.to_string(),
+            network: network.to_string(),
+        };
+        types::AddressTransaction::fetch_page(client, &query, offset, limit).await
+    }
+}
+
+#[cfg(feature = "tokens")]
+impl Page<types::TokenHolder> {
+    /// Fetch a page of holders of the token `identifier`.
+    pub async fn token_holders(
+        client: &Client,
+        identifier: &str,
+        network: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Self, ApiError> {
+        let query = PageQuery::TokenHolders {
+            identifier: identifier.to_string(),
+            network: network.to_string(),
+        };
+        types::TokenHolder::fetch_page(client, &query, offset, limit).await
+    }
+}
+
+#[cfg(feature = "tokens")]
+impl Page<types::TokenTransaction> {
+    /// Fetch a page of transactions of the token `identifier`.
+    pub async fn token_transactions(
+        client: &Client,
+        identifier: &str,
+        network: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Self, ApiError> {
+        let query = PageQuery::TokenTransactions {
+            identifier: identifier.to_string(),
+            network: network.to_string(),
+        };
+        types::TokenTransaction::fetch_page(client, &query, offset, limit).await
+    }
+}
+
+#[cfg(feature = "stats")]
+impl Page<types::TokenLeaderboardEntry> {
+    /// Fetch a page of the token leaderboard.
+    pub async fn token_leaderboard(
+        client: &Client,
+        network: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Self, ApiError> {
+        let query = PageQuery::TokenLeaderboard {
+            network: network.to_string(),
+        };
+        types::TokenLeaderboardEntry::fetch_page(client, &query, offset, limit).await
+    }
+}
+
+#[cfg(feature = "transactions")]
+impl Page<types::LatestNetworkTransactionItem> {
+    /// Fetch a page of the latest transactions on the network.
+    pub async fn latest_transactions(
+        client: &Client,
+        network: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Self, ApiError> {
+        let query = PageQuery::LatestTransactions {
+            network: network.to_string(),
+        };
+        types::LatestNetworkTransactionItem::fetch_page(client, &query, offset, limit).await
+    }
+}
+
+fn mismatched_query(query: &PageQuery) -> ApiError {
+    crate::Error::InvalidRequest(format!(
+        "page query {:?} does not match the requested item type",
+        query
+    ))
+}
+
+/// Check the page size, which the endpoints require to be at least one.
+#[allow(clippy::result_large_err)]
+fn page_size(limit: u64) -> Result<NonZeroU64, ApiError> {
+    NonZeroU64::new(limit)
+        .ok_or_else(|| crate::Error::InvalidRequest("page size must be at least 1".to_string()))
+}
+
+#[cfg(feature = "address")]
+impl PageItem for types::AddressTransaction {
+    async fn fetch_page(
+        client: &Client,
+        query: &PageQuery,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<Self>, ApiError> {
+        let PageQuery::AddressTransactions { address, network } = query else {
+            return Err(mismatched_query(query));
+        };
+        let response = client
+            .get_address_transactions_v1_address_address_transactions_get()
+            .address(address.as_str())
+            .network(network.as_str())
+            .offset(offset)
+            .limit(page_size(limit)?)
+            .send()
+            .await?
+            .into_inner();
+        Ok(Page::new(
+            query.clone(),
+            response.data,
+            offset,
+            limit,
+            Some(response.meta.total_items),
+        ))
+    }
+}
+
+#[cfg(feature = "tokens")]
+impl PageItem for types::TokenHolder {
+    async fn fetch_page(
+        client: &Client,
+        query: &PageQuery,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<Self>, ApiError> {
+        let PageQuery::TokenHolders {
+            identifier,
+            network,
+        } = query
+        else {
+            return Err(mismatched_query(query));
+        };
+        let response = client
+            .get_token_holders_v1_tokens_identifier_holders_get()
+            .identifier(identifier.as_str())
+            .network(network.as_str())
+            .offset(offset)
+            .limit(page_size(limit)?)
+            .send()
+            .await?
+            .into_inner();
+        Ok(Page::new(
+            query.clone(),
+            response.data,
+            offset,
+            limit,
+            Some(response.meta.total_items),
+        ))
+    }
+}
+
+#[cfg(feature = "tokens")]
+impl PageItem for types::TokenTransaction {
+    async fn fetch_page(
+        client: &Client,
+        query: &PageQuery,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<Self>, ApiError> {
+        let PageQuery::TokenTransactions {
+            identifier,
+            network,
+        } = query
+        else {
+            return Err(mismatched_query(query));
+        };
+        let response = client
+            .get_token_transactions_v1_tokens_identifier_transactions_get()
+            .identifier(identifier.as_str())
+            .network(network.as_str())
+            .offset(offset)
+            .limit(page_size(limit)?)
+            .send()
+            .await?
+            .into_inner();
+        Ok(Page::new(
+            query.clone(),
+            response.data,
+            offset,
+            limit,
+            Some(response.meta.total_items),
+        ))
+    }
+}
+
+#[cfg(feature = "stats")]
+impl PageItem for types::TokenLeaderboardEntry {
+    async fn fetch_page(
+        client: &Client,
+        query: &PageQuery,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<Self>, ApiError> {
+        let PageQuery::TokenLeaderboard { network } = query else {
+            return Err(mismatched_query(query));
+        };
+        let response = client
+            .get_token_leaderboard_v1_stats_leaderboard_tokens_get()
+            .network(network.as_str())
+            .offset(offset)
+            .limit(page_size(limit)?)
+            .send()
+            .await?
+            .into_inner();
+        Ok(Page::new(
+            query.clone(),
+            response.leaderboard,
+            offset,
+            limit,
+            Some(response.total_tokens),
+        ))
+    }
+}
+
+#[cfg(feature = "transactions")]
+impl PageItem for types::LatestNetworkTransactionItem {
+    async fn fetch_page(
+        client: &Client,
+        query: &PageQuery,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Page<Self>, ApiError> {
+        let PageQuery::LatestTransactions { network } = query else {
+            return Err(mismatched_query(query));
+        };
+        let items = client
+            .get_latest_transactions_v1_tx_latest_get()
+            .network(network.as_str())
+            .offset(offset)
+            .limit(page_size(limit)?)
+            .send()
+            .await?
+            .into_inner();
+        Ok(Page::new(query.clone(), items, offset, limit, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query() -> PageQuery {
+        PageQuery::TokenLeaderboard {
+            network: "MAINNET".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_has_more_with_total() {
+        let page = Page::new(query(), vec![1, 2, 3], 0, 3, Some(7));
+        assert!(page.has_more);
+        assert_eq!(page.total, Some(7));
+        assert_eq!(page.next_offset(), 3);
+
+        let last = Page::new(query(), vec![7], 6, 3, Some(7));
+        assert!(!last.has_more);
+    }
+
+    #[test]
+    fn test_has_more_without_total() {
+        assert!(Page::new(query(), vec![1, 2], 0, 2, None).has_more);
+        assert!(!Page::new(query(), vec![1], 0, 2, None).has_more);
+        assert!(!Page::<i32>::new(query(), vec![], 0, 2, None).has_more);
+    }
+
+    #[test]
+    fn test_map_keeps_state() {
+        let page = Page::new(query(), vec![1, 2], 4, 2, Some(10)).map(|n| n * 10);
+        assert_eq!(page.items, vec![10, 20]);
+        assert_eq!(page.offset, 4);
+        assert_eq!(page.total, Some(10));
+        assert!(page.has_more);
+        assert_eq!(page.into_iter().sum::<i32>(), 30);
+    }
+
+    #[cfg(feature = "tokens")]
+    #[tokio::test]
+    async fn test_fetch_page_sends_limit_and_offset() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/tokens/btkn1abc/holders"))
+            .and(query_param("limit", "2"))
+            .and(query_param("offset", "4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "meta": {"totalItems": 7, "limit": 2, "offset": 4},
+                "data": [{
+                    "address": "sp1pgssyv42njtxa7kkgvnukk2xnuwpg96n5mxm4985lvhe6sxgavl902js39la8k",
+                    "pubkey": "02a3f1c0d2e4b5968778695a4b3c2d1e0f11223344556677889900aabbccddeeff",
+                    "balance": 150000,
+                    "valueUsd": 1.25,
+                    "percentage": 1.25
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = Client::new(&server.uri());
+        let page = Page::token_holders(&client, "btkn1abc", "MAINNET", 4, 2)
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.total, Some(7));
+        assert!(page.has_more);
+
+        let result = Page::token_holders(&client, "btkn1abc", "MAINNET", 0, 0).await;
+        assert!(matches!(result, Err(crate::Error::InvalidRequest(_))));
+    }
+}
@@ -0,0 +1,209 @@
+//! REST-only polling helpers for environments without WebSocket access.
+//!
+//! [`latest_transactions_since`] polls `/v1/tx/latest` and returns only the transactions that a
+//! [`TxWatermark`] has not seen yet, so a loop around it behaves like a transaction feed:
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use sparkscan::{Client, polling::{TxWatermark, latest_transactions_since}};
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::new_with_api_key("https://api.sparkscan.io", "api-key");
+//!     let mut watermark = TxWatermark::new();
+//!
+//!     loop {
+//!         for tx in latest_transactions_since(&client, "MAINNET", &mut watermark).await.unwrap() {
+//!             println!("New transaction {}", tx.id);
+//!         }
+//!         # break;
+//!     }
+//! });
+//! ```
+
+use std::collections::{HashSet, VecDeque};
+
+use chrono::{DateTime, Utc};
+
+use crate::pagination::Page;
+use crate::{ApiError, Client, types};
+
+/// Number of transactions requested per poll.
+pub const DEFAULT_POLL_WINDOW: u64 = 100;
+
+/// Number of transaction ids remembered by a [`TxWatermark`] by default.
+pub const DEFAULT_SEEN_CAPACITY: usize = 10_000;
+
+/// Maximum number of pages fetched in one poll when catching up after a burst.
+pub const MAX_CATCH_UP_PAGES: usize = 5;
+
+/// Record of the transactions already returned by [`latest_transactions_since`].
+///
+/// Ids are remembered in insertion order and the oldest are forgotten once the capacity is
+/// reached, which keeps memory bounded for long-running pollers.
+#[derive(Debug, Clone)]
+pub struct TxWatermark {
+    seen: HashSet<String>,
+    order: VecDeque<String>,
+    capacity: usize,
+    window: u64,
+    latest_created_at: Option<DateTime<Utc>>,
+}
+
+impl Default for TxWatermark {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TxWatermark {
+    /// Create an empty watermark; the first poll returns the whole window.
+    pub fn new() -> Self {
+        Self {
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            capacity: DEFAULT_SEEN_CAPACITY,
+            window: DEFAULT_POLL_WINDOW,
+            latest_created_at: None,
+        }
+    }
+
+    /// Remember at most `capacity` transaction ids (default: [`DEFAULT_SEEN_CAPACITY`]).
+    ///
+    /// The capacity should stay well above the poll window, otherwise transactions still in the
+    /// window may be forgotten and returned again.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self.evict();
+        self
+    }
+
+    /// Request `window` transactions per page (default: [`DEFAULT_POLL_WINDOW`]).
+    pub fn with_window(mut self, window: u64) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Check if the transaction `id` was already returned.
+    pub fn is_seen(&self, id: &str) -> bool {
+        self.seen.contains(id)
+    }
+
+    /// Record the transaction `id` as returned. Returns `false` if it was already seen.
+    pub fn mark_seen(&mut self, id: &str) -> bool {
+        if !self.seen.insert(id.to_string()) {
+            return false;
+        }
+        self.order.push_back(id.to_string());
+        self.evict();
+        true
+    }
+
+    /// Creation time of the newest transaction returned so far.
+    pub fn latest_created_at(&self) -> Option<DateTime<Utc>> {
+        self.latest_created_at
+    }
+
+    /// Number of remembered transaction ids.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Check if no transaction was seen yet.
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    fn observe(&mut self, tx: &types::LatestNetworkTransactionItem) -> bool {
+        if !self.mark_seen(&tx.id) {
+            return false;
+        }
+        if let Some(created_at) = tx.created_at {
+            self.latest_created_at = self.latest_created_at.max(Some(created_at));
+        }
+        true
+    }
+
+    fn evict(&mut self) {
+        while self.order.len() > self.capacity {
+            if let Some(id) = self.order.pop_front() {
+                self.seen.remove(&id);
+            }
+        }
+    }
+}
+
+/// Get the transactions on `network` that `watermark` has not seen yet, oldest first.
+///
+/// When a whole page is new, older pages are fetched as well (up to [`MAX_CATCH_UP_PAGES`]) so
+/// that bursts between two polls are not silently skipped.
+///
+/// Requires the `transactions` feature.
+#[cfg(feature = "transactions")]
+pub async fn latest_transactions_since(
+    client: &Client,
+    network: &str,
+    watermark: &mut TxWatermark,
+) -> Result<Vec<types::LatestNetworkTransactionItem>, ApiError> {
+    let had_history = !watermark.is_empty();
+    let mut new_transactions = Vec::new();
+    let mut page = Page::latest_transactions(client, network, 0, watermark.window).await?;
+
+    for fetched in 1.. {
+        let page_len = page.len();
+        let unseen: Vec<_> = page
+            .items
+            .iter()
+            .filter(|tx| !watermark.is_seen(&tx.id))
+            .cloned()
+            .collect();
+        let reached_seen = unseen.len() < page_len;
+        new_transactions.extend(unseen);
+
+        if !had_history || reached_seen || fetched >= MAX_CATCH_UP_PAGES {
+            break;
+        }
+        match page.next(client).await? {
+            Some(next) => page = next,
+            None => break,
+        }
+    }
+
+    // The API lists newest first; hand transactions out in the order they happened
+    new_transactions.reverse();
+    new_transactions.retain(|tx| watermark.observe(tx));
+    Ok(new_transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_seen() {
+        let mut watermark = TxWatermark::new();
+        assert!(watermark.is_empty());
+        assert!(watermark.mark_seen("a"));
+        assert!(!watermark.mark_seen("a"));
+        assert!(watermark.is_seen("a"));
+        assert_eq!(watermark.len(), 1);
+    }
+
+    #[test]
+    fn test_capacity_evicts_oldest() {
+        let mut watermark = TxWatermark::new().with_capacity(2);
+        watermark.mark_seen("a");
+        watermark.mark_seen("b");
+        watermark.mark_seen("c");
+
+        assert!(!watermark.is_seen("a"));
+        assert!(watermark.is_seen("b"));
+        assert!(watermark.is_seen("c"));
+        assert_eq!(watermark.len(), 2);
+    }
+
+    #[test]
+    fn test_window_is_at_least_one() {
+        let watermark = TxWatermark::new().with_window(0);
+        assert_eq!(watermark.window, 1);
+    }
+}
@@ -0,0 +1,125 @@
+//! Positional shorthands for the most common endpoints.
+//!
+//! The generated client uses builders, which keep optional parameters readable but make simple
+//! calls verbose. The methods below take the required parameters positionally and cover the
+//! calls most applications start with:
+//!
+//! ```rust,no_run
+//! use sparkscan::{Client, Network};
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::mainnet("api-key");
+//!
+//!     let latest = client.get_latest_transactions(Network::Mainnet, 10).await.unwrap();
+//!     println!("Fetched {} transactions", latest.items.len());
+//!
+//!     let stats = client.get_network_stats(Network::Mainnet).await.unwrap();
+//!     println!("{:?}", stats);
+//! });
+//! ```
+//!
+//! They return the same values as [`SparkScanApi`], which they delegate to without a cache. The
+//! builders remain the way to pass optional parameters, and the paginated results can be walked
+//! with [`Page::next`].
+
+use crate::pagination::Page;
+use crate::{ApiError, Client, Network, SparkAddress, SparkScanApi, TokenIdentifier, types};
+
+impl Client {
+    fn api(&self, network: Network) -> SparkScanApi {
+        SparkScanApi::new(self.clone(), network)
+    }
+
+    /// Get the `limit` most recent transactions on `network`.
+    #[cfg(feature = "transactions")]
+    pub async fn get_latest_transactions(
+        &self,
+        network: Network,
+        limit: u64,
+    ) -> Result<Page<types::LatestNetworkTransactionItem>, ApiError> {
+        self.api(network).latest_transactions_page(0, limit).await
+    }
+
+    /// Get the network summary (TVL, active accounts, 24h transactions, BTC price).
+    #[cfg(feature = "stats")]
+    pub async fn get_network_stats(
+        &self,
+        network: Network,
+    ) -> Result<types::NetworkStats, ApiError> {
+        self.api(network).stats().summary().await
+    }
+
+    /// Get the balance, holdings and activity summary of `address`.
+    #[cfg(feature = "address")]
+    pub async fn get_address_summary(
+        &self,
+        network: Network,
+        address: &SparkAddress,
+    ) -> Result<types::AddressSummaryResponse, ApiError> {
+        self.api(network).address(address).summary().await
+    }
+
+    /// Get the token holdings of `address`.
+    #[cfg(feature = "address")]
+    pub async fn get_address_tokens(
+        &self,
+        network: Network,
+        address: &SparkAddress,
+    ) -> Result<types::AddressTokensResponse, ApiError> {
+        self.api(network).address(address).tokens().await
+    }
+
+    /// Get the `limit` most recent transactions of `address`.
+    #[cfg(feature = "address")]
+    pub async fn get_address_transactions(
+        &self,
+        network: Network,
+        address: &SparkAddress,
+        limit: u64,
+    ) -> Result<Page<types::AddressTransaction>, ApiError> {
+        self.api(network)
+            .address(address)
+            .transactions_page(0, limit)
+            .await
+    }
+
+    /// Get the metadata, supply and market data of the token `identifier`.
+    #[cfg(feature = "tokens")]
+    pub async fn get_token_details(
+        &self,
+        network: Network,
+        identifier: &TokenIdentifier,
+    ) -> Result<types::TokenDetailsResponse, ApiError> {
+        self.api(network).token(identifier).details().await
+    }
+
+    /// Get the `limit` largest holders of the token `identifier`.
+    #[cfg(feature = "tokens")]
+    pub async fn get_token_holders(
+        &self,
+        network: Network,
+        identifier: &TokenIdentifier,
+        limit: u64,
+    ) -> Result<Page<types::TokenHolder>, ApiError> {
+        self.api(network)
+            .token(identifier)
+            .holders_page(0, limit)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Client, Error, Network};
+
+    #[test]
+    #[cfg(all(feature = "stats", feature = "transactions"))]
+    fn test_positional_validation() {
+        let client = Client::regtest();
+        // Rejected locally by the builder the shorthand delegates to
+        let result = tokio_test::block_on(client.get_latest_transactions(Network::Regtest, 1000));
+        assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        let result = tokio_test::block_on(client.get_network_stats(Network::Testnet));
+        assert!(matches!(result, Err(Error::InvalidRequest(_))));
+    }
+}
@@ -0,0 +1,43 @@
+//! Base URLs and preset constructors for the hosted SparkScan API.
+//!
+//! Mainnet and regtest are served by the same host: the network of each call is still selected
+//! through its `network` parameter, or once for all calls with
+//! [`SparkScanApi`](crate::SparkScanApi).
+//!
+//! ```rust
+//! use sparkscan::{Client, Network, SparkScanApi};
+//!
+//! let api = SparkScanApi::new(Client::mainnet("api-key"), Network::Mainnet);
+//! let regtest = SparkScanApi::new(Client::regtest(), Network::Regtest);
+//! ```
+
+use crate::Client;
+
+/// Base URL of the SparkScan API serving mainnet.
+pub const MAINNET_BASE_URL: &str = "https://api.sparkscan.io";
+
+/// Base URL of the SparkScan API serving regtest.
+pub const REGTEST_BASE_URL: &str = "https://api.sparkscan.io";
+
+impl Client {
+    /// Create a new client for mainnet, authenticated with `api_key`.
+    pub fn mainnet(api_key: &str) -> Self {
+        Self::new_with_api_key(MAINNET_BASE_URL, api_key)
+    }
+
+    /// Create a new unauthenticated client for regtest.
+    pub fn regtest() -> Self {
+        Self::new(REGTEST_BASE_URL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_base_urls() {
+        assert_eq!(Client::mainnet("api-key").baseurl, MAINNET_BASE_URL);
+        assert_eq!(Client::regtest().baseurl, REGTEST_BASE_URL);
+    }
+}
@@ -0,0 +1,149 @@
+//! Request ID injection for correlating client calls with server-side logs.
+//!
+//! [`RequestIdMiddleware`] tags every outgoing request with an `x-request-id` header. Requests
+//! that already carry one keep it; otherwise a random UUID (or the output of a custom generator)
+//! is used. The same identifier is copied onto the response when the server does not echo it, so
+//! it is always available through [`ResponseValue::request_id`](crate::ResponseValue::request_id)
+//! and [`Error::request_id`](crate::Error::request_id).
+//!
+//! With the `request-id` feature enabled, the middleware is part of the default stack used by
+//! every constructor. When the `tracing` feature is enabled as well, each request runs inside a
+//! `sparkscan_request` span recording the identifier.
+
+use std::sync::Arc;
+
+use http::Extensions;
+use reqwest::header::HeaderValue;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use sparkscan_client::REQUEST_ID_HEADER;
+
+/// Identifier attached to a request, also stored in the middleware [`Extensions`] so that inner
+/// middleware can read it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(pub String);
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+type Generator = Arc<dyn Fn() -> String + Send + Sync>;
+
+/// Middleware attaching an `x-request-id` header to every request.
+#[derive(Clone)]
+pub struct RequestIdMiddleware {
+    generator: Generator,
+}
+
+impl Default for RequestIdMiddleware {
+    fn default() -> Self {
+        Self::with_generator(|| uuid::Uuid::new_v4().to_string())
+    }
+}
+
+impl std::fmt::Debug for RequestIdMiddleware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestIdMiddleware")
+            .finish_non_exhaustive()
+    }
+}
+
+impl RequestIdMiddleware {
+    /// Create a middleware generating identifiers with random UUIDs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a middleware generating identifiers with `generator`, e.g. to reuse the ID of the
+    /// incoming request being served.
+    ///
+    /// Generated values that are not valid header values are replaced with a random UUID.
+    pub fn with_generator(generator: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        Self {
+            generator: Arc::new(generator),
+        }
+    }
+
+    fn generate(&self) -> HeaderValue {
+        HeaderValue::from_str(&(self.generator)()).unwrap_or_else(|_| {
+            HeaderValue::from_str(&uuid::Uuid::new_v4().to_string())
+                .expect("UUIDs are valid header values")
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RequestIdMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let header = match req.headers().get(REQUEST_ID_HEADER) {
+            Some(existing) => existing.clone(),
+            None => {
+                let generated = self.generate();
+                req.headers_mut()
+                    .insert(REQUEST_ID_HEADER, generated.clone());
+                generated
+            }
+        };
+        let request_id = RequestId(String::from_utf8_lossy(header.as_bytes()).into_owned());
+        extensions.insert(request_id.clone());
+
+        #[cfg(feature = "tracing")]
+        let result = {
+            use tracing::Instrument;
+
+            let span = tracing::debug_span!("sparkscan_request", request_id = %request_id);
+            next.run(req, extensions).instrument(span).await
+        };
+        #[cfg(not(feature = "tracing"))]
+        let result = next.run(req, extensions).await;
+
+        match result {
+            Ok(mut response) => {
+                if !response.headers().contains_key(REQUEST_ID_HEADER) {
+                    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+                }
+                Ok(response)
+            }
+            Err(err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(request_id = %request_id, error = %err, "SparkScan request failed");
+                Err(err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_generates_uuids() {
+        let middleware = RequestIdMiddleware::default();
+        let first = middleware.generate();
+        let second = middleware.generate();
+
+        assert_ne!(first, second);
+        assert!(uuid::Uuid::parse_str(first.to_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_custom_generator() {
+        let middleware = RequestIdMiddleware::with_generator(|| "req-42".to_string());
+        assert_eq!(middleware.generate(), "req-42");
+    }
+
+    #[test]
+    fn test_invalid_generated_value_falls_back_to_uuid() {
+        let middleware = RequestIdMiddleware::with_generator(|| "bad\nvalue".to_string());
+        let value = middleware.generate();
+        assert!(uuid::Uuid::parse_str(value.to_str().unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,362 @@
+//! Structural validation of responses against the bundled OpenAPI document.
+//!
+//! With the `response-validation` feature, every response body is checked against the schema
+//! that `openapi.json` documents for its operation and status code, before it is decoded. A
+//! response that does not match fails the call with [`Error::Custom`](crate::Error::Custom)
+//! listing every offending field, rather than the first field serde trips over, which makes
+//! schema drift of a staging server easy to spot:
+//!
+//! ```text
+//! response of get_network_stats_v1_stats_summary_get (200 OK) does not match its schema:
+//!   /transactions24h: missing required property
+//!   /currentBtcPriceUsd: expected number, found null
+//! ```
+//!
+//! The feature buffers and walks every response, so it is meant for debugging rather than
+//! production builds. It is not available on WASM.
+//!
+//! [`validate_body`] checks bodies obtained elsewhere, e.g. through `send_raw`:
+//!
+//! ```rust
+//! use sparkscan::schema::validate_body;
+//!
+//! let body = serde_json::json!({ "totalValueLockedSats": 1 });
+//! let mismatches = validate_body("get_network_stats_v1_stats_summary_get", 200, &body).unwrap();
+//! assert_eq!(mismatches.len(), 4);
+//! ```
+
+use std::fmt;
+
+use reqwest::ResponseBuilderExt;
+use serde_json::Value;
+
+/// A field of a response that does not match its documented schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaMismatch {
+    /// JSON pointer to the field, empty for the whole body
+    pub path: String,
+    /// What was expected and what was found
+    pub message: String,
+}
+
+impl fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Every mismatch found in a response, attached to it by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaReport {
+    /// Operation id of the request, as in the OpenAPI document
+    pub operation_id: String,
+    /// HTTP status code of the response
+    pub status: reqwest::StatusCode,
+    /// Mismatches found in the body
+    pub mismatches: Vec<SchemaMismatch>,
+}
+
+impl fmt::Display for SchemaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "response of {} ({}) does not match its schema:",
+            self.operation_id, self.status
+        )?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check `body` against the schema documented for `operation_id` and `status`.
+///
+/// Returns `None` when the document does not describe a JSON body for this response.
+pub fn validate_body(operation_id: &str, status: u16, body: &Value) -> Option<Vec<SchemaMismatch>> {
+    let document = crate::openapi::openapi_document();
+    let operation = document["paths"]
+        .as_object()?
+        .values()
+        .filter_map(Value::as_object)
+        .flat_map(|methods| methods.values())
+        .find(|operation| operation["operationId"] == operation_id)?;
+    let responses = &operation["responses"];
+    let response = [
+        status.to_string(),
+        format!("{}XX", status / 100),
+        "default".into(),
+    ]
+    .iter()
+    .map(|key| &responses[key.as_str()])
+    .find(|response| !response.is_null())?;
+    let schema = response["content"]["application/json"].get("schema")?;
+
+    let mut mismatches = Vec::new();
+    Validator { document }.check(schema, body, &mut String::new(), &mut mismatches);
+    Some(mismatches)
+}
+
+/// Buffer the body of `response` and attach a [`SchemaReport`] to it if it does not match.
+pub(crate) async fn check_response(
+    response: reqwest::Response,
+    operation_id: &str,
+) -> reqwest::Result<reqwest::Response> {
+    let status = response.status();
+    let mut builder = http::Response::builder()
+        .status(status)
+        .version(response.version())
+        .url(response.url().clone());
+    if let Some(headers) = builder.headers_mut() {
+        headers.extend(response.headers().clone());
+    }
+    let body = response.bytes().await?;
+
+    // Bodies that are not JSON are left to the decoder to report
+    let mismatches = serde_json::from_slice(&body)
+        .ok()
+        .and_then(|value| validate_body(operation_id, status.as_u16(), &value))
+        .filter(|mismatches| !mismatches.is_empty());
+    if let Some(mismatches) = mismatches {
+        builder = builder.extension(SchemaReport {
+            operation_id: operation_id.to_string(),
+            status,
+            mismatches,
+        });
+    }
+    Ok(builder
+        .body(body)
+        .expect("buffered response parts are always valid")
+        .into())
+}
+
+struct Validator<'a> {
+    document: &'a Value,
+}
+
+impl<'a> Validator<'a> {
+    fn resolve(&self, schema: &'a Value) -> &'a Value {
+        match schema["$ref"].as_str() {
+            Some(reference) => reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.document.pointer(pointer))
+                .map_or(&Value::Null, |target| self.resolve(target)),
+            None => schema,
+        }
+    }
+
+    fn check(
+        &self,
+        schema: &'a Value,
+        value: &Value,
+        path: &mut String,
+        out: &mut Vec<SchemaMismatch>,
+    ) {
+        let schema = self.resolve(schema);
+        if !schema.is_object() {
+            return;
+        }
+        if value.is_null() && (schema["nullable"] == true || schema["type"] == "null") {
+            return;
+        }
+
+        for part in schema["allOf"].as_array().into_iter().flatten() {
+            self.check(part, value, path, out);
+        }
+        for key in ["anyOf", "oneOf"] {
+            if let Some(alternatives) = schema[key].as_array() {
+                self.check_alternatives(alternatives, value, path, out);
+            }
+        }
+
+        if let Some(allowed) = schema["enum"].as_array()
+            && !allowed.contains(value)
+        {
+            out.push(mismatch(
+                path,
+                format!("{} is not one of {}", value, Value::from(allowed.clone())),
+            ));
+            return;
+        }
+
+        let Some(expected) = schema["type"].as_str() else {
+            return;
+        };
+        let matches = match expected {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "boolean" => value.is_boolean(),
+            "number" => value.is_number(),
+            // Big integers are sent as whole floats or decimal strings, both accepted by the client
+            "integer" => match value {
+                Value::Number(number) => {
+                    number.is_i64()
+                        || number.is_u64()
+                        || number.as_f64().is_some_and(|n| n.fract() == 0.0)
+                }
+                Value::String(string) => string.parse::<i128>().is_ok(),
+                _ => false,
+            },
+            _ => true,
+        };
+        if !matches {
+            out.push(mismatch(
+                path,
+                format!("expected {}, found {}", expected, describe(value)),
+            ));
+            return;
+        }
+
+        match value {
+            Value::Object(fields) => {
+                for required in schema["required"].as_array().into_iter().flatten() {
+                    if let Some(name) = required.as_str()
+                        && !fields.contains_key(name)
+                    {
+                        with_segment(path, name, |path| {
+                            out.push(mismatch(path, "missing required property".into()))
+                        });
+                    }
+                }
+                let properties = &schema["properties"];
+                for (name, field) in fields {
+                    let field_schema = match properties.get(name) {
+                        Some(field_schema) => field_schema,
+                        None => &schema["additionalProperties"],
+                    };
+                    with_segment(path, name, |path| {
+                        self.check(field_schema, field, path, out)
+                    });
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    with_segment(path, &index.to_string(), |path| {
+                        self.check(&schema["items"], item, path, out)
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Report the mismatches of the closest alternative, if none of them matches.
+    fn check_alternatives(
+        &self,
+        alternatives: &'a [Value],
+        value: &Value,
+        path: &mut String,
+        out: &mut Vec<SchemaMismatch>,
+    ) {
+        let mut closest: Option<Vec<SchemaMismatch>> = None;
+        for alternative in alternatives {
+            let mut mismatches = Vec::new();
+            self.check(alternative, value, path, &mut mismatches);
+            if mismatches.is_empty() {
+                return;
+            }
+            if closest
+                .as_ref()
+                .is_none_or(|closest| mismatches.len() < closest.len())
+            {
+                closest = Some(mismatches);
+            }
+        }
+        out.extend(closest.into_iter().flatten());
+    }
+}
+
+fn mismatch(path: &str, message: String) -> SchemaMismatch {
+    SchemaMismatch {
+        path: path.to_string(),
+        message,
+    }
+}
+
+/// Run `f` with `segment` appended to the JSON pointer `path`.
+fn with_segment(path: &mut String, segment: &str, f: impl FnOnce(&mut String)) {
+    let len = path.len();
+    path.push('/');
+    path.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    f(path);
+    path.truncate(len);
+}
+
+/// Describe a JSON value for a report, without dumping large objects.
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Object(_) => "object".to_string(),
+        Value::Array(_) => "array".to_string(),
+        Value::Bool(_) => format!("boolean {}", value),
+        Value::Number(_) => format!("number {}", value),
+        Value::String(string) if string.len() <= 40 => format!("string {}", value),
+        Value::String(_) => "string".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_network_stats_mismatches() {
+        let body = json!({
+            "totalValueLockedSats": 1000,
+            "totalValueLockedUsd": 1.5,
+            "activeAccounts": "12",
+            "currentBtcPriceUsd": null,
+        });
+        let mismatches =
+            validate_body("get_network_stats_v1_stats_summary_get", 200, &body).unwrap();
+        let report: Vec<String> = mismatches.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            report,
+            [
+                "/transactions24h: missing required property",
+                "/currentBtcPriceUsd: expected number, found null",
+            ]
+        );
+
+        let body = json!({
+            "totalValueLockedSats": 1000,
+            "totalValueLockedUsd": 1.5,
+            "activeAccounts": 12,
+            "transactions24h": 2.1e23,
+            "currentBtcPriceUsd": 100000,
+        });
+        let mismatches =
+            validate_body("get_network_stats_v1_stats_summary_get", 200, &body).unwrap();
+        assert!(mismatches.is_empty());
+        assert!(validate_body("unknown_operation", 200, &body).is_none());
+    }
+
+    #[test]
+    fn test_nested_paths() {
+        let document = json!({
+            "components": { "schemas": {
+                "Item": {
+                    "type": "object",
+                    "properties": { "status": { "type": "string", "enum": ["ok"] } },
+                },
+            }},
+        });
+        let schema = json!({ "type": "array", "items": { "$ref": "#/components/schemas/Item" } });
+        let value = json!([{ "status": "ok" }, { "status": "failed" }, { "a/b": 1 }]);
+        let mut mismatches = Vec::new();
+        Validator {
+            document: &document,
+        }
+        .check(&schema, &value, &mut String::new(), &mut mismatches);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].path, "/1/status");
+        assert_eq!(mismatches[0].message, r#""failed" is not one of ["ok"]"#);
+    }
+}
@@ -0,0 +1,62 @@
+//! Application identity in the user agent.
+//!
+//! Requests are sent with a `sparkscan-rs/{version}` user agent. Services can prepend their own
+//! product token, which lets the SparkScan team attribute traffic to applications. Like API key
+//! overrides, the identity is applied through
+//! [`ClientHooks::pre`](sparkscan_client::ClientHooks::pre) and works with custom HTTP clients.
+
+use reqwest::header::{HeaderValue, InvalidHeaderValue};
+
+use crate::Client;
+
+/// Get the user agent sent by default, `sparkscan-rs/{version}`.
+pub(crate) fn default_user_agent() -> String {
+    format!("sparkscan-rs/{}", env!("CARGO_PKG_VERSION"))
+}
+
+fn app_user_agent(name: &str, version: &str) -> String {
+    format!("{}/{} {}", name, version, default_user_agent())
+}
+
+impl Client {
+    /// Get a copy of this client identifying requests as coming from application `name` at
+    /// `version`, e.g. `myapp/1.2.3 sparkscan-rs/0.3.8`.
+    ///
+    /// Fails if `name` or `version` contain characters that are not allowed in an HTTP header.
+    ///
+    /// ```rust
+    /// let client = sparkscan::Client::mainnet("api-key")
+    ///     .with_app_identity("myapp", "1.2.3")
+    ///     .unwrap();
+    /// ```
+    pub fn with_app_identity(&self, name: &str, version: &str) -> Result<Self, InvalidHeaderValue> {
+        let user_agent = HeaderValue::from_str(&app_user_agent(name, version))?;
+
+        let mut client = self.clone();
+        client.user_agent_override = Some(user_agent);
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_app_identity() {
+        let client = Client::new("https://api.sparkscan.io");
+        assert!(client.user_agent_override.is_none());
+
+        let client = client.with_app_identity("myapp", "1.2.3").unwrap();
+        assert_eq!(
+            client.user_agent_override.unwrap(),
+            format!("myapp/1.2.3 sparkscan-rs/{}", env!("CARGO_PKG_VERSION")).as_str()
+        );
+    }
+
+    #[test]
+    fn test_with_app_identity_rejects_invalid_header() {
+        let client = Client::new("https://api.sparkscan.io");
+        assert!(client.with_app_identity("my\napp", "1.2.3").is_err());
+    }
+}
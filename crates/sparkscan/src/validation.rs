@@ -0,0 +1,179 @@
+//! Local validation of request parameters.
+//!
+//! The generated builders check integer query parameters against the ranges declared in the
+//! OpenAPI spec as soon as they are set, so an out-of-range `limit` or `offset` fails with
+//! [`Error::InvalidRequest`](crate::Error::InvalidRequest) before any request is sent instead
+//! of an opaque 422 from the server:
+//!
+//! ```rust,no_run
+//! use sparkscan::{Client, Error, Network};
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::new_with_api_key("https://api.sparkscan.io", "api-key");
+//!
+//!     let result = client
+//!         .get_token_holders_v1_tokens_identifier_holders_get()
+//!         .identifier("btkn1...")
+//!         .network(Network::Mainnet)
+//!         .limit(1000)
+//!         .send()
+//!         .await;
+//!     assert!(matches!(result, Err(Error::InvalidRequest(_))));
+//! });
+//! ```
+//!
+//! Network setters accept the typed [`Network`](crate::Network) as well as its wire name; the
+//! networks only reported by the WebSocket feed (e.g. [`Network::Testnet`](crate::Network::Testnet))
+//! are rejected the same way.
+
+/// Request parameter rejected locally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// An integer parameter is outside of the range accepted by the endpoint
+    OutOfRange {
+        /// Name of the query parameter
+        parameter: &'static str,
+        /// Rejected value
+        value: i128,
+        /// Smallest accepted value
+        minimum: Option<i128>,
+        /// Largest accepted value
+        maximum: Option<i128>,
+    },
+    /// The network is not served by the REST API
+    UnknownNetwork(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::OutOfRange {
+                parameter,
+                value,
+                minimum,
+                maximum,
+            } => match (minimum, maximum) {
+                (Some(minimum), Some(maximum)) => write!(
+                    f,
+                    "{} must be between {} and {}, got {}",
+                    parameter, minimum, maximum, value
+                ),
+                (Some(minimum), None) => {
+                    write!(
+                        f,
+                        "{} must be at least {}, got {}",
+                        parameter, minimum, value
+                    )
+                }
+                (None, Some(maximum)) => {
+                    write!(
+                        f,
+                        "{} must be at most {}, got {}",
+                        parameter, maximum, value
+                    )
+                }
+                (None, None) => write!(f, "{} is out of range, got {}", parameter, value),
+            },
+            ConfigError::UnknownNetwork(network) => write!(f, "unknown network: {}", network),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Integer parameter type of the generated builders, widened to `i128` for range checks.
+pub trait RangeValue: Copy {
+    /// Get the value as `i128`, `None` if it is absent.
+    fn to_i128(self) -> Option<i128>;
+}
+
+macro_rules! range_value {
+    ($($ty:ty),*) => {
+        $(impl RangeValue for $ty {
+            fn to_i128(self) -> Option<i128> {
+                Some(i128::from(self))
+            }
+        })*
+    };
+}
+
+range_value!(i128, i64, u64);
+
+impl RangeValue for std::num::NonZeroU64 {
+    fn to_i128(self) -> Option<i128> {
+        Some(i128::from(self.get()))
+    }
+}
+
+impl<T: RangeValue> RangeValue for Option<T> {
+    fn to_i128(self) -> Option<i128> {
+        self.and_then(RangeValue::to_i128)
+    }
+}
+
+/// Check that an optional or required integer parameter lies within `minimum..=maximum`.
+///
+/// Used by the generated builder setters; absent optional values are always accepted.
+pub fn check_range<T: RangeValue>(
+    parameter: &'static str,
+    value: T,
+    minimum: Option<i128>,
+    maximum: Option<i128>,
+) -> Result<T, ConfigError> {
+    let Some(number) = value.to_i128() else {
+        return Ok(value);
+    };
+    if minimum.is_some_and(|minimum| number < minimum)
+        || maximum.is_some_and(|maximum| number > maximum)
+    {
+        return Err(ConfigError::OutOfRange {
+            parameter,
+            value: number,
+            minimum,
+            maximum,
+        });
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_range() {
+        assert_eq!(check_range("limit", 25i128, Some(1), Some(100)), Ok(25));
+        assert_eq!(
+            check_range("limit", None::<i128>, Some(1), Some(100)),
+            Ok(None)
+        );
+        assert_eq!(
+            check_range("offset", Some(0i128), Some(0), None),
+            Ok(Some(0))
+        );
+
+        let err = check_range("limit", Some(500i128), Some(1), Some(100)).unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::OutOfRange {
+                parameter: "limit",
+                value: 500,
+                minimum: Some(1),
+                maximum: Some(100),
+            }
+        );
+        assert_eq!(err.to_string(), "limit must be between 1 and 100, got 500");
+
+        let err = check_range("offset", -1i128, Some(0), None).unwrap_err();
+        assert_eq!(err.to_string(), "offset must be at least 0, got -1");
+
+        // Generated builders hold the values in their declared types
+        let limit = std::num::NonZeroU64::new(500);
+        let err = check_range("limit", limit, Some(1), Some(100)).unwrap_err();
+        assert_eq!(err.to_string(), "limit must be between 1 and 100, got 500");
+        assert_eq!(
+            check_range("offset", Some(3u64), Some(0), None),
+            Ok(Some(3))
+        );
+    }
+}
@@ -0,0 +1,458 @@
+//! Polling watcher emitting change events, as a fallback for environments without WebSocket
+//! access.
+//!
+//! [`PollWatcher`] fetches an endpoint at a fixed interval, compares each response with the
+//! previous one and yields a [`WatchEvent`] for every change it finds:
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use futures::StreamExt;
+//! use sparkscan::{Client, watch::{PollWatcher, WatchEvent}};
+//!
+//! tokio_test::block_on(async {
+//!     let client = Client::new_with_api_key("https://api.sparkscan.io", "api-key");
+//!     let watcher = PollWatcher::address_summary(client, "sp1...", "MAINNET")
+//!         .with_interval(Duration::from_secs(10));
+//!
+//!     let mut events = Box::pin(watcher.into_stream());
+//!     while let Some(event) = events.next().await {
+//!         match event {
+//!             Ok(WatchEvent::TokenBalanceChanged { token_identifier, current, .. }) => {
+//!                 println!("{} balance is now {}", token_identifier, current);
+//!             }
+//!             Ok(other) => println!("{:?}", other),
+//!             Err(e) => eprintln!("Poll failed: {}", e),
+//!         }
+//!     }
+//! });
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use futures::Stream;
+
+use crate::pagination::{MAX_PAGE_SIZE, Page};
+use crate::{ApiError, Client, types};
+
+/// Default time between two polls.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Endpoint polled by a [`PollWatcher`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchTarget {
+    /// Summary of a Spark address
+    AddressSummary {
+        /// Spark address
+        address: String,
+        /// API network name
+        network: String,
+    },
+    /// Largest holders of a token (first page of up to 100 holders)
+    TokenHolders {
+        /// Token identifier
+        identifier: String,
+        /// API network name
+        network: String,
+    },
+}
+
+/// A polled response, kept to compute the next diff.
+#[derive(Debug, Clone)]
+pub enum WatchSnapshot {
+    /// Response of the address summary endpoint
+    AddressSummary(Box<types::AddressSummaryResponse>),
+    /// First page of the token holders endpoint
+    TokenHolders(Page<types::TokenHolder>),
+}
+
+/// Change detected between two polls.
+///
+/// Balances that appear or disappear are reported as changes from or to zero.
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    /// First successful poll, carrying the initial state
+    Snapshot(WatchSnapshot),
+    /// The BTC balance of the watched address changed
+    BtcBalanceChanged {
+        /// Spark address
+        address: String,
+        /// Previous soft balance, in sats
+        previous_soft_sats: i128,
+        /// Current soft balance, in sats
+        current_soft_sats: i128,
+        /// Previous hard balance, in sats
+        previous_hard_sats: i128,
+        /// Current hard balance, in sats
+        current_hard_sats: i128,
+    },
+    /// The transaction count of the watched address changed
+    TransactionCountChanged {
+        /// Spark address
+        address: String,
+        /// Previous count
+        previous: i128,
+        /// Current count
+        current: i128,
+    },
+    /// A token balance of the watched address changed
+    TokenBalanceChanged {
+        /// Spark address
+        address: String,
+        /// Token identifier
+        token_identifier: String,
+        /// Previous balance, in base units
+        previous: i128,
+        /// Current balance, in base units
+        current: i128,
+    },
+    /// The balance of a holder of the watched token changed
+    HolderBalanceChanged {
+        /// Token identifier
+        identifier: String,
+        /// Holder address
+        holder: String,
+        /// Previous balance, in base units
+        previous: i128,
+        /// Current balance, in base units
+        current: i128,
+    },
+}
+
+/// Periodically polls an endpoint and emits the differences between responses.
+#[derive(Debug, Clone)]
+pub struct PollWatcher {
+    client: Client,
+    target: WatchTarget,
+    interval: Duration,
+}
+
+impl PollWatcher {
+    /// Watch the summary of `address` on `network`.
+    pub fn address_summary(client: Client, address: &str, network: &str) -> Self {
+        Self::new(
+            client,
+            WatchTarget::AddressSummary {
+                address: address.to_string(),
+                network: network.to_string(),
+            },
+        )
+    }
+
+    /// Watch the largest holders of the token `identifier` on `network`.
+    pub fn token_holders(client: Client, identifier: &str, network: &str) -> Self {
+        Self::new(
+            client,
+            WatchTarget::TokenHolders {
+                identifier: identifier.to_string(),
+                network: network.to_string(),
+            },
+        )
+    }
+
+    /// Watch `target`, polling every [`DEFAULT_POLL_INTERVAL`].
+    pub fn new(client: Client, target: WatchTarget) -> Self {
+        Self {
+            client,
+            target,
+            interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Configure the time between two polls.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Get the watched endpoint.
+    pub fn target(&self) -> &WatchTarget {
+        &self.target
+    }
+
+    /// Fetch the current state of the watched endpoint.
+    pub async fn poll(&self) -> Result<WatchSnapshot, ApiError> {
+        match &self.target {
+            WatchTarget::AddressSummary { address, network } => {
+                let summary = self
+                    .client
+                    .address_summary_v1_address_address_get()
+                    .address(address.as_str())
+                    .network(network.as_str())
+                    .send()
+                    .await?
+                    .into_inner();
+                Ok(WatchSnapshot::AddressSummary(Box::new(summary)))
+            }
+            WatchTarget::TokenHolders {
+                identifier,
+                network,
+            } => {
+                let page = Page::token_holders(&self.client, identifier, network, 0, MAX_PAGE_SIZE)
+                    .await?;
+                Ok(WatchSnapshot::TokenHolders(page))
+            }
+        }
+    }
+
+    /// Poll forever, yielding the initial snapshot and then every detected change.
+    ///
+    /// Failed polls yield an error and are retried at the next interval; the stream never ends.
+    pub fn into_stream(self) -> impl Stream<Item = Result<WatchEvent, ApiError>> {
+        let state = StreamState {
+            watcher: self,
+            previous: None,
+            pending: VecDeque::new(),
+        };
+
+        futures::stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(event) = state.pending.pop_front() {
+                    return Some((Ok(event), state));
+                }
+                if state.previous.is_some() {
+                    tokio::time::sleep(state.watcher.interval).await;
+                }
+
+                match state.watcher.poll().await {
+                    Ok(current) => {
+                        match &state.previous {
+                            Some(previous) => {
+                                state.pending.extend(diff_snapshots(previous, &current))
+                            }
+                            None => state
+                                .pending
+                                .push_back(WatchEvent::Snapshot(current.clone())),
+                        }
+                        state.previous = Some(current);
+                    }
+                    Err(err) => {
+                        // Keep the cadence on failures instead of hammering the API
+                        if state.previous.is_none() {
+                            tokio::time::sleep(state.watcher.interval).await;
+                        }
+                        return Some((Err(err), state));
+                    }
+                }
+            }
+        })
+    }
+}
+
+struct StreamState {
+    watcher: PollWatcher,
+    previous: Option<WatchSnapshot>,
+    pending: VecDeque<WatchEvent>,
+}
+
+/// Compute the events leading from `previous` to `current`.
+///
+/// Snapshots of different endpoints produce no events.
+pub fn diff_snapshots(previous: &WatchSnapshot, current: &WatchSnapshot) -> Vec<WatchEvent> {
+    match (previous, current) {
+        (WatchSnapshot::AddressSummary(previous), WatchSnapshot::AddressSummary(current)) => {
+            diff_address_summary(previous, current)
+        }
+        (WatchSnapshot::TokenHolders(previous), WatchSnapshot::TokenHolders(current)) => {
+            diff_token_holders(&previous.query, &previous.items, &current.items)
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn diff_address_summary(
+    previous: &types::AddressSummaryResponse,
+    current: &types::AddressSummaryResponse,
+) -> Vec<WatchEvent> {
+    let address = &current.spark_address;
+    let mut events = Vec::new();
+
+    if previous.balance.btc_soft_balance_sats != current.balance.btc_soft_balance_sats
+        || previous.balance.btc_hard_balance_sats != current.balance.btc_hard_balance_sats
+    {
+        events.push(WatchEvent::BtcBalanceChanged {
+            address: address.clone(),
+            previous_soft_sats: previous.balance.btc_soft_balance_sats,
+            current_soft_sats: current.balance.btc_soft_balance_sats,
+            previous_hard_sats: previous.balance.btc_hard_balance_sats,
+            current_hard_sats: current.balance.btc_hard_balance_sats,
+        });
+    }
+
+    if previous.transaction_count != current.transaction_count {
+        events.push(WatchEvent::TransactionCountChanged {
+            address: address.clone(),
+            previous: previous.transaction_count,
+            current: current.transaction_count,
+        });
+    }
+
+    let balances = |summary: &types::AddressSummaryResponse| {
+        summary
+            .tokens
+            .iter()
+            .flatten()
+            .map(|token| (token.token_identifier.clone(), token.balance))
+            .collect::<Vec<_>>()
+    };
+    for (token_identifier, previous, current) in
+        diff_balances(balances(previous), balances(current))
+    {
+        events.push(WatchEvent::TokenBalanceChanged {
+            address: address.clone(),
+            token_identifier,
+            previous,
+            current,
+        });
+    }
+
+    events
+}
+
+fn diff_token_holders(
+    query: &crate::pagination::PageQuery,
+    previous: &[types::TokenHolder],
+    current: &[types::TokenHolder],
+) -> Vec<WatchEvent> {
+    let crate::pagination::PageQuery::TokenHolders { identifier, .. } = query else {
+        return Vec::new();
+    };
+    let balances = |holders: &[types::TokenHolder]| {
+        holders
+            .iter()
+            .map(|holder| (holder.address.clone(), holder.balance))
+            .collect::<Vec<_>>()
+    };
+
+    diff_balances(balances(previous), balances(current))
+        .into_iter()
+        .map(
+            |(holder, previous, current)| WatchEvent::HolderBalanceChanged {
+                identifier: identifier.clone(),
+                holder,
+                previous,
+                current,
+            },
+        )
+        .collect()
+}
+
+/// Pair up keyed balances, treating missing entries as zero, and keep those that changed.
+///
+/// Results follow the order of `current`, then the keys only present in `previous`.
+fn diff_balances(
+    previous: Vec<(String, i128)>,
+    current: Vec<(String, i128)>,
+) -> Vec<(String, i128, i128)> {
+    let mut previous_by_key: HashMap<String, i128> = previous.iter().cloned().collect();
+    let mut changes = Vec::new();
+
+    for (key, balance) in current {
+        let before = previous_by_key.remove(&key).unwrap_or(0);
+        if before != balance {
+            changes.push((key, before, balance));
+        }
+    }
+    for (key, _) in previous {
+        if let Some(before) = previous_by_key.remove(&key)
+            && before != 0
+        {
+            changes.push((key, before, 0));
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(items: &[(&str, i128)]) -> Vec<(String, i128)> {
+        items.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn test_diff_balances() {
+        let changes = diff_balances(
+            entries(&[("a", 10), ("b", 5), ("c", 1)]),
+            entries(&[("a", 10), ("b", 7), ("d", 3)]),
+        );
+        assert_eq!(
+            changes,
+            vec![
+                ("b".to_string(), 5, 7),
+                ("d".to_string(), 0, 3),
+                ("c".to_string(), 1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_balances_unchanged() {
+        let balances = entries(&[("a", 10), ("b", 5)]);
+        assert!(diff_balances(balances.clone(), balances).is_empty());
+    }
+
+    #[test]
+    fn test_diff_address_summary() {
+        let summary = |soft: i128, tx_count: i128, token_balance: i128| {
+            serde_json::from_str::<types::AddressSummaryResponse>(&format!(
+                r#"{{
+                    "sparkAddress": "sp1abc",
+                    "publicKey": "02ab",
+                    "balance": {{
+                        "btcSoftBalanceSats": {soft},
+                        "btcHardBalanceSats": 100,
+                        "btcValueUsdHard": 0.1,
+                        "btcValueUsdSoft": 0.1,
+                        "totalTokenValueUsd": 0.0
+                    }},
+                    "totalValueUsd": 0.1,
+                    "transactionCount": {tx_count},
+                    "tokenCount": 1,
+                    "tokens": [{{
+                        "tokenIdentifier": "btkn1abc",
+                        "tokenAddress": "02cd",
+                        "name": "Token",
+                        "ticker": "TKN",
+                        "decimals": 8,
+                        "balance": {token_balance},
+                        "valueUsd": 0.0,
+                        "issuerPublicKey": "02ef"
+                    }}]
+                }}"#
+            ))
+            .unwrap()
+        };
+
+        assert!(diff_address_summary(&summary(100, 1, 5), &summary(100, 1, 5)).is_empty());
+
+        let events = diff_address_summary(&summary(100, 1, 5), &summary(150, 2, 8));
+        assert_eq!(events.len(), 3);
+        assert!(matches!(
+            events[0],
+            WatchEvent::BtcBalanceChanged {
+                previous_soft_sats: 100,
+                current_soft_sats: 150,
+                ..
+            }
+        ));
+        assert!(matches!(
+            events[1],
+            WatchEvent::TransactionCountChanged {
+                previous: 1,
+                current: 2,
+                ..
+            }
+        ));
+        assert!(matches!(
+            events[2],
+            WatchEvent::TokenBalanceChanged {
+                previous: 5,
+                current: 8,
+                ..
+            }
+        ));
+    }
+}